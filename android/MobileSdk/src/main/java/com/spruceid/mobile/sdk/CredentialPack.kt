@@ -450,7 +450,7 @@ class CredentialPackContents {
                                 "sprucekit", "credential '$it' in pack '${id}'" +
                                         " could not be found"
                             )
-                            Log.d("sprucekit", "VdcCollection: ${vdcCollection.allEntries()}")
+                            Log.d("sprucekit", "VdcCollection: ${vdcCollection.allIds()}")
                         }
                         credential
                     } catch (e: Exception) {
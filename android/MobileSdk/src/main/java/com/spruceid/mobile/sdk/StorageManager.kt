@@ -1,6 +1,7 @@
 import android.content.Context
 import android.util.Base64
 import com.spruceid.mobile.sdk.KeyManager
+import com.spruceid.mobile.sdk.rs.StorageEntry
 import com.spruceid.mobile.sdk.rs.StorageManagerInterface
 import java.io.File
 import java.io.FileNotFoundException
@@ -52,6 +53,34 @@ class StorageManager(val context: Context) : StorageManagerInterface {
         File(context.filesDir, filename(key)).delete()
     }
 
+    /// Function: contains
+    ///
+    /// Checks whether a value is stored under a key, without decrypting it.
+    ///
+    /// Arguments:
+    /// key - The key to look up
+    override suspend fun contains(key: String): Boolean =
+        File(context.filesDir, filename(key)).exists()
+
+
+    /// Function: addMany
+    ///
+    /// Adds several key-value pairs to storage, one after the other.
+    ///
+    /// Arguments:
+    /// entries - The key-value pairs to add
+    override suspend fun addMany(entries: List<StorageEntry>) =
+        entries.forEach { add(it.key, it.value) }
+
+    /// Function: removeMany
+    ///
+    /// Removes several key-value pairs from storage by key.
+    ///
+    /// Arguments:
+    /// keys - The keys to remove
+    override suspend fun removeMany(keys: List<String>) =
+        keys.forEach { remove(it) }
+
 
     /// Function: list
     ///
@@ -20,6 +20,18 @@ class RustLogger: LogWriter {
         buffer = ByteArray(0)
     }
 
+    override fun logRecord(level: String, target: String, message: String) {
+        if (!enabled) return
+        val line = "$target: $message"
+        when (level) {
+            "ERROR" -> Log.e("RustLogger", line)
+            "WARN" -> Log.w("RustLogger", line)
+            "INFO" -> Log.i("RustLogger", line)
+            "DEBUG" -> Log.d("RustLogger", line)
+            else -> Log.v("RustLogger", line)
+        }
+    }
+
     companion object {
         var enabled = false
 
@@ -95,6 +107,7 @@ fun CborValue.toText(): String {
     return when (this) {
         is CborValue.Text -> v1
         is CborValue.Integer -> v1.toText()
+        is CborValue.BigInt -> v1
         is CborValue.Float -> v1.toString()
         is CborValue.Bool -> v1.toString()
         is CborValue.Array -> v1.map { it.toText() }.joinToString { ", " }
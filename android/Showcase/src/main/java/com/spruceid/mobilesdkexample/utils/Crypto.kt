@@ -12,12 +12,31 @@ class CryptoImpl : Crypto {
         certificateDer: ByteArray,
         payload: ByteArray,
         signature: ByteArray,
+    ): VerificationResult = verify("SHA256withECDSA", certificateDer, payload, signature)
+
+    override fun k256Verify(
+        certificateDer: ByteArray,
+        payload: ByteArray,
+        signature: ByteArray,
+    ): VerificationResult = verify("SHA256withECDSA", certificateDer, payload, signature)
+
+    override fun ed25519Verify(
+        certificateDer: ByteArray,
+        payload: ByteArray,
+        signature: ByteArray,
+    ): VerificationResult = verify("Ed25519", certificateDer, payload, signature)
+
+    private fun verify(
+        algorithm: String,
+        certificateDer: ByteArray,
+        payload: ByteArray,
+        signature: ByteArray,
     ): VerificationResult {
         try {
             var certificate: Certificate =
                 CertificateFactory.getInstance("X.509").generateCertificate(certificateDer.inputStream())
             var publicKey: PublicKey = certificate.publicKey
-            var verifier = Signature.getInstance("SHA256withECDSA")
+            var verifier = Signature.getInstance(algorithm)
             verifier.initVerify(publicKey)
             verifier.update(payload)
             if (verifier.verify(signature)) {
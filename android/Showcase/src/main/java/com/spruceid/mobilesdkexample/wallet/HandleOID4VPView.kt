@@ -254,7 +254,7 @@ fun HandleOID4VPView(
                             permissionRequest!!.createPermissionResponse(
                                 lSelectedCredentials.value,
                                 it,
-                                ResponseOptions(false, false, false)
+                                ResponseOptions(false, false, false, emptyMap())
                             )
                         holder!!.submitPermissionResponse(permissionResponse!!)
                         val credentialPack =
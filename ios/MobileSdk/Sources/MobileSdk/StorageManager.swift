@@ -143,6 +143,40 @@ public class StorageManager: NSObject, StorageManagerInterface {
             // It's fine if the file isn't there.
         }
     }
+
+    /// Check whether a value is stored under a key, without decrypting it.
+    ///
+    /// - Parameters:
+    ///    - key: the name of the file
+    ///
+    /// - Returns: whether the file exists
+
+    public func contains(key: Key) async throws -> Bool {
+        guard let file = await path(file: key) else { return false }
+        return FileManager.default.fileExists(atPath: file.path)
+    }
+
+    /// Store several key/value pairs, one after the other.
+    ///
+    /// - Parameters:
+    ///    - entries: the key/value pairs to store
+
+    public func addMany(entries: [StorageEntry]) async throws {
+        for entry in entries {
+            try await add(key: entry.key, value: entry.value)
+        }
+    }
+
+    /// Remove several key/value pairs.
+    ///
+    /// - Parameters:
+    ///    - keys: the names of the files
+
+    public func removeMany(keys: [Key]) async throws {
+        for key in keys {
+            try await remove(key: key)
+        }
+    }
 }
 
 //
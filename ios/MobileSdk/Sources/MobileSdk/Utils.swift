@@ -1,8 +1,10 @@
 import Foundation
+import os
 import SpruceIDMobileSdkRs
 
 public final class RustLogger: LogWriter {
     private static var enabled: Bool = false
+    private static let log = os.Logger(subsystem: "com.spruceid.mobile.sdk", category: "RustLogger")
     private let buffer: Buffer = Buffer()
 
     public func writeToBuffer(message: Data) {
@@ -17,6 +19,18 @@ public final class RustLogger: LogWriter {
         }
     }
 
+    public func logRecord(level: String, target: String, message: String) {
+        guard RustLogger.enabled else { return }
+        let type: OSLogType
+        switch level {
+        case "ERROR": type = .error
+        case "WARN": type = .default
+        case "INFO": type = .info
+        default: type = .debug
+        }
+        RustLogger.log.log(level: type, "\(target, privacy: .public): \(message, privacy: .public)")
+    }
+
     public static func disable() {
         enabled = false
     }
@@ -70,6 +84,9 @@ extension CborValue {
             return .bool(bool)
         case .integer(let integer):
             return .number(Double(integer.toText())!)
+        case .bigInt(let string):
+            // Too large for a Double without losing precision, keep the decimal digits.
+            return .string(string)
         case .float(let double):
             return .number(double)
         case .bytes(let data):
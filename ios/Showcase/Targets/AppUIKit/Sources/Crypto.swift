@@ -16,4 +16,14 @@ class CryptoImpl: Crypto {
         }
         return .success
     }
-}
+
+    func k256Verify(certificateDer: Data, payload: Data, signature: Data) -> SpruceIDMobileSdkRs.VerificationResult {
+        // The Security framework does not support secp256k1 keys.
+        return .failure(cause: "secp256k1 signatures are not supported")
+    }
+
+    func ed25519Verify(certificateDer: Data, payload: Data, signature: Data) -> SpruceIDMobileSdkRs.VerificationResult {
+        // The Security framework does not support Ed25519 certificate keys.
+        return .failure(cause: "Ed25519 signatures are not supported")
+    }
+}
\ No newline at end of file
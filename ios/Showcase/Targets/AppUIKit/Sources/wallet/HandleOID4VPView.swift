@@ -199,7 +199,8 @@ struct HandleOID4VPView: View {
                                     responseOptions: ResponseOptions(
                                         shouldStripQuotes: false,
                                         forceArraySerialization: false,
-                                        removeVpPathPrefix: false
+                                        removeVpPathPrefix: false,
+                                        pathPrefixOverrides: [:]
                                     )
                                 )
                             _ = try await holder?.submitPermissionResponse(
@@ -1,7 +1,7 @@
-use cbor_ld::EncodeError;
-use json_syntax::Parse;
+use cbor_ld::{DecodeError, EncodeError};
+use json_syntax::{Parse, Print};
 use ssi::json_ld::{InvalidIri, IriBuf, NoLoader, RemoteDocument};
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 #[derive(Debug, uniffi::Error, thiserror::Error)]
 pub enum CborLdEncodingError {
@@ -10,6 +10,9 @@ pub enum CborLdEncodingError {
 
     #[error("CborLD encode error: {0}")]
     CborEncode(String),
+
+    #[error("CborLD decode error: {0}")]
+    CborDecode(String),
 }
 
 impl From<InvalidIri<String>> for CborLdEncodingError {
@@ -24,6 +27,12 @@ impl From<EncodeError> for CborLdEncodingError {
     }
 }
 
+impl From<DecodeError> for CborLdEncodingError {
+    fn from(value: DecodeError) -> Self {
+        Self::CborDecode(format!("cbor_ld::DecodeError: {value}"))
+    }
+}
+
 impl From<ssi::json_ld::syntax::parse::Error> for CborLdEncodingError {
     fn from(value: ssi::json_ld::syntax::parse::Error) -> Self {
         Self::JsonParse(format!("json_ld::syntax::parse::Error: {value}",))
@@ -38,28 +47,99 @@ pub async fn cbor_ld_encode_to_bytes(
     let credential = cbor_ld::JsonValue::from_str(&credential_str)?;
 
     let cborld = if let Some(map) = loader {
-        let loader = map
-            .into_iter()
-            .map(
-                |(k, v)| match (IriBuf::new(k), json_syntax::Value::parse_str(&v)) {
-                    (Ok(k), Ok((v, _))) => Ok((
-                        k.to_owned(),
-                        RemoteDocument::new(
-                            Some(k),
-                            Some("application/ld+json".parse().unwrap()),
-                            v,
-                        ),
-                    )),
-                    (Err(e), _) => Err(e.into()),
-                    (_, Err(e)) => Err(e.into()),
-                },
-            )
-            .collect::<Result<HashMap<IriBuf, RemoteDocument<IriBuf>>, CborLdEncodingError>>()?;
-
-        cbor_ld::encode_to_bytes(&credential, loader).await?
+        cbor_ld::encode_to_bytes(&credential, parse_contexts(map)?).await?
     } else {
         cbor_ld::encode_to_bytes(&credential, NoLoader).await?
     };
 
     Ok(cborld)
 }
+
+/// Parse JSON-LD contexts, keyed by their URL, into documents for a loader.
+fn parse_contexts(
+    contexts: HashMap<String, String>,
+) -> Result<HashMap<IriBuf, RemoteDocument<IriBuf>>, CborLdEncodingError> {
+    contexts
+        .into_iter()
+        .map(
+            |(k, v)| match (IriBuf::new(k), json_syntax::Value::parse_str(&v)) {
+                (Ok(k), Ok((v, _))) => Ok((
+                    k.to_owned(),
+                    RemoteDocument::new(Some(k), Some("application/ld+json".parse().unwrap()), v),
+                )),
+                (Err(e), _) => Err(e.into()),
+                (_, Err(e)) => Err(e.into()),
+            },
+        )
+        .collect()
+}
+
+/// A set of JSON-LD contexts, keyed by their URL, for encoding and decoding many CBOR-LD
+/// credentials.
+///
+/// The contexts are parsed once, when the registry is created, instead of on every call to
+/// [cbor_ld_encode_to_bytes].
+#[derive(uniffi::Object)]
+pub struct CborLdRegistry {
+    contexts: HashMap<IriBuf, RemoteDocument<IriBuf>>,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl CborLdRegistry {
+    #[uniffi::constructor]
+    pub fn new(contexts: HashMap<String, String>) -> Result<Arc<Self>, CborLdEncodingError> {
+        Ok(Arc::new(Self {
+            contexts: parse_contexts(contexts)?,
+        }))
+    }
+
+    /// Encode a JSON-LD credential as CBOR-LD.
+    pub async fn encode(&self, credential_str: String) -> Result<Vec<u8>, CborLdEncodingError> {
+        let credential = cbor_ld::JsonValue::from_str(&credential_str)?;
+        Ok(cbor_ld::encode_to_bytes(&credential, &self.contexts).await?)
+    }
+
+    /// Decode a CBOR-LD credential into JSON-LD.
+    pub async fn decode(&self, bytes: Vec<u8>) -> Result<String, CborLdEncodingError> {
+        let credential = cbor_ld::decode_from_bytes(&bytes, &self.contexts).await?;
+        Ok(credential.compact_print().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::context::default_ld_json_context;
+
+    fn alumni_credential(name: &str) -> String {
+        json!({
+            "@context": "https://examples.vcplayground.org/contexts/alumni/v2.json",
+            "@type": "AlumniCredential",
+            "name": name,
+            "alumniOf": "Example University",
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn registry_matches_one_shot_encoding() {
+        let registry = CborLdRegistry::new(default_ld_json_context()).unwrap();
+
+        for credential in [alumni_credential("Alice"), alumni_credential("Bob")] {
+            let encoded = registry.encode(credential.clone()).await.unwrap();
+            let one_shot =
+                cbor_ld_encode_to_bytes(credential.clone(), Some(default_ld_json_context()))
+                    .await
+                    .unwrap();
+            assert_eq!(encoded, one_shot);
+
+            let decoded = registry.decode(encoded).await.unwrap();
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&decoded).unwrap(),
+                serde_json::from_str::<serde_json::Value>(&credential).unwrap()
+            );
+        }
+    }
+}
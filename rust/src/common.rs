@@ -34,6 +34,91 @@ uniffi::custom_type!(Url, String, {
     lower: |url| url.to_string(),
 });
 
+/// A web origin, normalized so that different spellings of the same origin match.
+///
+/// Schemes and hosts are compared case-insensitively and default ports are implied, e.g.
+/// `HTTPS://Verifier.Example.com:443` is the same origin as `https://verifier.example.com`.
+/// Origins without a host, such as the `android:apk-key-hash:` origins of apps, are opaque
+/// and only match the exact same string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin(OriginKind);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OriginKind {
+    Tuple(url::Origin),
+    Opaque(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OriginError {
+    #[error("invalid origin '{0}'")]
+    Invalid(String),
+    #[error("origin '{0}' does not use HTTPS")]
+    NotHttps(String),
+    #[error("localhost origin '{0}' is not allowed")]
+    Localhost(String),
+}
+
+impl Origin {
+    pub fn parse(origin: &str) -> Result<Self, OriginError> {
+        let url = Url::parse(origin).map_err(|_| OriginError::Invalid(origin.to_string()))?;
+        Ok(match url.origin() {
+            tuple @ url::Origin::Tuple(..) => Self(OriginKind::Tuple(tuple)),
+            url::Origin::Opaque(_) => Self(OriginKind::Opaque(origin.to_string())),
+        })
+    }
+
+    /// Whether both origins are the same, after normalization.
+    pub fn matches(&self, other: &Origin) -> bool {
+        self == other
+    }
+
+    /// Whether the origin is `localhost`, or a loopback IP address.
+    pub fn is_localhost(&self) -> bool {
+        match &self.0 {
+            OriginKind::Tuple(url::Origin::Tuple(_, host, _)) => match host {
+                url::Host::Domain(domain) => {
+                    domain == "localhost" || domain.ends_with(".localhost")
+                }
+                url::Host::Ipv4(ip) => ip.is_loopback(),
+                url::Host::Ipv6(ip) => ip.is_loopback(),
+            },
+            _ => false,
+        }
+    }
+
+    /// Reject web origins that do not use HTTPS, and localhost origins unless
+    /// `allow_localhost` is set, in which case they may also use HTTP.
+    ///
+    /// Opaque origins are not web origins, and are always accepted.
+    pub fn require_secure(&self, allow_localhost: bool) -> Result<(), OriginError> {
+        let OriginKind::Tuple(url::Origin::Tuple(scheme, ..)) = &self.0 else {
+            return Ok(());
+        };
+
+        if self.is_localhost() {
+            return match allow_localhost {
+                true => Ok(()),
+                false => Err(OriginError::Localhost(self.to_string())),
+            };
+        }
+
+        match scheme.as_str() {
+            "https" => Ok(()),
+            _ => Err(OriginError::NotHttps(self.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            OriginKind::Tuple(origin) => write!(f, "{}", origin.ascii_serialization()),
+            OriginKind::Opaque(origin) => write!(f, "{origin}"),
+        }
+    }
+}
+
 uniffi::custom_newtype!(Key, String);
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -49,6 +134,48 @@ impl Key {
     pub fn strip_prefix(&self, prefix: &str) -> Option<String> {
         self.0.strip_prefix(prefix).map(ToOwned::to_owned)
     }
+
+    /// Create a new key for `id` in a namespace of the storage
+    pub fn namespaced(namespace: KeyNamespace, id: &str) -> Self {
+        Self::with_prefix(namespace.prefix(), id)
+    }
+
+    /// The namespace of the key, if it was created with [Key::namespaced]
+    pub fn namespace_of(&self) -> Option<KeyNamespace> {
+        KeyNamespace::ALL
+            .into_iter()
+            .find(|namespace| self.0.starts_with(namespace.prefix()))
+    }
+}
+
+/// The namespaces sharing a storage, which keep the keys of each store apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyNamespace {
+    Credential,
+    SigningKey,
+    Nonce,
+    Session,
+}
+
+impl KeyNamespace {
+    const ALL: [Self; 4] = [
+        Self::Credential,
+        Self::SigningKey,
+        Self::Nonce,
+        Self::Session,
+    ];
+
+    /// The prefix of the keys of the namespace.
+    ///
+    /// Prefixes end with a `.`, so that no prefix is the prefix of another.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Self::Credential => "Credential.",
+            Self::SigningKey => "SigningKey.",
+            Self::Nonce => "Nonce.",
+            Self::Session => "Session.",
+        }
+    }
 }
 
 impl From<Key> for String {
@@ -121,12 +248,71 @@ impl From<(u64, serde_cbor::Value)> for CborTag {
     }
 }
 
+/// The tag of a standard date/time string (RFC 8949 section 3.4.1).
+const DATE_TIME_TAG: u64 = 0;
+/// The tag of an epoch-based date/time (RFC 8949 section 3.4.2).
+const EPOCH_DATE_TIME_TAG: u64 = 1;
+/// The tag of an embedded CBOR data item (RFC 8949 section 3.4.5.1).
+const ENCODED_CBOR_TAG: u64 = 24;
+/// The tag of a full-date string (RFC 8943).
+const FULL_DATE_TAG: u64 = 1004;
+
+/// Tags are rendered according to their semantics: dates as RFC 3339 strings and embedded
+/// CBOR as its decoded value. Other tags, and tagged values that do not match the semantics
+/// of their tag, are rendered in diagnostic notation, e.g. `32(https://example.com)`.
+impl std::fmt::Display for CborTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+        match (self.id, self.value.as_ref()) {
+            (DATE_TIME_TAG, CborValue::Text(date))
+                if OffsetDateTime::parse(date, &Rfc3339).is_ok() =>
+            {
+                write!(f, "{date}")
+            }
+            (EPOCH_DATE_TIME_TAG, value) => {
+                let date = match value {
+                    CborValue::Integer(seconds) => i64::try_from(i128::from((**seconds).clone()))
+                        .ok()
+                        .and_then(|seconds| OffsetDateTime::from_unix_timestamp(seconds).ok()),
+                    CborValue::Float(seconds) => OffsetDateTime::from_unix_timestamp_nanos(
+                        (seconds * 1_000_000_000.0) as i128,
+                    )
+                    .ok(),
+                    _ => None,
+                };
+                match date.and_then(|date| date.format(&Rfc3339).ok()) {
+                    Some(date) => write!(f, "{date}"),
+                    None => write!(f, "{}({value})", self.id),
+                }
+            }
+            (ENCODED_CBOR_TAG, CborValue::Bytes(bytes)) => {
+                match serde_cbor::from_slice::<serde_cbor::Value>(bytes) {
+                    Ok(value) => write!(f, "{}", CborValue::from(value)),
+                    Err(_) => write!(f, "{}({})", self.id, self.value),
+                }
+            }
+            (FULL_DATE_TAG, CborValue::Text(date))
+                if time::Date::parse(
+                    date,
+                    time::macros::format_description!("[year]-[month]-[day]"),
+                )
+                .is_ok() =>
+            {
+                write!(f, "{date}")
+            }
+            (id, value) => write!(f, "{id}({value})"),
+        }
+    }
+}
+
 impl std::fmt::Display for CborValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             CborValue::Null => write!(f, ""),
             CborValue::Bool(v) => write!(f, "{}", v),
             CborValue::Integer(cbor_integer) => write!(f, "{}", cbor_integer.to_text()),
+            CborValue::BigInt(v) => write!(f, "{}", v),
             CborValue::Float(v) => write!(f, "{}", v),
             CborValue::Bytes(items) => items.iter().enumerate().try_fold((), |_, (i, item)| {
                 if i > 0 {
@@ -156,14 +342,15 @@ impl std::fmt::Display for CborValue {
                 })?;
                 write!(f, "}}")
             }
-            CborValue::Tag(cbor_tag) => write!(f, "{}", cbor_tag.value()),
+            CborValue::Tag(cbor_tag) => write!(f, "{}", cbor_tag),
         }
     }
 }
 
 #[derive(uniffi::Object, Debug, Clone)]
 pub struct CborInteger {
-    bytes: Vec<u8>,
+    /// The big-endian bytes of the integer.
+    bytes: [u8; 16],
 }
 
 #[uniffi::export]
@@ -196,31 +383,27 @@ impl CborInteger {
 impl From<i128> for CborInteger {
     fn from(value: i128) -> Self {
         Self {
-            bytes: vec![
-                (value >> 120) as u8,
-                (value >> 112) as u8,
-                (value >> 104) as u8,
-                (value >> 96) as u8,
-                (value >> 88) as u8,
-                (value >> 80) as u8,
-                (value >> 72) as u8,
-                (value >> 64) as u8,
-                (value >> 56) as u8,
-                (value >> 48) as u8,
-                (value >> 40) as u8,
-                (value >> 32) as u8,
-                (value >> 24) as u8,
-                (value >> 16) as u8,
-                (value >> 8) as u8,
-                (value) as u8,
-            ],
+            bytes: value.to_be_bytes(),
         }
     }
 }
 
+impl TryFrom<Vec<u8>> for CborInteger {
+    type Error = CborValueError;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        let len = bytes.len() as u64;
+        Ok(Self {
+            bytes: bytes
+                .try_into()
+                .map_err(|_| CborValueError::InvalidIntegerBytes(len))?,
+        })
+    }
+}
+
 impl From<CborInteger> for i128 {
     fn from(value: CborInteger) -> Self {
-        i128::from_be_bytes(value.bytes.try_into().unwrap_or([0; 16]))
+        i128::from_be_bytes(value.bytes)
     }
 }
 
@@ -229,6 +412,9 @@ pub enum CborValue {
     Null,
     Bool(bool),
     Integer(Arc<CborInteger>),
+    /// An integer outside the range of [CborInteger], in decimal, e.g. from a bignum
+    /// (tag 2 or 3).
+    BigInt(String),
     Float(f64),
     Bytes(Vec<u8>),
     Text(String),
@@ -254,12 +440,65 @@ impl From<serde_cbor::Value> for CborValue {
                     .map(|(k, v)| (CborValue::from(k).to_string(), v.into()))
                     .collect::<HashMap<_, CborValue>>(),
             ),
+            serde_cbor::Value::Tag(id @ (BIGNUM_TAG | NEGATIVE_BIGNUM_TAG), value) => {
+                match *value {
+                    serde_cbor::Value::Bytes(magnitude) => Self::from_bignum(id, &magnitude),
+                    value => Self::Tag(Arc::new((id, value).into())),
+                }
+            }
             serde_cbor::Value::Tag(id, value) => Self::Tag(Arc::new((id, *value).into())),
             _ => Self::Null,
         }
     }
 }
 
+/// The tag of an unsigned bignum (RFC 8949 section 3.4.3).
+const BIGNUM_TAG: u64 = 2;
+/// The tag of a negative bignum, whose value is -1 minus the tagged magnitude.
+const NEGATIVE_BIGNUM_TAG: u64 = 3;
+
+impl CborValue {
+    /// Decode the big-endian magnitude of a bignum, as an [CborValue::Integer] if it fits
+    /// in an `i128` and as a [CborValue::BigInt] otherwise.
+    fn from_bignum(tag: u64, magnitude: &[u8]) -> Self {
+        let magnitude = num_bigint::BigInt::from(num_bigint::BigUint::from_bytes_be(magnitude));
+        let value = if tag == NEGATIVE_BIGNUM_TAG {
+            -1 - magnitude
+        } else {
+            magnitude
+        };
+
+        match i128::try_from(&value) {
+            Ok(value) => Self::Integer(Arc::new(value.into())),
+            Err(_) => Self::BigInt(value.to_string()),
+        }
+    }
+
+    /// The value of an integer, however large.
+    fn big_integer(&self) -> Option<num_bigint::BigInt> {
+        match self {
+            CborValue::Integer(v) => Some(i128::from(v.deref().clone()).into()),
+            CborValue::BigInt(v) => v.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Encode an integer as a bignum, for those that do not fit in a CBOR integer.
+fn bignum(value: num_bigint::BigInt) -> serde_cbor::Value {
+    let (tag, magnitude) = if value.sign() == num_bigint::Sign::Minus {
+        (NEGATIVE_BIGNUM_TAG, -1 - value)
+    } else {
+        (BIGNUM_TAG, value)
+    };
+    serde_cbor::Value::Tag(
+        tag,
+        Box::new(serde_cbor::Value::Bytes(
+            magnitude.magnitude().to_bytes_be(),
+        )),
+    )
+}
+
 impl PartialEq for CborValue {
     fn eq(&self, other: &CborValue) -> bool {
         self.cmp(other) == Ordering::Equal
@@ -283,8 +522,8 @@ impl Ord for CborValue {
         match (self, other) {
             (Null, Null) => Ordering::Equal,
             (Bool(a), Bool(b)) => a.cmp(b),
-            (Integer(a), Integer(b)) => {
-                i128::from(a.deref().clone()).cmp(&i128::from(b.deref().clone()))
+            (Integer(_) | BigInt(_), Integer(_) | BigInt(_)) => {
+                self.big_integer().cmp(&other.big_integer())
             }
             (Float(a), Float(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
             (Bytes(a), Bytes(b)) => a.cmp(b),
@@ -310,6 +549,13 @@ impl CborValue {
                     1
                 }
             }
+            BigInt(v) => {
+                if v.starts_with('-') {
+                    1
+                } else {
+                    0
+                }
+            }
             Tag(_) => 6,
             Float(_) => 7,
             Bytes(_) => 2,
@@ -320,6 +566,250 @@ impl CborValue {
     }
 }
 
+#[derive(Debug, uniffi::Error, thiserror::Error)]
+pub enum CborValueError {
+    #[error("failed to encode CBOR: {0}")]
+    Encoding(String),
+    #[error("not a decimal integer: {0}")]
+    InvalidInteger(String),
+    #[error("expected the 16 big-endian bytes of an integer, found {0} bytes")]
+    InvalidIntegerBytes(u64),
+}
+
+/// Build a [CborValue::ItemMap] with text keys.
+#[uniffi::export]
+pub fn cbor_new_map(entries: HashMap<String, CborValue>) -> CborValue {
+    CborValue::ItemMap(entries)
+}
+
+#[uniffi::export]
+pub fn cbor_new_array(values: Vec<CborValue>) -> CborValue {
+    CborValue::Array(values)
+}
+
+/// Build an integer from its decimal representation, e.g. `-42`.
+///
+/// Integers outside the range of [CborInteger] are built as a [CborValue::BigInt].
+#[uniffi::export]
+pub fn cbor_new_integer(value: String) -> Result<CborValue, CborValueError> {
+    if let Ok(integer) = value.parse::<i128>() {
+        return Ok(CborValue::Integer(Arc::new(integer.into())));
+    }
+    value
+        .parse::<num_bigint::BigInt>()
+        .map(|integer| CborValue::BigInt(integer.to_string()))
+        .map_err(|_| CborValueError::InvalidInteger(value))
+}
+
+#[uniffi::export]
+pub fn cbor_new_text(value: String) -> CborValue {
+    CborValue::Text(value)
+}
+
+#[uniffi::export]
+pub fn cbor_new_bytes(value: Vec<u8>) -> CborValue {
+    CborValue::Bytes(value)
+}
+
+#[uniffi::export]
+pub fn cbor_new_bool(value: bool) -> CborValue {
+    CborValue::Bool(value)
+}
+
+/// Serialize a [CborValue], see [CborValue::to_canonical_cbor].
+#[uniffi::export]
+pub fn cbor_to_canonical_cbor(value: CborValue) -> Result<Vec<u8>, CborValueError> {
+    value.to_canonical_cbor()
+}
+
+impl CborValue {
+    /// Serialize the value with the core deterministic encoding of RFC 8949 (section 4.2.1),
+    /// as used when recomputing the digests of mdoc data elements.
+    ///
+    /// Integers and lengths use their shortest form, and map entries are sorted by the
+    /// bytewise order of their encoded keys. Map keys are encoded as text strings, as
+    /// [CborValue::ItemMap] does not retain their original type.
+    pub fn to_canonical_cbor(&self) -> Result<Vec<u8>, CborValueError> {
+        let mut bytes = Vec::new();
+        write_canonical_cbor(&serde_cbor::Value::from(self.clone()), &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl From<CborValue> for serde_cbor::Value {
+    fn from(value: CborValue) -> Self {
+        match value {
+            CborValue::Null => Self::Null,
+            CborValue::Bool(b) => Self::Bool(b),
+            CborValue::Integer(v) => {
+                let v = i128::from(v.deref().clone());
+                // CBOR integers range from -2^64 to 2^64 - 1.
+                if v > u64::MAX as i128 || v < -1 - u64::MAX as i128 {
+                    bignum(v.into())
+                } else {
+                    Self::Integer(v)
+                }
+            }
+            CborValue::BigInt(v) => v.parse().map(bignum).unwrap_or_else(|_| Self::Text(v)),
+            CborValue::Float(v) => Self::Float(v),
+            CborValue::Bytes(b) => Self::Bytes(b),
+            CborValue::Text(s) => Self::Text(s),
+            CborValue::Array(a) => Self::Array(a.into_iter().map(Into::into).collect()),
+            CborValue::ItemMap(m) => Self::Map(
+                m.into_iter()
+                    .map(|(k, v)| (Self::Text(k), v.into()))
+                    .collect(),
+            ),
+            CborValue::Tag(tag) => Self::Tag(tag.id, Box::new(tag.value().into())),
+        }
+    }
+}
+
+fn write_canonical_cbor(
+    value: &serde_cbor::Value,
+    bytes: &mut Vec<u8>,
+) -> Result<(), CborValueError> {
+    match value {
+        serde_cbor::Value::Array(values) => {
+            write_cbor_header(4, values.len() as u64, bytes);
+            for value in values {
+                write_canonical_cbor(value, bytes)?;
+            }
+        }
+        serde_cbor::Value::Map(map) => {
+            let mut entries = map
+                .iter()
+                .map(|(key, value)| {
+                    let mut key_bytes = Vec::new();
+                    write_canonical_cbor(key, &mut key_bytes)?;
+                    Ok((key_bytes, value))
+                })
+                .collect::<Result<Vec<_>, CborValueError>>()?;
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            write_cbor_header(5, entries.len() as u64, bytes);
+            for (key_bytes, value) in entries {
+                bytes.extend(key_bytes);
+                write_canonical_cbor(value, bytes)?;
+            }
+        }
+        serde_cbor::Value::Tag(id, value) => {
+            write_cbor_header(6, *id, bytes);
+            write_canonical_cbor(value, bytes)?;
+        }
+        // serde_cbor encodes scalars in their shortest form.
+        value => bytes.extend(
+            serde_cbor::to_vec(value).map_err(|e| CborValueError::Encoding(e.to_string()))?,
+        ),
+    }
+    Ok(())
+}
+
+/// Write the initial byte and argument of a data item, using the shortest argument.
+fn write_cbor_header(major_type: u8, argument: u64, bytes: &mut Vec<u8>) {
+    let major_type = major_type << 5;
+    match argument {
+        0..=23 => bytes.push(major_type | argument as u8),
+        24..=0xff => bytes.extend([major_type | 24, argument as u8]),
+        0x100..=0xffff => {
+            bytes.push(major_type | 25);
+            bytes.extend((argument as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            bytes.push(major_type | 26);
+            bytes.extend((argument as u32).to_be_bytes());
+        }
+        _ => {
+            bytes.push(major_type | 27);
+            bytes.extend(argument.to_be_bytes());
+        }
+    }
+}
+
+/// A leaf of a [CborValue] tree, addressed by its path from the root.
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct CborEntry {
+    /// The path to the leaf, with map keys separated by `.` and array indices
+    /// in brackets, e.g. `driving_privileges[0].vehicle_category_code`.
+    /// The path of a root that is itself a leaf is empty.
+    pub path: String,
+    /// The leaf value. Empty arrays and maps are reported as leaves.
+    pub value: CborValue,
+}
+
+/// Flatten a [CborValue] into its leaves, so it can be rendered as a
+/// key/value table without recursing over the enum.
+///
+/// Map entries are ordered by key and tags are transparent.
+#[uniffi::export]
+pub fn cbor_to_entries(value: CborValue) -> Vec<CborEntry> {
+    let mut entries = Vec::new();
+    collect_cbor_entries(String::new(), &value, &mut entries);
+    entries
+}
+
+/// Get the value at `path` in a [CborValue], see [CborValue::get_path].
+#[uniffi::export]
+pub fn cbor_get_path(value: CborValue, path: Vec<String>) -> Option<CborValue> {
+    value.get_path(path)
+}
+
+impl CborValue {
+    /// Get the value at `path`, traversing maps by key and arrays by index.
+    ///
+    /// Tags are transparent. Returns `None` if a segment is missing, or is not a valid index
+    /// into an array, or if a segment addresses into a value that is neither a map nor an
+    /// array.
+    pub fn get_path(&self, path: Vec<String>) -> Option<CborValue> {
+        path.iter()
+            .try_fold(self, |value, segment| value.untagged().child(segment))
+            .cloned()
+    }
+
+    fn child(&self, segment: &str) -> Option<&CborValue> {
+        match self {
+            CborValue::ItemMap(map) => map.get(segment),
+            CborValue::Array(values) => values.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+
+    /// The innermost value of nested tags.
+    fn untagged(&self) -> &CborValue {
+        match self {
+            CborValue::Tag(tag) => tag.value.untagged(),
+            value => value,
+        }
+    }
+}
+
+fn collect_cbor_entries(path: String, value: &CborValue, entries: &mut Vec<CborEntry>) {
+    match value {
+        CborValue::Array(values) if !values.is_empty() => {
+            for (i, value) in values.iter().enumerate() {
+                collect_cbor_entries(format!("{path}[{i}]"), value, entries);
+            }
+        }
+        CborValue::ItemMap(map) if !map.is_empty() => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            for key in keys {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                collect_cbor_entries(child_path, &map[key], entries);
+            }
+        }
+        CborValue::Tag(tag) => collect_cbor_entries(path, &tag.value, entries),
+        value => entries.push(CborEntry {
+            path,
+            value: value.clone(),
+        }),
+    }
+}
+
 // CBOR key constants - generic names for reusability
 pub mod cbor_keys {
     // Standard CBOR claims
@@ -410,10 +900,84 @@ impl CborKeyMapper {
     }
 }
 
+/// The difference between the device clock and `timestamp`, in seconds.
+///
+/// Positive when the device clock is ahead of the timestamp. Reported on time-based
+/// validation errors, so that apps can suggest checking the device clock.
+pub(crate) fn clock_skew_seconds(timestamp: time::OffsetDateTime) -> i64 {
+    (time::OffsetDateTime::now_utc() - timestamp).whole_seconds()
+}
+
 #[cfg(test)]
 mod tests {
+    use rstest::rstest;
+
     use super::*;
 
+    #[rstest]
+    #[case::same("https://verifier.example.com", "https://verifier.example.com")]
+    #[case::host_case("https://Verifier.Example.COM", "https://verifier.example.com")]
+    #[case::scheme_case("HTTPS://verifier.example.com", "https://verifier.example.com")]
+    #[case::default_port("https://verifier.example.com:443", "https://verifier.example.com")]
+    #[case::trailing_slash("https://verifier.example.com/", "https://verifier.example.com")]
+    #[case::app("android:apk-key-hash:abc", "android:apk-key-hash:abc")]
+    fn origins_match(#[case] a: &str, #[case] b: &str) {
+        assert!(Origin::parse(a)
+            .unwrap()
+            .matches(&Origin::parse(b).unwrap()));
+    }
+
+    #[rstest]
+    #[case::scheme("http://verifier.example.com", "https://verifier.example.com")]
+    #[case::port("https://verifier.example.com:8443", "https://verifier.example.com")]
+    #[case::subdomain("https://www.verifier.example.com", "https://verifier.example.com")]
+    #[case::app_case("android:apk-key-hash:ABC", "android:apk-key-hash:abc")]
+    fn origins_do_not_match(#[case] a: &str, #[case] b: &str) {
+        assert!(!Origin::parse(a)
+            .unwrap()
+            .matches(&Origin::parse(b).unwrap()));
+    }
+
+    #[rstest]
+    #[case::https("https://verifier.example.com", false, true)]
+    #[case::http("http://verifier.example.com", false, false)]
+    #[case::http_even_with_localhost_allowed("http://verifier.example.com", true, false)]
+    #[case::localhost("https://localhost:8080", false, false)]
+    #[case::allowed_localhost("http://localhost:8080", true, true)]
+    #[case::loopback_ip("http://127.0.0.1", false, false)]
+    #[case::app("android:apk-key-hash:abc", false, true)]
+    fn secure_origins(#[case] origin: &str, #[case] allow_localhost: bool, #[case] secure: bool) {
+        let result = Origin::parse(origin)
+            .unwrap()
+            .require_secure(allow_localhost);
+
+        assert_eq!(result.is_ok(), secure, "{result:?}");
+    }
+
+    #[test]
+    fn key_namespaces_do_not_collide() {
+        for namespace in KeyNamespace::ALL {
+            let key = Key::namespaced(namespace, "id");
+            assert_eq!(key.namespace_of(), Some(namespace));
+
+            for other in KeyNamespace::ALL
+                .into_iter()
+                .filter(|other| *other != namespace)
+            {
+                assert_eq!(key.strip_prefix(other.prefix()), None);
+                assert_eq!(
+                    Key::namespaced(other, namespace.prefix()).namespace_of(),
+                    Some(other)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn key_without_namespace() {
+        assert_eq!(Key::from("jwk").namespace_of(), None);
+    }
+
     #[test]
     fn test_cbor_integer_from_i128() {
         let test_cases = vec![
@@ -469,6 +1033,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cbor_integer_malformed_bytes() {
+        assert!(matches!(
+            CborInteger::try_from(vec![1, 2, 3]),
+            Err(CborValueError::InvalidIntegerBytes(3))
+        ));
+    }
+
     #[test]
     fn test_cbor_value_ordering() {
         // Test major type ordering
@@ -538,7 +1110,103 @@ mod tests {
             id: 1,
             value: Box::new(CborValue::Text("tagged".into())),
         };
-        assert_eq!(CborValue::Tag(Arc::new(tag)).to_string(), "tagged");
+        assert_eq!(CborValue::Tag(Arc::new(tag)).to_string(), "1(tagged)");
+    }
+
+    #[rstest]
+    #[case::date_time(
+        0,
+        serde_cbor::Value::Text("2024-01-02T03:04:05Z".into()),
+        "2024-01-02T03:04:05Z"
+    )]
+    #[case::invalid_date_time(0, serde_cbor::Value::Text("yesterday".into()), "0(yesterday)")]
+    #[case::epoch_date_time(1, serde_cbor::Value::Integer(1704164645), "2024-01-02T03:04:05Z")]
+    #[case::fractional_epoch_date_time(
+        1,
+        serde_cbor::Value::Float(1704164645.5),
+        "2024-01-02T03:04:05.5Z"
+    )]
+    #[case::encoded_cbor(
+        24,
+        serde_cbor::Value::Bytes(serde_cbor::to_vec(&serde_cbor::Value::Text("embedded".into())).unwrap()),
+        "embedded"
+    )]
+    #[case::nested_encoded_cbor(
+        24,
+        serde_cbor::Value::Bytes(
+            serde_cbor::to_vec(&serde_cbor::Value::Tag(
+                1004,
+                Box::new(serde_cbor::Value::Text("2024-01-02".into()))
+            ))
+            .unwrap()
+        ),
+        "2024-01-02"
+    )]
+    #[case::full_date(1004, serde_cbor::Value::Text("2024-01-02".into()), "2024-01-02")]
+    #[case::invalid_full_date(
+        1004,
+        serde_cbor::Value::Text("2024-01-02T03:04:05Z".into()),
+        "1004(2024-01-02T03:04:05Z)"
+    )]
+    #[case::unknown(32, serde_cbor::Value::Text("https://example.com".into()), "32(https://example.com)")]
+    fn test_tag_to_string(#[case] id: u64, #[case] value: serde_cbor::Value, #[case] text: &str) {
+        let value = CborValue::from(serde_cbor::Value::Tag(id, Box::new(value)));
+
+        assert_eq!(value.to_string(), text);
+    }
+
+    #[rstest]
+    #[case::beyond_i128_max(
+        BIGNUM_TAG,
+        vec![1; 17],
+        "341616807575530379006368233343265341697"
+    )]
+    #[case::beyond_i128_min(
+        NEGATIVE_BIGNUM_TAG,
+        vec![0xff; 17],
+        "-87112285931760246646623899502532662132736"
+    )]
+    fn test_bignum_beyond_i128(#[case] tag: u64, #[case] magnitude: Vec<u8>, #[case] text: &str) {
+        let value = CborValue::from(serde_cbor::Value::Tag(
+            tag,
+            Box::new(serde_cbor::Value::Bytes(magnitude.clone())),
+        ));
+
+        assert_eq!(value, CborValue::BigInt(text.to_string()));
+        assert_eq!(value.to_string(), text);
+        assert_eq!(
+            serde_cbor::Value::from(value),
+            serde_cbor::Value::Tag(tag, Box::new(serde_cbor::Value::Bytes(magnitude)))
+        );
+    }
+
+    #[rstest]
+    #[case::positive(BIGNUM_TAG, vec![0x01, 0x00], 256)]
+    #[case::negative(NEGATIVE_BIGNUM_TAG, vec![0x01, 0x00], -257)]
+    #[case::i128_max(BIGNUM_TAG, i128::MAX.to_be_bytes().to_vec(), i128::MAX)]
+    fn test_bignum_within_i128(
+        #[case] tag: u64,
+        #[case] magnitude: Vec<u8>,
+        #[case] expected: i128,
+    ) {
+        let value = CborValue::from(serde_cbor::Value::Tag(
+            tag,
+            Box::new(serde_cbor::Value::Bytes(magnitude)),
+        ));
+
+        let CborValue::Integer(integer) = &value else {
+            panic!("bignum was not decoded as an integer: {value:?}");
+        };
+        assert_eq!(i128::from(integer.deref().clone()), expected);
+    }
+
+    #[test]
+    fn test_bignum_ordering() {
+        let big = CborValue::BigInt(format!("{}0", i128::MAX));
+        let negative_big = CborValue::BigInt(format!("{}0", i128::MIN));
+
+        assert!(CborValue::Integer(Arc::new(i128::MAX.into())) < big);
+        assert!(negative_big < CborValue::Integer(Arc::new(i128::MIN.into())));
     }
 
     #[test]
@@ -602,4 +1270,181 @@ mod tests {
         assert_eq!(cbor_keys::NOT_BEFORE, 5);
         assert_eq!(cbor_keys::ISSUED, 6);
     }
+
+    #[test]
+    fn test_cbor_to_entries() {
+        let integer = |i: i128| CborValue::Integer(Arc::new(i.into()));
+
+        let privilege = CborValue::ItemMap(HashMap::from([
+            (
+                "vehicle_category_code".to_string(),
+                CborValue::Text("A".into()),
+            ),
+            (
+                "issue_date".to_string(),
+                CborValue::Text("2020-01-01".into()),
+            ),
+        ]));
+        let value = CborValue::ItemMap(HashMap::from([
+            ("family_name".to_string(), CborValue::Text("Doe".into())),
+            (
+                "driving_privileges".to_string(),
+                CborValue::Array(vec![privilege, integer(2)]),
+            ),
+            (
+                "address".to_string(),
+                CborValue::ItemMap(HashMap::from([(
+                    "zip".to_string(),
+                    CborValue::Tag(Arc::new(CborTag {
+                        id: 24,
+                        value: Box::new(integer(90210)),
+                    })),
+                )])),
+            ),
+            ("aliases".to_string(), CborValue::Array(vec![])),
+        ]));
+
+        let entries: Vec<(String, CborValue)> = cbor_to_entries(value)
+            .into_iter()
+            .map(|entry| (entry.path, entry.value))
+            .collect();
+
+        assert_eq!(
+            entries,
+            vec![
+                ("address.zip".to_string(), integer(90210)),
+                ("aliases".to_string(), CborValue::Array(vec![])),
+                (
+                    "driving_privileges[0].issue_date".to_string(),
+                    CborValue::Text("2020-01-01".into())
+                ),
+                (
+                    "driving_privileges[0].vehicle_category_code".to_string(),
+                    CborValue::Text("A".into())
+                ),
+                ("driving_privileges[1]".to_string(), integer(2)),
+                ("family_name".to_string(), CborValue::Text("Doe".into())),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::map_key(&["family_name"], Some(CborValue::Text("Doe".into())))]
+    #[case::nested_map(&["address", "city"], Some(CborValue::Text("Springfield".into())))]
+    #[case::array_index(
+        &["driving_privileges", "1", "vehicle_category_code"],
+        Some(CborValue::Text("B".into()))
+    )]
+    #[case::through_tag(&["address", "zip", "code"], Some(CborValue::Text("90210".into())))]
+    #[case::missing_key(&["given_name"], None)]
+    #[case::index_out_of_range(&["driving_privileges", "2"], None)]
+    #[case::non_numeric_index(&["driving_privileges", "first"], None)]
+    #[case::into_text(&["family_name", "0"], None)]
+    fn cbor_get_path_traverses_maps_and_arrays(
+        #[case] path: &[&str],
+        #[case] expected: Option<CborValue>,
+    ) {
+        let privilege = |code: &str| {
+            CborValue::ItemMap(HashMap::from([(
+                "vehicle_category_code".to_string(),
+                CborValue::Text(code.into()),
+            )]))
+        };
+        let value = CborValue::ItemMap(HashMap::from([
+            ("family_name".to_string(), CborValue::Text("Doe".into())),
+            (
+                "driving_privileges".to_string(),
+                CborValue::Array(vec![privilege("A"), privilege("B")]),
+            ),
+            (
+                "address".to_string(),
+                CborValue::ItemMap(HashMap::from([
+                    ("city".to_string(), CborValue::Text("Springfield".into())),
+                    (
+                        "zip".to_string(),
+                        CborValue::Tag(Arc::new(CborTag {
+                            id: 24,
+                            value: Box::new(CborValue::ItemMap(HashMap::from([(
+                                "code".to_string(),
+                                CborValue::Text("90210".into()),
+                            )]))),
+                        })),
+                    ),
+                ])),
+            ),
+        ]));
+
+        let path = path.iter().map(ToString::to_string).collect();
+
+        assert_eq!(cbor_get_path(value, path), expected);
+    }
+
+    #[test]
+    fn test_cbor_to_entries_leaf_root() {
+        assert_eq!(
+            cbor_to_entries(CborValue::Bool(true)),
+            vec![CborEntry {
+                path: String::new(),
+                value: CborValue::Bool(true),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cbor_value_to_canonical_cbor() {
+        let value = CborValue::ItemMap(HashMap::from([
+            ("b".to_string(), CborValue::Integer(Arc::new(2.into()))),
+            ("aa".to_string(), CborValue::Integer(Arc::new(500.into()))),
+            ("a".to_string(), CborValue::Integer(Arc::new((-1).into()))),
+        ]));
+
+        assert_eq!(
+            value.to_canonical_cbor().unwrap(),
+            [
+                0xa3, // map(3)
+                0x61, b'a', 0x20, // "a": -1
+                0x61, b'b', 0x02, // "b": 2
+                0x62, b'a', b'a', 0x19, 0x01, 0xf4, // "aa": 500
+            ]
+        );
+    }
+
+    #[test]
+    fn build_nested_cbor_value() {
+        let value = cbor_new_map(HashMap::from([
+            ("ok".to_string(), cbor_new_bool(true)),
+            (
+                "items".to_string(),
+                cbor_new_array(vec![
+                    cbor_new_integer("-1".to_string()).unwrap(),
+                    cbor_new_text("a".to_string()),
+                    cbor_new_bytes(vec![0xff]),
+                ]),
+            ),
+        ]));
+
+        assert_eq!(
+            cbor_to_canonical_cbor(value).unwrap(),
+            [
+                0xa2, // map(2)
+                0x62, b'o', b'k', 0xf5, // "ok": true
+                0x65, b'i', b't', b'e', b'm', b's', 0x83, // "items": array(3)
+                0x20, // -1
+                0x61, b'a', // "a"
+                0x41, 0xff, // h'ff'
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::small("500", Some(CborValue::Integer(Arc::new(500.into()))))]
+    #[case::negative("-42", Some(CborValue::Integer(Arc::new((-42).into()))))]
+    #[case::big(
+        "340282366920938463463374607431768211456",
+        Some(CborValue::BigInt("340282366920938463463374607431768211456".to_string()))
+    )]
+    #[case::not_an_integer("4.2", None)]
+    fn new_integer_from_text(#[case] text: &str, #[case] expected: Option<CborValue>) {
+        assert_eq!(cbor_new_integer(text.to_string()).ok(), expected);
+    }
 }
@@ -1,26 +1,76 @@
 use super::Credential;
+use crate::common::clock_skew_seconds;
 use crate::crypto::KeyAlias;
-use crate::verifier::crypto::{CoseP256Verifier, Crypto};
+use crate::did::{ConfiguredDidResolver, DidResolverConfig};
+use crate::verifier::crypto::{CoseAlgorithm, CoseVerifier, Crypto};
 use crate::verifier::helpers;
+use crate::verifier::offline::OfflinePolicy;
+use crate::verifier::outcome::{TrustMechanism, VerificationReport, VerificationWarning};
 use crate::{trusted_roots, CborKeyMapper};
 use crate::{CborValue, CredentialType};
-use cose_rs::cwt::claim::ExpirationTime;
 use cose_rs::{cwt::ClaimsSet, CoseSign1};
 use num_bigint::BigUint;
 use num_traits::Num;
-use ssi::dids::{AnyDidMethod, VerificationMethodDIDResolver};
+use ssi::dids::{DIDResolver, VerificationMethodDIDResolver};
 use ssi::jwk::JWKResolver;
 use ssi::prelude::AnyJwkMethod;
 use std::collections::HashMap;
 
 use std::sync::Arc;
-use time::{Date, OffsetDateTime};
+use time::{format_description::well_known::Rfc3339, Date, Duration, OffsetDateTime, UtcOffset};
 use time_macros::format_description;
 use uuid::Uuid;
 
 use cose_rs::sign1::VerificationResult;
 use uniffi::deps::anyhow::anyhow;
-use x509_cert::{certificate::CertificateInner, der::Encode};
+use x509_cert::{certificate::CertificateInner, der::Encode, Certificate};
+
+/// Clock leeway from a caller-supplied number of seconds, saturating on overflow.
+pub(crate) fn leeway_from_secs(leeway_secs: u64) -> Duration {
+    Duration::seconds(leeway_secs.try_into().unwrap_or(i64::MAX))
+}
+
+/// Upper bound on the size of a decompressed CWT, to reject decompression bombs.
+const MAX_DECOMPRESSED_CWT_SIZE: usize = 1024 * 1024;
+
+/// Prefix of base45-encoded CWT payloads, as used in health certificate QR codes.
+pub const HC1_PREFIX: &str = "HC1:";
+
+/// Whether the bytes start like a CBOR-encoded COSE_Sign1, either tagged (18) or as a bare
+/// array of four elements.
+pub(crate) fn is_cose_sign1(bytes: &[u8]) -> bool {
+    matches!(bytes.first(), Some(0xd2 | 0x84))
+}
+
+/// Compress an encoded CWT with raw DEFLATE, as expected by [Cwt::new_from_base10].
+///
+/// `level` ranges from 0 (no compression) to 10 (smallest output, slowest), higher levels are
+/// treated as 10. Higher levels help fitting large CWTs into a QR code.
+#[uniffi::export]
+pub fn compress_cwt(cwt_bytes: Vec<u8>, level: u8) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec(&cwt_bytes, level.min(10))
+}
+
+/// Encode a compressed CWT as a base10 barcode payload, with the `9` multibase prefix, as
+/// decoded by [Cwt::new_from_base10].
+#[uniffi::export]
+pub fn encode_base10(compressed: Vec<u8>) -> String {
+    format!("9{}", BigUint::from_bytes_be(&compressed).to_str_radix(10))
+}
+
+/// The base45 alphabet, as defined in RFC 9285.
+const BASE45_ALPHABET: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// A claim of a [Cwt], as returned by [Cwt::claims_ordered].
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct CwtClaim {
+    /// The human-readable name of the claim, as used as key by [Cwt::claims].
+    pub key_label: String,
+    /// The claim key as issued, either a [CborValue::Integer] or a [CborValue::Text].
+    pub raw_key: CborValue,
+    /// The claim value, with date claims normalized as in [Cwt::claims].
+    pub value: CborValue,
+}
 
 #[derive(uniffi::Object, Debug, Clone)]
 pub struct Cwt {
@@ -28,9 +78,36 @@ pub struct Cwt {
     payload: Vec<u8>,
     cwt: CoseSign1,
     claims: ClaimsSet,
+    /// The claim keys in the order they were issued, which [ClaimsSet] does not keep.
+    claim_order: Vec<ClaimKey>,
     key_alias: Option<KeyAlias>,
 }
 
+/// A claim key, comparable with the keys of a [ClaimsSet].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ClaimKey {
+    Integer(i128),
+    Text(String),
+}
+
+impl From<&cose_rs::cwt::Key> for ClaimKey {
+    fn from(key: &cose_rs::cwt::Key) -> Self {
+        match key {
+            cose_rs::cwt::Key::Text(v) => Self::Text(v.to_string()),
+            cose_rs::cwt::Key::Integer(v) => Self::Integer(*v),
+        }
+    }
+}
+
+impl From<ClaimKey> for CborValue {
+    fn from(key: ClaimKey) -> Self {
+        match key {
+            ClaimKey::Integer(v) => CborValue::Integer(Arc::new(v.into())),
+            ClaimKey::Text(v) => CborValue::Text(v),
+        }
+    }
+}
+
 #[uniffi::export]
 impl Cwt {
     #[uniffi::constructor]
@@ -39,6 +116,14 @@ impl Cwt {
         Ok(Self::from_base10(id, payload.as_bytes().to_vec())?.into())
     }
 
+    #[uniffi::constructor]
+    /// Construct a CWT from an `HC1:`-prefixed, base45-encoded and (optionally)
+    /// zlib-compressed payload.
+    pub fn new_from_base45(payload: String) -> Result<Arc<Self>, CwtError> {
+        let id = Uuid::new_v4();
+        Ok(Self::from_base45(id, payload.as_bytes().to_vec())?.into())
+    }
+
     /// The VdcCollection ID for this credential.
     pub fn id(&self) -> Uuid {
         self.id
@@ -49,6 +134,39 @@ impl Cwt {
         Self::claims_set_to_hash_map(self.claims.clone())
     }
 
+    /// The claims of the credential in the order they were issued, e.g. for a stable display.
+    ///
+    /// Unlike [claims](Self::claims), the original key of each claim is kept alongside its
+    /// label.
+    pub fn claims_ordered(&self) -> Vec<CwtClaim> {
+        let mut claims: Vec<_> = self.claims.iter().collect();
+        claims.sort_by_key(|(key, _)| {
+            let key = ClaimKey::from(*key);
+            self.claim_order
+                .iter()
+                .position(|issued| *issued == key)
+                .unwrap_or(usize::MAX)
+        });
+        claims
+            .into_iter()
+            .map(|(key, value)| CwtClaim {
+                key_label: Self::get_key_name(key),
+                raw_key: ClaimKey::from(key).into(),
+                value: Self::claim_value(key, value),
+            })
+            .collect()
+    }
+
+    /// The claims of the credential as they were issued, without normalizing date claims.
+    ///
+    /// Use this for exact comparison of claim values.
+    pub fn raw_claims(&self) -> HashMap<String, CborValue> {
+        self.claims
+            .iter()
+            .map(|(key, value)| (Self::get_key_name(key), CborValue::from(value.clone())))
+            .collect()
+    }
+
     pub fn r#type(&self) -> CredentialType {
         CredentialType("cwt".to_string())
     }
@@ -57,30 +175,223 @@ impl Cwt {
     pub fn key_alias(&self) -> Option<KeyAlias> {
         self.key_alias.clone()
     }
+
+    /// The DER-encoded signer certificate from the `x5chain` header, if the CWT has one.
+    ///
+    /// The certificate is returned whether or not the CWT has been verified.
+    pub fn signer_certificate_der(&self) -> Option<Vec<u8>> {
+        helpers::get_signer_certificate(&self.cwt)
+            .ok()?
+            .to_der()
+            .ok()
+    }
+
+    /// The subject of the signer certificate, as an RFC 4514 string, e.g.
+    /// `CN=Example Issuer,C=US`.
+    pub fn signer_subject(&self) -> Option<String> {
+        helpers::get_signer_certificate(&self.cwt)
+            .ok()
+            .map(|certificate| certificate.tbs_certificate.subject.to_string())
+    }
 }
 
 #[uniffi::export(async_runtime = "tokio")]
 impl Cwt {
-    pub async fn verify(&self, crypto: &dyn Crypto) -> Result<(), CwtError> {
-        self.validate(crypto).await
+    pub async fn verify(&self, crypto: &dyn Crypto) -> Result<VerificationReport, CwtError> {
+        let trusted_roots = trusted_roots::trusted_roots()
+            .map_err(|e| CwtError::LoadRootCertificate(e.to_string()))?;
+        self.validate(
+            crypto,
+            &trusted_roots,
+            &Default::default(),
+            Duration::ZERO,
+            OfflinePolicy::Online,
+        )
+        .await
+    }
+
+    /// Verify the CWT, trusting the caller-supplied PEM root certificates in addition to the
+    /// built-in trusted roots.
+    pub async fn verify_with_additional_roots(
+        &self,
+        crypto: &dyn Crypto,
+        additional_roots_pem: Vec<String>,
+    ) -> Result<VerificationReport, CwtError> {
+        let trusted_roots = trusted_roots::trusted_roots_with(additional_roots_pem)
+            .map_err(|e| CwtError::LoadRootCertificate(e.to_string()))?;
+        self.validate(
+            crypto,
+            &trusted_roots,
+            &Default::default(),
+            Duration::ZERO,
+            OfflinePolicy::Online,
+        )
+        .await
+    }
+
+    /// Verify the CWT, trusting only the caller-supplied PEM root certificates.
+    ///
+    /// `policy` defaults to [OfflinePolicy::Online], as for
+    /// [verify_with_policy](Self::verify_with_policy).
+    #[uniffi::method(default(policy = None))]
+    pub async fn verify_with_roots(
+        &self,
+        crypto: &dyn Crypto,
+        roots_pem: Vec<String>,
+        policy: Option<OfflinePolicy>,
+    ) -> Result<VerificationReport, CwtError> {
+        let trusted_roots = trusted_roots::parse_trusted_roots(roots_pem)
+            .map_err(|e| CwtError::LoadRootCertificate(e.to_string()))?;
+        self.validate(
+            crypto,
+            &trusted_roots,
+            &Default::default(),
+            Duration::ZERO,
+            policy.unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// Verify the CWT like [verify](Self::verify), resolving the `Issuer` DID of a CWT
+    /// without signer certificate as configured, e.g. from cached `did:web` documents.
+    ///
+    /// `policy` defaults to [OfflinePolicy::Online], as for
+    /// [verify_with_policy](Self::verify_with_policy).
+    #[uniffi::method(default(policy = None))]
+    pub async fn verify_with_did_resolver(
+        &self,
+        crypto: &dyn Crypto,
+        resolver_config: DidResolverConfig,
+        policy: Option<OfflinePolicy>,
+    ) -> Result<VerificationReport, CwtError> {
+        let trusted_roots = trusted_roots::trusted_roots()
+            .map_err(|e| CwtError::LoadRootCertificate(e.to_string()))?;
+        let did_resolver = ConfiguredDidResolver::try_from(resolver_config)
+            .map_err(|e| CwtError::Trust(e.to_string()))?;
+        self.validate(
+            crypto,
+            &trusted_roots,
+            &did_resolver,
+            Duration::ZERO,
+            policy.unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// Verify the CWT like [verify](Self::verify), accepting `exp` and `nbf` claims that are
+    /// off by at most `leeway_secs` from the device clock, e.g. to tolerate clock drift.
+    pub async fn verify_with_leeway(
+        &self,
+        crypto: &dyn Crypto,
+        leeway_secs: u64,
+    ) -> Result<VerificationReport, CwtError> {
+        let trusted_roots = trusted_roots::trusted_roots()
+            .map_err(|e| CwtError::LoadRootCertificate(e.to_string()))?;
+        self.validate(
+            crypto,
+            &trusted_roots,
+            &Default::default(),
+            leeway_from_secs(leeway_secs),
+            OfflinePolicy::Online,
+        )
+        .await
+    }
+
+    /// Verify the CWT like [verify](Self::verify), unless `policy` is offline, in which case
+    /// it must be trusted through its signer certificate, as the `Issuer` DID of a CWT without
+    /// one cannot be resolved.
+    pub async fn verify_with_policy(
+        &self,
+        crypto: &dyn Crypto,
+        policy: OfflinePolicy,
+    ) -> Result<VerificationReport, CwtError> {
+        let trusted_roots = trusted_roots::trusted_roots()
+            .map_err(|e| CwtError::LoadRootCertificate(e.to_string()))?;
+        self.validate(
+            crypto,
+            &trusted_roots,
+            &Default::default(),
+            Duration::ZERO,
+            policy,
+        )
+        .await
     }
 }
 
 impl Cwt {
+    /// The signature algorithm from the protected header of the COSE_Sign1.
+    pub(crate) fn signature_algorithm(&self) -> uniffi::deps::anyhow::Result<CoseAlgorithm> {
+        CoseAlgorithm::from_cose_sign1(&self.cwt)
+    }
+
     pub(crate) fn from_base10(id: Uuid, payload: Vec<u8>) -> Result<Self, CwtError> {
         let raw_payload = payload.clone();
         let payload =
             String::from_utf8(payload).map_err(|e| CwtError::CwsPayloadDecode(e.to_string()))?;
         let base10_str = payload.strip_prefix('9').ok_or(CwtError::Base10Decode)?;
+        if let Some((position, character)) = base10_str
+            .chars()
+            .enumerate()
+            .find(|(_, c)| !c.is_ascii_digit())
+        {
+            return Err(CwtError::InvalidBase10Character {
+                character: character.to_string(),
+                // Account for the stripped multibase prefix.
+                position: position as u64 + 1,
+            });
+        }
         let compressed_cwt_bytes = BigUint::from_str_radix(base10_str, 10)
             .map_err(|_| CwtError::Base10Decode)?
             .to_bytes_be();
 
-        let cwt_bytes = miniz_oxide::inflate::decompress_to_vec(&compressed_cwt_bytes)
-            .map_err(|e| CwtError::Decompression(e.to_string()))?;
+        let cwt_bytes = miniz_oxide::inflate::decompress_to_vec_with_limit(
+            &compressed_cwt_bytes,
+            MAX_DECOMPRESSED_CWT_SIZE,
+        )
+        .map_err(|e| CwtError::Decompression(e.to_string()))?;
+
+        Self::from_cwt_bytes(id, raw_payload, &cwt_bytes)
+    }
+
+    pub(crate) fn from_base45(id: Uuid, payload: Vec<u8>) -> Result<Self, CwtError> {
+        let raw_payload = payload.clone();
+        let payload =
+            String::from_utf8(payload).map_err(|e| CwtError::CwsPayloadDecode(e.to_string()))?;
+        let base45_str = payload
+            .strip_prefix(HC1_PREFIX)
+            .ok_or_else(|| CwtError::Base45Decode(format!("missing '{HC1_PREFIX}' prefix")))?;
+
+        let bytes = base45_decode(base45_str)?;
 
-        let cwt: CoseSign1 = serde_cbor::from_slice(&cwt_bytes)
-            .map_err(|e| CwtError::CborDecoding(e.to_string()))?;
+        // Payloads are usually zlib-compressed, but may also be a bare COSE_Sign1.
+        let cwt_bytes = if bytes.first() == Some(&0x78) {
+            miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(
+                &bytes,
+                MAX_DECOMPRESSED_CWT_SIZE,
+            )
+            .map_err(|e| CwtError::Decompression(e.to_string()))?
+        } else {
+            bytes
+        };
+
+        Self::from_cwt_bytes(id, raw_payload, &cwt_bytes)
+    }
+
+    /// Decode a stored payload, in either the base10 or the base45 encoding, or as a bare
+    /// COSE_Sign1.
+    pub(crate) fn from_payload(id: Uuid, payload: Vec<u8>) -> Result<Self, CwtError> {
+        if payload.starts_with(HC1_PREFIX.as_bytes()) {
+            Self::from_base45(id, payload)
+        } else if is_cose_sign1(&payload) {
+            Self::from_cwt_bytes(id, payload.clone(), &payload)
+        } else {
+            Self::from_base10(id, payload)
+        }
+    }
+
+    fn from_cwt_bytes(id: Uuid, raw_payload: Vec<u8>, cwt_bytes: &[u8]) -> Result<Self, CwtError> {
+        let cwt: CoseSign1 =
+            serde_cbor::from_slice(cwt_bytes).map_err(|e| CwtError::CborDecoding(e.to_string()))?;
 
         let claims = cwt
             .claims_set()
@@ -92,16 +403,69 @@ impl Cwt {
             payload: raw_payload,
             cwt,
             claims,
+            claim_order: Self::issued_claim_order(cwt_bytes),
             key_alias: None,
         })
     }
 
-    async fn validate(&self, crypto: &dyn Crypto) -> Result<(), CwtError> {
-        self.validate_claims()?;
+    /// The claim keys of a COSE_Sign1 encoded CWT, in the order of its payload.
+    ///
+    /// The payload is decoded a second time, as a [ciborium::Value::Map] keeps the order of its
+    /// entries. An undecodable payload yields no order, the claims set being authoritative.
+    fn issued_claim_order(cwt_bytes: &[u8]) -> Vec<ClaimKey> {
+        let Ok(mut cose_sign1) = ciborium::from_reader::<ciborium::Value, _>(cwt_bytes) else {
+            return vec![];
+        };
+        while let ciborium::Value::Tag(_, inner) = cose_sign1 {
+            cose_sign1 = *inner;
+        }
+        let Some(ciborium::Value::Bytes(payload)) = cose_sign1
+            .into_array()
+            .ok()
+            .and_then(|parts| parts.into_iter().nth(2))
+        else {
+            return vec![];
+        };
+        let Ok(ciborium::Value::Map(claims)) = ciborium::from_reader(payload.as_slice()) else {
+            return vec![];
+        };
+        claims
+            .into_iter()
+            .filter_map(|(key, _)| match key {
+                ciborium::Value::Integer(v) => Some(ClaimKey::Integer(v.into())),
+                ciborium::Value::Text(v) => Some(ClaimKey::Text(v)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub(crate) async fn validate<R: DIDResolver + Clone>(
+        &self,
+        crypto: &dyn Crypto,
+        trusted_roots: &[Certificate],
+        did_resolver: &R,
+        leeway: Duration,
+        policy: OfflinePolicy,
+    ) -> Result<VerificationReport, CwtError> {
+        self.validate_claims(leeway)?;
 
         let Ok(signer_certificate) = helpers::get_signer_certificate(&self.cwt) else {
             if let Some(CborValue::Text(issuer_did)) = self.claims().get("Issuer") {
-                return self.validate_using_issuer_did(issuer_did).await;
+                if policy.is_offline() {
+                    return Err(CwtError::OfflineDidResolution(issuer_did.clone()));
+                }
+                self.validate_using_issuer_did(issuer_did, did_resolver)
+                    .await?;
+                tracing::warn!(
+                    credential_id = %self.id,
+                    issuer_did,
+                    trust_mechanism = ?TrustMechanism::IssuerDid,
+                    "CWT has no signer certificate, trusted through its issuer DID instead"
+                );
+                return Ok(VerificationReport {
+                    warnings: vec![],
+                    trust_mechanism: Some(TrustMechanism::IssuerDid),
+                });
             } else {
                 return Err(CwtError::Trust(
                     "no signer certificate or issuer DID found".to_string(),
@@ -109,20 +473,17 @@ impl Cwt {
             }
         };
 
-        let trusted_roots = trusted_roots::trusted_roots()
-            .map_err(|e| CwtError::LoadRootCertificate(e.to_string()))?;
-
         // We want to manually handle the Err to get all errors, so try_fold would not work
         #[allow(clippy::manual_try_fold)]
-        trusted_roots
+        let warnings = trusted_roots
             .into_iter()
             .filter(|cert| {
                 cert.tbs_certificate.subject == signer_certificate.tbs_certificate.issuer
             })
             .fold(Result::Err("\n".to_string()), |res, cert| match res {
-                Ok(_) => Ok(()),
-                Err(err) => match self.validate_certificate_chain(crypto, &cert, &signer_certificate) {
-                    Ok(_) => Ok(()),
+                Ok(warnings) => Ok(warnings),
+                Err(err) => match self.validate_certificate_chain(crypto, cert, &signer_certificate) {
+                    Ok(warnings) => Ok(warnings),
                     Err(e) => Err(format!("{}\n--------------\n{}", err, e)),
                 },
             })
@@ -133,7 +494,12 @@ impl Cwt {
                     err
                 })
             })
-                    .map_err(|e|CwtError::Trust(e.to_string()))
+                    .map_err(|e|CwtError::Trust(e.to_string()))?;
+
+        Ok(VerificationReport {
+            warnings,
+            trust_mechanism: Some(TrustMechanism::CertificateChain),
+        })
     }
 
     fn validate_certificate_chain(
@@ -141,7 +507,7 @@ impl Cwt {
         crypto: &dyn Crypto,
         root_certificate: &CertificateInner,
         signer_certificate: &CertificateInner,
-    ) -> Result<(), CwtError> {
+    ) -> Result<Vec<VerificationWarning>, CwtError> {
         // Root validation.
         {
             helpers::check_validity(&root_certificate.tbs_certificate.validity)
@@ -202,15 +568,20 @@ impl Cwt {
         }
 
         // Validate that Signer issued CWT.
-        let verifier = CoseP256Verifier {
+        let verifier = CoseVerifier {
             crypto,
             certificate_der: signer_certificate
                 .to_der()
                 .map_err(|_| CwtError::UnableToEncodeSignerCertificateAsDer)?,
+            algorithm: CoseAlgorithm::from_cose_sign1(&self.cwt)
+                .map_err(|e| CwtError::CwtSignatureVerification(format!("{e:#}")))?,
         };
 
         match self.cwt.verify(&verifier, None, None) {
-            VerificationResult::Success => Ok(()),
+            VerificationResult::Success => Ok([root_certificate, signer_certificate]
+                .into_iter()
+                .filter_map(helpers::expiry_warning)
+                .collect()),
             VerificationResult::Failure(e) => {
                 Err(CwtError::CwtSignatureVerification(e.to_string()))
             }
@@ -218,9 +589,13 @@ impl Cwt {
         }
     }
 
-    async fn validate_using_issuer_did(&self, issuer_did: &str) -> Result<(), CwtError> {
-        let resolver: VerificationMethodDIDResolver<AnyDidMethod, AnyJwkMethod> =
-            Default::default();
+    async fn validate_using_issuer_did<R: DIDResolver + Clone>(
+        &self,
+        issuer_did: &str,
+        did_resolver: &R,
+    ) -> Result<(), CwtError> {
+        let resolver: VerificationMethodDIDResolver<R, AnyJwkMethod> =
+            VerificationMethodDIDResolver::new(did_resolver.clone());
         let jwk = resolver
             .fetch_public_jwk(Some(issuer_did))
             .await
@@ -247,31 +622,61 @@ impl Cwt {
         }
     }
 
-    fn validate_claims(&self) -> Result<(), CwtError> {
-        // Validate the expiration time claim
-        if let Some(ExpirationTime(exp)) = self.claims.get_claim().map_err(|e| {
-            CwtError::MalformedClaim(
-                "exp".to_string(),
-                e.to_string(),
-                "could not parse".to_string(),
-            )
-        })? {
-            let exp: OffsetDateTime =
-                exp.try_into()
-                    .map_err(|e: cose_rs::cwt::numericdate_conversion::Error| {
-                        CwtError::MalformedClaim(
-                            "exp".to_string(),
-                            e.to_string(),
-                            "could not parse".to_string(),
-                        )
-                    })?;
-            if exp < OffsetDateTime::now_utc() {
-                return Err(CwtError::CwtExpired(exp.to_string()));
+    /// Validate the `exp` and `nbf` claims, allowing the device clock to be off by `leeway`.
+    fn validate_claims(&self, leeway: Duration) -> Result<(), CwtError> {
+        let now = OffsetDateTime::now_utc();
+
+        if let Some(exp) = self.date_claim(4, "exp")? {
+            if now - exp > leeway {
+                return Err(CwtError::CwtExpired {
+                    expiration: exp.to_string(),
+                    skew_seconds: clock_skew_seconds(exp),
+                });
+            }
+        }
+
+        if let Some(nbf) = self.date_claim(5, "nbf")? {
+            if nbf - now > leeway {
+                return Err(CwtError::CwtNotYetValid {
+                    not_before: nbf.to_string(),
+                    skew_seconds: clock_skew_seconds(nbf),
+                });
             }
         }
+
         Ok(())
     }
 
+    /// The date of an integer-keyed NumericDate claim, if present.
+    fn date_claim(&self, key: i128, name: &str) -> Result<Option<OffsetDateTime>, CwtError> {
+        let Some(value) = self
+            .claims
+            .iter()
+            .find(|(claim_key, _)| matches!(claim_key, cose_rs::cwt::Key::Integer(k) if *k == key))
+            .map(|(_, value)| value)
+        else {
+            return Ok(None);
+        };
+
+        Self::numeric_date(value).map(Some).ok_or_else(|| {
+            CwtError::MalformedClaim(
+                name.to_string(),
+                format!("{value:?}"),
+                "not a NumericDate".to_string(),
+            )
+        })
+    }
+
+    /// An RFC 8392 NumericDate, which is an untagged integer or floating-point timestamp.
+    ///
+    /// Unlike [Self::parse_date], which is lenient for display, text dates are rejected.
+    fn numeric_date(value: &serde_cbor::Value) -> Option<OffsetDateTime> {
+        match value {
+            serde_cbor::Value::Integer(_) | serde_cbor::Value::Float(_) => Self::parse_date(value),
+            _ => None,
+        }
+    }
+
     fn get_key_name(key: &cose_rs::cwt::Key) -> String {
         match key {
             cose_rs::cwt::Key::Text(v) => {
@@ -287,45 +692,54 @@ impl Cwt {
 
     fn claims_set_to_hash_map(set: ClaimsSet) -> HashMap<String, CborValue> {
         set.iter()
-            .map(|c| {
-                (
-                    Self::get_key_name(c.0),
-                    match c.0 {
-                        cose_rs::cwt::Key::Text(_) => CborValue::from(c.1.clone()),
-                        cose_rs::cwt::Key::Integer(v) => {
-                            if *v == 4 || *v == 5 || *v == 6 {
-                                Self::parse_datestr(c.1)
-                            } else {
-                                CborValue::from(c.1.clone())
-                            }
-                        }
-                    },
-                )
-            })
+            .map(|c| (Self::get_key_name(c.0), Self::claim_value(c.0, c.1)))
             .collect()
     }
 
-    /// Parse date strings, handling both ISO format and Unix timestamps
+    /// The value of a claim, normalizing the `exp` (4), `nbf` (5) and `iat` (6) date claims.
+    fn claim_value(key: &cose_rs::cwt::Key, value: &serde_cbor::Value) -> CborValue {
+        match key {
+            cose_rs::cwt::Key::Integer(4..=6) => Self::parse_datestr(value),
+            _ => CborValue::from(value.clone()),
+        }
+    }
+
+    /// Parse date claims, handling date-only and RFC 3339 strings as well as integer and
+    /// floating point Unix timestamps.
+    ///
+    /// Dates are normalized to `[year]-[month]-[day]` in UTC for display. Values that cannot be
+    /// parsed as a date are returned as text unchanged; use [Cwt::raw_claims] for the original
+    /// values.
     fn parse_datestr(value: &serde_cbor::Value) -> CborValue {
+        Self::parse_date(value)
+            .and_then(|date| {
+                date.format(&format_description!("[year]-[month]-[day]"))
+                    .ok()
+            })
+            .map(CborValue::Text)
+            .unwrap_or_else(|| CborValue::Text(CborValue::from(value.clone()).to_string()))
+    }
+
+    fn parse_date(value: &serde_cbor::Value) -> Option<OffsetDateTime> {
         match value {
             serde_cbor::Value::Float(timestamp) => {
-                match OffsetDateTime::from_unix_timestamp_nanos(
-                    (timestamp * 1_000_000_000.0) as i128,
-                ) {
-                    Ok(date) => CborValue::Text(
-                        date.format(&format_description!("[year]-[month]-[day]"))
-                            .unwrap_or_else(|_| timestamp.to_string()),
-                    ),
-                    Err(_) => CborValue::Text(timestamp.to_string()),
-                }
+                OffsetDateTime::from_unix_timestamp_nanos((timestamp * 1_000_000_000.0) as i128)
+                    .ok()
             }
-            _ => {
-                let date_str = CborValue::from(value.clone()).to_string();
-                let format = format_description!("[year]-[month]-[day]");
-                Date::parse(&date_str, format)
-                    .map(|date| CborValue::Text(date.to_string()))
-                    .unwrap_or_else(|_| CborValue::Text(date_str))
+            serde_cbor::Value::Integer(timestamp) => {
+                OffsetDateTime::from_unix_timestamp((*timestamp).try_into().ok()?).ok()
             }
+            serde_cbor::Value::Text(date_str) => OffsetDateTime::parse(date_str, &Rfc3339)
+                .ok()
+                .or_else(|| {
+                    Date::parse(date_str, format_description!("[year]-[month]-[day]"))
+                        .ok()
+                        .map(|date| date.midnight().assume_utc())
+                })
+                .map(|date| date.to_offset(UtcOffset::UTC)),
+            // Standard date/time (0) and epoch-based date/time (1) tags.
+            serde_cbor::Value::Tag(0 | 1, value) => Self::parse_date(value),
+            _ => None,
         }
     }
 
@@ -338,7 +752,7 @@ impl TryFrom<Credential> for Arc<Cwt> {
     type Error = CwtError;
 
     fn try_from(credential: Credential) -> Result<Self, Self::Error> {
-        Cwt::from_base10(credential.id, credential.payload).map(|cwt| cwt.into())
+        Cwt::from_payload(credential.id, credential.payload).map(|cwt| cwt.into())
     }
 }
 
@@ -346,10 +760,56 @@ impl TryFrom<&Credential> for Arc<Cwt> {
     type Error = CwtError;
 
     fn try_from(credential: &Credential) -> Result<Self, Self::Error> {
-        Cwt::from_base10(credential.id, credential.payload.clone()).map(|cwt| cwt.into())
+        Cwt::from_payload(credential.id, credential.payload.clone()).map(|cwt| cwt.into())
     }
 }
 
+/// Decode a base45 string (RFC 9285).
+fn base45_decode(input: &str) -> Result<Vec<u8>, CwtError> {
+    let values = input
+        .chars()
+        .enumerate()
+        .map(|(position, character)| {
+            BASE45_ALPHABET
+                .iter()
+                .position(|c| character.is_ascii() && *c == character as u8)
+                .map(|value| value as u32)
+                .ok_or_else(|| {
+                    CwtError::Base45Decode(format!(
+                        "invalid character {character:?} at position {}",
+                        // Account for the stripped prefix.
+                        position + HC1_PREFIX.len()
+                    ))
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut bytes = Vec::with_capacity(values.len() / 3 * 2 + 1);
+    for chunk in values.chunks(3) {
+        match *chunk {
+            [c, d, e] => {
+                let value = c + d * 45 + e * 45 * 45;
+                let value = u16::try_from(value)
+                    .map_err(|_| CwtError::Base45Decode(format!("invalid triplet {value}")))?;
+                bytes.extend_from_slice(&value.to_be_bytes());
+            }
+            [c, d] => {
+                let value = c + d * 45;
+                let value = u8::try_from(value)
+                    .map_err(|_| CwtError::Base45Decode(format!("invalid pair {value}")))?;
+                bytes.push(value);
+            }
+            _ => {
+                return Err(CwtError::Base45Decode(
+                    "trailing single character".to_string(),
+                ))
+            }
+        }
+    }
+
+    Ok(bytes)
+}
+
 #[derive(Debug, uniffi::Error, thiserror::Error)]
 pub enum CwtError {
     #[error("failed to decode string as a JWS of the form <base64-encoded-header>.<base64-encoded-payload>.<base64-encoded-signature>")]
@@ -358,6 +818,10 @@ pub enum CwtError {
     CwsPayloadDecode(String),
     #[error("Payload did not begin with multibase prefix '9'")]
     Base10Decode,
+    #[error("Payload contains non-digit character {character:?} at position {position}")]
+    InvalidBase10Character { character: String, position: u64 },
+    #[error("Payload is not valid base45: {0}")]
+    Base45Decode(String),
     #[error("Unable to decompress the payload of the QR code. {0}")]
     Decompression(String),
     #[error("Unable to decode the credential: {0}")]
@@ -374,8 +838,20 @@ pub enum CwtError {
     MalformedClaim(String, String, String),
     #[error("Could not establish trust in the credential: {0}")]
     Trust(String),
-    #[error("Expiration Date: {0}")]
-    CwtExpired(String),
+    #[error("Issuer DID {0} would need to be resolved, which is not allowed offline")]
+    OfflineDidResolution(String),
+    #[error("Expiration Date: {expiration} (device clock is {skew_seconds}s past it)")]
+    CwtExpired {
+        expiration: String,
+        /// Seconds by which the device clock is past the expiration date.
+        skew_seconds: i64,
+    },
+    #[error("Not Before: {not_before} (device clock is {skew_seconds}s from it)")]
+    CwtNotYetValid {
+        not_before: String,
+        /// Seconds between the device clock and the not-before date (negative).
+        skew_seconds: i64,
+    },
     #[error("Root certificates could not be loaded: {0}")]
     LoadRootCertificate(String),
     #[error("Internal Error")]
@@ -401,3 +877,430 @@ pub enum CwtError {
     #[error("Unable to extract extensions from root certificate")]
     UnableToExtractExtensionsFromRootCertificate,
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn base10_payload_with_non_digit() {
+        let payload = "9123456x7890".as_bytes().to_vec();
+
+        match Cwt::from_base10(Uuid::new_v4(), payload) {
+            Err(CwtError::InvalidBase10Character {
+                character,
+                position,
+            }) => {
+                assert_eq!(character, "x");
+                assert_eq!(position, 7);
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[rstest]
+    #[case::date_only(serde_cbor::Value::Text("2024-01-02".into()), "2024-01-02")]
+    #[case::datetime(serde_cbor::Value::Text("2024-01-02T03:04:05Z".into()), "2024-01-02")]
+    #[case::datetime_with_offset(
+        serde_cbor::Value::Text("2024-01-02T23:04:05-05:00".into()),
+        "2024-01-03"
+    )]
+    #[case::integer_timestamp(serde_cbor::Value::Integer(1704164645), "2024-01-02")]
+    #[case::float_timestamp(serde_cbor::Value::Float(1704164645.5), "2024-01-02")]
+    #[case::tagged_datetime(
+        serde_cbor::Value::Tag(0, Box::new(serde_cbor::Value::Text("2024-01-02T03:04:05Z".into()))),
+        "2024-01-02"
+    )]
+    #[case::unparseable(serde_cbor::Value::Text("next tuesday".into()), "next tuesday")]
+    fn date_claims_are_normalized(#[case] value: serde_cbor::Value, #[case] expected: &str) {
+        assert_eq!(Cwt::parse_datestr(&value).to_string(), expected);
+    }
+
+    #[test]
+    fn claims_are_ordered_with_formatted_dates() {
+        use ciborium::Value;
+
+        fn to_vec(value: &Value) -> Vec<u8> {
+            let mut bytes = vec![];
+            ciborium::into_writer(value, &mut bytes).unwrap();
+            bytes
+        }
+
+        // Out of the canonical order of the keys, which a sorted map would not keep.
+        let claims = to_vec(&Value::Map(vec![
+            (Value::Text("name".into()), Value::Text("Alice".into())),
+            (
+                Value::Integer(6.into()),
+                Value::Text("2024-01-02T03:04:05Z".into()),
+            ),
+            (
+                Value::Integer(1.into()),
+                Value::Text("did:example:issuer".into()),
+            ),
+            (Value::Integer(4.into()), Value::Integer(1704164645.into())),
+        ]));
+        let cwt_bytes = to_vec(&Value::Array(vec![
+            Value::Bytes(vec![0xa0]),
+            Value::Map(vec![]),
+            Value::Bytes(claims),
+            Value::Bytes(vec![0; 64]),
+        ]));
+        let cwt = Cwt::from_cwt_bytes(Uuid::new_v4(), vec![], &cwt_bytes).unwrap();
+
+        let claims = cwt.claims_ordered();
+
+        assert_eq!(
+            claims
+                .iter()
+                .map(|claim| claim.key_label.as_str())
+                .collect::<Vec<_>>(),
+            vec!["name", "Issued", "Issuer", "Expires"]
+        );
+        assert!(matches!(&claims[0].raw_key, CborValue::Text(key) if key == "name"));
+        for (claim, key) in claims[1..].iter().zip(["6", "1", "4"]) {
+            assert!(
+                matches!(&claim.raw_key, CborValue::Integer(raw) if raw.to_text() == key),
+                "{claim:?}"
+            );
+        }
+        for claim in [&claims[1], &claims[3]] {
+            assert!(
+                matches!(&claim.value, CborValue::Text(date) if date == "2024-01-02"),
+                "{claim:?}"
+            );
+        }
+        assert!(matches!(&claims[0].value, CborValue::Text(name) if name == "Alice"));
+    }
+
+    #[test]
+    fn base10_payload_decompression_bomb() {
+        let compressed =
+            miniz_oxide::deflate::compress_to_vec(&vec![0u8; MAX_DECOMPRESSED_CWT_SIZE + 1], 10);
+        let payload = format!("9{}", BigUint::from_bytes_be(&compressed).to_str_radix(10));
+
+        assert!(matches!(
+            Cwt::from_base10(Uuid::new_v4(), payload.into_bytes()),
+            Err(CwtError::Decompression(_))
+        ));
+    }
+
+    #[rstest]
+    #[case::uncompressed(0)]
+    #[case::fastest(1)]
+    #[case::default(6)]
+    #[case::smallest(10)]
+    fn base10_payload_round_trip(#[case] level: u8) {
+        use std::collections::BTreeMap;
+
+        use serde_cbor::Value;
+
+        let claims = serde_cbor::to_vec(&Value::Map(BTreeMap::from([(
+            Value::Integer(1),
+            Value::Text("did:example:issuer".into()),
+        )])))
+        .unwrap();
+        let cwt_bytes = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Bytes(vec![0xa0]),
+            Value::Map(BTreeMap::new()),
+            Value::Bytes(claims),
+            Value::Bytes(vec![0; 64]),
+        ]))
+        .unwrap();
+
+        let compressed = compress_cwt(cwt_bytes.clone(), level);
+        assert_eq!(
+            miniz_oxide::inflate::decompress_to_vec(&compressed).unwrap(),
+            cwt_bytes
+        );
+
+        let payload = encode_base10(compressed);
+        assert!(payload.starts_with('9'));
+
+        let cwt = Cwt::new_from_base10(payload).unwrap();
+        assert!(matches!(
+            cwt.claims().get("Issuer"),
+            Some(CborValue::Text(issuer)) if issuer == "did:example:issuer"
+        ));
+    }
+
+    #[test]
+    fn base10_payload_without_prefix() {
+        let payload = "123".as_bytes().to_vec();
+
+        assert!(matches!(
+            Cwt::from_base10(Uuid::new_v4(), payload),
+            Err(CwtError::Base10Decode)
+        ));
+    }
+
+    #[test]
+    fn expired_cwt_reports_clock_skew() {
+        use std::collections::BTreeMap;
+
+        use serde_cbor::Value;
+
+        // A device clock set an hour ahead of the expiration date.
+        let exp = OffsetDateTime::now_utc().unix_timestamp() - 3600;
+        let claims = serde_cbor::to_vec(&Value::Map(BTreeMap::from([(
+            Value::Integer(4),
+            Value::Integer(exp.into()),
+        )])))
+        .unwrap();
+        let cwt_bytes = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Bytes(vec![0xa0]),
+            Value::Map(BTreeMap::new()),
+            Value::Bytes(claims),
+            Value::Bytes(vec![0; 64]),
+        ]))
+        .unwrap();
+        let cwt = Cwt::from_cwt_bytes(Uuid::new_v4(), vec![], &cwt_bytes).unwrap();
+
+        match cwt.validate_claims(Duration::ZERO) {
+            Err(CwtError::CwtExpired { skew_seconds, .. }) => {
+                assert!((3600..3610).contains(&skew_seconds), "{skew_seconds}")
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    /// An unsigned CWT with the given `exp` and `nbf` claims, as offsets from now in seconds.
+    fn cwt_with_validity(exp_offset: Option<i64>, nbf_offset: Option<i64>) -> Cwt {
+        use std::collections::BTreeMap;
+
+        use serde_cbor::Value;
+
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let claims = [(4, exp_offset), (5, nbf_offset)]
+            .into_iter()
+            .filter_map(|(key, offset)| {
+                Some((Value::Integer(key), Value::Integer((now + offset?).into())))
+            })
+            .collect::<BTreeMap<_, _>>();
+        let cwt_bytes = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Bytes(vec![0xa0]),
+            Value::Map(BTreeMap::new()),
+            Value::Bytes(serde_cbor::to_vec(&Value::Map(claims)).unwrap()),
+            Value::Bytes(vec![0; 64]),
+        ]))
+        .unwrap();
+        Cwt::from_cwt_bytes(Uuid::new_v4(), vec![], &cwt_bytes).unwrap()
+    }
+
+    #[rstest]
+    #[case::exp_as_text(4, serde_cbor::Value::Text("2999-01-01T00:00:00Z".into()))]
+    #[case::nbf_as_text(5, serde_cbor::Value::Text("2000-01-01".into()))]
+    #[case::nbf_as_tagged_date(
+        5,
+        serde_cbor::Value::Tag(0, Box::new(serde_cbor::Value::Text("2000-01-01T00:00:00Z".into())))
+    )]
+    fn validity_claims_must_be_numeric_dates(#[case] key: i128, #[case] value: serde_cbor::Value) {
+        use std::collections::BTreeMap;
+
+        use serde_cbor::Value;
+
+        let claims =
+            serde_cbor::to_vec(&Value::Map(BTreeMap::from([(Value::Integer(key), value)])))
+                .unwrap();
+        let cwt_bytes = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Bytes(vec![0xa0]),
+            Value::Map(BTreeMap::new()),
+            Value::Bytes(claims),
+            Value::Bytes(vec![0; 64]),
+        ]))
+        .unwrap();
+        let cwt = Cwt::from_cwt_bytes(Uuid::new_v4(), vec![], &cwt_bytes).unwrap();
+
+        assert!(matches!(
+            cwt.validate_claims(Duration::ZERO),
+            Err(CwtError::MalformedClaim(..))
+        ));
+    }
+
+    #[rstest]
+    #[case::expired_without_leeway(Some(-10), None, 0, false)]
+    #[case::expired_within_leeway(Some(-10), None, 60, true)]
+    #[case::expired_beyond_leeway(Some(-120), None, 60, false)]
+    #[case::not_yet_valid_without_leeway(None, Some(10), 0, false)]
+    #[case::not_yet_valid_within_leeway(None, Some(10), 60, true)]
+    #[case::not_yet_valid_beyond_leeway(None, Some(120), 60, false)]
+    #[case::valid_without_leeway(Some(60), Some(-60), 0, true)]
+    fn validity_with_leeway(
+        #[case] exp_offset: Option<i64>,
+        #[case] nbf_offset: Option<i64>,
+        #[case] leeway_secs: u64,
+        #[case] valid: bool,
+    ) {
+        let cwt = cwt_with_validity(exp_offset, nbf_offset);
+
+        let result = cwt.validate_claims(leeway_from_secs(leeway_secs));
+
+        match (valid, result) {
+            (true, Ok(())) => {}
+            (false, Err(CwtError::CwtExpired { .. })) if exp_offset.is_some() => {}
+            (false, Err(CwtError::CwtNotYetValid { .. })) if nbf_offset.is_some() => {}
+            (_, result) => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn signer_certificate_of_cwt() {
+        use std::collections::BTreeMap;
+
+        use serde_cbor::Value;
+        use x509_cert::der::DecodePem;
+
+        let certificate =
+            Certificate::from_pem(include_str!("../../tests/res/mdl/iaca-certificate.pem"))
+                .unwrap();
+        let der = certificate.to_der().unwrap();
+        let protected = serde_cbor::to_vec(&Value::Map(BTreeMap::from([(
+            Value::Integer(33),
+            Value::Bytes(der.clone()),
+        )])))
+        .unwrap();
+        let cwt_bytes = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(BTreeMap::new()),
+            Value::Bytes(vec![0xa0]),
+            Value::Bytes(vec![0; 64]),
+        ]))
+        .unwrap();
+        let cwt = Cwt::from_cwt_bytes(Uuid::new_v4(), vec![], &cwt_bytes).unwrap();
+
+        assert_eq!(cwt.signer_certificate_der(), Some(der));
+        assert_eq!(
+            cwt.signer_subject().as_deref(),
+            Some("CN=SpruceID Test IACA,C=US")
+        );
+    }
+
+    #[test]
+    fn no_signer_certificate() {
+        let cwt = cwt_with_validity(None, None);
+
+        assert_eq!(cwt.signer_certificate_der(), None);
+        assert_eq!(cwt.signer_subject(), None);
+    }
+
+    /// A CWT without signer certificate, signed by `secret_key` and issued by `issuer_did`.
+    fn cwt_issued_by_did(secret_key: &p256::SecretKey, issuer_did: &str) -> Cwt {
+        use std::collections::BTreeMap;
+
+        use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+        use serde_cbor::Value;
+
+        // ES256, without an x5chain.
+        let protected = serde_cbor::to_vec(&Value::Map(BTreeMap::from([(
+            Value::Integer(1),
+            Value::Integer(-7),
+        )])))
+        .unwrap();
+        let claims = serde_cbor::to_vec(&Value::Map(BTreeMap::from([(
+            Value::Integer(1),
+            Value::Text(issuer_did.to_string()),
+        )])))
+        .unwrap();
+        let sig_structure = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Text("Signature1".into()),
+            Value::Bytes(protected.clone()),
+            Value::Bytes(vec![]),
+            Value::Bytes(claims.clone()),
+        ]))
+        .unwrap();
+        let signature: Signature = SigningKey::from(secret_key).sign(&sig_structure);
+
+        let cwt_bytes = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(BTreeMap::new()),
+            Value::Bytes(claims),
+            Value::Bytes(signature.to_vec()),
+        ]))
+        .unwrap();
+        Cwt::from_cwt_bytes(Uuid::new_v4(), vec![], &cwt_bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn trust_through_issuer_did_is_reported() {
+        use ssi::dids::DIDKey;
+
+        let secret_key = p256::SecretKey::random(&mut ssi::crypto::rand::thread_rng());
+        let jwk: ssi::JWK = serde_json::from_str(&secret_key.public_key().to_jwk_string()).unwrap();
+        let issuer_did = DIDKey::generate(&jwk).unwrap().to_string();
+        let cwt = cwt_issued_by_did(&secret_key, &issuer_did);
+
+        let report = cwt
+            .validate(
+                &crate::verifier::crypto::RustCrypto,
+                &[],
+                &Default::default(),
+                Duration::ZERO,
+                OfflinePolicy::Online,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(report.trust_mechanism, Some(TrustMechanism::IssuerDid));
+    }
+
+    #[tokio::test]
+    async fn offline_issuer_did_is_not_resolved() {
+        use wiremock::{matchers::any, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let issuer_did = format!("did:web:localhost%3A{}", server.address().port());
+        let secret_key = p256::SecretKey::random(&mut ssi::crypto::rand::thread_rng());
+        let cwt = cwt_issued_by_did(&secret_key, &issuer_did);
+
+        match cwt
+            .verify_with_policy(&crate::verifier::crypto::RustCrypto, OfflinePolicy::Offline)
+            .await
+        {
+            Err(CwtError::OfflineDidResolution(did)) => assert_eq!(did, issuer_did),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn issuer_did_web_document_is_injected() {
+        let issuer_did = "did:web:issuer.example.com";
+        let secret_key = p256::SecretKey::random(&mut ssi::crypto::rand::thread_rng());
+        let public_jwk: serde_json::Value =
+            serde_json::from_str(&secret_key.public_key().to_jwk_string()).unwrap();
+        let document = serde_json::json!({
+            "@context": "https://www.w3.org/ns/did/v1",
+            "id": issuer_did,
+            "verificationMethod": [{
+                "id": format!("{issuer_did}#key-1"),
+                "type": "JsonWebKey2020",
+                "controller": issuer_did,
+                "publicKeyJwk": public_jwk,
+            }],
+            "assertionMethod": [format!("{issuer_did}#key-1")],
+        });
+        let cwt = cwt_issued_by_did(&secret_key, issuer_did);
+
+        let report = cwt
+            .verify_with_did_resolver(
+                &crate::verifier::crypto::RustCrypto,
+                DidResolverConfig {
+                    did_web_documents: HashMap::from([(
+                        issuer_did.to_string(),
+                        document.to_string(),
+                    )]),
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(report.trust_mechanism, Some(TrustMechanism::IssuerDid));
+    }
+}
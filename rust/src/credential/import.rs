@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use isomdl::definitions::IssuerSigned;
+use uuid::Uuid;
+
+use super::{
+    cwt::{is_cose_sign1, Cwt, HC1_PREFIX},
+    json_vc::JsonVc,
+    mdoc::Mdoc,
+    CredentialFormat, ParsedCredential,
+};
+use crate::{crypto::KeyAlias, verifier::crypto::Crypto};
+
+#[derive(Debug, uniffi::Error, thiserror::Error)]
+pub enum CredentialImportError {
+    /// The bytes are not in any of the supported credential formats.
+    #[error("unknown credential format")]
+    UnknownFormat,
+    /// The format was recognized, but the credential could not be decoded.
+    #[error("failed to decode {format} credential: {reason}")]
+    Decoding {
+        format: CredentialFormat,
+        reason: String,
+    },
+    /// The credential was decoded, but its issuer could not be verified.
+    #[error("failed to verify {format} credential: {reason}")]
+    VerificationFailed {
+        format: CredentialFormat,
+        reason: String,
+    },
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl ParsedCredential {
+    #[uniffi::constructor]
+    /// Import a credential of unknown format, and verify its issuer.
+    ///
+    /// The supported formats are:
+    /// - `ldp_vc`: a JSON credential secured with a data-integrity proof.
+    /// - `mso_mdoc`: a CBOR-encoded isomdl `Document` or ISO 18013-5 `IssuerSigned`, whose
+    ///   signer must be issued by one of `roots_pem`.
+    /// - `cwt`: a base10, base45 (`HC1:`) or CBOR-encoded COSE_Sign1, whose signer must be
+    ///   issued by one of `roots_pem`, or by its `Issuer` DID.
+    ///
+    /// Imported mdocs are not bound to a key of the wallet, and have an empty key alias.
+    pub async fn from_bytes_verified(
+        bytes: Vec<u8>,
+        crypto: &dyn Crypto,
+        roots_pem: Vec<String>,
+    ) -> Result<Arc<Self>, CredentialImportError> {
+        let format = sniff_format(&bytes).ok_or(CredentialImportError::UnknownFormat)?;
+
+        let decoding = |reason: String| CredentialImportError::Decoding {
+            format: format.clone(),
+            reason,
+        };
+        let verification = |reason: String| CredentialImportError::VerificationFailed {
+            format: format.clone(),
+            reason,
+        };
+
+        match format {
+            CredentialFormat::LdpVc => {
+                let json = String::from_utf8(bytes).map_err(|e| decoding(e.to_string()))?;
+                let json_vc = JsonVc::new_from_json(json).map_err(|e| decoding(e.to_string()))?;
+                json_vc
+                    .verify()
+                    .await
+                    .map_err(|e| verification(e.to_string()))?;
+                Ok(ParsedCredential::new_ldp_vc(json_vc))
+            }
+            CredentialFormat::MsoMdoc => {
+                let mdoc = decode_mdoc(bytes).map_err(decoding)?;
+                mdoc.verify_issuer_signature(crypto, roots_pem)
+                    .await
+                    .map_err(|e| verification(e.to_string()))?;
+                Ok(ParsedCredential::new_mso_mdoc(mdoc))
+            }
+            CredentialFormat::Cwt => {
+                let cwt: Arc<Cwt> = Cwt::from_payload(Uuid::new_v4(), bytes)
+                    .map_err(|e| decoding(e.to_string()))?
+                    .into();
+                cwt.verify_with_roots(crypto, roots_pem, None)
+                    .await
+                    .map_err(|e| verification(e.to_string()))?;
+                Ok(ParsedCredential::new_cwt(cwt))
+            }
+            _ => Err(CredentialImportError::UnknownFormat),
+        }
+    }
+}
+
+/// Guess the format of a credential from its first bytes.
+fn sniff_format(bytes: &[u8]) -> Option<CredentialFormat> {
+    let text = bytes.trim_ascii_start();
+
+    if text.starts_with(b"{") {
+        return Some(CredentialFormat::LdpVc);
+    }
+
+    if text.starts_with(HC1_PREFIX.as_bytes())
+        || (text.starts_with(b"9") && text.trim_ascii_end().iter().all(u8::is_ascii_digit))
+        || is_cose_sign1(bytes)
+    {
+        return Some(CredentialFormat::Cwt);
+    }
+
+    // CBOR maps (major type 5), as both `Document` and `IssuerSigned` are.
+    if matches!(bytes.first(), Some(0xa0..=0xbf)) {
+        return Some(CredentialFormat::MsoMdoc);
+    }
+
+    None
+}
+
+/// Decode an isomdl `Document`, or otherwise an `IssuerSigned`.
+fn decode_mdoc(bytes: Vec<u8>) -> Result<Arc<Mdoc>, String> {
+    let key_alias = KeyAlias(String::new());
+
+    let document_error = match Mdoc::from_cbor_encoded_document(bytes.clone(), key_alias.clone()) {
+        Ok(mdoc) => return Ok(mdoc),
+        Err(e) => e,
+    };
+
+    let issuer_signed: IssuerSigned = isomdl::cbor::from_slice(&bytes)
+        .map_err(|e| format!("neither a Document ({document_error}) nor an IssuerSigned ({e})"))?;
+    Mdoc::new_from_issuer_signed(key_alias, issuer_signed).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_cbor::Value;
+
+    use super::*;
+    use crate::{credential::mdoc::tests::mdoc_issued_by, verifier::crypto::RustCrypto};
+
+    const IACA_CERTIFICATE: &str = include_str!("../../tests/res/mdl/iaca-certificate.pem");
+    const IACA_KEY: &str = include_str!("../../tests/res/mdl/iaca-key.pem");
+
+    #[tokio::test]
+    async fn import_json_vc() {
+        let bytes = include_bytes!("../../tests/res/vc").to_vec();
+
+        let credential = ParsedCredential::from_bytes_verified(bytes, &RustCrypto, vec![])
+            .await
+            .unwrap();
+
+        assert_eq!(credential.format(), CredentialFormat::LdpVc);
+    }
+
+    #[tokio::test]
+    async fn import_mdoc() {
+        let mdoc = mdoc_issued_by(IACA_CERTIFICATE, IACA_KEY).await;
+        let bytes = isomdl::cbor::to_vec(mdoc.document()).unwrap();
+
+        let credential = ParsedCredential::from_bytes_verified(
+            bytes,
+            &RustCrypto,
+            vec![IACA_CERTIFICATE.to_string()],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(credential.format(), CredentialFormat::MsoMdoc);
+    }
+
+    #[tokio::test]
+    async fn import_untrusted_cwt() {
+        let claims = serde_cbor::to_vec(&Value::Map(BTreeMap::from([(
+            Value::Integer(1),
+            Value::Text("issuer".into()),
+        )])))
+        .unwrap();
+        let bytes = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Bytes(vec![0xa0]),
+            Value::Map(BTreeMap::new()),
+            Value::Bytes(claims),
+            Value::Bytes(vec![0; 64]),
+        ]))
+        .unwrap();
+
+        let result = ParsedCredential::from_bytes_verified(bytes, &RustCrypto, vec![]).await;
+
+        assert!(matches!(
+            result,
+            Err(CredentialImportError::VerificationFailed {
+                format: CredentialFormat::Cwt,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn import_unknown_format() {
+        let result = ParsedCredential::from_bytes_verified(
+            b"not a credential".to_vec(),
+            &RustCrypto,
+            vec![],
+        )
+        .await;
+
+        assert!(matches!(result, Err(CredentialImportError::UnknownFormat)));
+    }
+}
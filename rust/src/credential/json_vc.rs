@@ -1,19 +1,27 @@
 use super::{
     status::{BitStringStatusListResolver, Status, StatusListError},
+    status_revocation_list_2020::{revocation_list_2020_status, REVOCATION_LIST_2020_STATUS},
     Credential, CredentialEncodingError, CredentialFormat, VcdmVersion,
 };
 use crate::{
-    crypto::KeyAlias,
+    common::clock_skew_seconds,
+    crypto::{parse_jwk, KeyAlias, KeyStore},
+    did::{CachingDidResolver, ConfiguredDidResolver, DidResolverConfig},
     oid4vp::{
         error::OID4VPError,
         presentation::{CredentialPresentation, PresentationOptions},
         ResponseOptions,
     },
+    verifier::{
+        offline::OfflinePolicy,
+        outcome::{VerificationReport, VerificationWarning},
+    },
     CredentialType,
 };
 
 use std::sync::Arc;
 
+use base64::prelude::*;
 use openid4vp::{
     core::{
         credential_format::ClaimFormatDesignation, presentation_submission::DescriptorMap,
@@ -22,7 +30,9 @@ use openid4vp::{
     JsonPath,
 };
 use serde_json::Value as Json;
-use ssi::status::bitstring_status_list::BitstringStatusListEntry;
+use ssi::status::bitstring_status_list::{
+    BitstringStatusListEntry, StatusPurpose as BitStringStatusPurpose,
+};
 use ssi::{
     claims::vc::{
         syntax::{IdOr, NonEmptyObject, NonEmptyVec},
@@ -35,9 +45,35 @@ use ssi::{
     json_ld::iref::UriBuf,
     prelude::{AnyJsonCredential, AnyJsonPresentation},
 };
+use ssi::{
+    claims::{data_integrity::AnyDataIntegrity, ProofValidationError, VerificationParameters},
+    dids::{DIDKey, DIDResolver, VerificationMethodDIDResolver},
+    jwk::JWK,
+    verification_methods::AnyMethod,
+};
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
 use uuid::Uuid;
 
-const ACCEPTED_CRYPTOSUITES: &[&str] = &["ecdsa-rdfc-2019"];
+const ACCEPTED_CRYPTOSUITES: &[&str] = &["ecdsa-rdfc-2019", "eddsa-rdfc-2022"];
+/// Proof types accepted for legacy proofs, which are identified by their type instead of a
+/// `cryptosuite`.
+const ACCEPTED_PROOF_TYPES: &[&str] = &["Ed25519Signature2020"];
+
+/// The cryptosuite of a proof, or the type of a legacy proof without one.
+fn proof_suite(proof: &Json) -> &str {
+    proof
+        .get("cryptosuite")
+        .or_else(|| proof.get("type"))
+        .and_then(Json::as_str)
+        .unwrap_or_default()
+}
+
+fn is_accepted_proof(proof: &Json) -> bool {
+    match proof.get("cryptosuite").and_then(Json::as_str) {
+        Some(suite) => ACCEPTED_CRYPTOSUITES.contains(&suite),
+        None => ACCEPTED_PROOF_TYPES.contains(&proof_suite(proof)),
+    }
+}
 
 #[derive(Debug, uniffi::Error, thiserror::Error)]
 pub enum JsonVcInitError {
@@ -57,6 +93,46 @@ pub enum JsonVcEncodingError {
     JsonBytesEncoding,
 }
 
+#[derive(Debug, uniffi::Error, thiserror::Error)]
+pub enum JsonVcVerificationError {
+    #[error("failed to decode the credential proof: {0}")]
+    ProofDecoding(String),
+    #[error("no proof uses an accepted cryptosuite, found: {0:?}")]
+    UnsupportedCryptosuite(Vec<String>),
+    #[error("failed to resolve the issuer verification method: {0}")]
+    Resolution(String),
+    #[error("the credential signature is invalid: {0}")]
+    SignatureMismatch(String),
+    #[error("the credential proof could not be validated: {0}")]
+    InvalidProof(String),
+    #[error("the issuer DID {0} would need to be resolved, which is not allowed offline")]
+    OfflineDidResolution(String),
+    #[error(
+        "the credential is not valid before {valid_from} (device clock is {skew_seconds}s from it)"
+    )]
+    NotYetValid {
+        valid_from: String,
+        /// Seconds between the device clock and the start of the validity period (negative).
+        skew_seconds: i64,
+    },
+    #[error("the credential expired at {valid_until} (device clock is {skew_seconds}s past it)")]
+    Expired {
+        valid_until: String,
+        /// Seconds by which the device clock is past the end of the validity period.
+        skew_seconds: i64,
+    },
+}
+
+#[derive(Debug, uniffi::Error, thiserror::Error)]
+pub enum JsonVcBindingError {
+    #[error("the credential is not bound to a key")]
+    NotKeyBound,
+    #[error("failed to retrieve the key from the key store: {0}")]
+    KeyStore(String),
+    #[error("failed to parse JWK: {0}")]
+    JwkParse(String),
+}
+
 #[derive(uniffi::Object, Debug, Clone)]
 /// A verifiable credential secured as JSON.
 pub struct JsonVc {
@@ -127,10 +203,263 @@ impl JsonVc {
         }
     }
 
+    /// The identifier of the credential's issuer.
+    pub fn issuer_id(&self) -> Option<String> {
+        match &self.parsed {
+            AnyJsonCredential::V1(vc) => Some(vc.issuer.id().to_string()),
+            AnyJsonCredential::V2(vc) => Some(vc.issuer.id().to_string()),
+        }
+    }
+
+    /// The start of the credential's validity period as an ISO-8601 string,
+    /// from `issuanceDate` (VCDM v1) or `validFrom` (VCDM v2).
+    pub fn valid_from(&self) -> Option<String> {
+        match &self.parsed {
+            AnyJsonCredential::V1(vc) => vc.issuance_date.as_ref().map(ToString::to_string),
+            AnyJsonCredential::V2(vc) => vc.valid_from.as_ref().map(ToString::to_string),
+        }
+    }
+
+    /// The end of the credential's validity period as an ISO-8601 string,
+    /// from `expirationDate` (VCDM v1) or `validUntil` (VCDM v2).
+    pub fn valid_until(&self) -> Option<String> {
+        match &self.parsed {
+            AnyJsonCredential::V1(vc) => vc.expiration_date.as_ref().map(ToString::to_string),
+            AnyJsonCredential::V2(vc) => vc.valid_until.as_ref().map(ToString::to_string),
+        }
+    }
+
+    /// The identifiers of the credential subjects. Subjects without an `id` are omitted.
+    pub fn subject_ids(&self) -> Vec<String> {
+        let subjects = match &self.parsed {
+            AnyJsonCredential::V1(vc) => vc.credential_subjects.iter().collect::<Vec<_>>(),
+            AnyJsonCredential::V2(vc) => vc.credential_subjects.iter().collect::<Vec<_>>(),
+        };
+
+        subjects
+            .into_iter()
+            .filter_map(|subject| subject.get("id").next()?.as_string().map(ToOwned::to_owned))
+            .collect()
+    }
+
+    /// Whether the key the credential is bound to is the key stored under `alias`, i.e.
+    /// whether the wallet is able to present the credential.
+    ///
+    /// The bound key is taken from a `cnf.jwk` claim (on the credential or a subject),
+    /// otherwise from `did:jwk` or `did:key` subject identifiers.
+    pub fn bound_key_alias_matches(
+        &self,
+        keystore: Arc<dyn KeyStore>,
+        alias: KeyAlias,
+    ) -> Result<bool, JsonVcBindingError> {
+        let key = keystore
+            .get_signing_key(alias)
+            .and_then(|key| key.jwk())
+            .map_err(|e| JsonVcBindingError::KeyStore(format!("{e:#}")))?;
+        let key = parse_jwk(&key).map_err(|e| JsonVcBindingError::JwkParse(e.to_string()))?;
+
+        if let Some(bound_key) = self.confirmation_jwk() {
+            let bound_key: JWK = serde_json::from_value(bound_key.clone())
+                .map_err(|e| JsonVcBindingError::JwkParse(e.to_string()))?;
+            return Ok(bound_key.equals_public(&key));
+        }
+
+        let mut key_bound = false;
+        for subject_id in self.subject_ids() {
+            let did = subject_id.split('#').next().unwrap_or_default();
+
+            if let Some(encoded_jwk) = did.strip_prefix("did:jwk:") {
+                key_bound = true;
+                let bound_key: JWK = BASE64_URL_SAFE_NO_PAD
+                    .decode(encoded_jwk)
+                    .map_err(|e| JsonVcBindingError::JwkParse(e.to_string()))
+                    .and_then(|jwk| {
+                        serde_json::from_slice(&jwk)
+                            .map_err(|e| JsonVcBindingError::JwkParse(e.to_string()))
+                    })?;
+                if bound_key.equals_public(&key) {
+                    return Ok(true);
+                }
+            } else if did.starts_with("did:key:") {
+                key_bound = true;
+                if DIDKey::generate(&key).is_ok_and(|key_did| key_did.as_str() == did) {
+                    return Ok(true);
+                }
+            }
+        }
+
+        if key_bound {
+            Ok(false)
+        } else {
+            Err(JsonVcBindingError::NotKeyBound)
+        }
+    }
+
     /// Returns the status of the credential, resolving the value in the status list,
     /// along with the purpose of the status.
+    ///
+    /// Both `BitstringStatusListEntry` and `RevocationList2020Status` entries are supported.
     pub async fn status(&self) -> Result<Status, StatusListError> {
-        self.status_list_value().await
+        self.status_with_policy(OfflinePolicy::Online).await
+    }
+
+    /// Returns the status of the credential like [status](Self::status), unless `policy` is
+    /// offline, in which case the status list is not fetched and the status is unknown.
+    pub async fn status_with_policy(
+        &self,
+        policy: OfflinePolicy,
+    ) -> Result<Status, StatusListError> {
+        let entry = self.credential_status()?;
+
+        match (entry.get("type").and_then(Json::as_str), policy) {
+            (Some("BitstringStatusListEntry"), OfflinePolicy::Online) => {
+                self.status_list_value().await
+            }
+            (Some("BitstringStatusListEntry"), OfflinePolicy::Offline) => {
+                Ok(Status::unknown(self.status_list_entry()?.status_purpose))
+            }
+            (Some(REVOCATION_LIST_2020_STATUS), OfflinePolicy::Online) => {
+                revocation_list_2020_status(&entry).await
+            }
+            (Some(REVOCATION_LIST_2020_STATUS), OfflinePolicy::Offline) => {
+                Ok(Status::unknown(BitStringStatusPurpose::Revocation))
+            }
+            (Some(other), _) => Err(StatusListError::UnsupportedStatusType(other.to_string())),
+            (None, _) => Err(StatusListError::Resolution(
+                "Credential status is missing a type".into(),
+            )),
+        }
+    }
+
+    /// Verify the data-integrity proof of the credential against the issuer's DID.
+    ///
+    /// Only proofs using an accepted cryptosuite (e.g. `ecdsa-rdfc-2019`) or an accepted legacy
+    /// proof type (`Ed25519Signature2020`) are verified, any others are ignored and reported
+    /// as warnings.
+    pub async fn verify(&self) -> Result<VerificationReport, JsonVcVerificationError> {
+        self.verify_with(
+            &did_verification_parameters(CachingDidResolver::new(Default::default(), None)),
+            Duration::ZERO,
+        )
+        .await
+    }
+
+    /// Verify the data-integrity proof of the credential like [verify](Self::verify),
+    /// resolving the issuer's DID as configured, e.g. from cached `did:web` documents.
+    pub async fn verify_with_did_resolver(
+        &self,
+        resolver_config: DidResolverConfig,
+    ) -> Result<VerificationReport, JsonVcVerificationError> {
+        let resolver = ConfiguredDidResolver::try_from(resolver_config)
+            .map_err(|e| JsonVcVerificationError::Resolution(e.to_string()))?;
+        self.verify_with(
+            &did_verification_parameters(CachingDidResolver::new(resolver, None)),
+            Duration::ZERO,
+        )
+        .await
+    }
+
+    /// Verify the data-integrity proof of the credential like [verify](Self::verify).
+    ///
+    /// The proof is verified against the issuer's DID, so an offline `policy` fails with
+    /// [JsonVcVerificationError::OfflineDidResolution] rather than resolving it.
+    pub async fn verify_with_policy(
+        &self,
+        policy: OfflinePolicy,
+    ) -> Result<VerificationReport, JsonVcVerificationError> {
+        self.check_offline_policy(policy)?;
+        self.verify().await
+    }
+}
+
+/// Verification parameters resolving verification methods from any supported DID method.
+pub(crate) type DidVerificationParameters = VerificationParameters<
+    VerificationMethodDIDResolver<CachingDidResolver<ConfiguredDidResolver>, AnyMethod>,
+>;
+
+pub(crate) fn did_verification_parameters(
+    resolver: CachingDidResolver<ConfiguredDidResolver>,
+) -> DidVerificationParameters {
+    VerificationParameters::from_resolver(resolver.into_vm_resolver())
+}
+
+impl JsonVc {
+    /// Refuse offline verification, as data-integrity proofs require resolving the issuer's DID.
+    pub(crate) fn check_offline_policy(
+        &self,
+        policy: OfflinePolicy,
+    ) -> Result<(), JsonVcVerificationError> {
+        if policy.is_offline() {
+            return Err(JsonVcVerificationError::OfflineDidResolution(
+                self.issuer_id().unwrap_or_default(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Verify the data-integrity proof of the credential, resolving the issuer's DID with the
+    /// given parameters, so that they can be shared when verifying several credentials.
+    ///
+    /// The validity period may be off by `leeway` from the device clock.
+    pub(crate) async fn verify_with(
+        &self,
+        params: &DidVerificationParameters,
+        leeway: Duration,
+    ) -> Result<VerificationReport, JsonVcVerificationError> {
+        self.check_validity_period(leeway)?;
+
+        let mut credential = self.raw.clone();
+
+        let proofs = match credential.get_mut("proof").map(Json::take) {
+            Some(Json::Array(proofs)) => proofs,
+            Some(proof @ Json::Object(_)) => vec![proof],
+            _ => {
+                return Err(JsonVcVerificationError::ProofDecoding(
+                    "credential has no proof".into(),
+                ))
+            }
+        };
+
+        let (accepted, rejected): (Vec<Json>, Vec<Json>) =
+            proofs.into_iter().partition(is_accepted_proof);
+
+        if accepted.is_empty() {
+            return Err(JsonVcVerificationError::UnsupportedCryptosuite(
+                rejected
+                    .iter()
+                    .map(|proof| proof_suite(proof).to_string())
+                    .collect(),
+            ));
+        }
+
+        credential["proof"] = Json::Array(accepted);
+
+        let vc: AnyDataIntegrity<AnyJsonCredential> = serde_json::from_value(credential)
+            .map_err(|e| JsonVcVerificationError::ProofDecoding(e.to_string()))?;
+
+        vc.verify(params)
+            .await
+            .map_err(|e| match e {
+                // Verification method resolution failures surface as these two.
+                ProofValidationError::MissingPublicKey | ProofValidationError::Other(_) => {
+                    JsonVcVerificationError::Resolution(e.to_string())
+                }
+                ProofValidationError::MissingSignature | ProofValidationError::InvalidSignature => {
+                    JsonVcVerificationError::SignatureMismatch(e.to_string())
+                }
+                e => JsonVcVerificationError::InvalidProof(e.to_string()),
+            })?
+            .map_err(|e| JsonVcVerificationError::SignatureMismatch(e.to_string()))?;
+
+        Ok(VerificationReport {
+            warnings: rejected
+                .iter()
+                .map(|proof| VerificationWarning::UnsupportedProofIgnored {
+                    cryptosuite: proof_suite(proof).to_string(),
+                })
+                .collect(),
+            trust_mechanism: None,
+        })
     }
 }
 
@@ -173,6 +502,63 @@ impl JsonVc {
     pub fn format() -> CredentialFormat {
         CredentialFormat::LdpVc
     }
+
+    /// Check the validity period against the device clock, reporting the clock skew on failure.
+    fn check_validity_period(&self, leeway: Duration) -> Result<(), JsonVcVerificationError> {
+        let parse = |date: &str| OffsetDateTime::parse(date, &Rfc3339).ok();
+        let now = OffsetDateTime::now_utc();
+
+        if let Some(valid_from) = self.valid_from() {
+            if let Some(date) = parse(&valid_from).filter(|date| *date - now > leeway) {
+                return Err(JsonVcVerificationError::NotYetValid {
+                    valid_from,
+                    skew_seconds: clock_skew_seconds(date),
+                });
+            }
+        }
+
+        if let Some(valid_until) = self.valid_until() {
+            if let Some(date) = parse(&valid_until).filter(|date| now - *date >= leeway) {
+                return Err(JsonVcVerificationError::Expired {
+                    valid_until,
+                    skew_seconds: clock_skew_seconds(date),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The `cnf.jwk` claim of the credential, or of its first subject that has one.
+    fn confirmation_jwk(&self) -> Option<&Json> {
+        let subjects = match self.raw.get("credentialSubject") {
+            Some(Json::Array(subjects)) => subjects.iter().collect(),
+            Some(subject) => vec![subject],
+            None => vec![],
+        };
+
+        std::iter::once(&self.raw)
+            .chain(subjects)
+            .find_map(|claims| claims.get("cnf")?.get("jwk"))
+    }
+
+    /// The first `credentialStatus` entry of the credential, as JSON.
+    pub(crate) fn credential_status(&self) -> Result<Json, StatusListError> {
+        match &self.parsed {
+            AnyJsonCredential::V1(credential) => credential
+                .credential_status
+                .first()
+                .map(serde_json::to_value),
+            AnyJsonCredential::V2(credential) => credential
+                .credential_status
+                .first()
+                .map(serde_json::to_value),
+        }
+        .ok_or(StatusListError::Resolution(
+            "Credential status not found in credential".into(),
+        ))?
+        .map_err(|e| StatusListError::Resolution(format!("{e:?}")))
+    }
 }
 
 impl CredentialPresentation for JsonVc {
@@ -194,7 +580,7 @@ impl CredentialPresentation for JsonVc {
 
     fn create_descriptor_map(
         &self,
-        _options: ResponseOptions,
+        options: ResponseOptions,
         input_descriptor_id: impl Into<String>,
         index: Option<usize>,
     ) -> Result<DescriptorMap, OID4VPError> {
@@ -207,8 +593,15 @@ impl CredentialPresentation for JsonVc {
 
         let id = input_descriptor_id.into();
 
+        let vp_path = match options.path_prefix_override(&id) {
+            Some(prefix) => prefix
+                .parse()
+                .map_err(|e| OID4VPError::JsonPathParse(format!("{e:?}")))?,
+            None => JsonPath::default(),
+        };
+
         Ok(
-            DescriptorMap::new(id.clone(), self.presentation_format(), JsonPath::default())
+            DescriptorMap::new(id.clone(), self.presentation_format(), vp_path)
                 .set_path_nested(DescriptorMap::new(id, self.credential_format(), path)),
         )
     }
@@ -257,7 +650,7 @@ impl CredentialPresentation for JsonVc {
                                 if let Some(suite) = cryptosuite.as_string() {
                                     // Check if the cryptosuite is supported.
                                     // NOTE: we're filtering proofs for only supported
-                                    // cryptosuites, e.g., `ecdsa-rdfc-2019`
+                                    // cryptosuites, e.g., `ecdsa-rdfc-2019`, `eddsa-rdfc-2022`
                                     return ACCEPTED_CRYPTOSUITES.contains(&suite);
                                 }
                             }
@@ -286,20 +679,7 @@ impl CredentialPresentation for JsonVc {
 
 impl BitStringStatusListResolver for JsonVc {
     fn status_list_entry(&self) -> Result<BitstringStatusListEntry, StatusListError> {
-        let value = match &self.parsed {
-            AnyJsonCredential::V1(credential) => credential
-                .credential_status
-                .first()
-                .map(serde_json::to_value),
-            AnyJsonCredential::V2(credential) => credential
-                .credential_status
-                .first()
-                .map(serde_json::to_value),
-        }
-        .ok_or(StatusListError::Resolution(
-            "Credential status not found in credential".into(),
-        ))?
-        .map_err(|e| StatusListError::Resolution(format!("{e:?}")))?;
+        let value = self.credential_status()?;
 
         let entry = serde_json::from_value(value).map_err(|e| {
             StatusListError::Resolution(format!("Failed to parse credential status: {e:?}"))
@@ -352,3 +732,491 @@ fn try_map_subjects<T, U, E: std::fmt::Debug>(
         extra_properties: cred.extra_properties,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use wiremock::{matchers::any, Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[test]
+    fn v1_credential_details() {
+        let json_vc = JsonVc::new_from_json(
+            serde_json::json!({
+                "@context": ["https://www.w3.org/2018/credentials/v1"],
+                "type": ["VerifiableCredential"],
+                "issuer": { "id": "did:example:issuer", "name": "Example Issuer" },
+                "issuanceDate": "2024-01-01T00:00:00Z",
+                "expirationDate": "2029-01-01T00:00:00Z",
+                "credentialSubject": { "id": "did:example:subject" },
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(json_vc.issuer_id().as_deref(), Some("did:example:issuer"));
+        assert_eq!(
+            json_vc.valid_from().as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            json_vc.valid_until().as_deref(),
+            Some("2029-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            json_vc.subject_ids(),
+            vec!["did:example:subject".to_string()]
+        );
+    }
+
+    #[test]
+    fn v2_credential_details() {
+        let json_vc = JsonVc::new_from_json(
+            serde_json::json!({
+                "@context": ["https://www.w3.org/ns/credentials/v2"],
+                "type": ["VerifiableCredential"],
+                "issuer": "did:example:issuer",
+                "validFrom": "2024-01-01T00:00:00Z",
+                "credentialSubject": [
+                    { "id": "did:example:subject1" },
+                    { "name": "Anonymous" },
+                    { "id": "did:example:subject2" },
+                ],
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(json_vc.issuer_id().as_deref(), Some("did:example:issuer"));
+        assert_eq!(
+            json_vc.valid_from().as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+        assert_eq!(json_vc.valid_until(), None);
+        assert_eq!(
+            json_vc.subject_ids(),
+            vec![
+                "did:example:subject1".to_string(),
+                "did:example:subject2".to_string()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn unsupported_status_type() {
+        let json_vc = JsonVc::new_from_json(
+            serde_json::json!({
+                "@context": ["https://www.w3.org/2018/credentials/v1"],
+                "type": ["VerifiableCredential"],
+                "issuer": "did:example:issuer",
+                "issuanceDate": "2024-01-01T00:00:00Z",
+                "credentialSubject": { "id": "did:example:subject" },
+                "credentialStatus": {
+                    "id": "https://example.com/status/1#0",
+                    "type": "StatusList2021Entry",
+                },
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            json_vc.status().await,
+            Err(StatusListError::UnsupportedStatusType(ty)) if ty == "StatusList2021Entry"
+        ));
+    }
+
+    /// A credential whose status list is served by `server`.
+    fn credential_with_status(server: &MockServer, status_type: &str) -> Arc<JsonVc> {
+        let list = format!("{}/status/1", server.uri());
+        let status = match status_type {
+            REVOCATION_LIST_2020_STATUS => serde_json::json!({
+                "id": format!("{list}#3"),
+                "type": REVOCATION_LIST_2020_STATUS,
+                "revocationListIndex": "3",
+                "revocationListCredential": list,
+            }),
+            _ => serde_json::json!({
+                "id": format!("{list}#3"),
+                "type": status_type,
+                "statusPurpose": "suspension",
+                "statusListIndex": "3",
+                "statusListCredential": list,
+            }),
+        };
+
+        JsonVc::new_from_json(
+            serde_json::json!({
+                "@context": ["https://www.w3.org/2018/credentials/v1"],
+                "type": ["VerifiableCredential"],
+                "issuer": "did:example:issuer",
+                "issuanceDate": "2024-01-01T00:00:00Z",
+                "credentialSubject": { "id": "did:example:subject" },
+                "credentialStatus": status,
+            })
+            .to_string(),
+        )
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case::bitstring("BitstringStatusListEntry", BitStringStatusPurpose::Suspension)]
+    #[case::revocation_list_2020(REVOCATION_LIST_2020_STATUS, BitStringStatusPurpose::Revocation)]
+    #[tokio::test]
+    async fn offline_status_is_unknown(
+        #[case] status_type: &str,
+        #[case] purpose: BitStringStatusPurpose,
+    ) {
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let status = credential_with_status(&server, status_type)
+            .status_with_policy(OfflinePolicy::Offline)
+            .await
+            .unwrap();
+
+        assert!(status.is_unknown());
+        assert!(!status.is_revoked());
+        assert!(!status.is_suspended());
+        assert_eq!(status.purpose(), purpose);
+    }
+
+    #[tokio::test]
+    async fn offline_verification_requires_certificate_trust() {
+        let json_vc = JsonVc::new_from_json(include_str!("../../tests/res/vc").into()).unwrap();
+
+        assert!(matches!(
+            json_vc.verify_with_policy(OfflinePolicy::Offline).await,
+            Err(JsonVcVerificationError::OfflineDidResolution(_))
+        ));
+    }
+
+    fn key_bound_credential(binding: Json) -> Arc<JsonVc> {
+        let mut credential = serde_json::json!({
+            "@context": ["https://www.w3.org/2018/credentials/v1"],
+            "type": ["VerifiableCredential"],
+            "issuer": "did:example:issuer",
+            "issuanceDate": "2024-01-01T00:00:00Z",
+            "credentialSubject": {},
+        });
+        credential["credentialSubject"] = binding;
+        JsonVc::new_from_json(credential.to_string()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn bound_key_alias_matches() {
+        use crate::crypto::RustTestKeyManager;
+
+        let key_manager = Arc::new(RustTestKeyManager::default());
+        let bound = KeyAlias("bound".to_string());
+        let other = KeyAlias("other".to_string());
+        for alias in [&bound, &other] {
+            key_manager
+                .generate_p256_signing_key(alias.clone())
+                .await
+                .unwrap();
+        }
+
+        let bound_jwk: JWK = serde_json::from_str(
+            &key_manager
+                .get_signing_key(bound.clone())
+                .unwrap()
+                .jwk()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let credentials = [
+            key_bound_credential(serde_json::json!({
+                "cnf": { "jwk": bound_jwk },
+            })),
+            key_bound_credential(serde_json::json!({
+                "id": ssi::dids::DIDJWK::generate_url(&bound_jwk).to_string(),
+            })),
+            key_bound_credential(serde_json::json!({
+                "id": DIDKey::generate(&bound_jwk).unwrap().to_string(),
+            })),
+        ];
+
+        for credential in credentials {
+            assert!(credential
+                .bound_key_alias_matches(key_manager.clone(), bound.clone())
+                .unwrap());
+            assert!(!credential
+                .bound_key_alias_matches(key_manager.clone(), other.clone())
+                .unwrap());
+        }
+
+        let unbound = key_bound_credential(serde_json::json!({ "id": "did:example:subject" }));
+        assert!(matches!(
+            unbound.bound_key_alias_matches(key_manager, bound),
+            Err(JsonVcBindingError::NotKeyBound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn expired_credential_reports_clock_skew() {
+        // A device clock set an hour ahead of the expiration date.
+        let expiration = OffsetDateTime::now_utc() - time::Duration::hours(1);
+        let json_vc = JsonVc::new_from_json(
+            serde_json::json!({
+                "@context": ["https://www.w3.org/2018/credentials/v1"],
+                "type": ["VerifiableCredential"],
+                "issuer": "did:example:issuer",
+                "issuanceDate": "2024-01-01T00:00:00Z",
+                "expirationDate": expiration.format(&Rfc3339).unwrap(),
+                "credentialSubject": { "id": "did:example:subject" },
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        match json_vc.verify().await {
+            Err(JsonVcVerificationError::Expired { skew_seconds, .. }) => {
+                assert!((3600..3610).contains(&skew_seconds), "{skew_seconds}")
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_signed_credential() {
+        let json_vc = include_str!("../../tests/res/vc");
+        JsonVc::new_from_json(json_vc.into())
+            .unwrap()
+            .verify()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn verify_tampered_credential() {
+        let mut json: Json = serde_json::from_str(include_str!("../../tests/res/vc")).unwrap();
+        json["credentialSubject"]["id"] = Json::String("did:example:tampered".into());
+
+        let result = JsonVc::new_from_json(json.to_string())
+            .unwrap()
+            .verify()
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(JsonVcVerificationError::SignatureMismatch(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn verify_unresolvable_verification_method() {
+        let mut json: Json = serde_json::from_str(include_str!("../../tests/res/vc")).unwrap();
+        json["proof"][0]["verificationMethod"] = Json::String("did:key:zInvalid#zInvalid".into());
+
+        let result = JsonVc::new_from_json(json.to_string())
+            .unwrap()
+            .verify()
+            .await;
+
+        assert!(
+            matches!(result, Err(JsonVcVerificationError::Resolution(_))),
+            "unexpected result: {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn verify_unsupported_cryptosuite() {
+        let mut json: Json =
+            serde_json::from_str(include_str!("../../tests/examples/alumni_vc.json")).unwrap();
+        // Keep only the `ecdsa-sd-2023` proof.
+        json["proof"] = json["proof"][1].clone();
+
+        let result = JsonVc::new_from_json(json.to_string())
+            .unwrap()
+            .verify()
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(JsonVcVerificationError::UnsupportedCryptosuite(suites))
+                if suites == vec!["ecdsa-sd-2023".to_string()]
+        ));
+    }
+
+    /// Presentation signer backed by an Ed25519 JWK.
+    #[derive(Debug)]
+    struct Ed25519Signer {
+        jwk: ssi::JWK,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::oid4vp::presentation::PresentationSigner for Ed25519Signer {
+        async fn sign(
+            &self,
+            payload: Vec<u8>,
+        ) -> Result<Vec<u8>, crate::oid4vp::presentation::PresentationError> {
+            use ssi::claims::jws::JwsSigner;
+
+            self.jwk.sign_bytes(&payload).await.map_err(|e| {
+                crate::oid4vp::presentation::PresentationError::Signing(format!("{e:?}"))
+            })
+        }
+
+        fn algorithm(&self) -> ssi::crypto::Algorithm {
+            ssi::crypto::Algorithm::EdDSA
+        }
+
+        async fn verification_method(&self) -> String {
+            crate::did::DidMethod::Key
+                .vm_from_jwk(&self.jwk())
+                .await
+                .unwrap()
+                .id
+                .to_string()
+        }
+
+        fn did(&self) -> String {
+            crate::did::DidMethod::Key
+                .did_from_jwk(&self.jwk())
+                .unwrap()
+                .to_string()
+        }
+
+        fn cryptosuite(&self) -> ssi::claims::data_integrity::CryptosuiteString {
+            ssi::claims::data_integrity::CryptosuiteString::new("eddsa-rdfc-2022".to_string())
+                .unwrap()
+        }
+
+        fn jwk(&self) -> String {
+            serde_json::to_string(&self.jwk.to_public()).unwrap()
+        }
+    }
+
+    /// Issue a VCDM v2 credential signed with `eddsa-rdfc-2022` by a fresh `did:key`.
+    async fn ed25519_credential() -> Arc<JsonVc> {
+        use ssi::{
+            claims::data_integrity::{AnySuite, CryptographicSuite, ProofOptions},
+            dids::{DIDKey, DIDResolver as _},
+            verification_methods::SingleSecretSigner,
+        };
+
+        let issuer_jwk = ssi::JWK::generate_ed25519().unwrap();
+        let issuer_vm = DIDKey::generate_url(&issuer_jwk).unwrap();
+
+        let credential: JsonCredentialV2 = serde_json::from_value(serde_json::json!({
+            "@context": ["https://www.w3.org/ns/credentials/v2"],
+            "type": ["VerifiableCredential"],
+            "issuer": issuer_vm.did().as_str(),
+            "credentialSubject": { "name": "Alice" },
+        }))
+        .unwrap();
+
+        let signed = AnySuite::EdDsaRdfc2022
+            .sign(
+                credential,
+                DIDKey.into_vm_resolver(),
+                SingleSecretSigner::new(issuer_jwk).into_local(),
+                ProofOptions::from_method(issuer_vm.into_iri().into()),
+            )
+            .await
+            .unwrap();
+
+        JsonVc::new_from_json(serde_json::to_string(&signed).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn verify_ed25519_signature_2020_credential() {
+        use ssi::{
+            claims::{
+                data_integrity::{AnySuite, CryptographicSuite, ProofOptions},
+                vc::v1::JsonCredential,
+            },
+            dids::{DIDKey, DIDResolver as _},
+            verification_methods::SingleSecretSigner,
+        };
+
+        let issuer_jwk = ssi::JWK::generate_ed25519().unwrap();
+        let issuer_vm = DIDKey::generate_url(&issuer_jwk).unwrap();
+
+        let credential: JsonCredential = serde_json::from_value(serde_json::json!({
+            "@context": ["https://www.w3.org/2018/credentials/v1"],
+            "type": ["VerifiableCredential"],
+            "issuer": issuer_vm.did().as_str(),
+            "issuanceDate": "2024-01-01T00:00:00Z",
+            "credentialSubject": { "id": "did:example:subject" },
+        }))
+        .unwrap();
+
+        let signed = AnySuite::Ed25519Signature2020
+            .sign(
+                credential,
+                DIDKey.into_vm_resolver(),
+                SingleSecretSigner::new(issuer_jwk).into_local(),
+                ProofOptions::from_method(issuer_vm.into_iri().into()),
+            )
+            .await
+            .unwrap();
+        let json_vc = JsonVc::new_from_json(serde_json::to_string(&signed).unwrap()).unwrap();
+
+        let report = json_vc.verify().await.unwrap();
+        assert!(report.warnings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn present_ed25519_credential() {
+        let json_vc = ed25519_credential().await;
+        json_vc.verify().await.unwrap();
+
+        let request: openid4vp::core::authorization_request::AuthorizationRequestObject =
+            serde_json::from_value(serde_json::json!({
+                "client_id": "https://verifier.example.com",
+                "client_id_scheme": "redirect_uri",
+                "nonce": "nonce",
+                "response_type": "vp_token",
+                "response_mode": "direct_post",
+                "response_uri": "https://verifier.example.com/response",
+                "client_metadata": {
+                    "vp_formats": { "ldp_vp": { "proof_type": ["eddsa-rdfc-2022"] } }
+                },
+            }))
+            .unwrap();
+
+        let signer: Box<dyn crate::oid4vp::presentation::PresentationSigner> =
+            Box::new(Ed25519Signer {
+                jwk: ssi::JWK::generate_ed25519().unwrap(),
+            });
+        let response_options = ResponseOptions::default();
+        let options = PresentationOptions {
+            request: &request,
+            signer: Arc::new(signer),
+            context_map: None,
+            response_options: &response_options,
+            key_store: None,
+            user_authentication_error: Default::default(),
+        };
+
+        let vp_token_item = json_vc
+            .as_vp_token_item(&options, None, false)
+            .await
+            .unwrap();
+        let presentation = serde_json::to_value(&vp_token_item).unwrap();
+
+        // Single values may be serialized without an enclosing array.
+        let first = |value: &Json| match value {
+            Json::Array(values) => values[0].clone(),
+            value => value.clone(),
+        };
+
+        assert_eq!(
+            first(&presentation["proof"])["cryptosuite"],
+            "eddsa-rdfc-2022"
+        );
+        let credential = first(&presentation["verifiableCredential"]);
+        assert_eq!(
+            first(&credential["proof"])["cryptosuite"],
+            "eddsa-rdfc-2022"
+        );
+    }
+}
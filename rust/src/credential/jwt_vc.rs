@@ -24,6 +24,7 @@ use ssi::{
         JwsString,
     },
     json_ld::iref::UriBuf,
+    prelude::AnyJsonPresentation,
 };
 use uuid::Uuid;
 
@@ -198,22 +199,15 @@ impl CredentialPresentation for JwtVc {
         _selected_fields: Option<Vec<String>>,
         _limit_disclosure: bool,
     ) -> Result<VpTokenItem, OID4VPError> {
-        let vm = options.verification_method_id().await?.to_string();
-        let holder_id = options.signer.did();
-
-        let subject = self
-            .credential()
-            .credential_subjects
-            .iter()
-            .flat_map(|obj| obj.get("id"))
-            .find(|id| id.as_str() == Some(&holder_id));
-
-        if subject.is_none() {
-            return Err(OID4VPError::VpTokenCreate(
-                "supplied verificationMethod does not match the subject of the jwt-vc".into(),
-            ));
+        let format = options
+            .presentation_format(self.presentation_format(), &[ClaimFormatDesignation::LdpVp])?;
+        if format == ClaimFormatDesignation::LdpVp {
+            return self.as_ldp_vp_token_item(options).await;
         }
 
+        let vm = options.verification_method_id().await?.to_string();
+        let holder_id = self.holder_id(options)?;
+
         let mut vp = serde_json::to_value(JsonPresentation::new(
             UriBuf::new(format!("urn:uuid:{}", Uuid::new_v4()).as_bytes().to_vec()).ok(),
             holder_id.parse().ok(),
@@ -279,7 +273,10 @@ impl CredentialPresentation for JwtVc {
             .signer
             .sign(unsigned_vp_token_jwt.as_bytes().to_vec())
             .await
-            .map_err(|e| CredentialEncodingError::VpToken(format!("{e:?}")))?;
+            .map_err(|e| match e {
+                e if e.is_user_authentication_error() => OID4VPError::Presentation(e),
+                e => CredentialEncodingError::VpToken(format!("{e:?}")).into(),
+            })?;
 
         let signature = options
             .curve_utils()
@@ -302,10 +299,10 @@ impl CredentialPresentation for JwtVc {
         index: Option<usize>,
     ) -> Result<DescriptorMap, OID4VPError> {
         let id = input_descriptor_id.into();
-        let vp_path = if options.remove_vp_path_prefix {
-            "$"
-        } else {
-            "$.vp"
+        let vp_path = match options.path_prefix_override(&id) {
+            Some(prefix) => prefix,
+            None if options.remove_vp_path_prefix => "$",
+            None => "$.vp",
         }
         .parse()
         .map_err(|e| OID4VPError::JsonPathParse(format!("{e:?}")))?;
@@ -330,6 +327,85 @@ impl CredentialPresentation for JwtVc {
     }
 }
 
+impl JwtVc {
+    /// Return the DID of the signer, which must be a subject of the credential.
+    fn holder_id(&self, options: &PresentationOptions<'_>) -> Result<String, OID4VPError> {
+        let holder_id = options.signer.did();
+
+        let subject = self
+            .credential()
+            .credential_subjects
+            .iter()
+            .flat_map(|obj| obj.get("id"))
+            .find(|id| id.as_str() == Some(&holder_id));
+
+        if subject.is_none() {
+            return Err(OID4VPError::VpTokenCreate(
+                "supplied verificationMethod does not match the subject of the jwt-vc".into(),
+            ));
+        }
+
+        Ok(holder_id)
+    }
+
+    /// Present the credential in a data-integrity secured `ldp_vp`, for verifiers that do not
+    /// accept `jwt_vp_json`.
+    ///
+    /// The JWT is embedded as is, as a string in `verifiableCredential`.
+    async fn as_ldp_vp_token_item<'a>(
+        &self,
+        options: &'a PresentationOptions<'a>,
+    ) -> Result<VpTokenItem, OID4VPError> {
+        options.supports_security_method(ClaimFormatDesignation::LdpVp)?;
+
+        let id = UriBuf::new(format!("urn:uuid:{}", Uuid::new_v4()).as_bytes().to_vec())
+            .map_err(|e| CredentialEncodingError::VpToken(format!("Error parsing ID: {e:?}")))?;
+        let holder_id: UriBuf = self
+            .holder_id(options)?
+            .parse()
+            .map_err(|e| CredentialEncodingError::VpToken(format!("Error parsing DID: {e:?}")))?;
+
+        let unsigned_presentation: AnyJsonPresentation<JwsString> = AnyJsonPresentation::V1(
+            JsonPresentation::new(Some(id), Some(holder_id), vec![self.jws.clone()]),
+        );
+
+        let signed_presentation = options.sign_presentation(unsigned_presentation).await?;
+
+        serde_json::to_value(signed_presentation)
+            .and_then(serde_json::from_value)
+            .map_err(|e| CredentialEncodingError::VpToken(format!("{e:?}")).into())
+    }
+
+    /// Create a descriptor map for the credential presented in an `ldp_vp`, see
+    /// [as_ldp_vp_token_item](Self::as_ldp_vp_token_item).
+    pub(crate) fn create_ldp_vp_descriptor_map(
+        &self,
+        options: ResponseOptions,
+        input_descriptor_id: impl Into<String>,
+        index: Option<usize>,
+    ) -> Result<DescriptorMap, OID4VPError> {
+        let id = input_descriptor_id.into();
+        let vp_path = match options.path_prefix_override(&id) {
+            Some(prefix) => prefix,
+            None => "$",
+        }
+        .parse()
+        .map_err(|e| OID4VPError::JsonPathParse(format!("{e:?}")))?;
+
+        let cred_path = match index {
+            Some(idx) => format!("$.verifiableCredential[{idx}]"),
+            None => "$.verifiableCredential".into(),
+        }
+        .parse()
+        .map_err(|e| OID4VPError::JsonPathParse(format!("{e:?}")))?;
+
+        Ok(
+            DescriptorMap::new(id.clone(), ClaimFormatDesignation::LdpVp, vp_path)
+                .set_path_nested(DescriptorMap::new(id, self.credential_format(), cred_path)),
+        )
+    }
+}
+
 impl TryFrom<Credential> for Arc<JwtVc> {
     type Error = JwtVcInitError;
 
@@ -369,3 +445,102 @@ pub enum JwtVcInitError {
     #[error("failed to decode JWT payload as base64-encoded JSON")]
     PayloadDecoding,
 }
+
+#[cfg(test)]
+mod tests {
+    use openid4vp::core::authorization_request::AuthorizationRequestObject;
+    use serde_json::{json, Value as Json};
+
+    use super::*;
+    use crate::{oid4vp::presentation::PresentationSigner, tests::load_signer};
+
+    /// A JWT VC whose subject is `holder`. The signature is not checked when presenting.
+    fn jwt_vc(holder: &str) -> Arc<JwtVc> {
+        let encode = |value: Json| BASE64_URL_SAFE_NO_PAD.encode(value.to_string());
+        let header = encode(json!({ "alg": "ES256", "typ": "JWT" }));
+        let payload = encode(json!({
+            "iss": "did:example:issuer",
+            "sub": holder,
+            "vc": {
+                "@context": ["https://www.w3.org/2018/credentials/v1"],
+                "type": ["VerifiableCredential"],
+                "issuer": "did:example:issuer",
+                "issuanceDate": "2024-01-01T00:00:00Z",
+                "credentialSubject": { "id": holder },
+            },
+        }));
+        JwtVc::new_from_compact_jws(format!("{header}.{payload}.c2lnbmF0dXJl")).unwrap()
+    }
+
+    fn request(vp_formats: Json) -> AuthorizationRequestObject {
+        serde_json::from_value(json!({
+            "client_id": "https://verifier.example.com",
+            "client_id_scheme": "redirect_uri",
+            "nonce": "nonce",
+            "response_type": "vp_token",
+            "response_mode": "direct_post",
+            "response_uri": "https://verifier.example.com/response",
+            "client_metadata": { "vp_formats": vp_formats },
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn present_in_ldp_vp() {
+        let signer = load_signer();
+        let vc = jwt_vc(&signer.did());
+        let request = request(json!({ "ldp_vp": { "proof_type": ["ecdsa-rdfc-2019"] } }));
+
+        let signer: Box<dyn PresentationSigner> = Box::new(signer);
+        let response_options = ResponseOptions::default();
+        let options = PresentationOptions {
+            request: &request,
+            signer: Arc::new(signer),
+            context_map: None,
+            response_options: &response_options,
+            key_store: None,
+            user_authentication_error: Default::default(),
+        };
+
+        let vp_token_item = vc.as_vp_token_item(&options, None, false).await.unwrap();
+        let presentation = serde_json::to_value(&vp_token_item).unwrap();
+
+        let credential = match &presentation["verifiableCredential"] {
+            Json::Array(credentials) => credentials[0].clone(),
+            credential => credential.clone(),
+        };
+        assert_eq!(credential, Json::String(vc.jws.to_string()));
+        assert!(presentation.get("proof").is_some());
+
+        let descriptor_map = vc
+            .create_ldp_vp_descriptor_map(response_options.clone(), "id", None)
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(descriptor_map).unwrap()["format"],
+            json!("ldp_vp")
+        );
+    }
+
+    #[tokio::test]
+    async fn format_not_accepted() {
+        let signer = load_signer();
+        let vc = jwt_vc(&signer.did());
+        let request = request(json!({ "mso_mdoc": { "alg": ["ES256"] } }));
+
+        let signer: Box<dyn PresentationSigner> = Box::new(signer);
+        let response_options = ResponseOptions::default();
+        let options = PresentationOptions {
+            request: &request,
+            signer: Arc::new(signer),
+            context_map: None,
+            response_options: &response_options,
+            key_store: None,
+            user_authentication_error: Default::default(),
+        };
+
+        assert!(matches!(
+            vc.as_vp_token_item(&options, None, false).await,
+            Err(OID4VPError::FormatNotAccepted(_))
+        ));
+    }
+}
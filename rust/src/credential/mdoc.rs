@@ -5,20 +5,60 @@ use std::{
 
 use base64::prelude::*;
 use isomdl::{
-    definitions::{helpers::Tag24, IssuerSigned, Mso},
+    definitions::{
+        device_key::cose_key::OKPCurve,
+        helpers::{NonEmptyMap, NonEmptyVec, Tag24},
+        CoseKey, DeviceResponse, DigestAlgorithm, EC2Curve, IssuerSigned, Mso, EC2Y,
+    },
     presentation::{device::Document, Stringify},
 };
+use openid4vp::{
+    core::{
+        credential_format::ClaimFormatDesignation, presentation_definition::PresentationDefinition,
+        presentation_submission::DescriptorMap, response::parameters::VpTokenItem,
+    },
+    JsonPath,
+};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use serde_json::Value as Json;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 use uuid::Uuid;
+use x509_cert::{der::Encode, Certificate};
 
-use crate::{crypto::KeyAlias, CredentialType};
+use crate::{
+    crypto::KeyAlias,
+    oid4vp::{
+        error::OID4VPError,
+        iso_18013_7::{
+            prepare_response::{prepare_response, OpenID4VPHandover},
+            requested_values::{FieldId180137, FieldMap},
+        },
+        permission_request::RequestedField,
+        presentation::{CredentialPresentation, PresentationOptions},
+        ResponseOptions,
+    },
+    trusted_roots::parse_trusted_roots,
+    verifier::{
+        crypto::{CoseAlgorithm, CoseSignature, CoseVerifier, Crypto},
+        helpers,
+        outcome::{TrustMechanism, VerificationReport},
+    },
+    CredentialType,
+};
 
-use super::{Credential, CredentialFormat};
+use super::{Credential, CredentialEncodingError, CredentialFormat};
 
 uniffi::custom_newtype!(Namespace, String);
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 /// A namespace for mdoc data elements.
 pub struct Namespace(String);
 
+impl AsRef<str> for Namespace {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
 #[derive(Debug, Clone, uniffi::Record)]
 /// Simple representation of an mdoc data element.
 pub struct Element {
@@ -28,6 +68,19 @@ pub struct Element {
     pub value: Option<String>,
 }
 
+#[derive(Debug, Clone, uniffi::Record)]
+/// The validity period of an mdoc, with dates as RFC 3339 strings.
+pub struct MdocValidityInfo {
+    /// When the MSO was signed.
+    pub signed: String,
+    /// When the document becomes valid.
+    pub valid_from: String,
+    /// When the document expires.
+    pub valid_until: String,
+    /// When the issuer expects to re-sign the MSO, if given.
+    pub expected_update: Option<String>,
+}
+
 #[derive(uniffi::Object, Debug, Clone)]
 pub struct Mdoc {
     inner: Document,
@@ -42,15 +95,52 @@ impl Mdoc {
         base64url_encoded_issuer_signed: String,
         key_alias: KeyAlias,
     ) -> Result<Arc<Self>, MdocInitError> {
-        let issuer_signed = isomdl::cbor::from_slice(
-            &BASE64_URL_SAFE_NO_PAD
-                .decode(base64url_encoded_issuer_signed)
-                .map_err(|_| MdocInitError::IssuerSignedBase64UrlDecoding)?,
-        )
-        .map_err(|_| MdocInitError::IssuerSignedCborDecoding)?;
+        let issuer_signed = decode_issuer_signed(base64url_encoded_issuer_signed)?;
         Self::new_from_issuer_signed(key_alias, issuer_signed)
     }
 
+    #[uniffi::constructor]
+    /// Construct a new MDoc from base64url-encoded IssuerSigned, retaining only the data
+    /// elements listed in `keep` (namespace to element identifiers).
+    ///
+    /// The IssuerAuth is kept intact, so the MSO still contains the digests of the
+    /// removed elements. At least one element must be retained.
+    pub fn new_from_issuer_signed_filtered(
+        base64url_encoded_issuer_signed: String,
+        key_alias: KeyAlias,
+        keep: HashMap<String, Vec<String>>,
+    ) -> Result<Arc<Self>, MdocInitError> {
+        let IssuerSigned {
+            namespaces,
+            issuer_auth,
+        } = decode_issuer_signed(base64url_encoded_issuer_signed)?;
+
+        let namespaces = namespaces
+            .ok_or(MdocInitError::NamespacesMissing)?
+            .into_inner()
+            .into_iter()
+            .filter_map(|(namespace, elements)| {
+                let identifiers = keep.get(&namespace)?;
+                let elements = elements
+                    .into_inner()
+                    .into_iter()
+                    .filter(|element| identifiers.contains(&element.as_ref().element_identifier))
+                    .collect();
+                Some((namespace, NonEmptyVec::maybe_new(elements)?))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        Self::new_from_issuer_signed(
+            key_alias,
+            IssuerSigned {
+                namespaces: Some(
+                    NonEmptyMap::maybe_new(namespaces).ok_or(MdocInitError::NamespacesMissing)?,
+                ),
+                issuer_auth,
+            },
+        )
+    }
+
     #[uniffi::constructor]
     /// Compatibility feature: construct an MDoc from a
     /// [stringified spruceid/isomdl `Document`](https://github.com/spruceid/isomdl/blob/main/src/presentation/mod.rs#L100)
@@ -75,6 +165,45 @@ impl Mdoc {
         Ok(Arc::new(Self { inner, key_alias }))
     }
 
+    /// Replace the base64url-encoded IssuerSigned of this mdoc, e.g. once it has been
+    /// re-issued by its issuer with a new MSO.
+    ///
+    /// The returned mdoc keeps the local ID of this one, so that references to it remain
+    /// valid. The new IssuerSigned must be for the same doctype.
+    ///
+    /// Without a `key_alias`, the key alias of this mdoc is kept, and the new MSO must then be
+    /// bound to the same device key. An mdoc re-issued for another device key must be given the
+    /// `key_alias` of that key.
+    #[uniffi::method(default(key_alias = None))]
+    pub fn replace_issuer_signed(
+        &self,
+        base64url_encoded_issuer_signed: String,
+        key_alias: Option<KeyAlias>,
+    ) -> Result<Arc<Self>, MdocInitError> {
+        let issuer_signed = decode_issuer_signed(base64url_encoded_issuer_signed)?;
+        let keeps_key_alias = key_alias.is_none();
+        let mut mdoc = Arc::unwrap_or_clone(Self::new_from_issuer_signed(
+            key_alias.unwrap_or_else(|| self.key_alias.clone()),
+            issuer_signed,
+        )?);
+
+        if mdoc.doctype() != self.doctype() {
+            return Err(MdocInitError::DoctypeMismatch {
+                expected: self.doctype(),
+                found: mdoc.doctype(),
+            });
+        }
+
+        let device_key =
+            |mdoc: &Self| isomdl::cbor::to_vec(&mdoc.inner.mso.device_key_info.device_key).ok();
+        if keeps_key_alias && device_key(&mdoc) != device_key(self) {
+            return Err(MdocInitError::DeviceKeyMismatch(self.key_alias.0.clone()));
+        }
+
+        mdoc.inner.id = self.id();
+        Ok(Arc::new(mdoc))
+    }
+
     /// The local ID of this credential.
     pub fn id(&self) -> Uuid {
         self.inner.id
@@ -118,12 +247,178 @@ impl Mdoc {
             .collect()
     }
 
+    /// The raw bytes of a data element, if it exists and is encoded as a CBOR byte string.
+    pub fn element_bytes(&self, namespace: String, identifier: String) -> Option<Vec<u8>> {
+        let element = self
+            .document()
+            .namespaces
+            .get(&namespace)?
+            .get(&identifier)?
+            .as_ref();
+        match &element.element_value {
+            ciborium::Value::Bytes(bytes) => Some(bytes.clone()),
+            _ => None,
+        }
+    }
+
+    /// The JPEG-encoded `portrait` of an mDL, if present.
+    pub fn portrait_jpeg(&self) -> Option<Vec<u8>> {
+        self.element_bytes("org.iso.18013.5.1".into(), "portrait".into())
+            // JPEG start of image marker.
+            .filter(|bytes| bytes.starts_with(&[0xFF, 0xD8]))
+    }
+
     pub fn key_alias(&self) -> KeyAlias {
         self.key_alias.clone()
     }
+
+    /// The validity period of the document, as signed by the issuer in the MSO.
+    pub fn validity_info(&self) -> MdocValidityInfo {
+        let validity_info = &self.inner.mso.validity_info;
+        let format = |date: &OffsetDateTime| {
+            date.format(&Rfc3339)
+                .unwrap_or_else(|_| date.unix_timestamp().to_string())
+        };
+
+        MdocValidityInfo {
+            signed: format(&validity_info.signed),
+            valid_from: format(&validity_info.valid_from),
+            valid_until: format(&validity_info.valid_until),
+            expected_update: validity_info.expected_update.as_ref().map(format),
+        }
+    }
+
+    /// The digest algorithm used for the value digests in the MSO, e.g. `SHA-256`.
+    pub fn digest_algorithm(&self) -> String {
+        match self.inner.mso.digest_algorithm {
+            DigestAlgorithm::SHA256 => "SHA-256",
+            DigestAlgorithm::SHA384 => "SHA-384",
+            DigestAlgorithm::SHA512 => "SHA-512",
+        }
+        .to_string()
+    }
+
+    /// The device public key (`deviceKeyInfo.deviceKey`) from the MSO, as a JWK encoded as a
+    /// JSON string.
+    ///
+    /// This is the key the holder must use for device authentication, and can be compared
+    /// against the key identified by [Mdoc::key_alias]. Returns
+    /// [MdocError::DeviceKeyUnavailable] if the key uses a curve or encoding that cannot be
+    /// represented as a JWK.
+    pub fn device_key_jwk(&self) -> Result<String, MdocError> {
+        let unavailable = |reason: &str| MdocError::DeviceKeyUnavailable(reason.to_string());
+
+        let jwk = match &self.inner.mso.device_key_info.device_key {
+            CoseKey::EC2 { crv, x, y } => {
+                let crv = match crv {
+                    EC2Curve::P256 => "P-256",
+                    EC2Curve::P384 => "P-384",
+                    EC2Curve::P521 => "P-521",
+                    crv => return Err(unavailable(&format!("unsupported curve {crv:?}"))),
+                };
+                let y = match y {
+                    EC2Y::Value(y) => y.clone(),
+                    // Only P-256 points can be decompressed here.
+                    EC2Y::SignBit(sign) if crv == "P-256" => {
+                        let mut compressed = vec![if *sign { 0x03 } else { 0x02 }];
+                        compressed.extend_from_slice(x);
+                        p256::PublicKey::from_sec1_bytes(&compressed)
+                            .map_err(|_| unavailable("invalid compressed P-256 point"))?
+                            .to_encoded_point(false)
+                            .y()
+                            .ok_or_else(|| unavailable("invalid compressed P-256 point"))?
+                            .to_vec()
+                    }
+                    EC2Y::SignBit(_) => {
+                        return Err(unavailable(&format!(
+                            "compressed {crv} points are not supported"
+                        )))
+                    }
+                };
+                serde_json::json!({
+                    "kty": "EC",
+                    "crv": crv,
+                    "x": BASE64_URL_SAFE_NO_PAD.encode(x),
+                    "y": BASE64_URL_SAFE_NO_PAD.encode(y),
+                })
+            }
+            CoseKey::OKP { crv, x } => {
+                let crv = match crv {
+                    OKPCurve::Ed25519 => "Ed25519",
+                    OKPCurve::X25519 => "X25519",
+                    crv => return Err(unavailable(&format!("unsupported curve {crv:?}"))),
+                };
+                serde_json::json!({
+                    "kty": "OKP",
+                    "crv": crv,
+                    "x": BASE64_URL_SAFE_NO_PAD.encode(x),
+                })
+            }
+        };
+
+        Ok(jwk.to_string())
+    }
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl Mdoc {
+    /// Verify the issuer signature over the MSO.
+    ///
+    /// The document signer certificate is taken from the x5chain of `IssuerAuth`, and must
+    /// be issued by one of the provided PEM-encoded root certificates (e.g. IACAs).
+    pub async fn verify_issuer_signature(
+        &self,
+        crypto: &dyn Crypto,
+        trusted_roots_pem: Vec<String>,
+    ) -> Result<VerificationReport, MdocVerificationError> {
+        let trusted_roots = parse_trusted_roots(trusted_roots_pem)
+            .map_err(|e| MdocVerificationError::LoadRootCertificate(format!("{e:#}")))?;
+        self.verify_issuer_signature_with_roots(crypto, &trusted_roots)
+    }
 }
 
 impl Mdoc {
+    /// Verify the issuer signature over the MSO against already parsed root certificates.
+    pub(crate) fn verify_issuer_signature_with_roots(
+        &self,
+        crypto: &dyn Crypto,
+        trusted_roots: &[Certificate],
+    ) -> Result<VerificationReport, MdocVerificationError> {
+        let signer_certificate =
+            helpers::get_x5chain_signer_certificate(&self.inner.issuer_auth)
+                .map_err(|e| MdocVerificationError::SignerCertificateInvalid(format!("{e:#}")))?;
+
+        let root_certificate =
+            helpers::validate_signer_certificate(crypto, trusted_roots, &signer_certificate)?;
+
+        // Validate that Signer issued the MSO.
+        let verifier = CoseVerifier {
+            crypto,
+            certificate_der: signer_certificate
+                .to_der()
+                .map_err(|e| MdocVerificationError::SignerCertificateInvalid(e.to_string()))?,
+            algorithm: CoseAlgorithm::from_coset_header(&self.inner.issuer_auth.protected.header)
+                .map_err(|e| MdocVerificationError::BadSignature(format!("{e:#}")))?,
+        };
+
+        self.inner
+            .issuer_auth
+            .verify_signature(&[], |signature, payload| {
+                let signature = CoseSignature::try_from(signature).map_err(|e| e.to_string())?;
+                signature::Verifier::verify(&verifier, payload, &signature)
+                    .map_err(|e| format!("{e:?}"))
+            })
+            .map_err(MdocVerificationError::BadSignature)?;
+
+        Ok(VerificationReport {
+            warnings: [root_certificate, &signer_certificate]
+                .into_iter()
+                .filter_map(helpers::expiry_warning)
+                .collect(),
+            trust_mechanism: Some(TrustMechanism::CertificateChain),
+        })
+    }
+
     pub(crate) fn document(&self) -> &Document {
         &self.inner
     }
@@ -132,7 +427,7 @@ impl Mdoc {
         Self { inner, key_alias }
     }
 
-    fn new_from_issuer_signed(
+    pub(crate) fn new_from_issuer_signed(
         key_alias: KeyAlias,
         IssuerSigned {
             namespaces,
@@ -150,14 +445,12 @@ impl Mdoc {
                     .map(|i| (i.as_ref().element_identifier.clone(), i))
                     .collect::<BTreeMap<_, _>>()
                     .try_into()
-                    // Unwrap safety: safe to convert BTreeMap to NonEmptyMap since we're iterating over a NonEmptyVec.
-                    .unwrap();
-                (k, m)
+                    .map_err(|_| MdocInitError::NamespacesMissing)?;
+                Ok((k, m))
             })
-            .collect::<BTreeMap<_, _>>()
+            .collect::<Result<BTreeMap<_, _>, MdocInitError>>()?
             .try_into()
-            // Unwrap safety: safe to convert BTreeMap to NonEmptyMap since we're iterating over a NonEmptyMap.
-            .unwrap();
+            .map_err(|_| MdocInitError::NamespacesMissing)?;
 
         let mso: Tag24<Mso> = isomdl::cbor::from_slice(
             issuer_auth
@@ -179,6 +472,244 @@ impl Mdoc {
     }
 }
 
+// Presentation of the mdoc through OID4VP presentation exchange.
+impl Mdoc {
+    /// The data elements as JSON, keyed by namespace and element identifier, against which
+    /// presentation definition paths such as `$['org.iso.18013.5.1']['family_name']` are
+    /// matched.
+    pub(crate) fn claims_json(&self) -> Json {
+        Json::Object(
+            self.inner
+                .namespaces
+                .iter()
+                .map(|(namespace, elements)| {
+                    let elements = elements
+                        .iter()
+                        .map(|(identifier, element)| {
+                            let value = to_json_for_display(&element.as_ref().element_value)
+                                .unwrap_or(Json::Null);
+                            (identifier.clone(), value)
+                        })
+                        .collect();
+                    (namespace.clone(), Json::Object(elements))
+                })
+                .collect(),
+        )
+    }
+
+    /// The claims that [as_vp_token_item](CredentialPresentation::as_vp_token_item) would
+    /// disclose for the selected fields.
+    pub(crate) fn disclosed_claims(
+        &self,
+        selected_fields: Option<Vec<String>>,
+    ) -> Result<Json, OID4VPError> {
+        let mut claims = self.claims_json();
+        if let (Some(selected_fields), Json::Object(namespaces)) = (selected_fields, &mut claims) {
+            let selected = self.selected_elements(selected_fields)?;
+            namespaces.retain(|namespace, elements| {
+                let Some(identifiers) = selected.get(namespace) else {
+                    return false;
+                };
+                if let Json::Object(elements) = elements {
+                    elements.retain(|identifier, _| identifiers.contains(identifier));
+                }
+                true
+            });
+        }
+        Ok(claims)
+    }
+
+    /// Resolve the selected fields, i.e. base64url-encoded JSON paths into
+    /// [claims_json](Self::claims_json), to data element identifiers by namespace.
+    fn selected_elements(
+        &self,
+        selected_fields: Vec<String>,
+    ) -> Result<BTreeMap<String, Vec<String>>, OID4VPError> {
+        let json = self.claims_json();
+        let mut selected: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+        for field in selected_fields {
+            // Only the first of the alternative paths of a requested field is used.
+            let path = field.split(',').next().unwrap_or_default();
+            let path = BASE64_URL_SAFE
+                .decode(path)
+                .map_err(|e| OID4VPError::JsonPathParse(e.to_string()))?;
+            let path =
+                String::from_utf8(path).map_err(|e| OID4VPError::JsonPathParse(e.to_string()))?;
+            let path =
+                JsonPath::parse(&path).map_err(|e| OID4VPError::JsonPathParse(e.to_string()))?;
+
+            let located_nodes = path.query_located(&json);
+            let pointer = located_nodes
+                .first()
+                .ok_or_else(|| {
+                    OID4VPError::JsonPathResolve(format!("Unable to resolve JsonPath: {path}"))
+                })?
+                .location()
+                .to_json_pointer();
+
+            let segments = pointer
+                .split('/')
+                .skip(1)
+                .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+                .collect::<Vec<_>>();
+            let [namespace, identifier] = <[String; 2]>::try_from(segments).map_err(|_| {
+                OID4VPError::JsonPathResolve(format!("JsonPath is not a data element: {path}"))
+            })?;
+
+            selected.entry(namespace).or_default().push(identifier);
+        }
+
+        Ok(selected)
+    }
+
+    /// Build a `DeviceResponse` disclosing only the `selected` data elements, or every data
+    /// element if there is no selection.
+    ///
+    /// The device authentication is signed by the device key of the mdoc, i.e. the key of the
+    /// presentation key store under the mdoc key alias.
+    ///
+    /// The session transcript uses the `OpenID4VPHandover` of OpenID4VP 1.0 rather than the
+    /// ISO 18013-7 Annex B [Handover](crate::oid4vp::iso_18013_7::prepare_response::Handover).
+    /// The latter is bound to an `mdoc_generated_nonce`, which the verifier only learns from
+    /// the `apu` header of an encrypted `direct_post.jwt` response, whereas presentation
+    /// exchange responses are not encrypted.
+    fn device_response(
+        &self,
+        options: &PresentationOptions<'_>,
+        selected: Option<BTreeMap<String, Vec<String>>>,
+    ) -> Result<DeviceResponse, OID4VPError> {
+        let encoding_error = |e: String| CredentialEncodingError::VpToken(e);
+
+        let key_store = options.key_store.clone().ok_or_else(|| {
+            encoding_error("presenting an mdoc requires the key store of its device key".into())
+        })?;
+
+        let is_selected = |namespace: &String, identifier: &String| match &selected {
+            Some(selected) => selected
+                .get(namespace)
+                .is_some_and(|identifiers| identifiers.contains(identifier)),
+            None => true,
+        };
+
+        let mut field_map = FieldMap::new();
+        let mut approved_fields = Vec::new();
+        for (namespace, elements) in self.inner.namespaces.iter() {
+            for (identifier, element) in elements.iter() {
+                if !is_selected(namespace, identifier) {
+                    continue;
+                }
+                let field_id = FieldId180137(format!("{namespace}/{identifier}"));
+                field_map.insert(field_id.clone(), (namespace.clone(), element.clone()));
+                approved_fields.push(field_id);
+            }
+        }
+
+        let handover = OpenID4VPHandover::new(options.request)
+            .map_err(|e| encoding_error(format!("{e:#}")))?;
+
+        Ok(
+            prepare_response(key_store, self, approved_fields, &[], field_map, handover)
+                .map_err(|e| encoding_error(format!("{e:#}")))?,
+        )
+    }
+}
+
+impl CredentialPresentation for Mdoc {
+    type Credential = Document;
+    type CredentialFormat = ClaimFormatDesignation;
+    type PresentationFormat = ClaimFormatDesignation;
+
+    fn credential(&self) -> &Self::Credential {
+        &self.inner
+    }
+
+    fn presentation_format(&self) -> Self::PresentationFormat {
+        ClaimFormatDesignation::MsoMDoc
+    }
+
+    fn credential_format(&self) -> Self::CredentialFormat {
+        ClaimFormatDesignation::MsoMDoc
+    }
+
+    /// Mdocs are matched against their [claims_json](Mdoc::claims_json).
+    fn satisfies_presentation_definition(
+        &self,
+        presentation_definition: &PresentationDefinition,
+    ) -> bool {
+        if !presentation_definition.format().is_empty()
+            && !presentation_definition.contains_format(self.credential_format())
+        {
+            return false;
+        }
+
+        presentation_definition.is_credential_match(&self.claims_json())
+    }
+
+    fn requested_fields(
+        &self,
+        presentation_definition: &PresentationDefinition,
+    ) -> Vec<Arc<RequestedField>> {
+        let json = self.claims_json();
+        presentation_definition
+            .requested_fields(&json)
+            .into_iter()
+            .map(Into::into)
+            .map(Arc::new)
+            .collect()
+    }
+
+    fn create_descriptor_map(
+        &self,
+        options: ResponseOptions,
+        input_descriptor_id: impl Into<String>,
+        index: Option<usize>,
+    ) -> Result<DescriptorMap, OID4VPError> {
+        let input_descriptor_id = input_descriptor_id.into();
+        let path = match (options.path_prefix_override(&input_descriptor_id), index) {
+            (None, None) => JsonPath::default(),
+            (Some(prefix), None) => prefix
+                .parse()
+                .map_err(|e| OID4VPError::JsonPathParse(format!("{e:?}")))?,
+            (prefix, Some(i)) => format!("{}[{i}]", prefix.unwrap_or("$"))
+                .parse()
+                .map_err(|e| OID4VPError::JsonPathParse(format!("{e:?}")))?,
+        };
+
+        Ok(DescriptorMap::new(
+            input_descriptor_id,
+            self.credential_format(),
+            path,
+        ))
+    }
+
+    /// Return a base64url-encoded `DeviceResponse` disclosing the selected fields.
+    async fn as_vp_token_item<'a>(
+        &self,
+        options: &'a PresentationOptions<'a>,
+        selected_fields: Option<Vec<String>>,
+        limit_disclosure: bool,
+    ) -> Result<VpTokenItem, OID4VPError> {
+        let selected = match selected_fields {
+            Some(selected_fields) => Some(self.selected_elements(selected_fields)?),
+            None if limit_disclosure => {
+                return Err(OID4VPError::LimitDisclosure(
+                    "Limit disclosure is required, but no fields were selected.".to_string(),
+                ))
+            }
+            None => None,
+        };
+
+        let device_response = self.device_response(options, selected)?;
+        let device_response = isomdl::cbor::to_vec(&device_response)
+            .map_err(|e| CredentialEncodingError::VpToken(e.to_string()))?;
+
+        Ok(VpTokenItem::String(
+            BASE64_URL_SAFE_NO_PAD.encode(device_response),
+        ))
+    }
+}
+
 impl TryFrom<Credential> for Arc<Mdoc> {
     type Error = MdocInitError;
 
@@ -223,6 +754,56 @@ pub enum MdocInitError {
     NamespacesMissing,
     #[error("failed to decode Document from UTF-8 string")]
     DocumentUtf8Decoding,
+    #[error("expected an mdoc of doctype {expected}, found {found}")]
+    DoctypeMismatch { expected: String, found: String },
+    #[error("the mdoc is bound to another device key than the key `{0}`")]
+    DeviceKeyMismatch(String),
+}
+
+fn decode_issuer_signed(
+    base64url_encoded_issuer_signed: String,
+) -> Result<IssuerSigned, MdocInitError> {
+    isomdl::cbor::from_slice(
+        &BASE64_URL_SAFE_NO_PAD
+            .decode(base64url_encoded_issuer_signed)
+            .map_err(|_| MdocInitError::IssuerSignedBase64UrlDecoding)?,
+    )
+    .map_err(|_| MdocInitError::IssuerSignedCborDecoding)
+}
+
+#[derive(Debug, uniffi::Error, thiserror::Error)]
+pub enum MdocError {
+    #[error("the MSO device key cannot be represented as a JWK: {0}")]
+    DeviceKeyUnavailable(String),
+}
+
+#[derive(Debug, uniffi::Error, thiserror::Error)]
+pub enum MdocVerificationError {
+    #[error("failed to load root certificates: {0}")]
+    LoadRootCertificate(String),
+    #[error("document signer certificate is missing or invalid: {0}")]
+    SignerCertificateInvalid(String),
+    #[error("root certificate is expired or not yet valid")]
+    RootCertificateExpired,
+    #[error("document signer certificate is expired or not yet valid")]
+    SignerCertificateExpired,
+    #[error("document signer certificate does not chain to a trusted root: {0}")]
+    UntrustedChain(String),
+    #[error("IssuerAuth signature is invalid: {0}")]
+    BadSignature(String),
+}
+
+impl From<helpers::CertificateChainError> for MdocVerificationError {
+    fn from(error: helpers::CertificateChainError) -> Self {
+        use helpers::CertificateChainError;
+
+        match error {
+            CertificateChainError::UntrustedChain(e) => Self::UntrustedChain(e),
+            CertificateChainError::RootCertificateExpired => Self::RootCertificateExpired,
+            CertificateChainError::SignerCertificateExpired => Self::SignerCertificateExpired,
+            CertificateChainError::SignerCertificateInvalid(e) => Self::SignerCertificateInvalid(e),
+        }
+    }
 }
 
 #[derive(Debug, uniffi::Error, thiserror::Error)]
@@ -232,7 +813,7 @@ pub enum MdocEncodingError {
 }
 
 /// Convert a ciborium value to a serde_json value for display.
-fn to_json_for_display(value: &ciborium::Value) -> Option<serde_json::Value> {
+pub(crate) fn to_json_for_display(value: &ciborium::Value) -> Option<serde_json::Value> {
     /// Convert integer and text keys to strings for display.
     fn key_to_string_for_display(value: &ciborium::Value) -> Option<String> {
         match value {
@@ -287,3 +868,294 @@ fn to_json_for_display(value: &ciborium::Value) -> Option<serde_json::Value> {
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::{
+        crypto::KeyStore,
+        verifier::{crypto::RustCrypto, outcome::VerificationWarning},
+    };
+
+    use super::*;
+
+    const IACA_CERTIFICATE: &str = include_str!("../../tests/res/mdl/iaca-certificate.pem");
+    const IACA_KEY: &str = include_str!("../../tests/res/mdl/iaca-key.pem");
+    const EXPIRED_IACA_CERTIFICATE: &str =
+        include_str!("../../tests/res/mdl/utrecht-certificate.pem");
+
+    pub(crate) async fn mdoc_issued_by(iaca_cert_pem: &str, iaca_key_pem: &str) -> Mdoc {
+        let (key_manager, key_alias) = crate::mdl::util::test_device_key().await;
+        crate::mdl::util::generate_test_mdl_with_iaca(
+            key_manager,
+            key_alias,
+            iaca_cert_pem,
+            iaca_key_pem,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn issuer_signature_trusted() {
+        let mdoc = mdoc_issued_by(IACA_CERTIFICATE, IACA_KEY).await;
+
+        mdoc.verify_issuer_signature(&RustCrypto, vec![IACA_CERTIFICATE.to_string()])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn issuer_signature_signer_expiring_soon() {
+        let (key_manager, key_alias) = crate::mdl::util::test_device_key().await;
+        let mdoc = crate::mdl::util::generate_test_mdl_with_signer_validity(
+            key_manager,
+            key_alias,
+            IACA_CERTIFICATE,
+            IACA_KEY,
+            std::time::Duration::from_secs(60 * 60 * 24 * 10),
+        )
+        .unwrap();
+
+        let report = mdoc
+            .verify_issuer_signature(&RustCrypto, vec![IACA_CERTIFICATE.to_string()])
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            report.warnings.as_slice(),
+            [VerificationWarning::CertificateExpiringSoon {
+                subject,
+                expires_in_days,
+            }] if subject.contains("SpruceID Test DS") && *expires_in_days < 10
+        ));
+    }
+
+    #[tokio::test]
+    async fn issuer_signature_untrusted_chain() {
+        let mdoc = mdoc_issued_by(IACA_CERTIFICATE, IACA_KEY).await;
+
+        let result = mdoc
+            .verify_issuer_signature(&RustCrypto, vec![EXPIRED_IACA_CERTIFICATE.to_string()])
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(MdocVerificationError::UntrustedChain(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn issuer_signature_expired_root() {
+        let mdoc = mdoc_issued_by(
+            EXPIRED_IACA_CERTIFICATE,
+            include_str!("../../tests/res/mdl/utrecht-key.pem"),
+        )
+        .await;
+
+        let result = mdoc
+            .verify_issuer_signature(&RustCrypto, vec![EXPIRED_IACA_CERTIFICATE.to_string()])
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(MdocVerificationError::RootCertificateExpired)
+        ));
+    }
+
+    #[tokio::test]
+    async fn issuer_signature_tampered() {
+        let mdoc = mdoc_issued_by(IACA_CERTIFICATE, IACA_KEY).await;
+        let mut document = mdoc.document().clone();
+        document.issuer_auth.signature[0] ^= 0xff;
+        let mdoc = Mdoc::new_from_parts(document, mdoc.key_alias());
+
+        let result = mdoc
+            .verify_issuer_signature(&RustCrypto, vec![IACA_CERTIFICATE.to_string()])
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(MdocVerificationError::BadSignature(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn device_key_matches_key_store() {
+        let (key_manager, key_alias, mdoc) = crate::mdl::util::test_mdl().await;
+
+        let device_key: serde_json::Value =
+            serde_json::from_str(&mdoc.device_key_jwk().unwrap()).unwrap();
+        let stored_key: serde_json::Value = serde_json::from_str(
+            &key_manager
+                .get_signing_key(key_alias)
+                .unwrap()
+                .jwk()
+                .unwrap(),
+        )
+        .unwrap();
+
+        for field in ["kty", "crv", "x", "y"] {
+            assert_eq!(device_key[field], stored_key[field], "{field} differs");
+        }
+
+        let mut document = mdoc.document().clone();
+        document.mso.device_key_info.device_key = CoseKey::EC2 {
+            crv: EC2Curve::P384,
+            x: vec![0; 48],
+            y: EC2Y::SignBit(true),
+        };
+        assert!(matches!(
+            Mdoc::new_from_parts(document, mdoc.key_alias()).device_key_jwk(),
+            Err(MdocError::DeviceKeyUnavailable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn filtered_issuer_signed() {
+        let (_, key_alias, mdoc) = crate::mdl::util::test_mdl().await;
+
+        let document = mdoc.document();
+        let issuer_signed = encoded_issuer_signed(&mdoc);
+
+        let keep = [(
+            "org.iso.18013.5.1".to_string(),
+            vec!["family_name".to_string(), "not_present".to_string()],
+        )]
+        .into_iter()
+        .collect();
+        let filtered =
+            Mdoc::new_from_issuer_signed_filtered(issuer_signed.clone(), key_alias.clone(), keep)
+                .unwrap();
+
+        let details = filtered.details();
+        assert_eq!(details.len(), 1);
+        let elements = &details[&Namespace("org.iso.18013.5.1".to_string())];
+        assert_eq!(elements.len(), 1);
+        assert_eq!(elements[0].identifier, "family_name");
+        assert_eq!(filtered.doctype(), mdoc.doctype());
+        assert_eq!(
+            isomdl::cbor::to_vec(&filtered.document().issuer_auth).unwrap(),
+            isomdl::cbor::to_vec(&document.issuer_auth).unwrap()
+        );
+
+        assert!(matches!(
+            Mdoc::new_from_issuer_signed_filtered(issuer_signed, key_alias, HashMap::new()),
+            Err(MdocInitError::NamespacesMissing)
+        ));
+    }
+
+    /// The base64url-encoded IssuerSigned of an mdoc.
+    fn encoded_issuer_signed(mdoc: &Mdoc) -> String {
+        let document = mdoc.document();
+        let namespaces = document
+            .namespaces
+            .clone()
+            .into_inner()
+            .into_iter()
+            .map(|(namespace, elements)| {
+                let elements =
+                    NonEmptyVec::maybe_new(elements.into_inner().into_values().collect()).unwrap();
+                (namespace, elements)
+            })
+            .collect::<BTreeMap<_, _>>();
+        BASE64_URL_SAFE_NO_PAD.encode(
+            isomdl::cbor::to_vec(&IssuerSigned {
+                namespaces: NonEmptyMap::maybe_new(namespaces),
+                issuer_auth: document.issuer_auth.clone(),
+            })
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn reissued_mdoc_keeps_id() {
+        let (key_manager, key_alias, mdoc) = crate::mdl::util::test_mdl().await;
+        let reissued = crate::mdl::util::generate_test_mdl_with_validity(
+            key_manager,
+            key_alias.clone(),
+            include_str!("../../tests/res/mdl/utrecht-certificate.pem"),
+            include_str!("../../tests/res/mdl/utrecht-key.pem"),
+            std::time::Duration::from_secs(60 * 60 * 24 * 60),
+            std::time::Duration::from_secs(60 * 60 * 24 * 365),
+        )
+        .unwrap();
+
+        let refreshed = mdoc
+            .replace_issuer_signed(encoded_issuer_signed(&reissued), None)
+            .unwrap();
+
+        assert_eq!(refreshed.id(), mdoc.id());
+        assert_ne!(refreshed.id(), reissued.id());
+        assert_eq!(refreshed.key_alias(), key_alias);
+        assert_eq!(
+            refreshed.validity_info().valid_until,
+            reissued.validity_info().valid_until
+        );
+        assert_ne!(
+            refreshed.validity_info().valid_until,
+            mdoc.validity_info().valid_until
+        );
+    }
+
+    #[tokio::test]
+    async fn reissued_mdoc_for_another_device_key() {
+        let (key_manager, key_alias, mdoc) = crate::mdl::util::test_mdl().await;
+        let new_key_alias = KeyAlias("reissued_mdoc_new_key".to_string());
+        key_manager
+            .generate_p256_signing_key(new_key_alias.clone())
+            .await
+            .unwrap();
+        let reissued =
+            crate::mdl::util::generate_test_mdl(key_manager, new_key_alias.clone()).unwrap();
+
+        assert!(matches!(
+            mdoc.replace_issuer_signed(encoded_issuer_signed(&reissued), None),
+            Err(MdocInitError::DeviceKeyMismatch(alias)) if alias == key_alias.0
+        ));
+
+        let refreshed = mdoc
+            .replace_issuer_signed(
+                encoded_issuer_signed(&reissued),
+                Some(new_key_alias.clone()),
+            )
+            .unwrap();
+
+        assert_eq!(refreshed.id(), mdoc.id());
+        assert_eq!(refreshed.key_alias(), new_key_alias);
+    }
+
+    #[tokio::test]
+    async fn portrait_bytes() {
+        let (_, _, mdoc) = crate::mdl::util::test_mdl().await;
+
+        let portrait = BASE64_STANDARD
+            .decode(include_str!("../../tests/res/mdl/portrait.base64").trim())
+            .unwrap();
+
+        assert_eq!(mdoc.portrait_jpeg(), Some(portrait.clone()));
+        assert_eq!(
+            mdoc.element_bytes("org.iso.18013.5.1".into(), "portrait".into()),
+            Some(portrait)
+        );
+        assert_eq!(
+            mdoc.element_bytes("org.iso.18013.5.1".into(), "family_name".into()),
+            None
+        );
+        assert_eq!(
+            mdoc.element_bytes("org.example".into(), "portrait".into()),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn validity_info_and_digest_algorithm() {
+        let (_, _, mdoc) = crate::mdl::util::test_mdl().await;
+
+        let validity_info = mdoc.validity_info();
+        let parse = |date: &str| OffsetDateTime::parse(date, &Rfc3339).unwrap();
+
+        assert!(parse(&validity_info.signed) <= parse(&validity_info.valid_from));
+        assert!(parse(&validity_info.valid_from) < parse(&validity_info.valid_until));
+        assert_eq!(validity_info.expected_update, None);
+        assert_eq!(mdoc.digest_algorithm(), "SHA-256");
+    }
+}
@@ -1,9 +1,11 @@
 pub mod cwt;
+pub mod import;
 pub mod json_vc;
 pub mod jwt_vc;
 pub mod mdoc;
 pub mod status;
 pub mod status_20240406;
+pub mod status_revocation_list_2020;
 pub mod vcdm2_sd_jwt;
 
 use std::sync::Arc;
@@ -13,7 +15,10 @@ use crate::{
     oid4vp::{
         error::OID4VPError,
         permission_request::RequestedField,
-        presentation::{CredentialPresentation, PresentationError, PresentationOptions},
+        presentation::{
+            negotiate_presentation_format, CredentialPresentation, PresentationError,
+            PresentationOptions,
+        },
         ResponseOptions,
     },
     CredentialType,
@@ -23,6 +28,7 @@ use json_vc::{JsonVc, JsonVcEncodingError, JsonVcInitError};
 use jwt_vc::{JwtVc, JwtVcInitError};
 use mdoc::{Mdoc, MdocEncodingError, MdocInitError};
 use openid4vp::core::{
+    authorization_request::AuthorizationRequestObject, credential_format::ClaimFormatDesignation,
     presentation_definition::PresentationDefinition, presentation_submission::DescriptorMap,
     response::parameters::VpTokenItem,
 };
@@ -48,6 +54,43 @@ pub struct Credential {
     pub key_alias: Option<KeyAlias>,
 }
 
+impl Credential {
+    /// Construct a credential directly from its parts.
+    ///
+    /// The payload is not parsed or validated, so the credential can be stored and
+    /// retrieved without going through a format-specific parser.
+    pub fn new(
+        id: Uuid,
+        format: CredentialFormat,
+        r#type: CredentialType,
+        payload: Vec<u8>,
+        key_alias: Option<KeyAlias>,
+    ) -> Self {
+        Self {
+            id,
+            format,
+            r#type,
+            payload,
+            key_alias,
+        }
+    }
+}
+
+/// Construct a credential directly from its parts.
+///
+/// The payload is not parsed or validated, so the credential can be stored and
+/// retrieved without going through a format-specific parser.
+#[uniffi::export]
+pub fn credential_from_parts(
+    id: Uuid,
+    format: CredentialFormat,
+    r#type: CredentialType,
+    payload: Vec<u8>,
+    key_alias: Option<KeyAlias>,
+) -> Credential {
+    Credential::new(id, format, r#type, payload, key_alias)
+}
+
 // Internal helper methods.
 impl Credential {
     /// Convert the parsed credential into a specialized JSON credential.
@@ -93,10 +136,10 @@ impl PresentableCredential {
     }
 
     /// Return if the credential supports selective disclosure
-    /// For now only SdJwts are supported
+    /// For now only SdJwts and mdocs are supported
     pub fn selective_disclosable(&self) -> bool {
         match &self.inner {
-            ParsedCredentialInner::MsoMdoc(_) => false,
+            ParsedCredentialInner::MsoMdoc(_) => true,
             ParsedCredentialInner::JwtVcJson(_) => false,
             ParsedCredentialInner::JwtVcJsonLd(_) => false,
             ParsedCredentialInner::VCDM2SdJwt(_) => true,
@@ -365,6 +408,47 @@ impl PresentableCredential {
                 vc.as_vp_token_item(options, None, false).await
             }
             ParsedCredentialInner::LdpVc(vc) => vc.as_vp_token_item(options, None, false).await,
+            ParsedCredentialInner::MsoMdoc(mdoc) => {
+                mdoc.as_vp_token_item(options, self.selected_fields.clone(), self.limit_disclosure)
+                    .await
+            }
+            _ => Err(CredentialEncodingError::VpToken(format!(
+                "Credential encoding for VP Token is not implemented for {:?}.",
+                self.inner,
+            ))
+            .into()),
+        }
+    }
+
+    /// Return the claims that [as_vp_token](Self::as_vp_token) would disclose to the
+    /// verifier, along with whether the credential is disclosed in full because its format
+    /// does not support selective disclosure.
+    pub(crate) fn disclosed_claims(&self) -> Result<(serde_json::Value, bool), OID4VPError> {
+        match &self.inner {
+            ParsedCredentialInner::VCDM2SdJwt(sd_jwt) => {
+                let disclosed = VCDM2SdJwt::new_from_compact_sd_jwt(
+                    sd_jwt.disclosed_sd_jwt(self.selected_fields.clone())?,
+                )
+                .map_err(CredentialEncodingError::SdJwt)?;
+                Ok((
+                    disclosed
+                        .revealed_claims_as_json()
+                        .map_err(CredentialEncodingError::SdJwt)?,
+                    self.selected_fields.is_none(),
+                ))
+            }
+            ParsedCredentialInner::JwtVcJson(vc) | ParsedCredentialInner::JwtVcJsonLd(vc) => Ok((
+                CredentialPresentation::credential(vc.as_ref()).clone(),
+                true,
+            )),
+            ParsedCredentialInner::LdpVc(vc) => Ok((
+                CredentialPresentation::credential(vc.as_ref()).clone(),
+                true,
+            )),
+            ParsedCredentialInner::MsoMdoc(mdoc) => Ok((
+                mdoc.disclosed_claims(self.selected_fields.clone())?,
+                self.selected_fields.is_none(),
+            )),
             _ => Err(CredentialEncodingError::VpToken(format!(
                 "Credential encoding for VP Token is not implemented for {:?}.",
                 self.inner,
@@ -374,8 +458,12 @@ impl PresentableCredential {
     }
 
     /// Return the descriptor map with the associated format type of the inner credential.
+    ///
+    /// The format is the one the credential is presented in for the given request, see
+    /// [as_vp_token](Self::as_vp_token).
     pub fn create_descriptor_map(
         self: &Arc<Self>,
+        request: &AuthorizationRequestObject,
         options: ResponseOptions,
         input_descriptor_id: impl Into<String>,
         index: Option<usize>,
@@ -384,17 +472,23 @@ impl PresentableCredential {
             ParsedCredentialInner::VCDM2SdJwt(sd_jwt) => {
                 sd_jwt.create_descriptor_map(options, input_descriptor_id, index)
             }
-            ParsedCredentialInner::JwtVcJson(vc) => {
-                vc.create_descriptor_map(options, input_descriptor_id, index)
-            }
-            ParsedCredentialInner::JwtVcJsonLd(vc) => {
-                vc.create_descriptor_map(options, input_descriptor_id, index)
+            ParsedCredentialInner::JwtVcJson(vc) | ParsedCredentialInner::JwtVcJsonLd(vc) => {
+                match negotiate_presentation_format(
+                    request,
+                    vc.presentation_format(),
+                    &[ClaimFormatDesignation::LdpVp],
+                )? {
+                    ClaimFormatDesignation::LdpVp => {
+                        vc.create_ldp_vp_descriptor_map(options, input_descriptor_id, index)
+                    }
+                    _ => vc.create_descriptor_map(options, input_descriptor_id, index),
+                }
             }
             ParsedCredentialInner::LdpVc(vc) => {
                 vc.create_descriptor_map(options, input_descriptor_id, index)
             }
-            ParsedCredentialInner::MsoMdoc(_mdoc) => {
-                unimplemented!("Mdoc create descriptor map not implemented")
+            ParsedCredentialInner::MsoMdoc(mdoc) => {
+                mdoc.create_descriptor_map(options, input_descriptor_id, index)
             }
             ParsedCredentialInner::Cwt(_cwt) => {
                 unimplemented!("Cwt create descriptor map not implemented")
@@ -418,7 +512,9 @@ impl ParsedCredential {
             ParsedCredentialInner::VCDM2SdJwt(sd_jwt) => {
                 sd_jwt.satisfies_presentation_definition(definition)
             }
-            ParsedCredentialInner::MsoMdoc(_mdoc) => false,
+            ParsedCredentialInner::MsoMdoc(mdoc) => {
+                mdoc.satisfies_presentation_definition(definition)
+            }
             ParsedCredentialInner::Cwt(_cwt) => false,
         }
     }
@@ -436,9 +532,7 @@ impl ParsedCredential {
             ParsedCredentialInner::Cwt(_cwt) => {
                 unimplemented!("Cwt requested fields not implemented")
             }
-            ParsedCredentialInner::MsoMdoc(_mdoc) => {
-                unimplemented!("Mdoc requested fields not implemented")
-            }
+            ParsedCredentialInner::MsoMdoc(mdoc) => mdoc.requested_fields(definition),
         }
     }
 }
@@ -621,4 +715,84 @@ mod test {
 
         assert_eq!(CredentialFormat::MsoMdoc, roundtripped);
     }
+
+    #[tokio::test]
+    async fn credential_from_parts_roundtrips_through_vdc_collection() {
+        use crate::{local_store::LocalStore, vdc_collection::VdcCollection};
+
+        let vdc = VdcCollection::new(Arc::new(LocalStore::new()));
+
+        let credential = credential_from_parts(
+            Uuid::new_v4(),
+            CredentialFormat::Other("custom_format".into()),
+            CredentialType("CustomCredential".into()),
+            b"opaque payload".to_vec(),
+            Some(KeyAlias("key".into())),
+        );
+
+        vdc.add(&credential).await.unwrap();
+
+        let stored = vdc.get(credential.id).await.unwrap().unwrap();
+        assert_eq!(stored.id, credential.id);
+        assert_eq!(stored.format, credential.format);
+        assert_eq!(stored.r#type, credential.r#type);
+        assert_eq!(stored.payload, credential.payload);
+        assert_eq!(stored.key_alias, credential.key_alias);
+    }
+
+    /// Deterministic random inputs of up to 512 bytes.
+    fn random_inputs() -> Vec<Vec<u8>> {
+        use ssi::crypto::rand::{self, Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x5eed);
+        (0..256)
+            .map(|_| {
+                let len = rng.gen_range(0..512);
+                (0..len).map(|_| rng.gen()).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn constructors_reject_random_input() {
+        use base64::prelude::*;
+
+        let key_alias = KeyAlias("fuzz".into());
+
+        for input in random_inputs() {
+            assert!(Mdoc::from_cbor_encoded_document(input.clone(), key_alias.clone()).is_err());
+            assert!(Mdoc::new_from_base64url_encoded_issuer_signed(
+                BASE64_URL_SAFE_NO_PAD.encode(&input),
+                key_alias.clone()
+            )
+            .is_err());
+
+            let text = String::from_utf8_lossy(&input).into_owned();
+            assert!(JsonVc::new_from_json(text.clone()).is_err());
+            assert!(Cwt::new_from_base10(text).is_err());
+
+            let digits: String = input.iter().map(|b| char::from(b'0' + b % 10)).collect();
+            assert!(Cwt::new_from_base10(format!("9{digits}")).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn constructors_reject_truncated_input() {
+        let (_, key_alias, mdoc) = crate::mdl::util::test_mdl().await;
+        let document = isomdl::cbor::to_vec(mdoc.document()).unwrap();
+
+        for len in 0..document.len() {
+            assert!(
+                Mdoc::from_cbor_encoded_document(document[..len].to_vec(), key_alias.clone())
+                    .is_err()
+            );
+        }
+
+        let json_vc = include_str!("../../tests/res/vc");
+        for len in (0..json_vc.len()).step_by(97) {
+            if let Some(truncated) = json_vc.get(..len) {
+                assert!(JsonVc::new_from_json(truncated.to_string()).is_err());
+            }
+        }
+    }
 }
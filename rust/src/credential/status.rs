@@ -1,11 +1,22 @@
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 use reqwest::StatusCode;
+use serde_json::Value as Json;
 use ssi::status::bitstring_status_list::{
     BitString, BitstringStatusListCredential, BitstringStatusListEntry,
     StatusMessage as BitStringStatusMessage, StatusPurpose as BitStringStatusPurpose,
 };
 use url::Url;
+use uuid::Uuid;
+
+use super::{
+    status_revocation_list_2020::{
+        revocation_list_2020_entry, revocation_list_2020_status_from_list,
+        REVOCATION_LIST_2020_STATUS,
+    },
+    ParsedCredential, ParsedCredentialInner,
+};
+use crate::verifier::offline::OfflinePolicy;
 
 #[derive(Debug, uniffi::Error, thiserror::Error)]
 pub enum StatusListError {
@@ -13,6 +24,8 @@ pub enum StatusListError {
     Resolution(String),
     #[error("Credential Format Not Supported for Status List")]
     UnsupportedCredentialFormat,
+    #[error("Credential Status Type Not Supported: {0}")]
+    UnsupportedStatusType(String),
 }
 
 uniffi::custom_type!(BitStringStatusPurpose, String, {
@@ -51,8 +64,8 @@ impl From<BitStringStatusMessage> for StatusMessage {
 pub struct Status {
     /// The raw value of the status at the entry list index,
     /// which depends on the purpose of the status for its
-    /// meaning.
-    pub(crate) value: u8,
+    /// meaning, or `None` if the status list was not resolved.
+    pub(crate) value: Option<u8>,
     /// The purpose of the credential.
     pub(crate) purpose: BitStringStatusPurpose,
     /// List of status messages to include if the purpose is a message.
@@ -68,12 +81,12 @@ impl Status {
 
     /// Return whether the credential status is revoked.
     pub fn is_revoked(&self) -> bool {
-        self.purpose == BitStringStatusPurpose::Revocation && self.value == 1
+        self.purpose == BitStringStatusPurpose::Revocation && self.value == Some(1)
     }
 
     /// Return whether the credential status is suspended.
     pub fn is_suspended(&self) -> bool {
-        self.purpose == BitStringStatusPurpose::Suspension && self.value == 1
+        self.purpose == BitStringStatusPurpose::Suspension && self.value == Some(1)
     }
 
     /// Return whether the credential status has a message.
@@ -85,6 +98,23 @@ impl Status {
     pub fn messages(&self) -> Vec<StatusMessage> {
         self.status_messages.clone()
     }
+
+    /// Return whether the status is unknown, as its status list was not resolved, e.g. when
+    /// verifying offline. An unknown status is neither revoked nor suspended.
+    pub fn is_unknown(&self) -> bool {
+        self.value.is_none()
+    }
+}
+
+impl Status {
+    /// A status of the given purpose, whose status list was not resolved.
+    pub(crate) fn unknown(purpose: BitStringStatusPurpose) -> Self {
+        Self {
+            value: None,
+            purpose,
+            status_messages: vec![],
+        }
+    }
 }
 
 /// Interface for resolving the status of a credential
@@ -106,20 +136,7 @@ pub trait BitStringStatusListResolver {
             .parse()
             .map_err(|e| StatusListError::Resolution(format!("{e:?}")))?;
 
-        let response = reqwest::get(url)
-            .await
-            .map_err(|e| StatusListError::Resolution(format!("{e:?}")))?;
-
-        if response.status() != StatusCode::OK {
-            return Err(StatusListError::Resolution(format!(
-                "Failed to resolve status list credential: {}",
-                response.status()
-            )));
-        }
-
-        response
-            .json()
-            .await
+        serde_json::from_value(fetch_status_list(url).await?)
             .map_err(|e| StatusListError::Resolution(format!("{e:?}")))
     }
 
@@ -129,23 +146,303 @@ pub trait BitStringStatusListResolver {
     async fn status_list_value(&self) -> Result<Status, StatusListError> {
         let entry = self.status_list_entry()?;
         let credential = self.status_list_credential().await?;
-        let bit_string = credential
-            .credential_subject
-            .encoded_list
-            .decode(None)
-            .map(BitString::from_bytes)
-            .map_err(|e| StatusListError::Resolution(format!("{e:?}")))?;
+        bitstring_status(entry, &credential)
+    }
+}
 
-        let value = bit_string
-            .get(entry.status_size, entry.status_list_index)
-            .ok_or(StatusListError::Resolution(
-                "No status found at index".to_string(),
-            ))?;
+/// Fetch a status list credential, as JSON.
+pub(crate) async fn fetch_status_list(url: Url) -> Result<Json, StatusListError> {
+    let response = reqwest::get(url)
+        .await
+        .map_err(|e| StatusListError::Resolution(format!("{e:?}")))?;
 
-        Ok(Status {
-            value,
-            purpose: credential.credential_subject.status_purpose,
-            status_messages: entry.status_messages.into_iter().map(Into::into).collect(),
+    if response.status() != StatusCode::OK {
+        return Err(StatusListError::Resolution(format!(
+            "Failed to resolve status list credential: {}",
+            response.status()
+        )));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| StatusListError::Resolution(format!("{e:?}")))
+}
+
+/// The status of a bitstring status list entry, in an already resolved status list.
+fn bitstring_status(
+    entry: BitstringStatusListEntry,
+    credential: &BitstringStatusListCredential,
+) -> Result<Status, StatusListError> {
+    let bit_string = credential
+        .credential_subject
+        .encoded_list
+        .decode(None)
+        .map(BitString::from_bytes)
+        .map_err(|e| StatusListError::Resolution(format!("{e:?}")))?;
+
+    let value = bit_string
+        .get(entry.status_size, entry.status_list_index)
+        .ok_or(StatusListError::Resolution(
+            "No status found at index".to_string(),
+        ))?;
+
+    Ok(Status {
+        value: Some(value),
+        purpose: credential.credential_subject.status_purpose,
+        status_messages: entry.status_messages.into_iter().map(Into::into).collect(),
+    })
+}
+
+/// The status of a credential, resolved by [refresh_statuses].
+#[derive(Debug, uniffi::Record)]
+pub struct StatusResult {
+    pub credential_id: Uuid,
+    /// The status of the credential, if it could be resolved.
+    pub status: Option<Arc<Status>>,
+    /// Why the status could not be resolved.
+    pub error: Option<String>,
+}
+
+/// A status entry, to be evaluated against the status list it points into.
+enum ListEntry {
+    Bitstring(BitstringStatusListEntry),
+    RevocationList2020 { index: usize },
+}
+
+impl ListEntry {
+    /// The status of the entry, without its status list.
+    fn unknown_status(&self) -> Status {
+        match self {
+            Self::Bitstring(entry) => Status::unknown(entry.status_purpose),
+            Self::RevocationList2020 { .. } => Status::unknown(BitStringStatusPurpose::Revocation),
+        }
+    }
+
+    fn status(self, list: &Json) -> Result<Status, StatusListError> {
+        match self {
+            Self::Bitstring(entry) => {
+                let credential = serde_json::from_value(list.clone())
+                    .map_err(|e| StatusListError::Resolution(format!("{e:?}")))?;
+                bitstring_status(entry, &credential)
+            }
+            Self::RevocationList2020 { index } => {
+                revocation_list_2020_status_from_list(list, index)
+            }
+        }
+    }
+}
+
+/// Resolve the status of every credential, e.g. to refresh the statuses of a whole wallet.
+///
+/// Each status list is fetched once, however many credentials point into it. The results
+/// are returned in the order of `credentials`, and a credential whose status cannot be
+/// resolved does not prevent the others from being resolved.
+///
+/// Like [JsonVc::status](super::json_vc::JsonVc::status), `ldp_vc` credentials with a
+/// `BitstringStatusListEntry` or `RevocationList2020Status` entry are supported.
+///
+/// If `policy` is offline, no status list is fetched and every resolvable entry has an
+/// unknown status.
+#[uniffi::export(async_runtime = "tokio", default(policy = None))]
+pub async fn refresh_statuses(
+    credentials: Vec<Arc<ParsedCredential>>,
+    policy: Option<OfflinePolicy>,
+) -> Vec<StatusResult> {
+    let entries: Vec<_> = credentials
+        .iter()
+        .map(|credential| list_entry(credential))
+        .collect();
+
+    // Offline, no status list is fetched, leaving the status of every entry unknown.
+    let mut urls: Vec<Url> = match policy.unwrap_or_default() {
+        OfflinePolicy::Online => entries
+            .iter()
+            .flatten()
+            .map(|(url, _)| url.clone())
+            .collect(),
+        OfflinePolicy::Offline => vec![],
+    };
+    urls.sort();
+    urls.dedup();
+    let lists: HashMap<Url, Result<Json, String>> =
+        futures::future::join_all(urls.into_iter().map(|url| async move {
+            let list = fetch_status_list(url.clone())
+                .await
+                .map_err(|e| e.to_string());
+            (url, list)
+        }))
+        .await
+        .into_iter()
+        .collect();
+
+    credentials
+        .iter()
+        .zip(entries)
+        .map(|(credential, entry)| {
+            let status =
+                entry
+                    .map_err(|e| e.to_string())
+                    .and_then(|(url, entry)| match lists.get(&url) {
+                        Some(Ok(list)) => entry.status(list).map_err(|e| e.to_string()),
+                        Some(Err(e)) => Err(e.clone()),
+                        None => Ok(entry.unknown_status()),
+                    });
+            match status {
+                Ok(status) => StatusResult {
+                    credential_id: credential.id(),
+                    status: Some(Arc::new(status)),
+                    error: None,
+                },
+                Err(error) => StatusResult {
+                    credential_id: credential.id(),
+                    status: None,
+                    error: Some(error),
+                },
+            }
         })
+        .collect()
+}
+
+/// The status entry of a credential, and the URL of its status list.
+fn list_entry(credential: &ParsedCredential) -> Result<(Url, ListEntry), StatusListError> {
+    let ParsedCredentialInner::LdpVc(json_vc) = &credential.inner else {
+        return Err(StatusListError::UnsupportedCredentialFormat);
+    };
+    let entry = json_vc.credential_status()?;
+
+    match entry.get("type").and_then(Json::as_str) {
+        Some("BitstringStatusListEntry") => {
+            let entry: BitstringStatusListEntry = serde_json::from_value(entry).map_err(|e| {
+                StatusListError::Resolution(format!("Failed to parse credential status: {e:?}"))
+            })?;
+            let url = entry
+                .status_list_credential
+                .parse()
+                .map_err(|e| StatusListError::Resolution(format!("{e:?}")))?;
+            Ok((url, ListEntry::Bitstring(entry)))
+        }
+        Some(REVOCATION_LIST_2020_STATUS) => {
+            let (index, url) = revocation_list_2020_entry(&entry)?;
+            Ok((url, ListEntry::RevocationList2020 { index }))
+        }
+        Some(other) => Err(StatusListError::UnsupportedStatusType(other.to_string())),
+        None => Err(StatusListError::Resolution(
+            "Credential status is missing a type".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::credential::json_vc::JsonVc;
+
+    const REVOCATION_LIST: &str = include_str!("../../tests/res/revocation_list_2020.json");
+
+    fn credential_with_status(status_list: &str, index: usize) -> Arc<ParsedCredential> {
+        let json_vc = JsonVc::new_from_json(
+            json!({
+                "@context": ["https://www.w3.org/2018/credentials/v1"],
+                "type": ["VerifiableCredential"],
+                "issuer": "did:example:issuer",
+                "issuanceDate": "2024-01-01T00:00:00Z",
+                "credentialSubject": { "id": "did:example:subject" },
+                "credentialStatus": {
+                    "id": format!("{status_list}#{index}"),
+                    "type": REVOCATION_LIST_2020_STATUS,
+                    "revocationListIndex": index.to_string(),
+                    "revocationListCredential": status_list,
+                },
+            })
+            .to_string(),
+        )
+        .unwrap();
+        ParsedCredential::new_ldp_vc(json_vc)
+    }
+
+    #[tokio::test]
+    async fn shared_status_list_is_fetched_once() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/status/3"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(REVOCATION_LIST))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let status_list = format!("{}/status/3", server.uri());
+        let credentials = vec![
+            credential_with_status(&status_list, 0),
+            credential_with_status(&status_list, 1),
+        ];
+        let ids: Vec<Uuid> = credentials.iter().map(|c| c.id()).collect();
+
+        let results = refresh_statuses(credentials, None).await;
+
+        assert_eq!(
+            results.iter().map(|r| r.credential_id).collect::<Vec<_>>(),
+            ids
+        );
+        let revoked: Vec<bool> = results
+            .iter()
+            .map(|result| result.status.as_ref().unwrap().is_revoked())
+            .collect();
+        assert_eq!(revoked, vec![false, true]);
+    }
+
+    #[tokio::test]
+    async fn unresolvable_status_does_not_fail_others() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/status/3"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(REVOCATION_LIST))
+            .mount(&server)
+            .await;
+
+        let results = refresh_statuses(
+            vec![
+                credential_with_status(&format!("{}/status/missing", server.uri()), 0),
+                credential_with_status(&format!("{}/status/3", server.uri()), 1),
+            ],
+            None,
+        )
+        .await;
+
+        assert!(results[0].status.is_none());
+        assert!(results[0].error.is_some());
+        assert!(results[1].status.as_ref().unwrap().is_revoked());
+    }
+
+    #[tokio::test]
+    async fn offline_statuses_are_unknown() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(REVOCATION_LIST))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let results = refresh_statuses(
+            vec![credential_with_status(
+                &format!("{}/status/3", server.uri()),
+                1,
+            )],
+            Some(OfflinePolicy::Offline),
+        )
+        .await;
+
+        let status = results[0].status.as_ref().unwrap();
+        assert!(status.is_unknown());
+        assert!(!status.is_revoked());
     }
 }
@@ -1,4 +1,5 @@
 use super::status::{StatusListError, StatusMessage};
+use crate::verifier::offline::OfflinePolicy;
 
 use std::str::FromStr;
 
@@ -39,8 +40,8 @@ impl From<BitStringStatusMessage> for StatusMessage {
 pub struct Status20240406 {
     /// The raw value of the status at the entry list index,
     /// which depends on the purpose of the status for its
-    /// meaning.
-    pub(crate) value: u8,
+    /// meaning, or `None` if the status list was not resolved.
+    pub(crate) value: Option<u8>,
     /// The purpose of the credential.
     pub(crate) purpose: BitString20240406StatusPurpose,
     /// List of status messages to include if the purpose is a message.
@@ -56,12 +57,12 @@ impl Status20240406 {
 
     /// Return whether the credential status is revoked.
     pub fn is_revoked(&self) -> bool {
-        self.purpose == BitString20240406StatusPurpose::Revocation && self.value == 1
+        self.purpose == BitString20240406StatusPurpose::Revocation && self.value == Some(1)
     }
 
     /// Return whether the credential status is suspended.
     pub fn is_suspended(&self) -> bool {
-        self.purpose == BitString20240406StatusPurpose::Suspension && self.value == 1
+        self.purpose == BitString20240406StatusPurpose::Suspension && self.value == Some(1)
     }
 
     /// Return whether the credential status has a message.
@@ -73,6 +74,23 @@ impl Status20240406 {
     pub fn messages(&self) -> Vec<StatusMessage> {
         self.status_messages.clone()
     }
+
+    /// Return whether the status is unknown, as its status list was not resolved, e.g. when
+    /// verifying offline. An unknown status is neither revoked nor suspended.
+    pub fn is_unknown(&self) -> bool {
+        self.value.is_none()
+    }
+}
+
+impl Status20240406 {
+    /// A status of the given purpose, whose status list was not resolved.
+    pub(crate) fn unknown(purpose: BitString20240406StatusPurpose) -> Self {
+        Self {
+            value: None,
+            purpose,
+            status_messages: vec![],
+        }
+    }
 }
 
 /// Interface for resolving the status of a credential
@@ -119,6 +137,23 @@ pub trait BitStringStatusListResolver20240406 {
             .collect()
     }
 
+    /// Returns the status of the credential like [status_list_values](Self::status_list_values),
+    /// unless `policy` is offline, in which case the status lists are not fetched and the status
+    /// of each entry is unknown.
+    async fn status_list_values_with_policy(
+        &self,
+        policy: OfflinePolicy,
+    ) -> Result<Vec<Status20240406>, StatusListError> {
+        if policy.is_offline() {
+            return Ok(self
+                .status_list_entries()?
+                .into_iter()
+                .map(|entry| Status20240406::unknown(entry.status_purpose))
+                .collect());
+        }
+        self.status_list_values().await
+    }
+
     /// Returns the status of the credential, returning
     /// an object that provides the value in the status list,
     /// and the purpose of the status.
@@ -153,7 +188,7 @@ pub trait BitStringStatusListResolver20240406 {
                     ))?;
 
                 Ok(Status20240406 {
-                    value,
+                    value: Some(value),
                     purpose: credential.credential_subject.status_purpose,
                     status_messages: credential
                         .credential_subject
@@ -0,0 +1,171 @@
+use super::status::{fetch_status_list, Status, StatusListError};
+
+use base64::{
+    prelude::{BASE64_STANDARD_NO_PAD, BASE64_URL_SAFE_NO_PAD},
+    Engine,
+};
+use serde_json::Value as Json;
+use ssi::status::bitstring_status_list::StatusPurpose as BitStringStatusPurpose;
+use url::Url;
+
+/// The `credentialStatus` type of a [RevocationList2020](https://w3c-ccg.github.io/vc-status-rl-2020/) entry.
+pub const REVOCATION_LIST_2020_STATUS: &str = "RevocationList2020Status";
+
+/// Resolve the status of a `RevocationList2020Status` credential status entry.
+///
+/// The revocation list credential is fetched from `revocationListCredential`, and the
+/// bit at `revocationListIndex` of its encoded list is returned as a revocation status.
+pub async fn revocation_list_2020_status(entry: &Json) -> Result<Status, StatusListError> {
+    let (index, url) = revocation_list_2020_entry(entry)?;
+    let credential = fetch_status_list(url).await?;
+    revocation_list_2020_status_from_list(&credential, index)
+}
+
+/// The `revocationListIndex` and `revocationListCredential` URL of a status entry.
+pub(crate) fn revocation_list_2020_entry(entry: &Json) -> Result<(usize, Url), StatusListError> {
+    let index = match entry.get("revocationListIndex") {
+        Some(Json::String(index)) => index.parse::<usize>().ok(),
+        Some(Json::Number(index)) => index.as_u64().map(|index| index as usize),
+        _ => None,
+    }
+    .ok_or(StatusListError::Resolution(
+        "Missing or invalid revocationListIndex".to_string(),
+    ))?;
+
+    let url: Url = entry
+        .get("revocationListCredential")
+        .and_then(Json::as_str)
+        .ok_or(StatusListError::Resolution(
+            "Missing revocationListCredential".to_string(),
+        ))?
+        .parse()
+        .map_err(|e| StatusListError::Resolution(format!("{e:?}")))?;
+
+    Ok((index, url))
+}
+
+/// The revocation status at `index` of an already resolved revocation list credential.
+pub(crate) fn revocation_list_2020_status_from_list(
+    credential: &Json,
+    index: usize,
+) -> Result<Status, StatusListError> {
+    let encoded_list = credential
+        .get("credentialSubject")
+        .and_then(|subject| subject.get("encodedList"))
+        .and_then(Json::as_str)
+        .ok_or(StatusListError::Resolution(
+            "Revocation list credential is missing encodedList".to_string(),
+        ))?;
+
+    Ok(Status {
+        value: Some(revocation_list_value(encoded_list, index)?),
+        purpose: BitStringStatusPurpose::Revocation,
+        status_messages: vec![],
+    })
+}
+
+/// Returns the bit at `index` of a base64-encoded, GZIP-compressed revocation list,
+/// where index 0 is the most significant bit of the first byte.
+fn revocation_list_value(encoded_list: &str, index: usize) -> Result<u8, StatusListError> {
+    let encoded_list = encoded_list.trim_end_matches('=');
+    let compressed = BASE64_URL_SAFE_NO_PAD
+        .decode(encoded_list)
+        .or_else(|_| BASE64_STANDARD_NO_PAD.decode(encoded_list))
+        .map_err(|e| StatusListError::Resolution(format!("{e:?}")))?;
+
+    let list = gunzip(&compressed)?;
+
+    let byte = list.get(index / 8).ok_or(StatusListError::Resolution(
+        "No status found at index".to_string(),
+    ))?;
+
+    Ok((byte >> (7 - index % 8)) & 1)
+}
+
+/// Decompress a GZIP member (RFC 1952), skipping over the optional header fields.
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, StatusListError> {
+    const FHCRC: u8 = 0x02;
+    const FEXTRA: u8 = 0x04;
+    const FNAME: u8 = 0x08;
+    const FCOMMENT: u8 = 0x10;
+
+    let invalid = || StatusListError::Resolution("Encoded list is not valid GZIP".to_string());
+
+    if bytes.len() < 10 || bytes[0..3] != [0x1f, 0x8b, 0x08] {
+        return Err(invalid());
+    }
+
+    let flags = bytes[3];
+    let mut offset = 10;
+
+    if flags & FEXTRA != 0 {
+        let len = bytes.get(offset..offset + 2).ok_or_else(invalid)?;
+        offset += 2 + u16::from_le_bytes([len[0], len[1]]) as usize;
+    }
+
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let end = bytes
+                .get(offset..)
+                .and_then(|rest| rest.iter().position(|b| *b == 0))
+                .ok_or_else(invalid)?;
+            offset += end + 1;
+        }
+    }
+
+    if flags & FHCRC != 0 {
+        offset += 2;
+    }
+
+    miniz_oxide::inflate::decompress_to_vec(bytes.get(offset..).ok_or_else(invalid)?)
+        .map_err(|e| StatusListError::Resolution(format!("{e:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    const REVOCATION_LIST: &str = include_str!("../../tests/res/revocation_list_2020.json");
+
+    #[rstest]
+    #[case::active(0, false)]
+    #[case::revoked(1, true)]
+    #[case::active_after_revoked(2, false)]
+    #[tokio::test]
+    async fn resolve_revocation_list_2020_status(#[case] index: usize, #[case] revoked: bool) {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/status/3"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(REVOCATION_LIST))
+            .mount(&server)
+            .await;
+
+        let entry = json!({
+            "id": format!("{}/status/3#{index}", server.uri()),
+            "type": REVOCATION_LIST_2020_STATUS,
+            "revocationListIndex": index.to_string(),
+            "revocationListCredential": format!("{}/status/3", server.uri()),
+        });
+
+        let status = revocation_list_2020_status(&entry).await.unwrap();
+
+        assert_eq!(status.is_revoked(), revoked);
+        assert_eq!(status.purpose(), BitStringStatusPurpose::Revocation);
+    }
+
+    #[test]
+    fn index_out_of_range() {
+        let list: Json = serde_json::from_str(REVOCATION_LIST).unwrap();
+        let encoded_list = list["credentialSubject"]["encodedList"].as_str().unwrap();
+
+        assert!(revocation_list_value(encoded_list, 16 * 1024 * 8).is_err());
+    }
+}
@@ -3,7 +3,7 @@ use super::{
     status_20240406::{
         BitStringStatusListResolver20240406 as BitStringStatusListResolver, Status20240406,
     },
-    Credential, CredentialFormat, ParsedCredential, ParsedCredentialInner,
+    Credential, CredentialEncodingError, CredentialFormat, ParsedCredential, ParsedCredentialInner,
 };
 use crate::{
     crypto::KeyAlias,
@@ -12,13 +12,14 @@ use crate::{
         presentation::{CredentialPresentation, PresentationOptions},
         ResponseOptions,
     },
+    verifier::offline::OfflinePolicy,
     CredentialType,
 };
 
 use core::str;
 use std::sync::Arc;
 
-use base64::{engine::general_purpose::URL_SAFE, Engine as _};
+use base64::{engine::general_purpose::URL_SAFE, prelude::BASE64_URL_SAFE_NO_PAD, Engine as _};
 use futures::stream::{self, StreamExt};
 use openid4vp::{
     core::{
@@ -28,6 +29,7 @@ use openid4vp::{
     JsonPath,
 };
 use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
 use ssi::{
     claims::{
         jwt::AnyClaims,
@@ -44,6 +46,9 @@ use ssi::{
 use url::Url;
 use uuid::Uuid;
 
+/// The `typ` header of key binding JWTs.
+const KEY_BINDING_JWT_TYPE: &str = "kb+jwt";
+
 #[derive(Debug, uniffi::Object)]
 pub struct VCDM2SdJwt {
     pub(crate) id: Uuid,
@@ -60,6 +65,84 @@ impl VCDM2SdJwt {
             .map_err(|e| SdJwtError::Serialization(format!("{e:?}")))
     }
 
+    /// Return the compact SD-JWT presented to a verifier, retaining only the disclosures
+    /// needed to reveal `selected_fields`, or every disclosure if no fields are selected.
+    pub(crate) fn disclosed_sd_jwt(
+        &self,
+        selected_fields: Option<Vec<String>>,
+    ) -> Result<String, OID4VPError> {
+        let compact: &str = self.inner.as_ref();
+        let sd_jwt = if let Some(selected_fields) = selected_fields {
+            let json = self.revealed_claims_as_json().map_err(|e| {
+                OID4VPError::CredentialEncoding(super::CredentialEncodingError::SdJwt(e))
+            })?;
+
+            let selected_fields_pointers = selected_fields
+                .into_iter()
+                .map(|sfield| {
+                    // TODO: Remove hotfix encoding and improve path usage
+                    // SAFETY: encoded by client (sprucekit-mobile@holder)
+                    let path = sfield.split(",").next().unwrap().to_owned();
+                    let path = match URL_SAFE.decode(path) {
+                        Ok(path) => path,
+                        Err(err) => return Err(OID4VPError::JsonPathParse(err.to_string())),
+                    };
+                    let path = match str::from_utf8(&path) {
+                        Ok(path) => path,
+                        Err(err) => return Err(OID4VPError::JsonPathParse(err.to_string())),
+                    };
+                    let path = match JsonPath::parse(path) {
+                        Ok(path) => path,
+                        Err(err) => return Err(OID4VPError::JsonPathParse(err.to_string())),
+                    };
+                    let located_node = path.query_located(&json);
+
+                    if located_node.is_empty() {
+                        Err(OID4VPError::JsonPathResolve(format!(
+                            "Unable to resolve JsonPath: {}",
+                            path
+                        )))
+                    } else {
+                        // SAFETY: Empty check above
+                        JsonPointerBuf::new(
+                            located_node.first().unwrap().location().to_json_pointer(),
+                        )
+                        .map_err(|e| OID4VPError::JsonPathToPointer(e.to_string()))
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let ret = self
+                .inner
+                .decode_reveal::<AnyClaims>()
+                .map_err(|e| OID4VPError::Debug(e.to_string()))?
+                .retaining(&selected_fields_pointers)
+                .into_encoded()
+                .as_str()
+                .to_string();
+            ret
+        } else {
+            compact.to_string()
+        };
+
+        Ok(sd_jwt)
+    }
+
+    /// Whether the issuer bound the credential to a holder key with a `cnf` claim, in which
+    /// case presentations must end with a key binding JWT.
+    fn requires_key_binding(&self) -> Result<bool, OID4VPError> {
+        let compact: &str = self.inner.as_ref();
+        let payload = compact
+            .split('~')
+            .next()
+            .and_then(|jwt| jwt.split('.').nth(1))
+            .and_then(|payload| BASE64_URL_SAFE_NO_PAD.decode(payload).ok())
+            .and_then(|payload| serde_json::from_slice::<serde_json::Value>(&payload).ok())
+            .ok_or_else(|| CredentialEncodingError::VpToken("malformed SD-JWT payload".into()))?;
+
+        Ok(payload.get("cnf").is_some())
+    }
+
     /// The types of the credential from the VCDM, excluding the base `VerifiableCredential` type.
     pub fn types(&self) -> Vec<String> {
         self.credential.additional_types().to_vec()
@@ -135,7 +218,16 @@ impl VCDM2SdJwt {
     /// Returns the status of the credential, resolving the value in the status list,
     /// along with the purpose of the status.
     pub async fn status(&self) -> Result<Vec<Arc<Status20240406>>, StatusListError> {
-        self.status_list_values()
+        self.status_with_policy(OfflinePolicy::Online).await
+    }
+
+    /// Returns the status of the credential like [status](Self::status), unless `policy` is
+    /// offline, in which case the status lists are not fetched and the statuses are unknown.
+    pub async fn status_with_policy(
+        &self,
+        policy: OfflinePolicy,
+    ) -> Result<Vec<Arc<Status20240406>>, StatusListError> {
+        self.status_list_values_with_policy(policy)
             .await
             .map(|v| v.into_iter().map(Arc::new).collect())
     }
@@ -161,82 +253,49 @@ impl CredentialPresentation for VCDM2SdJwt {
     /// Return the credential as a VpToken
     async fn as_vp_token_item<'a>(
         &self,
-        _options: &'a PresentationOptions<'a>,
+        options: &'a PresentationOptions<'a>,
         selected_fields: Option<Vec<String>>,
         limit_disclosure: bool,
     ) -> Result<VpTokenItem, OID4VPError> {
-        if limit_disclosure {
+        if limit_disclosure && selected_fields.is_none() {
             return Err(OID4VPError::LimitDisclosure(
-                "Limit disclosure is required but is not supported.".to_string(),
+                "Limit disclosure is required, but no fields were selected.".to_string(),
             ));
         }
 
-        let compact: &str = self.inner.as_ref();
-        let vp_token = if let Some(selected_fields) = selected_fields {
-            let json = self.revealed_claims_as_json().map_err(|e| {
-                OID4VPError::CredentialEncoding(super::CredentialEncodingError::SdJwt(e))
-            })?;
-
-            let selected_fields_pointers = selected_fields
-                .into_iter()
-                .map(|sfield| {
-                    // TODO: Remove hotfix encoding and improve path usage
-                    // SAFETY: encoded by client (sprucekit-mobile@holder)
-                    let path = sfield.split(",").next().unwrap().to_owned();
-                    let path = match URL_SAFE.decode(path) {
-                        Ok(path) => path,
-                        Err(err) => return Err(OID4VPError::JsonPathParse(err.to_string())),
-                    };
-                    let path = match str::from_utf8(&path) {
-                        Ok(path) => path,
-                        Err(err) => return Err(OID4VPError::JsonPathParse(err.to_string())),
-                    };
-                    let path = match JsonPath::parse(path) {
-                        Ok(path) => path,
-                        Err(err) => return Err(OID4VPError::JsonPathParse(err.to_string())),
-                    };
-                    let located_node = path.query_located(&json);
-
-                    if located_node.is_empty() {
-                        Err(OID4VPError::JsonPathResolve(format!(
-                            "Unable to resolve JsonPath: {}",
-                            path
-                        )))
-                    } else {
-                        // SAFETY: Empty check above
-                        JsonPointerBuf::new(
-                            located_node.first().unwrap().location().to_json_pointer(),
-                        )
-                        .map_err(|e| OID4VPError::JsonPathToPointer(e.to_string()))
-                    }
-                })
-                .collect::<Result<Vec<_>, _>>()?;
-
-            let ret = self
-                .inner
-                .decode_reveal::<AnyClaims>()
-                .map_err(|e| OID4VPError::Debug(e.to_string()))?
-                .retaining(&selected_fields_pointers)
-                .into_encoded()
-                .as_str()
-                .to_string();
-            ret
-        } else {
-            compact.to_string()
-        };
+        let sd_jwt = self.disclosed_sd_jwt(selected_fields)?;
+        if !self.requires_key_binding()? {
+            return Ok(VpTokenItem::String(sd_jwt));
+        }
 
-        Ok(VpTokenItem::String(vp_token))
+        let key_binding_jwt = options
+            .sign_jwt(
+                serde_json::json!({ "typ": KEY_BINDING_JWT_TYPE }),
+                serde_json::json!({
+                    "iat": time::OffsetDateTime::now_utc().unix_timestamp(),
+                    "aud": options.audience(),
+                    "nonce": options.nonce(),
+                    "sd_hash": BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(&sd_jwt)),
+                }),
+            )
+            .await?;
+
+        Ok(VpTokenItem::String(format!("{sd_jwt}{key_binding_jwt}")))
     }
 
     fn create_descriptor_map(
         &self,
-        _options: ResponseOptions,
+        options: ResponseOptions,
         input_descriptor_id: impl Into<String>,
         index: Option<usize>,
     ) -> Result<DescriptorMap, OID4VPError> {
-        let path = match index {
-            None => JsonPath::default(),
-            Some(i) => format!("$[{i}]")
+        let input_descriptor_id = input_descriptor_id.into();
+        let path = match (options.path_prefix_override(&input_descriptor_id), index) {
+            (None, None) => JsonPath::default(),
+            (Some(prefix), None) => prefix
+                .parse()
+                .map_err(|e| OID4VPError::JsonPathParse(format!("{e:?}")))?,
+            (prefix, Some(i)) => format!("{}[{i}]", prefix.unwrap_or("$"))
                 .parse()
                 .map_err(|e| OID4VPError::JsonPathParse(format!("{e:?}")))?,
         };
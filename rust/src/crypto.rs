@@ -1,6 +1,11 @@
 use std::sync::Arc;
 
+use p256::pkcs8::{EncodePublicKey, LineEnding};
 use serde::{Deserialize, Serialize};
+use ssi::{
+    jwk::{Base64urlUInt, Params},
+    JWK,
+};
 
 uniffi::custom_newtype!(KeyAlias, String);
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -10,6 +15,15 @@ pub struct KeyAlias(pub String);
 pub enum CryptoError {
     #[error("{0}")]
     General(String),
+    /// The key requires user presence, and the user has not yet been authenticated.
+    #[error("user authentication is required to use the signing key")]
+    UserAuthenticationRequired,
+    /// The user could not be authenticated, e.g. a biometric prompt was failed or cancelled.
+    #[error("user authentication failed: {0}")]
+    UserAuthenticationFailed(String),
+    /// A JWK could not be parsed, or its key material does not match its key type and curve.
+    #[error("invalid JWK: {0}")]
+    InvalidJwk(String),
 }
 
 impl From<anyhow::Error> for CryptoError {
@@ -28,6 +42,12 @@ pub trait KeyStore: Send + Sync {
     fn get_signing_key(&self, alias: KeyAlias) -> Result<Arc<dyn SigningKey>>;
 }
 
+impl std::fmt::Debug for dyn KeyStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "KeyStore")
+    }
+}
+
 #[uniffi::export(with_foreign)]
 /// A cryptographic keypair that can be used for signing.
 pub trait SigningKey: Send + Sync {
@@ -37,6 +57,170 @@ pub trait SigningKey: Send + Sync {
     fn sign(&self, payload: Vec<u8>) -> Result<Vec<u8>>;
 }
 
+#[derive(thiserror::Error, uniffi::Error, Debug)]
+pub enum KeyTransformationError {
+    #[error("{value}")]
+    ToPKCS8 { value: String },
+    #[error("{value}")]
+    FromPKCS8 { value: String },
+    #[error("{value}")]
+    FromSEC1 { value: String },
+    #[error("{value}")]
+    ToSEC1 { value: String },
+}
+
+/// The public key of a [SigningKey] as a PEM-encoded SubjectPublicKeyInfo, for tooling that
+/// expects SPKI rather than a JWK.
+///
+/// Only P-256 keys are supported.
+#[uniffi::export]
+pub fn public_key_pem(key: Arc<dyn SigningKey>) -> Result<String, KeyTransformationError> {
+    public_key(key.as_ref())?
+        .to_public_key_pem(LineEnding::LF)
+        .map_err(|e| KeyTransformationError::ToPKCS8 {
+            value: e.to_string(),
+        })
+}
+
+/// The public key of a [SigningKey] as a DER-encoded SubjectPublicKeyInfo.
+///
+/// Only P-256 keys are supported.
+#[uniffi::export]
+pub fn public_key_der(key: Arc<dyn SigningKey>) -> Result<Vec<u8>, KeyTransformationError> {
+    public_key(key.as_ref())?
+        .to_public_key_der()
+        .map(|der| der.into_vec())
+        .map_err(|e| KeyTransformationError::ToPKCS8 {
+            value: e.to_string(),
+        })
+}
+
+/// Decode the P-256 public key of a [SigningKey] from the SEC1 point of its JWK.
+fn public_key(key: &dyn SigningKey) -> Result<p256::PublicKey, KeyTransformationError> {
+    let to_sec1 = |value: String| KeyTransformationError::ToSEC1 { value };
+
+    let jwk = key.jwk().map_err(|e| to_sec1(e.to_string()))?;
+    let jwk = parse_jwk(&jwk).map_err(|e| to_sec1(e.to_string()))?;
+    let Params::EC(ec) = &jwk.params else {
+        return Err(to_sec1("not an EC key".to_string()));
+    };
+    if ec.curve.as_deref() != Some("P-256") {
+        return Err(to_sec1(format!("unsupported curve {:?}", ec.curve)));
+    }
+    let (Some(x), Some(y)) = (&ec.x_coordinate, &ec.y_coordinate) else {
+        return Err(to_sec1("missing coordinates".to_string()));
+    };
+
+    // An uncompressed SEC1 point.
+    let mut point = vec![0x04];
+    point.extend_from_slice(&x.0);
+    point.extend_from_slice(&y.0);
+
+    p256::PublicKey::from_sec1_bytes(&point).map_err(|e| KeyTransformationError::FromSEC1 {
+        value: e.to_string(),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+/// A summary of a validated JWK.
+pub struct JwkInfo {
+    /// The key type (`kty`), e.g. `EC` or `OKP`.
+    pub key_type: String,
+    /// The curve (`crv`) of `EC` and `OKP` keys, e.g. `P-256` or `Ed25519`.
+    pub curve: Option<String>,
+    /// Whether the JWK contains private key material.
+    pub is_private: bool,
+}
+
+#[uniffi::export]
+/// Validate a JSON-encoded JWK, e.g. one returned by [SigningKey::jwk].
+pub fn validate_jwk(jwk_json: String) -> Result<JwkInfo> {
+    let jwk = parse_jwk(&jwk_json)?;
+
+    let info = match &jwk.params {
+        Params::EC(ec) => JwkInfo {
+            key_type: "EC".into(),
+            curve: ec.curve.clone(),
+            is_private: ec.ecc_private_key.is_some(),
+        },
+        Params::OKP(okp) => JwkInfo {
+            key_type: "OKP".into(),
+            curve: Some(okp.curve.clone()),
+            is_private: okp.private_key.is_some(),
+        },
+        Params::RSA(rsa) => JwkInfo {
+            key_type: "RSA".into(),
+            curve: None,
+            is_private: rsa.private_exponent.is_some(),
+        },
+        Params::Symmetric(_) => JwkInfo {
+            key_type: "oct".into(),
+            curve: None,
+            is_private: true,
+        },
+    };
+
+    Ok(info)
+}
+
+/// Parse a JSON-encoded JWK, checking that the size of its key material matches its curve.
+pub(crate) fn parse_jwk(jwk_json: &str) -> Result<JWK> {
+    let invalid = |reason: String| CryptoError::InvalidJwk(reason);
+
+    let jwk: JWK = serde_json::from_str(jwk_json).map_err(|e| invalid(e.to_string()))?;
+
+    let check_size = |name: &str, value: &Base64urlUInt, size: usize| {
+        if value.0.len() == size {
+            Ok(())
+        } else {
+            Err(invalid(format!(
+                "expected {size} bytes for {name}, found {}",
+                value.0.len()
+            )))
+        }
+    };
+
+    match &jwk.params {
+        Params::EC(ec) => {
+            let curve = ec.curve.as_deref().ok_or(invalid("missing crv".into()))?;
+            let size = match curve {
+                "P-256" | "secp256k1" => 32,
+                "P-384" => 48,
+                "P-521" => 66,
+                _ => return Err(invalid(format!("unsupported EC curve {curve}"))),
+            };
+            let x = ec
+                .x_coordinate
+                .as_ref()
+                .ok_or(invalid("missing x".into()))?;
+            let y = ec
+                .y_coordinate
+                .as_ref()
+                .ok_or(invalid("missing y".into()))?;
+            check_size("x", x, size)?;
+            check_size("y", y, size)?;
+            if let Some(d) = &ec.ecc_private_key {
+                check_size("d", d, size)?;
+            }
+        }
+        Params::OKP(okp) => {
+            let size = match okp.curve.as_str() {
+                "Ed25519" | "X25519" => 32,
+                "Ed448" => 57,
+                "X448" => 56,
+                curve => return Err(invalid(format!("unsupported OKP curve {curve}"))),
+            };
+            check_size("x", &okp.public_key, size)?;
+            if let Some(d) = &okp.private_key {
+                check_size("d", d, size)?;
+            }
+        }
+        Params::RSA(_) | Params::Symmetric(_) => {}
+    }
+
+    Ok(jwk)
+}
+
 #[derive(uniffi::Object)]
 /// Utility functions for cryptographic curves
 pub struct CryptoCurveUtils(Curve);
@@ -72,7 +256,9 @@ pub(crate) use test::*;
 
 #[cfg(test)]
 mod test {
-    use crate::{local_store::LocalStore, storage_manager::StorageManagerInterface, Key, Value};
+    use crate::{
+        local_store::LocalStore, storage_manager::StorageManagerInterface, Key, KeyNamespace, Value,
+    };
     use anyhow::Context;
 
     use super::*;
@@ -82,7 +268,7 @@ mod test {
 
     impl RustTestKeyManager {
         pub async fn generate_p256_signing_key(&self, alias: KeyAlias) -> Result<()> {
-            let key = Key(alias.0);
+            let key = Key::namespaced(KeyNamespace::SigningKey, &alias.0);
             if self
                 .0
                 .get(key.clone())
@@ -107,7 +293,7 @@ mod test {
 
     impl KeyStore for RustTestKeyManager {
         fn get_signing_key(&self, alias: KeyAlias) -> Result<Arc<dyn SigningKey>> {
-            let key = Key(alias.0);
+            let key = Key::namespaced(KeyNamespace::SigningKey, &alias.0);
 
             let fut = self.0.get(key.clone());
 
@@ -137,4 +323,62 @@ mod test {
             Ok(signature.to_vec())
         }
     }
+
+    #[test]
+    fn validate_p256_jwk() {
+        let secret_key = p256::SecretKey::random(&mut ssi::crypto::rand::thread_rng());
+
+        let public = validate_jwk(secret_key.public_key().to_jwk_string()).unwrap();
+        assert_eq!(
+            public,
+            JwkInfo {
+                key_type: "EC".into(),
+                curve: Some("P-256".into()),
+                is_private: false,
+            }
+        );
+
+        let private = validate_jwk(secret_key.to_jwk_string().to_string()).unwrap();
+        assert!(private.is_private);
+    }
+
+    #[test]
+    fn export_public_key() {
+        use p256::pkcs8::DecodePublicKey;
+
+        let secret_key = p256::SecretKey::random(&mut ssi::crypto::rand::thread_rng());
+        let signing_key: Arc<dyn SigningKey> = Arc::new(RustTestSigningKey(secret_key.clone()));
+
+        let pem = public_key_pem(signing_key.clone()).unwrap();
+        assert!(pem.starts_with("-----BEGIN PUBLIC KEY-----"));
+        assert_eq!(
+            p256::PublicKey::from_public_key_pem(&pem).unwrap(),
+            secret_key.public_key()
+        );
+
+        let der = public_key_der(signing_key).unwrap();
+        assert_eq!(
+            p256::PublicKey::from_public_key_der(&der).unwrap(),
+            secret_key.public_key()
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::not_json("not a jwk")]
+    #[case::missing_kty(r#"{"crv":"P-256"}"#)]
+    #[case::missing_y(
+        r#"{"kty":"EC","crv":"P-256","x":"gI0GAILBdu7T53akrFmMyGcsF3n5dO7MmwNBHKW5SV0"}"#
+    )]
+    #[case::short_x(
+        r#"{"kty":"EC","crv":"P-256","x":"gI0GAILBdu7T53akrFmMyGcsF3n5dO7M","y":"SLW_xSffzlPWrHEVI30DHM_4egVwt3NQqeUD7nMFpps"}"#
+    )]
+    #[case::unsupported_curve(
+        r#"{"kty":"EC","crv":"P-192","x":"gI0GAILBdu7T53akrFmMyGcsF3n5dO7M","y":"SLW_xSffzlPWrHEVI30DHM_4egVwt3NQ"}"#
+    )]
+    fn validate_malformed_jwk(#[case] jwk: &str) {
+        assert!(matches!(
+            validate_jwk(jwk.to_string()),
+            Err(CryptoError::InvalidJwk(_))
+        ));
+    }
 }
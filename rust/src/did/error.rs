@@ -12,4 +12,7 @@ pub enum DidError {
 
     #[error("DID document is missing a verification method")]
     MissingVerificationMethod,
+
+    #[error("invalid DID resolver configuration: {_0}")]
+    ResolverConfig(String),
 }
@@ -1,8 +1,11 @@
 use ssi::dids::{document::DIDVerificationMethod, DIDBuf, DIDResolver};
 
 pub use error::*;
+pub(crate) use resolver::{CachingDidResolver, ConfiguredDidResolver};
+pub use resolver::{DidResolutionCache, DidResolverConfig};
 
 mod error;
+mod resolver;
 
 #[derive(Debug, uniffi::Enum)]
 pub enum DidMethod {
@@ -67,3 +70,118 @@ impl DidMethodUtils {
             .map(|vm| vm.id.to_string())
     }
 }
+
+/// Whether two DIDs, or DID URLs, identify the same DID.
+///
+/// The path, query and fragment of DID URLs are ignored, the `did` scheme and method name
+/// are compared case-insensitively, and percent-encoded octets are decoded. The
+/// method-specific identifier is otherwise case-sensitive, except for the domain name of a
+/// `did:web` DID.
+///
+/// Strings that are not DIDs are compared exactly.
+pub fn dids_equal(a: &str, b: &str) -> bool {
+    match (normalize_did(a), normalize_did(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Whether a signer using `algorithm` can sign for a DID, or DID URL.
+///
+/// The multicodec of a `did:key` public key, recognized from the prefix of its base58btc
+/// encoding, must correspond to `algorithm`; `did:key` DIDs of any other key type are
+/// refused. DIDs of other methods are not constrained.
+pub(crate) fn did_key_matches_algorithm(did: &str, algorithm: ssi::crypto::Algorithm) -> bool {
+    use ssi::crypto::Algorithm;
+
+    let Some((method, method_specific_id)) = normalize_did(did) else {
+        return true;
+    };
+    if method != "key" {
+        return true;
+    }
+
+    let expected = match method_specific_id.as_str() {
+        id if id.starts_with("z6Mk") => Algorithm::EdDSA,
+        id if id.starts_with("zQ3s") => Algorithm::ES256K,
+        id if id.starts_with("zDn") => Algorithm::ES256,
+        id if id.starts_with("z82") => Algorithm::ES384,
+        _ => return false,
+    };
+    algorithm.is_compatible_with(expected)
+}
+
+/// Normalize a DID into its method name and method-specific identifier.
+fn normalize_did(did: &str) -> Option<(String, String)> {
+    let did = did.split(['/', '?', '#']).next()?;
+    let (scheme, rest) = did.split_once(':')?;
+    let (method, method_specific_id) = rest.split_once(':')?;
+    if !scheme.eq_ignore_ascii_case("did") || method.is_empty() || method_specific_id.is_empty() {
+        return None;
+    }
+
+    let method = method.to_ascii_lowercase();
+    let mut method_specific_id = method_specific_id.to_string();
+    if method == "web" {
+        // The domain name, up to the first path segment, is case-insensitive.
+        let domain_end = method_specific_id
+            .find(':')
+            .unwrap_or(method_specific_id.len());
+        method_specific_id[..domain_end].make_ascii_lowercase();
+    }
+
+    Some((method, normalize_percent_encoding(&method_specific_id)))
+}
+
+/// Uppercase the hexadecimal digits of percent-encoded octets, which are case-insensitive.
+///
+/// The octets are not decoded, as an encoded character is not equivalent to the character
+/// itself, e.g. `%3A` in a `did:web` is a port separator while `:` separates path segments.
+fn normalize_percent_encoding(value: &str) -> String {
+    let mut normalized = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        normalized.push(c);
+        if c != '%' {
+            continue;
+        }
+        let hex: String = chars.clone().take(2).collect();
+        if hex.len() == 2 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            normalized.push_str(&hex.to_ascii_uppercase());
+            chars.nth(1);
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::identical("did:example:123", "did:example:123")]
+    #[case::fragment("did:example:123#key-1", "did:example:123")]
+    #[case::query_and_path("did:example:123/path?service=agent", "did:example:123")]
+    #[case::method_case("DID:Example:123", "did:example:123")]
+    #[case::percent_encoding_case("did:example:a%2fb", "did:example:a%2Fb")]
+    #[case::web_domain_case("did:web:Example.COM:user", "did:web:example.com:user")]
+    #[case::web_port_case("did:web:Example.COM%3a8443", "did:web:example.com%3A8443")]
+    fn equivalent_dids(#[case] a: &str, #[case] b: &str) {
+        assert!(dids_equal(a, b));
+        assert!(dids_equal(b, a));
+    }
+
+    #[rstest]
+    #[case::different_id("did:example:123", "did:example:456")]
+    #[case::different_method("did:key:123", "did:jwk:123")]
+    #[case::id_case("did:key:z6MkAbc", "did:key:z6Mkabc")]
+    #[case::web_path_case("did:web:example.com:User", "did:web:example.com:user")]
+    #[case::percent_encoding("did:example:abc%3A123", "did:example:abc:123")]
+    #[case::web_port("did:web:example.com%3A8443", "did:web:example.com:8443")]
+    #[case::not_a_did("https://example.com", "https://example.com/")]
+    fn different_dids(#[case] a: &str, #[case] b: &str) {
+        assert!(!dids_equal(a, b));
+    }
+}
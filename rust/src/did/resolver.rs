@@ -0,0 +1,182 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use ssi::dids::{
+    document,
+    resolution::{self, Output},
+    AnyDidMethod, DIDResolver, DID,
+};
+
+use super::{normalize_did, DidError};
+
+/// The media type of the DID documents of a [DidResolverConfig].
+const DID_JSON_MEDIA_TYPE: &str = "application/did+json";
+
+/// How DIDs are resolved when verifying credentials.
+#[derive(Debug, Clone, Default, uniffi::Record)]
+pub struct DidResolverConfig {
+    /// JSON-encoded `did:web` DID documents, keyed by DID.
+    ///
+    /// These DIDs are resolved to the given documents instead of being fetched, e.g. for
+    /// cached or offline resolution. Any other DID is resolved as usual.
+    pub did_web_documents: HashMap<String, String>,
+}
+
+/// A DID resolver that resolves the `did:web` DIDs of a [DidResolverConfig] to their
+/// configured documents, and any other DID with [AnyDidMethod].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConfiguredDidResolver {
+    did_web_documents: HashMap<(String, String), Vec<u8>>,
+}
+
+impl TryFrom<DidResolverConfig> for ConfiguredDidResolver {
+    type Error = DidError;
+
+    fn try_from(config: DidResolverConfig) -> Result<Self, Self::Error> {
+        let did_web_documents = config
+            .did_web_documents
+            .into_iter()
+            .map(|(did, document)| {
+                let key = normalize_did(&did)
+                    .filter(|(method, _)| method == "web")
+                    .ok_or_else(|| DidError::ResolverConfig(format!("not a did:web DID: {did}")))?;
+                serde_json::from_str::<document::Document>(&document)?;
+                Ok((key, document.into_bytes()))
+            })
+            .collect::<Result<_, DidError>>()?;
+
+        Ok(Self { did_web_documents })
+    }
+}
+
+impl DIDResolver for ConfiguredDidResolver {
+    async fn resolve_representation<'a>(
+        &'a self,
+        did: &'a DID,
+        options: resolution::Options,
+    ) -> Result<Output<Vec<u8>>, resolution::Error> {
+        match normalize_did(did.as_str()).and_then(|key| self.did_web_documents.get(&key)) {
+            Some(document) => Ok(Output::new(
+                document.clone(),
+                document::Metadata::default(),
+                resolution::Metadata::from_content_type(Some(DID_JSON_MEDIA_TYPE.to_string())),
+            )),
+            None => {
+                AnyDidMethod::default()
+                    .resolve_representation(did, options)
+                    .await
+            }
+        }
+    }
+}
+
+/// A cache of resolved DID documents, which can be shared by several presentation builders so
+/// that the holder's DID is only resolved once, e.g. when presenting credentials in a batch.
+///
+/// Entries expire after the time-to-live given on creation.
+#[derive(Debug, uniffi::Object)]
+pub struct DidResolutionCache {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<String, CachedDocument>>>,
+    /// The number of DIDs resolved by their DID method, rather than from the cache.
+    resolutions: AtomicUsize,
+}
+
+#[derive(Debug, Clone)]
+struct CachedDocument {
+    document: Vec<u8>,
+    content_type: Option<String>,
+    resolved_at: Instant,
+}
+
+#[uniffi::export]
+impl DidResolutionCache {
+    #[uniffi::constructor]
+    pub fn new(ttl_seconds: u64) -> Arc<Self> {
+        Arc::new(Self {
+            ttl: Duration::from_secs(ttl_seconds),
+            entries: Default::default(),
+            resolutions: AtomicUsize::new(0),
+        })
+    }
+
+    /// Drop every cached DID document.
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+impl DidResolutionCache {
+    fn get(&self, did: &str) -> Option<CachedDocument> {
+        let entries = self.entries.lock().ok()?;
+        entries
+            .get(did)
+            .filter(|entry| entry.resolved_at.elapsed() < self.ttl)
+            .cloned()
+    }
+
+    fn insert(&self, did: &str, output: &Output<Vec<u8>>) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                did.to_string(),
+                CachedDocument {
+                    document: output.document.clone(),
+                    content_type: output.metadata.content_type.clone(),
+                    resolved_at: Instant::now(),
+                },
+            );
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn resolutions(&self) -> usize {
+        self.resolutions.load(Ordering::Relaxed)
+    }
+}
+
+/// A DID resolver that goes through a [DidResolutionCache], if any, before resolving DIDs
+/// with the inner resolver.
+#[derive(Debug, Clone)]
+pub(crate) struct CachingDidResolver<R> {
+    inner: R,
+    cache: Option<Arc<DidResolutionCache>>,
+}
+
+impl<R> CachingDidResolver<R> {
+    pub(crate) fn new(inner: R, cache: Option<Arc<DidResolutionCache>>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+impl<R: DIDResolver> DIDResolver for CachingDidResolver<R> {
+    async fn resolve_representation<'a>(
+        &'a self,
+        did: &'a DID,
+        options: resolution::Options,
+    ) -> Result<Output<Vec<u8>>, resolution::Error> {
+        let Some(cache) = &self.cache else {
+            return self.inner.resolve_representation(did, options).await;
+        };
+
+        if let Some(cached) = cache.get(did.as_str()) {
+            return Ok(Output::new(
+                cached.document,
+                document::Metadata::default(),
+                resolution::Metadata::from_content_type(cached.content_type),
+            ));
+        }
+
+        let output = self.inner.resolve_representation(did, options).await?;
+        cache.resolutions.fetch_add(1, Ordering::Relaxed);
+        cache.insert(did.as_str(), &output);
+        Ok(output)
+    }
+}
@@ -1,3 +1,7 @@
+use url::Url;
+
+use crate::proxy::{self, HttpProxyError};
+
 #[derive(Debug, Clone)]
 pub struct HaciHttpClient(reqwest::Client);
 
@@ -9,13 +13,21 @@ impl AsRef<reqwest::Client> for HaciHttpClient {
 
 impl HaciHttpClient {
     pub fn new() -> Self {
-        Self(
-            reqwest::Client::builder()
-                .use_rustls_tls()
-                .timeout(std::time::Duration::from_secs(30))
-                .build()
-                .unwrap_or_else(|e| panic!("Failed to build HTTP client: {}", e)),
-        )
+        proxy::client_builder()
+            .and_then(Self::from_builder)
+            .unwrap_or_else(|e| panic!("Failed to build HTTP client: {}", e))
+    }
+
+    pub fn with_proxy(proxy_url: Option<&Url>) -> Result<Self, HttpProxyError> {
+        Self::from_builder(proxy::client_builder_with_proxy(proxy_url)?)
+    }
+
+    fn from_builder(builder: reqwest::ClientBuilder) -> Result<Self, HttpProxyError> {
+        builder
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map(Self)
+            .map_err(|e| HttpProxyError::InvalidProxy(format!("{e:?}")))
     }
 
     pub fn get(&self, url: String) -> reqwest::RequestBuilder {
@@ -26,3 +38,37 @@ impl HaciHttpClient {
         self.0.post(url)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn requests_are_sent_through_proxy() {
+        let proxy = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/nonce"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("proxied"))
+            .expect(1)
+            .mount(&proxy)
+            .await;
+
+        let proxy_url: Url = proxy.uri().parse().unwrap();
+        let client = HaciHttpClient::with_proxy(Some(&proxy_url)).unwrap();
+
+        let response = client
+            .post("http://wallet-service.invalid/nonce".to_string())
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "proxied");
+    }
+}
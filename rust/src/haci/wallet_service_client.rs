@@ -1,9 +1,12 @@
+use crate::common::clock_skew_seconds;
 use crate::haci::http_client::HaciHttpClient;
+use base64::{prelude::BASE64_STANDARD, Engine};
 use serde_json::Value;
 use ssi::{
     claims::jwt::{ExpirationTime, StringOrURI, Subject, ToDecodedJwt},
     prelude::*,
 };
+use std::collections::{HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
 use time::OffsetDateTime;
@@ -30,15 +33,74 @@ pub enum WalletServiceError {
     #[error("Token is expired or invalid")]
     InvalidToken,
 
+    /// Token is expired, by `skew_seconds` according to the device clock
+    #[error("Token expired {skew_seconds}s ago")]
+    TokenExpired { skew_seconds: i64 },
+
     /// Failed to parse JWT claims
     #[error("Failed to parse JWT claims: {0}")]
     JwtParseError(String),
 
+    /// The nonce has already been consumed by this client
+    #[error("Nonce has already been used: {0}")]
+    NonceReplay(String),
+
     /// Internal error
     #[error("Internal error: {0}")]
     InternalError(String),
 }
 
+/// The number of consumed nonces remembered by a [WalletServiceClient].
+const USED_NONCE_CAPACITY: usize = 256;
+
+/// The most recently consumed nonces, forgetting the oldest once full.
+#[derive(Debug)]
+struct UsedNonces {
+    capacity: usize,
+    order: VecDeque<String>,
+    nonces: HashSet<String>,
+}
+
+impl UsedNonces {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            nonces: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Record `nonce` as used, returning `false` if it already was.
+    fn insert(&mut self, nonce: String) -> bool {
+        if !self.nonces.insert(nonce.clone()) {
+            return false;
+        }
+        self.order.push_back(nonce);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.nonces.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    /// Forget `nonce`, so that it can be used again.
+    fn remove(&mut self, nonce: &str) {
+        if self.nonces.remove(nonce) {
+            self.order.retain(|used| used != nonce);
+        }
+    }
+}
+
+/// The nonce of an app attestation, found in its base64-encoded `clientData`.
+fn attestation_nonce(attestation: &Value) -> Option<String> {
+    let client_data = BASE64_STANDARD
+        .decode(attestation.get("clientData")?.as_str()?)
+        .ok()?;
+    let client_data: Value = serde_json::from_slice(&client_data).ok()?;
+    client_data.get("nonce")?.as_str().map(ToOwned::to_owned)
+}
+
 #[derive(Debug, Clone)]
 struct TokenInfo {
     token: String,
@@ -46,8 +108,18 @@ struct TokenInfo {
     expires_at: OffsetDateTime,
 }
 
+/// Expected values for the registered claims of tokens issued by the wallet service.
+#[derive(Debug, Clone, Default)]
+struct ExpectedClaims {
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
 /// Internal function to create TokenInfo from JWT
-fn create_token_info(token: String) -> Result<TokenInfo, WalletServiceError> {
+fn create_token_info(
+    token: String,
+    expected: &ExpectedClaims,
+) -> Result<TokenInfo, WalletServiceError> {
     let jws_bytes: Vec<u8> = token.as_bytes().to_vec();
 
     let jws_buf = JwsBuf::new(jws_bytes)
@@ -70,6 +142,29 @@ fn create_token_info(token: String) -> Result<TokenInfo, WalletServiceError> {
             WalletServiceError::JwtParseError(format!("Invalid expiration timestamp: {}", e))
         })?;
 
+    let claims_json = serde_json::to_value(&jwt_claims)
+        .map_err(|e| WalletServiceError::JwtParseError(format!("Failed to encode claims: {e}")))?;
+
+    if let Some(expected_issuer) = &expected.issuer {
+        if claims_json.get("iss").and_then(Value::as_str) != Some(expected_issuer.as_str()) {
+            return Err(WalletServiceError::InvalidToken);
+        }
+    }
+
+    if let Some(expected_audience) = &expected.audience {
+        // The audience is either a single string or an array of strings.
+        let audience_matches = match claims_json.get("aud") {
+            Some(Value::String(aud)) => aud == expected_audience,
+            Some(Value::Array(auds)) => auds
+                .iter()
+                .any(|aud| aud.as_str() == Some(expected_audience.as_str())),
+            _ => false,
+        };
+        if !audience_matches {
+            return Err(WalletServiceError::InvalidToken);
+        }
+    }
+
     Ok(TokenInfo {
         token,
         claims: jwt_claims,
@@ -82,16 +177,34 @@ pub struct WalletServiceClient {
     client: HaciHttpClient,
     base_url: String,
     token_info: Arc<Mutex<Option<TokenInfo>>>,
+    expected_claims: ExpectedClaims,
+    used_nonces: Mutex<UsedNonces>,
 }
 
 #[uniffi::export(async_runtime = "tokio")]
 impl WalletServiceClient {
     #[uniffi::constructor]
     pub fn new(base_url: String) -> Self {
+        Self::new_with_expected_claims(base_url, None, None)
+    }
+
+    /// Create a client that rejects tokens whose `iss` claim is not `expected_issuer`,
+    /// or whose `aud` claim does not contain `expected_audience`, when provided.
+    #[uniffi::constructor]
+    pub fn new_with_expected_claims(
+        base_url: String,
+        expected_issuer: Option<String>,
+        expected_audience: Option<String>,
+    ) -> Self {
         Self {
             client: HaciHttpClient::new(),
             base_url,
             token_info: Arc::new(Mutex::new(None)),
+            expected_claims: ExpectedClaims {
+                issuer: expected_issuer,
+                audience: expected_audience,
+            },
+            used_nonces: Mutex::new(UsedNonces::new(USED_NONCE_CAPACITY)),
         }
     }
 
@@ -163,17 +276,71 @@ impl WalletServiceClient {
         Ok(nonce)
     }
 
+    /// Mark a nonce obtained with [nonce](Self::nonce) as used.
+    ///
+    /// Fails with [WalletServiceError::NonceReplay] if the nonce was already consumed by this
+    /// client. Only the most recently consumed nonces are remembered.
+    pub fn consume_nonce(&self, nonce: String) -> Result<(), WalletServiceError> {
+        let mut used_nonces = self
+            .used_nonces
+            .lock()
+            .map_err(|e| WalletServiceError::InternalError(e.to_string()))?;
+        if !used_nonces.insert(nonce.clone()) {
+            return Err(WalletServiceError::NonceReplay(nonce));
+        }
+        Ok(())
+    }
+
+    /// Log in with an app attestation, consuming the nonce of its client data.
+    ///
+    /// The nonce is reserved during the login, so that a concurrent login cannot replay it,
+    /// and released if the login fails, so that the attestation can be retried.
     pub async fn login(&self, app_attestation: &str) -> Result<String, WalletServiceError> {
         // Parse the app attestation string into a Value to ensure it's valid JSON
         let attestation_value: Value = serde_json::from_str(app_attestation)
             .map_err(|e| WalletServiceError::InvalidJson(e.to_string()))?;
 
+        let nonce = attestation_nonce(&attestation_value);
+        if let Some(nonce) = &nonce {
+            self.consume_nonce(nonce.clone())?;
+        }
+
+        let result = self.post_login(&attestation_value).await;
+        if let (Err(_), Some(nonce)) = (&result, nonce) {
+            self.release_nonce(&nonce);
+        }
+        result
+    }
+
+    /// Helper method to get an authorization header with the current token
+    pub fn get_auth_header(&self) -> Result<String, WalletServiceError> {
+        if let Ok(guard) = self.token_info.lock() {
+            if let Some(token_info) = guard.as_ref() {
+                if token_info.expires_at > OffsetDateTime::now_utc() {
+                    Ok(format!("Bearer {}", token_info.token))
+                } else {
+                    Err(WalletServiceError::TokenExpired {
+                        skew_seconds: clock_skew_seconds(token_info.expires_at),
+                    })
+                }
+            } else {
+                Err(WalletServiceError::InvalidToken)
+            }
+        } else {
+            Err(WalletServiceError::InvalidToken)
+        }
+    }
+}
+
+impl WalletServiceClient {
+    /// Post an app attestation to `/login`, and store the returned token.
+    async fn post_login(&self, attestation_value: &Value) -> Result<String, WalletServiceError> {
         // Make POST request to /login endpoint
         let response = self
             .client
             .post(format!("{}/login", self.base_url))
             .header("Content-Type", "application/json")
-            .json(&attestation_value)
+            .json(attestation_value)
             .send()
             .await
             .map_err(|e| WalletServiceError::NetworkError(e.to_string()))?;
@@ -195,7 +362,7 @@ impl WalletServiceClient {
             .map_err(|e| WalletServiceError::ResponseError(e.to_string()))?;
 
         // Store the token info
-        let token_info = create_token_info(token.clone())?;
+        let token_info = create_token_info(token.clone(), &self.expected_claims)?;
 
         if let Ok(mut guard) = self.token_info.lock() {
             *guard = Some(token_info);
@@ -203,20 +370,10 @@ impl WalletServiceClient {
         Ok(token)
     }
 
-    /// Helper method to get an authorization header with the current token
-    pub fn get_auth_header(&self) -> Result<String, WalletServiceError> {
-        if let Ok(guard) = self.token_info.lock() {
-            if let Some(token_info) = guard.as_ref() {
-                if token_info.expires_at > OffsetDateTime::now_utc() {
-                    Ok(format!("Bearer {}", token_info.token))
-                } else {
-                    Err(WalletServiceError::InvalidToken)
-                }
-            } else {
-                Err(WalletServiceError::InvalidToken)
-            }
-        } else {
-            Err(WalletServiceError::InvalidToken)
+    /// Forget a nonce reserved by [consume_nonce](Self::consume_nonce).
+    fn release_nonce(&self, nonce: &str) {
+        if let Ok(mut used_nonces) = self.used_nonces.lock() {
+            used_nonces.remove(nonce);
         }
     }
 }
@@ -490,4 +647,155 @@ mod tests {
             "Auth header should start with 'Bearer '"
         );
     }
+
+    #[tokio::test]
+    async fn test_expected_issuer() {
+        let (mock_server, base_url) = setup_mock_server().await;
+
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(generate_valid_jwt(JWK::generate_p256()).await.as_bytes()),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = WalletServiceClient::new_with_expected_claims(
+            base_url.clone(),
+            Some("wallet_service".to_string()),
+            None,
+        );
+        assert!(client.login(MOCK_APP_ATTESTATION).await.is_ok());
+        assert!(client.is_token_valid());
+
+        let client = WalletServiceClient::new_with_expected_claims(
+            base_url,
+            Some("other_service".to_string()),
+            None,
+        );
+        assert!(matches!(
+            client.login(MOCK_APP_ATTESTATION).await,
+            Err(WalletServiceError::InvalidToken)
+        ));
+        assert!(
+            !client.is_token_valid(),
+            "Token from an unexpected issuer should not be stored"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expected_audience() {
+        let (mock_server, base_url) = setup_mock_server().await;
+
+        // The generated token has no audience.
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(generate_valid_jwt(JWK::generate_p256()).await.as_bytes()),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = WalletServiceClient::new_with_expected_claims(
+            base_url,
+            None,
+            Some("https://wallet.example.com".to_string()),
+        );
+        assert!(matches!(
+            client.login(MOCK_APP_ATTESTATION).await,
+            Err(WalletServiceError::InvalidToken)
+        ));
+    }
+
+    #[test]
+    fn test_nonce_replay() {
+        let client = WalletServiceClient::new("https://wallet.example.com".to_string());
+
+        assert!(client.consume_nonce("test-nonce-123".to_string()).is_ok());
+        assert!(matches!(
+            client.consume_nonce("test-nonce-123".to_string()),
+            Err(WalletServiceError::NonceReplay(nonce)) if nonce == "test-nonce-123"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_login_consumes_nonce() {
+        let (mock_server, base_url) = setup_mock_server().await;
+        let client = WalletServiceClient::new(base_url);
+
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(generate_valid_jwt(JWK::generate_p256()).await.as_bytes()),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert!(client.login(MOCK_APP_ATTESTATION).await.is_ok());
+        assert!(matches!(
+            client.login(MOCK_APP_ATTESTATION).await,
+            Err(WalletServiceError::NonceReplay(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_failed_login_releases_nonce() {
+        let (mock_server, base_url) = setup_mock_server().await;
+        let client = WalletServiceClient::new(base_url);
+
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(generate_valid_jwt(JWK::generate_p256()).await.as_bytes()),
+            )
+            .mount(&mock_server)
+            .await;
+
+        assert!(matches!(
+            client.login(MOCK_APP_ATTESTATION).await,
+            Err(WalletServiceError::ServerError { status: 503, .. })
+        ));
+        assert!(client.login(MOCK_APP_ATTESTATION).await.is_ok());
+    }
+
+    #[test]
+    fn test_oldest_used_nonce_is_forgotten() {
+        let mut used_nonces = UsedNonces::new(2);
+
+        assert!(used_nonces.insert("a".to_string()));
+        assert!(used_nonces.insert("b".to_string()));
+        assert!(!used_nonces.insert("a".to_string()));
+        assert!(used_nonces.insert("c".to_string()));
+        assert!(used_nonces.insert("a".to_string()));
+    }
+
+    #[test]
+    fn test_expired_token_reports_clock_skew() {
+        let client = WalletServiceClient::new("https://wallet.example.com".to_string());
+
+        // A device clock set an hour ahead of the token expiry.
+        *client.token_info.lock().unwrap() = Some(TokenInfo {
+            token: "token".to_string(),
+            claims: JWTClaims::default(),
+            expires_at: OffsetDateTime::now_utc() - time::Duration::hours(1),
+        });
+
+        match client.get_auth_header() {
+            Err(WalletServiceError::TokenExpired { skew_seconds }) => {
+                assert!((3600..3610).contains(&skew_seconds), "{skew_seconds}")
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
 }
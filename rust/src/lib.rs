@@ -14,6 +14,7 @@ pub mod oid4vci;
 pub mod oid4vp;
 pub mod presentation;
 pub mod proof_of_possession;
+pub mod proxy;
 pub mod storage_manager;
 #[cfg(test)]
 mod tests;
@@ -1,29 +1,100 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
 use async_trait::async_trait;
 
 use crate::common::*;
 use crate::storage_manager::*;
 
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::{Arc, Mutex};
 
+/// Length of the AES-GCM nonce prepended to encrypted values.
+const NONCE_LENGTH: usize = 12;
+
 /// A version of secure storage for debugging purposes, and as a minimal interface example.  Do not
-/// use in production!  This encrypts nothing, uses a path relative to the current working directory,
-/// and is generally cavalier about errors it encounters along the way.
+/// use in production!  This only encrypts values when created with [LocalStore::new_encrypted],
+/// keeps everything in memory, and is generally cavalier about errors it encounters along the way.
 #[derive(Debug, Default, Clone)]
 pub struct LocalStore {
     store: Arc<Mutex<HashMap<Key, Value>>>,
+    cipher: Option<ValueCipher>,
+}
+
+/// Seals stored values with AES-256-GCM.
+#[derive(Clone)]
+struct ValueCipher(Aes256Gcm);
+
+impl fmt::Debug for ValueCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ValueCipher(..)")
+    }
+}
+
+impl ValueCipher {
+    fn seal(&self, value: Value) -> Result<Value, StorageManagerError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, value.0.as_slice())
+            .map_err(|_| StorageManagerError::InternalError)?;
+        Ok(Value([nonce.as_slice(), &ciphertext].concat()))
+    }
+
+    fn open(&self, value: &Value) -> Result<Value, StorageManagerError> {
+        if value.0.len() < NONCE_LENGTH {
+            return Err(StorageManagerError::CouldNotDecryptValue);
+        }
+        let (nonce, ciphertext) = value.0.split_at(NONCE_LENGTH);
+        self.0
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map(Value)
+            .map_err(|_| StorageManagerError::CouldNotDecryptValue)
+    }
 }
 
 impl LocalStore {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Create a store that encrypts values with AES-256-GCM under the given 32-byte key.
+    ///
+    /// Keys are stored in plaintext.
+    pub fn new_encrypted(key: Vec<u8>) -> Result<Self, StorageManagerError> {
+        let cipher =
+            Aes256Gcm::new_from_slice(&key).map_err(|_| StorageManagerError::CouldNotMakeKey)?;
+        Ok(Self {
+            store: Default::default(),
+            cipher: Some(ValueCipher(cipher)),
+        })
+    }
+
+    fn seal(&self, value: Value) -> Result<Value, StorageManagerError> {
+        match &self.cipher {
+            Some(cipher) => cipher.seal(value),
+            None => Ok(value),
+        }
+    }
+
+    /// Keys must not be empty.
+    fn check_key(key: &Key) -> Result<(), StorageManagerError> {
+        if key.0.is_empty() {
+            return Err(StorageManagerError::InvalidLookupKey);
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl StorageManagerInterface for LocalStore {
     /// Add a key/value pair to storage.
     async fn add(&self, key: Key, value: Value) -> Result<(), StorageManagerError> {
+        Self::check_key(&key)?;
+        let value = self.seal(value)?;
+
         let mut store = self.store.lock().unwrap();
 
         store.insert(key, value);
@@ -35,9 +106,10 @@ impl StorageManagerInterface for LocalStore {
     async fn get(&self, key: Key) -> Result<Option<Value>, StorageManagerError> {
         let store = self.store.lock().unwrap();
 
-        match store.get(&key) {
-            Some(x) => Ok(Some(Value(x.0.clone()))),
-            None => Ok(None),
+        match (store.get(&key), &self.cipher) {
+            (Some(x), Some(cipher)) => cipher.open(x).map(Some),
+            (Some(x), None) => Ok(Some(Value(x.0.clone()))),
+            (None, _) => Ok(None),
         }
     }
 
@@ -48,6 +120,13 @@ impl StorageManagerInterface for LocalStore {
         Ok(store.keys().map(|x| x.to_owned()).collect())
     }
 
+    /// Check whether a key is stored, without decrypting its value.
+    async fn contains(&self, key: Key) -> Result<bool, StorageManagerError> {
+        let store = self.store.lock().unwrap();
+
+        Ok(store.contains_key(&key))
+    }
+
     /// Delete a given key/value pair from storage.
     async fn remove(&self, key: Key) -> Result<(), StorageManagerError> {
         let mut store = self.store.lock().unwrap();
@@ -56,4 +135,170 @@ impl StorageManagerInterface for LocalStore {
 
         Ok(())
     }
+
+    /// Add several key/value pairs to storage, under a single lock.
+    ///
+    /// If any key is invalid, nothing is stored.
+    async fn add_many(&self, entries: Vec<StorageEntry>) -> Result<(), StorageManagerError> {
+        let entries = entries
+            .into_iter()
+            .map(|entry| {
+                Self::check_key(&entry.key)?;
+                Ok((entry.key, self.seal(entry.value)?))
+            })
+            .collect::<Result<Vec<_>, StorageManagerError>>()?;
+
+        let mut store = self.store.lock().unwrap();
+
+        store.extend(entries);
+
+        Ok(())
+    }
+
+    /// Delete several key/value pairs from storage, under a single lock.
+    async fn remove_many(&self, keys: Vec<Key>) -> Result<(), StorageManagerError> {
+        let mut store = self.store.lock().unwrap();
+
+        for key in keys {
+            _ = store.remove(&key);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, value: &[u8]) -> StorageEntry {
+        StorageEntry {
+            key: key.into(),
+            value: Value(value.to_vec()),
+        }
+    }
+
+    #[tokio::test]
+    async fn add_many_applies_all_entries() {
+        let store = LocalStore::new();
+
+        store
+            .add_many(vec![entry("credential", b"mdl"), entry("key", b"alias")])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get("credential".into()).await.unwrap(),
+            Some(Value(b"mdl".to_vec()))
+        );
+        assert_eq!(
+            store.get("key".into()).await.unwrap(),
+            Some(Value(b"alias".to_vec()))
+        );
+
+        store
+            .remove_many(vec!["credential".into(), "key".into()])
+            .await
+            .unwrap();
+
+        assert!(store.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn add_many_failure_stores_nothing() {
+        let store = LocalStore::new();
+
+        let result = store
+            .add_many(vec![entry("credential", b"mdl"), entry("", b"alias")])
+            .await;
+
+        assert!(matches!(result, Err(StorageManagerError::InvalidLookupKey)));
+        assert!(store.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn list_keys_with_prefix() {
+        let store = LocalStore::new();
+
+        store
+            .add_many(vec![
+                entry("Credential.a", b"mdl"),
+                entry("Credential.b", b"vc"),
+                entry("KeyAlias.a", b"alias"),
+            ])
+            .await
+            .unwrap();
+
+        let mut credentials: Vec<String> = store
+            .list()
+            .await
+            .unwrap()
+            .iter()
+            .filter_map(|key| key.strip_prefix("Credential."))
+            .collect();
+        credentials.sort();
+
+        assert_eq!(credentials, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn contains_stored_keys() {
+        let store = LocalStore::new_encrypted(vec![7; 32]).unwrap();
+        store
+            .add("jwk".into(), Value(b"secret".to_vec()))
+            .await
+            .unwrap();
+
+        assert!(store.contains("jwk".into()).await.unwrap());
+        assert!(!store.contains("other".into()).await.unwrap());
+
+        store.remove("jwk".into()).await.unwrap();
+        assert!(!store.contains("jwk".into()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn encrypted_round_trip() {
+        let store = LocalStore::new_encrypted(vec![7; 32]).unwrap();
+
+        store
+            .add("jwk".into(), Value(b"secret".to_vec()))
+            .await
+            .unwrap();
+
+        // The value is sealed at rest.
+        let sealed = store.store.lock().unwrap()[&Key::from("jwk")].0.clone();
+        assert!(!sealed.windows(6).any(|window| window == b"secret"));
+
+        assert_eq!(
+            store.get("jwk".into()).await.unwrap(),
+            Some(Value(b"secret".to_vec()))
+        );
+    }
+
+    #[tokio::test]
+    async fn encrypted_wrong_key() {
+        let store = LocalStore::new_encrypted(vec![7; 32]).unwrap();
+        store
+            .add("jwk".into(), Value(b"secret".to_vec()))
+            .await
+            .unwrap();
+
+        let wrong_key = LocalStore {
+            store: store.store.clone(),
+            ..LocalStore::new_encrypted(vec![8; 32]).unwrap()
+        };
+
+        assert!(matches!(
+            wrong_key.get("jwk".into()).await,
+            Err(StorageManagerError::CouldNotDecryptValue)
+        ));
+    }
+
+    #[test]
+    fn encrypted_invalid_key_length() {
+        assert!(matches!(
+            LocalStore::new_encrypted(vec![7; 16]),
+            Err(StorageManagerError::CouldNotMakeKey)
+        ));
+    }
 }
@@ -1,9 +1,16 @@
-use std::sync::Arc;
+use std::{fmt::Write as _, sync::Arc};
 
-use tracing::Level;
-use tracing_subscriber::fmt::MakeWriter;
+use tracing::{
+    field::{Field, Visit},
+    Event, Subscriber,
+};
+use tracing_subscriber::{
+    filter::LevelFilter,
+    layer::{Context, SubscriberExt as _},
+    util::SubscriberInitExt as _,
+    EnvFilter, Layer, Registry,
+};
 
-#[derive(Clone)]
 struct Logger(Arc<dyn LogWriter>);
 
 impl Drop for Logger {
@@ -16,44 +23,176 @@ impl Drop for Logger {
 trait LogWriter: Send + Sync {
     fn write_to_buffer(&self, message: Vec<u8>);
     fn flush(&self);
+
+    /// Log a single event, with its level (e.g. `ERROR`) and target (e.g. `openid4vp::core`).
+    ///
+    /// This can be used to route events to the native log severities. Defaults to writing
+    /// the formatted event to the buffer and flushing it.
+    fn log_record(&self, level: String, target: String, message: String) {
+        self.write_to_buffer(format!("{level} {target}: {message}\n").into_bytes());
+        self.flush();
+    }
 }
 
-impl<'a> MakeWriter<'a> for Logger {
-    type Writer = Self;
+impl<S: Subscriber> Layer<S> for Logger {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
 
-    fn make_writer(&'a self) -> Self::Writer {
-        self.clone()
+        let metadata = event.metadata();
+        self.0.log_record(
+            metadata.level().to_string(),
+            metadata.target().to_string(),
+            message.0,
+        );
     }
 }
 
-impl std::io::Write for Logger {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.0.write_to_buffer(buf.to_vec());
-        Ok(buf.len())
-    }
+/// Formats the `message` of an event, followed by its other fields as `name=value`.
+#[derive(Default)]
+struct MessageVisitor(String);
 
-    fn flush(&mut self) -> std::io::Result<()> {
-        self.0.flush();
-        Ok(())
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        let _ = if field.name() == "message" {
+            write!(self.0, "{value:?}")
+        } else {
+            write!(self.0, "{}={value:?}", field.name())
+        };
     }
 }
 
-/// Configure the global logger for the mobile SDK.
+#[derive(Debug, uniffi::Error, thiserror::Error)]
+pub enum LoggerError {
+    #[error("Invalid log level: {0}")]
+    InvalidLevel(String),
+    #[error("Invalid log directives: {0}")]
+    InvalidDirectives(String),
+}
+
+/// Configure the global logger for the mobile SDK, logging at the `info` level.
 ///
 /// This method should be called once per application lifecycle. Subsequent calls will be ignored.
-// Improvements:
-// - Support native log levels through a direct Subscriber implementation.
 #[uniffi::export]
 fn configure_logger(writer: Arc<dyn LogWriter>) {
-    let _ = tracing_subscriber::fmt()
-        .with_level(true)
-        .with_ansi(false)
-        .with_max_level(Level::DEBUG)
-        .with_writer(Logger(writer))
-        .try_init();
+    // Unwrap safety: `info` is a valid level and there are no directives to parse.
+    let _ = subscriber(writer, "info", None).unwrap().try_init();
+}
+
+/// Configure the global logger for the mobile SDK with a maximum log level.
+///
+/// `level` is one of `error`, `warn`, `info`, `debug` or `trace`. `directives` optionally
+/// overrides the level per target, using the `EnvFilter` syntax, e.g.
+/// `"openid4vp=debug,reqwest=warn"`.
+///
+/// This method should be called once per application lifecycle. Subsequent calls will be ignored.
+#[uniffi::export]
+fn configure_logger_with_level(
+    writer: Arc<dyn LogWriter>,
+    level: String,
+    directives: Option<String>,
+) -> Result<(), LoggerError> {
+    let _ = subscriber(writer, &level, directives.as_deref())?.try_init();
+    Ok(())
+}
+
+fn subscriber(
+    writer: Arc<dyn LogWriter>,
+    level: &str,
+    directives: Option<&str>,
+) -> Result<impl Subscriber + Send + Sync, LoggerError> {
+    let level: LevelFilter = level
+        .parse()
+        .map_err(|_| LoggerError::InvalidLevel(level.to_string()))?;
+    let filter = EnvFilter::builder()
+        .with_default_directive(level.into())
+        .parse(directives.unwrap_or_default())
+        .map_err(|e| LoggerError::InvalidDirectives(e.to_string()))?;
+
+    Ok(Registry::default().with(filter).with(Logger(writer)))
 }
 
 #[uniffi::export]
 fn log_something(message: String) {
     tracing::info!("{}", message);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct CapturingWriter(Mutex<Vec<u8>>);
+
+    impl LogWriter for CapturingWriter {
+        fn write_to_buffer(&self, message: Vec<u8>) {
+            self.0.lock().unwrap().extend(message);
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Captures the `(level, target, message)` of each record.
+    #[derive(Default)]
+    struct RecordingWriter(Mutex<Vec<(String, String, String)>>);
+
+    impl LogWriter for RecordingWriter {
+        fn write_to_buffer(&self, _message: Vec<u8>) {
+            panic!("records should not be written to the buffer");
+        }
+
+        fn flush(&self) {}
+
+        fn log_record(&self, level: String, target: String, message: String) {
+            self.0.lock().unwrap().push((level, target, message));
+        }
+    }
+
+    #[test]
+    fn filters_below_level() {
+        let writer = Arc::new(CapturingWriter::default());
+        let subscriber = subscriber(writer.clone(), "warn", None).unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("filtered out");
+            tracing::warn!("written");
+        });
+
+        let output = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("written"));
+        assert!(!output.contains("filtered out"));
+    }
+
+    #[test]
+    fn invalid_level() {
+        assert!(matches!(
+            subscriber(Arc::new(CapturingWriter::default()), "verbose", None),
+            Err(LoggerError::InvalidLevel(_))
+        ));
+    }
+
+    #[test]
+    fn records_level_and_target() {
+        let writer = Arc::new(RecordingWriter::default());
+        let subscriber = subscriber(writer.clone(), "info", None).unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!(target: "mobile_sdk_rs::test", code = 7, "failed");
+        });
+
+        let records = writer.0.lock().unwrap();
+        assert_eq!(
+            *records,
+            vec![(
+                "ERROR".to_string(),
+                "mobile_sdk_rs::test".to_string(),
+                "failed code=7".to_string()
+            )]
+        );
+    }
+}
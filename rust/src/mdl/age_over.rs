@@ -0,0 +1,241 @@
+//! Age attestations (`age_over_NN`), following the rules of ISO/IEC 18013-5 Section 7.2.5.
+//!
+//! These rules are shared by every transport, so that the same age attestations are disclosed
+//! whether the mdoc is presented over BLE, OID4VP or the DC API.
+
+use std::collections::BTreeMap;
+
+use isomdl::definitions::{helpers::NonEmptyMap, issuer_signed::IssuerSignedItemBytes};
+
+/// The namespace of the `age_over_NN` elements.
+pub const AGE_OVER_NAMESPACE: &str = "org.iso.18013.5.1";
+
+/// The maximum number of `age_over_NN` elements that may be returned in a response.
+pub const MAX_AGE_OVER_ATTESTATIONS: usize = 2;
+
+/// Map each `age_over_NN` element of the mdoc to the age attestations it responds to.
+///
+/// The keys are the element identifiers present in the `org.iso.18013.5.1` namespace, and
+/// the values are every `age_over_NN` that a reader may request and that is answered by that
+/// element, including the element itself.
+pub fn age_over_attestations(
+    namespaces: &NonEmptyMap<String, NonEmptyMap<String, IssuerSignedItemBytes>>,
+) -> BTreeMap<String, Vec<String>> {
+    let mut age_over_x_elements: Vec<(u8, bool)> = namespaces
+        .iter()
+        .filter(|(ns, _)| *ns == AGE_OVER_NAMESPACE)
+        .flat_map(|(_, elems)| elems.iter())
+        .filter_map(|(id, elem)| {
+            id.strip_prefix("age_over_")
+                .and_then(age_from_str)
+                .and_then(|age| elem.as_ref().element_value.as_bool().map(|b| (age, b)))
+        })
+        .collect();
+
+    age_over_x_elements.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Transform this mapping from (requested_age -> responded_age) into
+    // (responded_age -> requested_age[]]) so that virtual elements for every possible requested_age
+    // can be constructed.
+    reverse_mapping(age_over_x_elements)
+        .into_iter()
+        .map(|(request, response)| {
+            (
+                format!("age_over_{request:02}"),
+                format!("age_over_{response:02}"),
+            )
+        })
+        .fold(BTreeMap::new(), |mut acc, (request, response)| {
+            if let Some(arr) = acc.get_mut(&response) {
+                arr.push(request);
+            } else {
+                acc.insert(response, vec![request]);
+            }
+            acc
+        })
+}
+
+/// Whether an element identifier is an age attestation, i.e. `age_over_NN`.
+pub fn is_age_over(element_identifier: &str) -> bool {
+    element_identifier
+        .strip_prefix("age_over_")
+        .and_then(age_from_str)
+        .is_some()
+}
+
+/// Keep the first [MAX_AGE_OVER_ATTESTATIONS] age attestations of `items`, along with every
+/// item that is not an age attestation.
+pub fn limit_age_over_attestations<T, S: AsRef<str>>(
+    items: impl IntoIterator<Item = T>,
+    element_identifier: impl Fn(&T) -> S,
+) -> Vec<T> {
+    let mut seen_age_over_attestations = 0;
+    items
+        .into_iter()
+        .filter(|item| {
+            if is_age_over(element_identifier(item).as_ref()) {
+                seen_age_over_attestations += 1;
+                seen_age_over_attestations <= MAX_AGE_OVER_ATTESTATIONS
+            } else {
+                true
+            }
+        })
+        .collect()
+}
+
+fn age_from_str(s: &str) -> Option<u8> {
+    let mut chars = s.chars();
+    let first = match chars.next() {
+        Some(d @ '0'..='9') => d,
+        _ => return None,
+    };
+    match chars.next() {
+        Some(_d @ '0'..='9') => (),
+        _ => return None,
+    };
+    if chars.next().is_some() {
+        return None;
+    }
+
+    if first == '0' {
+        s[1..].parse().ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+/// Create a reverse mapping of age_over attestation responses, where the key is the requested
+/// age and the value is the responding age.
+///
+/// For example, if we had "age_over_18: true", "age_over_21: true", "age_over_30: false" and
+/// "age_over_60: false", then we would want to construct the following mapping:
+///
+/// 0..=18 -> 18 (requests for age over 0-18 are responded with age_over_18: true)
+/// 19..=21 -> 21 (requests for age over 19-21 are responded with age_over_21: true)
+/// 22..=29 -> None (requests for age over 22-29 have no response)
+/// 30..=59 -> 30 (requests for age over 30-59 are responded with age_over_30: false)
+/// 60..=99 -> 30 (requests for age over 30-59 are responded with age_over_30: false)
+///
+/// This follows the rules defined in ISO/IEC 18013-5 Section 7.2.5.
+fn reverse_mapping(age_over_x_elements: Vec<(u8, bool)>) -> BTreeMap<u8, u8> {
+    let mut reverse_mapping = BTreeMap::<u8, u8>::new();
+
+    // Starting with the lowest age_over_XX: false claims.
+    //
+    // Using the above example, before the first iteration the mapping will be:
+    // 0..=99 -> None
+    //
+    // After the first iteration, the mapping will be:
+    // 0..=29 -> None
+    // 30..=99 -> 30
+    //
+    // After the second and final iteration, the mapping will be:
+    // 0..=29 -> None
+    // 30..=59 -> 30
+    // 60..=99 -> 60
+    for age in age_over_x_elements
+        .iter()
+        .filter_map(|(age, b)| if !b { Some(age) } else { None })
+    {
+        for xx in *age..=99 {
+            reverse_mapping.insert(xx, *age);
+        }
+    }
+
+    // Starting with the highest age_over_XX: true claims.
+    //
+    // Using the above example, before the first iteration the mapping will be:
+    // 0..=29 -> None
+    // 30..=59 -> 30
+    // 60..=99 -> 60
+    //
+    // After the first iteration, the mapping will be:
+    // 0..=21 -> 21
+    // 22..=29 -> None
+    // 30..=59 -> 30
+    // 60..=99 -> 60
+    //
+    // After the second and final iteration, the mapping will be:
+    // 0..=18 -> 18
+    // 19..=21 -> 21
+    // 22..=29 -> None
+    // 30..=59 -> 30
+    // 60..=99 -> 60
+    for age in age_over_x_elements
+        .iter()
+        .rev()
+        .filter_map(|(age, b)| if *b { Some(age) } else { None })
+    {
+        for xx in 0..=*age {
+            reverse_mapping.insert(xx, *age);
+        }
+    }
+
+    reverse_mapping
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn age_attestation_mapping() {
+        let reverse_mapping =
+            reverse_mapping(vec![(18, true), (21, true), (30, false), (60, false)]);
+        assert_eq!(reverse_mapping.len(), 92);
+
+        reverse_mapping
+            .into_iter()
+            .for_each(|(request, response)| match request {
+                0..=18 => assert_eq!(response, 18),
+                19..=21 => assert_eq!(response, 21),
+                30..=59 => assert_eq!(response, 30),
+                60..=99 => assert_eq!(response, 60),
+                _ => panic!("unexpected value"),
+            })
+    }
+
+    #[tokio::test]
+    async fn virtual_age_over_elements() {
+        // The test mDL attests to `age_over_18: true` and `age_over_21: true`.
+        let (_, _, mdoc) = crate::mdl::util::test_mdl().await;
+
+        let attestations = age_over_attestations(&mdoc.document().namespaces);
+
+        assert_eq!(attestations.len(), 2);
+        assert_eq!(attestations["age_over_18"].len(), 19);
+        assert!(attestations["age_over_18"].contains(&"age_over_00".to_string()));
+        assert!(attestations["age_over_18"].contains(&"age_over_18".to_string()));
+        assert_eq!(
+            attestations["age_over_21"],
+            ["age_over_19", "age_over_20", "age_over_21"]
+        );
+    }
+
+    #[test]
+    fn at_most_two_age_over_attestations() {
+        let elements = [
+            "age_over_18",
+            "given_name",
+            "age_over_21",
+            "age_over_65",
+            "family_name",
+        ];
+
+        let limited = limit_age_over_attestations(elements, |element| *element);
+
+        assert_eq!(
+            limited,
+            ["age_over_18", "given_name", "age_over_21", "family_name"]
+        );
+    }
+
+    #[test]
+    fn age_over_identifiers() {
+        assert!(is_age_over("age_over_18"));
+        assert!(is_age_over("age_over_05"));
+        assert!(!is_age_over("age_over_5"));
+        assert!(!is_age_over("age_over_100"));
+        assert!(!is_age_over("age_in_years"));
+    }
+}
@@ -9,11 +9,14 @@
 //! will use for the BLE central client:
 //!
 
-use crate::credential::mdoc::Mdoc;
+use crate::credential::mdoc::{Element, Mdoc, Namespace};
+use crate::mdl::age_over::{
+    age_over_attestations, limit_age_over_attestations, AGE_OVER_NAMESPACE,
+};
 use crate::{storage_manager::StorageManagerInterface, vdc_collection::VdcCollection};
 use std::ops::DerefMut;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     sync::{Arc, Mutex},
 };
 
@@ -88,8 +91,10 @@ pub async fn initialize_mdl_presentation(
             value: format!("Could not generate qr engagement: {e:?}"),
         })?;
     Ok(MdlPresentationSession {
+        mdoc,
         engaged: Mutex::new(engaged_state),
         in_process: Mutex::new(None),
+        response: Mutex::new(None),
         qr_code_uri,
         ble_ident,
     })
@@ -138,8 +143,10 @@ pub fn initialize_mdl_presentation_from_bytes(
             value: format!("Could not generate qr engagement: {e:?}"),
         })?;
     Ok(MdlPresentationSession {
+        mdoc,
         engaged: Mutex::new(engaged_state),
         in_process: Mutex::new(None),
+        response: Mutex::new(None),
         qr_code_uri,
         ble_ident,
     })
@@ -147,8 +154,11 @@ pub fn initialize_mdl_presentation_from_bytes(
 
 #[derive(uniffi::Object)]
 pub struct MdlPresentationSession {
+    mdoc: Arc<Mdoc>,
     engaged: Mutex<device::SessionManagerEngaged>,
     in_process: Mutex<Option<InProcessRecord>>,
+    /// The last response returned by [submit_response](Self::submit_response).
+    response: Mutex<Option<Vec<u8>>>,
     pub qr_code_uri: String,
     pub ble_ident: Vec<u8>,
 }
@@ -220,21 +230,19 @@ impl MdlPresentationSession {
     /// as the id of a key stored in the key manager to be used to sign the response.
     /// Returns a byte array containing the signed response to be returned to the
     /// reader.
+    ///
+    /// Requested `age_over_NN` elements that the mdoc does not contain are answered by the
+    /// element that attests to them, and at most two age attestations are disclosed.
     pub fn generate_response(
         &self,
         permitted_items: HashMap<String, HashMap<String, Vec<String>>>,
     ) -> Result<Vec<u8>, SignatureError> {
-        let permitted = permitted_items
-            .into_iter()
-            .map(|(doc_type, namespaces)| {
-                let ns = namespaces.into_iter().collect();
-                (doc_type, ns)
-            })
-            .collect();
         if let Some(ref mut in_process) = self.in_process.lock().unwrap().deref_mut() {
+            let (items_request, permitted) =
+                apply_age_over_rules(&self.mdoc, &in_process.items_request, permitted_items);
             in_process
                 .session
-                .prepare_response(&in_process.items_request, permitted);
+                .prepare_response(&items_request, permitted);
             Ok(in_process
                 .session
                 .get_next_signature_payload()
@@ -250,6 +258,114 @@ impl MdlPresentationSession {
         }
     }
 
+    /// Preview the items of information that [generate_response](Self::generate_response)
+    /// would disclose for the given permitted items, without preparing a response to sign.
+    ///
+    /// Only elements that are present in the mdoc, requested by the reader and permitted by
+    /// the user are disclosed, so this can be used to show the user exactly what they are
+    /// about to share.
+    pub fn preview_disclosure(
+        &self,
+        permitted_items: HashMap<String, HashMap<String, Vec<String>>>,
+    ) -> Result<DisclosurePreview, ResponseError> {
+        let in_process = self.in_process.lock().map_err(|_| ResponseError::Generic {
+            value: "Could not lock mutex".to_string(),
+        })?;
+        let in_process = in_process.as_ref().ok_or(ResponseError::Generic {
+            value: "No request has been received from the reader".to_string(),
+        })?;
+
+        let (items_request, permitted_items) =
+            apply_age_over_rules(&self.mdoc, &in_process.items_request, permitted_items);
+
+        let doc_type = self.mdoc.doctype();
+        let Some(permitted) = permitted_items.get(&doc_type) else {
+            return Ok(DisclosurePreview {
+                doc_type,
+                namespaces: HashMap::new(),
+            });
+        };
+
+        let details = self.mdoc.details();
+        let namespaces = items_request
+            .iter()
+            .filter(|request| request.doc_type == doc_type)
+            .flat_map(|request| request.namespaces.clone().into_inner())
+            .filter_map(|(namespace, requested)| {
+                let permitted = permitted.get(&namespace)?;
+                let requested = requested.into_inner();
+                let (namespace, elements) =
+                    details.iter().find(|(ns, _)| ns.as_ref() == namespace)?;
+                let elements: Vec<Element> = elements
+                    .iter()
+                    .filter(|element| {
+                        requested.contains_key(&element.identifier)
+                            && permitted.contains(&element.identifier)
+                    })
+                    .cloned()
+                    .collect();
+                (!elements.is_empty()).then(|| (namespace.clone(), elements))
+            })
+            .collect();
+
+        Ok(DisclosurePreview {
+            doc_type,
+            namespaces,
+        })
+    }
+
+    /// Check that a response can be generated for the given permitted items, without asking
+    /// the holder to sign it.
+    ///
+    /// The response is prepared on a copy of the session and signed with a throwaway key, so
+    /// that structural and encoding errors are reported before the holder is prompted to sign,
+    /// e.g. by a biometric prompt. The session itself is left untouched.
+    pub fn dry_run_response(
+        &self,
+        permitted_items: HashMap<String, HashMap<String, Vec<String>>>,
+    ) -> Result<(), SignatureError> {
+        use p256::ecdsa::signature::Signer;
+
+        let mut in_process = self
+            .in_process
+            .lock()
+            .map_err(|_| SignatureError::Generic {
+                value: "Could not lock mutex".to_string(),
+            })?
+            .clone()
+            .ok_or(SignatureError::Generic {
+                value: "No request has been received from the reader".to_string(),
+            })?;
+
+        let (items_request, permitted) =
+            apply_age_over_rules(&self.mdoc, &in_process.items_request, permitted_items);
+        in_process
+            .session
+            .prepare_response(&items_request, permitted);
+        let payload = in_process
+            .session
+            .get_next_signature_payload()
+            .map(|(_, payload)| payload.to_vec())
+            .ok_or(SignatureError::Generic {
+                value: "Failed to get next signature payload".to_string(),
+            })?;
+
+        let key = p256::ecdsa::SigningKey::random(&mut ssi::crypto::rand::thread_rng());
+        let signature: p256::ecdsa::Signature = key.sign(&payload);
+        in_process
+            .session
+            .submit_next_signature(signature.to_bytes().to_vec())
+            .map_err(|e| SignatureError::Generic {
+                value: format!("Could not submit next signature: {e:?}"),
+            })?;
+        in_process
+            .session
+            .retrieve_response()
+            .ok_or(SignatureError::TooManyDocuments)?;
+
+        Ok(())
+    }
+
     pub fn submit_response(&self, signature: Vec<u8>) -> Result<Vec<u8>, SignatureError> {
         let signature = p256::ecdsa::Signature::from_slice(&signature).map_err(|e| {
             SignatureError::InvalidSignature {
@@ -263,10 +379,14 @@ impl MdlPresentationSession {
                 .map_err(|e| SignatureError::Generic {
                     value: format!("Could not submit next signature: {e:?}"),
                 })?;
-            in_process
+            let response = in_process
                 .session
                 .retrieve_response()
-                .ok_or(SignatureError::TooManyDocuments)
+                .ok_or(SignatureError::TooManyDocuments)?;
+            *self.response.lock().map_err(|_| SignatureError::Generic {
+                value: "Could not lock mutex".to_string(),
+            })? = Some(response.clone());
+            Ok(response)
         } else {
             Err(SignatureError::Generic {
                 value: "Could not get lock on session".to_string(),
@@ -274,6 +394,43 @@ impl MdlPresentationSession {
         }
     }
 
+    /// Split the response returned by [submit_response](Self::submit_response) into BLE
+    /// messages of at most `max_chunk_bytes` bytes.
+    ///
+    /// As per ISO/IEC 18013-5 8.3.3.1.1.6, each chunk starts with `0x01` if more chunks
+    /// follow, or `0x00` for the last chunk, so `max_chunk_bytes` is usually the negotiated
+    /// MTU minus 3. The reader reassembles them with
+    /// [reassemble_response_chunks](crate::mdl::reader::reassemble_response_chunks).
+    pub fn response_chunks(&self, max_chunk_bytes: u32) -> Result<Vec<Vec<u8>>, SignatureError> {
+        let payload_bytes = (max_chunk_bytes as usize)
+            .checked_sub(1)
+            .filter(|bytes| *bytes > 0)
+            .ok_or(SignatureError::Generic {
+                value: format!("Chunks of {max_chunk_bytes} bytes cannot carry any data"),
+            })?;
+
+        let response = self.response.lock().map_err(|_| SignatureError::Generic {
+            value: "Could not lock mutex".to_string(),
+        })?;
+        let response = response.as_ref().ok_or(SignatureError::Generic {
+            value: "No response has been submitted".to_string(),
+        })?;
+
+        let count = response.len().div_ceil(payload_bytes).max(1);
+        Ok((0..count)
+            .map(|i| {
+                let start = i * payload_bytes;
+                let end = (start + payload_bytes).min(response.len());
+                let marker = if i + 1 == count {
+                    super::CHUNK_LAST
+                } else {
+                    super::CHUNK_MORE
+                };
+                [&[marker], &response[start..end]].concat()
+            })
+            .collect())
+    }
+
     /// Terminates the mDL exchange session.
     ///
     /// Returns the termination message to be transmitted to the reader.
@@ -299,6 +456,97 @@ impl MdlPresentationSession {
     }
 }
 
+/// Apply the rules of ISO/IEC 18013-5 Section 7.2.5 to the requested and permitted
+/// `age_over_NN` elements.
+///
+/// A requested age attestation that the mdoc does not contain is answered by the element
+/// that attests to it, e.g. a request for `age_over_19` by `age_over_21`, and at most two age
+/// attestations are permitted.
+fn apply_age_over_rules(
+    mdoc: &Mdoc,
+    items_request: &device::RequestedItems,
+    permitted_items: HashMap<String, HashMap<String, Vec<String>>>,
+) -> (device::RequestedItems, device::PermittedItems) {
+    let responding: BTreeMap<String, String> = age_over_attestations(&mdoc.document().namespaces)
+        .into_iter()
+        .flat_map(|(response, requests)| {
+            requests
+                .into_iter()
+                .map(move |request| (request, response.clone()))
+        })
+        .collect();
+    let respond = |namespace: &str, element_identifier: String| {
+        if namespace == AGE_OVER_NAMESPACE {
+            responding
+                .get(&element_identifier)
+                .cloned()
+                .unwrap_or(element_identifier)
+        } else {
+            element_identifier
+        }
+    };
+
+    let items_request: device::RequestedItems = items_request
+        .iter()
+        .cloned()
+        .map(|mut request| {
+            let namespaces = request
+                .namespaces
+                .clone()
+                .into_inner()
+                .into_iter()
+                .filter_map(|(namespace, elements)| {
+                    let elements = elements
+                        .into_inner()
+                        .into_iter()
+                        .map(|(element, intent_to_retain)| {
+                            (respond(&namespace, element), intent_to_retain)
+                        })
+                        .collect();
+                    Some((namespace, NonEmptyMap::maybe_new(elements)?))
+                })
+                .collect();
+            if let Some(namespaces) = NonEmptyMap::maybe_new(namespaces) {
+                request.namespaces = namespaces;
+            }
+            request
+        })
+        .collect();
+
+    let permitted = permitted_items
+        .into_iter()
+        .map(|(doc_type, namespaces)| {
+            let requested = items_request
+                .iter()
+                .find(|request| request.doc_type == doc_type);
+            let namespaces = namespaces
+                .into_iter()
+                .map(|(namespace, elements)| {
+                    let requested =
+                        requested.and_then(|request| request.namespaces.get(&namespace));
+                    let mut seen = HashSet::new();
+                    let elements = elements
+                        .into_iter()
+                        .map(|element| respond(&namespace, element))
+                        .filter(|element| {
+                            requested.is_some_and(|requested| requested.contains_key(element))
+                                && seen.insert(element.clone())
+                        });
+                    let elements = if namespace == AGE_OVER_NAMESPACE {
+                        limit_age_over_attestations(elements, |element| element.clone())
+                    } else {
+                        elements.collect()
+                    };
+                    (namespace, elements)
+                })
+                .collect();
+            (doc_type, namespaces)
+        })
+        .collect();
+
+    (items_request, permitted)
+}
+
 #[derive(thiserror::Error, uniffi::Error, Debug)]
 pub enum SessionError {
     #[error("{value}")]
@@ -311,6 +559,15 @@ pub enum RequestError {
     Generic { value: String },
 }
 
+/// The items of information that will be disclosed to the reader, with their values.
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct DisclosurePreview {
+    /// The document type of the disclosed mdoc.
+    pub doc_type: String,
+    /// The disclosed data elements, grouped by namespace.
+    pub namespaces: HashMap<Namespace, Vec<Element>>,
+}
+
 #[derive(uniffi::Record, Clone)]
 pub struct ItemsRequest {
     doc_type: String,
@@ -341,18 +598,6 @@ pub enum TerminationError {
     Generic { value: String },
 }
 
-#[derive(thiserror::Error, uniffi::Error, Debug)]
-pub enum KeyTransformationError {
-    #[error("{value}")]
-    ToPKCS8 { value: String },
-    #[error("{value}")]
-    FromPKCS8 { value: String },
-    #[error("{value}")]
-    FromSEC1 { value: String },
-    #[error("{value}")]
-    ToSEC1 { value: String },
-}
-
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
@@ -364,27 +609,81 @@ mod tests {
         },
         presentation::reader,
     };
+    use rstest::rstest;
 
     use crate::{
         crypto::{KeyAlias, KeyStore, RustTestKeyManager},
         local_store,
+        mdl::util::test_mdl,
+        reader::MDLSessionManager,
     };
 
     use super::*;
 
-    #[test_log::test(tokio::test)]
-    async fn end_to_end_ble_presentment_holder() {
-        let key_alias = KeyAlias(Uuid::new_v4().to_string());
-        let key_manager = Arc::new(RustTestKeyManager::default());
-        key_manager
-            .generate_p256_signing_key(key_alias.clone())
-            .await
-            .unwrap();
-        let mdl = Arc::new(
-            crate::mdl::util::generate_test_mdl(key_manager.clone(), key_alias.clone()).unwrap(),
+    /// Establish a reader session requesting `elements` of the `org.iso.18013.5.1` namespace,
+    /// and hand its request to the holder.
+    fn establish_reader_session(
+        presentation_session: &MdlPresentationSession,
+        elements: &[(&str, bool)],
+    ) -> Arc<MDLSessionManager> {
+        let namespaces = [(
+            "org.iso.18013.5.1".to_string(),
+            elements
+                .iter()
+                .map(|(element, intent_to_retain)| (element.to_string(), *intent_to_retain))
+                .collect(),
+        )]
+        .into_iter()
+        .collect();
+        let reader_session_data = crate::reader::establish_session(
+            presentation_session.qr_code_uri.clone(),
+            namespaces,
+            Some(vec![include_str!(
+                "../../tests/res/mdl/utrecht-certificate.pem"
+            )
+            .to_string()]),
         )
-        .try_into()
         .unwrap();
+        presentation_session
+            .handle_request(reader_session_data.request)
+            .unwrap();
+        reader_session_data.state
+    }
+
+    /// The `org.iso.18013.5.1` `elements` of the mDL, as permitted items.
+    fn permitted_mdl_items(elements: &[&str]) -> HashMap<String, HashMap<String, Vec<String>>> {
+        [(
+            "org.iso.18013.5.1.mDL".to_string(),
+            [(
+                "org.iso.18013.5.1".to_string(),
+                elements.iter().map(|element| element.to_string()).collect(),
+            )]
+            .into_iter()
+            .collect(),
+        )]
+        .into_iter()
+        .collect()
+    }
+
+    /// Sign and submit the response disclosing `permitted_items`.
+    fn sign_response(
+        presentation_session: &MdlPresentationSession,
+        key_manager: &RustTestKeyManager,
+        key_alias: KeyAlias,
+        permitted_items: HashMap<String, HashMap<String, Vec<String>>>,
+    ) -> Vec<u8> {
+        let signing_payload = presentation_session
+            .generate_response(permitted_items)
+            .unwrap();
+        let key = key_manager.get_signing_key(key_alias).unwrap();
+        let signature = key.sign(signing_payload).unwrap();
+        presentation_session.submit_response(signature).unwrap()
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn end_to_end_ble_presentment_holder() {
+        let (key_manager, key_alias, mdl) = test_mdl().await;
+        let mdl = Arc::new(mdl).try_into().unwrap();
 
         let smi = Arc::new(local_store::LocalStore::new());
 
@@ -423,23 +722,12 @@ mod tests {
             )
             .unwrap();
         let _request_data = presentation_session.handle_request(request).unwrap();
-        let permitted_items = [(
-            "org.iso.18013.5.1.mDL".to_string(),
-            [(
-                "org.iso.18013.5.1".to_string(),
-                vec!["given_name".to_string()],
-            )]
-            .into_iter()
-            .collect(),
-        )]
-        .into_iter()
-        .collect();
-        let signing_payload = presentation_session
-            .generate_response(permitted_items)
-            .unwrap();
-        let key = key_manager.get_signing_key(key_alias).unwrap();
-        let signature = key.sign(signing_payload).unwrap();
-        let response = presentation_session.submit_response(signature).unwrap();
+        let response = sign_response(
+            &presentation_session,
+            &key_manager,
+            key_alias,
+            permitted_mdl_items(&["given_name"]),
+        );
         let res = reader_session_manager.handle_response(&response);
         vdc_collection.delete(mdl.id).await.unwrap();
         assert_eq!(res.errors, BTreeMap::new());
@@ -447,17 +735,8 @@ mod tests {
 
     #[test_log::test(tokio::test)]
     async fn end_to_end_ble_presentment_holder_reader() {
-        let key_alias = KeyAlias(Uuid::new_v4().to_string());
-        let key_manager = Arc::new(RustTestKeyManager::default());
-        key_manager
-            .generate_p256_signing_key(key_alias.clone())
-            .await
-            .unwrap();
-        let mdl = Arc::new(
-            crate::mdl::util::generate_test_mdl(key_manager.clone(), key_alias.clone()).unwrap(),
-        )
-        .try_into()
-        .unwrap();
+        let (key_manager, key_alias, mdl) = test_mdl().await;
+        let mdl = Arc::new(mdl).try_into().unwrap();
 
         let smi = Arc::new(local_store::LocalStore::new());
 
@@ -467,49 +746,225 @@ mod tests {
         let presentation_session = initialize_mdl_presentation(mdl.id, Uuid::new_v4(), smi.clone())
             .await
             .unwrap();
-        let namespaces = [(
-            "org.iso.18013.5.1".to_string(),
-            [
-                ("given_name".to_string(), true),
-                ("family_name".to_string(), false),
-            ]
-            .into_iter()
-            .collect(),
-        )]
-        .into_iter()
-        .collect();
-        let reader_session_data = crate::reader::establish_session(
+        let reader_state = establish_reader_session(
+            &presentation_session,
+            &[("given_name", true), ("family_name", false)],
+        );
+        let response = sign_response(
+            &presentation_session,
+            &key_manager,
+            key_alias,
+            permitted_mdl_items(&["given_name"]),
+        );
+        let res = crate::reader::handle_response(reader_state, response).unwrap();
+        assert_eq!(res.errors, None);
+
+        vdc_collection.delete(mdl.id).await.unwrap();
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn chunked_response_is_reassembled() {
+        let (key_manager, key_alias, mdl) = test_mdl().await;
+
+        let presentation_session =
+            initialize_mdl_presentation_from_bytes(Arc::new(mdl), Uuid::new_v4()).unwrap();
+        let reader_state = establish_reader_session(
+            &presentation_session,
+            &[("given_name", true), ("family_name", false)],
+        );
+        let response = sign_response(
+            &presentation_session,
+            &key_manager,
+            key_alias,
+            permitted_mdl_items(&["given_name", "family_name"]),
+        );
+
+        let chunks = presentation_session.response_chunks(20).unwrap();
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 20));
+        assert!(chunks[..chunks.len() - 1]
+            .iter()
+            .all(|chunk| chunk[0] == crate::mdl::CHUNK_MORE));
+        assert_eq!(chunks.last().unwrap()[0], crate::mdl::CHUNK_LAST);
+
+        let reassembled = crate::reader::reassemble_response_chunks(chunks).unwrap();
+        assert_eq!(reassembled, response);
+        let res = crate::reader::handle_response(reader_state, reassembled).unwrap();
+        assert_eq!(res.errors, None);
+    }
+
+    #[tokio::test]
+    async fn termination_is_reported_to_reader() {
+        let (_, _, mdl) = test_mdl().await;
+
+        let presentation_session =
+            initialize_mdl_presentation_from_bytes(Arc::new(mdl), Uuid::new_v4()).unwrap();
+        let reader_state = establish_reader_session(&presentation_session, &[("given_name", true)]);
+
+        let termination = presentation_session.terminate_session().unwrap();
+        assert!(crate::reader::is_session_termination(&termination));
+        assert!(!crate::reader::is_session_termination(
+            b"not a session message"
+        ));
+
+        let result = crate::reader::handle_response(reader_state, termination);
+        assert!(matches!(
+            result,
+            Err(crate::reader::MDLReaderResponseError::SessionTerminated)
+        ));
+    }
+
+    #[tokio::test]
+    async fn no_chunks_before_response() {
+        let (_, _, mdl) = test_mdl().await;
+        let presentation_session =
+            initialize_mdl_presentation_from_bytes(Arc::new(mdl), Uuid::new_v4()).unwrap();
+
+        assert!(presentation_session.response_chunks(20).is_err());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn preview_matches_disclosed_items() {
+        let (key_manager, key_alias, mdoc) = test_mdl().await;
+
+        let presentation_session =
+            initialize_mdl_presentation_from_bytes(Arc::new(mdoc), Uuid::new_v4()).unwrap();
+        let reader_state = establish_reader_session(
+            &presentation_session,
+            &[("given_name", true), ("family_name", false)],
+        );
+
+        // `portrait` is permitted but was not requested, so it must not be disclosed.
+        let permitted_items = permitted_mdl_items(&["given_name", "portrait"]);
+
+        let preview = presentation_session
+            .preview_disclosure(permitted_items.clone())
+            .unwrap();
+        let response = sign_response(
+            &presentation_session,
+            &key_manager,
+            key_alias,
+            permitted_items,
+        );
+        let response = crate::reader::handle_response(reader_state, response).unwrap();
+        let disclosed = response.verified_response_as_json().unwrap();
+
+        assert_eq!(preview.doc_type, "org.iso.18013.5.1.mDL");
+        assert_eq!(preview.namespaces.len(), 1);
+        let (namespace, elements) = preview.namespaces.iter().next().unwrap();
+        assert_eq!(namespace.as_ref(), "org.iso.18013.5.1");
+
+        let previewed: Vec<_> = elements.iter().map(|e| e.identifier.as_str()).collect();
+        let disclosed: Vec<_> = disclosed["org.iso.18013.5.1"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(previewed, vec!["given_name"]);
+        assert_eq!(previewed, disclosed);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn dry_run_leaves_session_untouched() {
+        let (key_manager, key_alias, mdoc) = test_mdl().await;
+
+        let presentation_session =
+            initialize_mdl_presentation_from_bytes(Arc::new(mdoc), Uuid::new_v4()).unwrap();
+        let permitted_items = permitted_mdl_items(&["given_name"]);
+
+        assert!(presentation_session
+            .dry_run_response(permitted_items.clone())
+            .is_err());
+
+        let reader_state =
+            establish_reader_session(&presentation_session, &[("given_name", false)]);
+
+        presentation_session
+            .dry_run_response(permitted_items.clone())
+            .unwrap();
+
+        // The real response is still the first one the reader receives.
+        let response = sign_response(
+            &presentation_session,
+            &key_manager,
+            key_alias,
+            permitted_items,
+        );
+        let response = crate::reader::handle_response(reader_state, response).unwrap();
+        let disclosed = response.verified_response_as_json().unwrap();
+
+        assert!(disclosed["org.iso.18013.5.1"]["given_name"].is_string());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn age_over_requests_are_answered_by_attesting_elements() {
+        // The test mDL attests to `age_over_18: true` and `age_over_21: true`.
+        let (key_manager, key_alias, mdoc) = test_mdl().await;
+
+        let presentation_session =
+            initialize_mdl_presentation_from_bytes(Arc::new(mdoc), Uuid::new_v4()).unwrap();
+        let requested = ["age_over_16", "age_over_19", "given_name"];
+        let reader_state = establish_reader_session(
+            &presentation_session,
+            &requested.map(|element| (element, false)),
+        );
+
+        let response = sign_response(
+            &presentation_session,
+            &key_manager,
+            key_alias,
+            permitted_mdl_items(&requested),
+        );
+        let response = crate::reader::handle_response(reader_state, response).unwrap();
+        let disclosed = response.verified_response_as_json().unwrap();
+
+        let mut disclosed: Vec<_> = disclosed["org.iso.18013.5.1"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        disclosed.sort();
+        assert_eq!(disclosed, ["age_over_18", "age_over_21", "given_name"]);
+    }
+
+    #[rstest]
+    #[case::two_digits(21, "age_over_21")]
+    #[case::single_digit(5, "age_over_05")]
+    #[tokio::test]
+    async fn age_verification_request(#[case] minimum_age: u8, #[case] attestation: &str) {
+        let (_, _, mdoc) = test_mdl().await;
+
+        let presentation_session =
+            initialize_mdl_presentation_from_bytes(Arc::new(mdoc), Uuid::new_v4()).unwrap();
+        let age_verification = crate::reader::establish_session_age_verification(
             presentation_session.qr_code_uri.clone(),
-            namespaces,
-            Some(vec![include_str!(
-                "../../tests/res/mdl/utrecht-certificate.pem"
-            )
-            .to_string()]),
+            minimum_age,
+            None,
         )
         .unwrap();
-        let _request_data = presentation_session
-            .handle_request(reader_session_data.request)
+        assert_eq!(age_verification.requested_attestation, attestation);
+
+        let items_requests = presentation_session
+            .handle_request(age_verification.session.request)
             .unwrap();
-        let permitted_items = [(
-            "org.iso.18013.5.1.mDL".to_string(),
-            [(
+
+        assert_eq!(items_requests.len(), 1);
+        assert_eq!(
+            items_requests[0].namespaces,
+            HashMap::from([(
                 "org.iso.18013.5.1".to_string(),
-                vec!["given_name".to_string()],
-            )]
-            .into_iter()
-            .collect(),
-        )]
-        .into_iter()
-        .collect();
-        let signing_payload = presentation_session
-            .generate_response(permitted_items)
-            .unwrap();
-        let key = key_manager.get_signing_key(key_alias).unwrap();
-        let signature = key.sign(signing_payload).unwrap();
-        let response = presentation_session.submit_response(signature).unwrap();
-        let res = crate::reader::handle_response(reader_session_data.state, response).unwrap();
-        assert_eq!(res.errors, None);
+                HashMap::from([(attestation.to_string(), false)])
+            )])
+        );
+    }
 
-        vdc_collection.delete(mdl.id).await.unwrap();
+    #[test]
+    fn age_verification_beyond_99() {
+        assert!(
+            crate::reader::establish_session_age_verification("mdoc:".to_string(), 100, None)
+                .is_err()
+        );
     }
 }
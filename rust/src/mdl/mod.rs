@@ -1,3 +1,4 @@
+pub mod age_over;
 pub mod holder;
 pub mod reader;
 pub mod util;
@@ -7,6 +8,12 @@ use ssi::{
     dids::{AnyDidMethod, DIDResolver},
 };
 
+/// First byte of a BLE message chunk that is followed by more chunks (ISO/IEC 18013-5
+/// 8.3.3.1.1.6).
+pub(crate) const CHUNK_MORE: u8 = 0x01;
+/// First byte of the last BLE message chunk.
+pub(crate) const CHUNK_LAST: u8 = 0x00;
+
 #[derive(thiserror::Error, uniffi::Error, Debug)]
 pub enum VCVerificationError {
     #[error("{value}")]
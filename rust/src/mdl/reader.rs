@@ -3,18 +3,35 @@ use std::{
     sync::Arc,
 };
 
+use base64::prelude::*;
 use isomdl::{
     definitions::{
         device_request,
-        helpers::{non_empty_map, NonEmptyMap},
+        device_signed::DeviceAuthentication,
+        helpers::{non_empty_map, NonEmptyMap, Tag24},
+        session::{self, SessionData, SessionTranscript},
         x509::{
             self,
             trust_anchor::{PemTrustAnchor, TrustAnchorRegistry},
         },
+        DeviceAuth, DeviceResponse, DigestAlgorithm, Document,
     },
     presentation::{authentication::AuthenticationStatus as IsoMdlAuthenticationStatus, reader},
 };
+use p256::ecdsa::{signature::Verifier as _, Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use time::OffsetDateTime;
 use uuid::Uuid;
+use x509_cert::Certificate;
+
+use super::age_over::AGE_OVER_NAMESPACE;
+use crate::{
+    credential::mdoc::{to_json_for_display, Mdoc},
+    crypto::KeyAlias,
+    trusted_roots::parse_trusted_roots,
+    verifier::crypto::RustCrypto,
+};
 
 #[derive(thiserror::Error, uniffi::Error, Debug)]
 pub enum MDLReaderSessionError {
@@ -101,6 +118,44 @@ pub fn establish_session(
     })
 }
 
+/// A reader session that requests a single age attestation.
+#[derive(uniffi::Record)]
+pub struct MDLAgeVerificationSessionData {
+    pub session: MDLReaderSessionData,
+    /// The requested age attestation, e.g. `age_over_21`.
+    pub requested_attestation: String,
+}
+
+/// Establish a session that only asks the holder whether they are at least `minimum_age`.
+///
+/// Only the `age_over_NN` element for `minimum_age` is requested, without the intent to
+/// retain it. Following ISO/IEC 18013-5 Section 7.2.5, a holder that lacks this element
+/// answers with the nearest one that attests to it, so the response may contain e.g.
+/// `age_over_18: false` instead of `age_over_21`.
+#[uniffi::export]
+pub fn establish_session_age_verification(
+    uri: String,
+    minimum_age: u8,
+    trust_anchor_registry: Option<Vec<String>>,
+) -> Result<MDLAgeVerificationSessionData, MDLReaderSessionError> {
+    if minimum_age > 99 {
+        return Err(MDLReaderSessionError::Generic {
+            value: format!("age attestations only go up to 99, not {minimum_age}"),
+        });
+    }
+
+    let requested_attestation = format!("age_over_{minimum_age:02}");
+    let requested_items = HashMap::from([(
+        AGE_OVER_NAMESPACE.to_string(),
+        HashMap::from([(requested_attestation.clone(), false)]),
+    )]);
+
+    Ok(MDLAgeVerificationSessionData {
+        session: establish_session(uri, requested_items, trust_anchor_registry)?,
+        requested_attestation,
+    })
+}
+
 #[derive(thiserror::Error, uniffi::Error, Debug, PartialEq)]
 pub enum MDLReaderResponseError {
     #[error("Invalid decryption")]
@@ -111,6 +166,11 @@ pub enum MDLReaderResponseError {
     InvalidIssuerAuthentication,
     #[error("Invalid device authentication")]
     InvalidDeviceAuthentication,
+    #[error("Invalid trust anchors: {value}")]
+    InvalidTrustAnchors { value: String },
+    /// The holder terminated the session instead of sending a response.
+    #[error("Session terminated by the holder")]
+    SessionTerminated,
     #[error("{value}")]
     Generic { value: String },
 }
@@ -233,11 +293,73 @@ pub fn verified_response_as_json_string(
     })
 }
 
+/// Reassemble a response received as BLE message chunks, e.g. those of
+/// [response_chunks](crate::mdl::holder::MdlPresentationSession::response_chunks).
+///
+/// Each chunk starts with `0x01` if more chunks follow, or `0x00` for the last chunk, as per
+/// ISO/IEC 18013-5 8.3.3.1.1.6.
+#[uniffi::export]
+pub fn reassemble_response_chunks(chunks: Vec<Vec<u8>>) -> Result<Vec<u8>, MDLReaderResponseError> {
+    let mut response = Vec::new();
+    let mut chunks = chunks.into_iter().peekable();
+    while let Some(chunk) = chunks.next() {
+        let (marker, data) =
+            chunk
+                .split_first()
+                .ok_or_else(|| MDLReaderResponseError::Generic {
+                    value: "Empty response chunk".to_string(),
+                })?;
+        response.extend_from_slice(data);
+
+        match (*marker, chunks.peek().is_some()) {
+            (super::CHUNK_MORE, true) | (super::CHUNK_LAST, false) => {}
+            (super::CHUNK_MORE, false) => {
+                return Err(MDLReaderResponseError::Generic {
+                    value: "Response is missing its last chunk".to_string(),
+                })
+            }
+            (super::CHUNK_LAST, true) => {
+                return Err(MDLReaderResponseError::Generic {
+                    value: "Response has chunks after its last chunk".to_string(),
+                })
+            }
+            (marker, _) => {
+                return Err(MDLReaderResponseError::Generic {
+                    value: format!("Invalid response chunk marker: {marker:#04x}"),
+                })
+            }
+        }
+    }
+
+    if response.is_empty() {
+        return Err(MDLReaderResponseError::Generic {
+            value: "Response has no chunks".to_string(),
+        });
+    }
+    Ok(response)
+}
+
+/// Whether `bytes` is a `SessionData` terminating the session without a response, as sent by
+/// [terminate_session](crate::mdl::holder::MdlPresentationSession::terminate_session).
+pub fn is_session_termination(bytes: &[u8]) -> bool {
+    isomdl::cbor::from_slice::<SessionData>(bytes).is_ok_and(|message| {
+        message.data.is_none()
+            && matches!(message.status, Some(session::Status::SessionTermination))
+    })
+}
+
+/// Handle a response from the holder.
+///
+/// Fails with [MDLReaderResponseError::SessionTerminated] if the holder terminated the session
+/// instead.
 #[uniffi::export]
 pub fn handle_response(
     state: Arc<MDLSessionManager>,
     response: Vec<u8>,
 ) -> Result<MDLReaderResponseData, MDLReaderResponseError> {
+    if is_session_termination(&response) {
+        return Err(MDLReaderResponseError::SessionTerminated);
+    }
     let mut state = state.0.clone();
     let validated_response = state.handle_response(&response);
     let errors = if !validated_response.errors.is_empty() {
@@ -279,3 +401,420 @@ pub fn handle_response(
         errors,
     })
 }
+
+/// The documents of a `DeviceResponse`, as verified by [verify_device_response].
+#[derive(uniffi::Record, Debug)]
+pub struct VerifiedDocuments {
+    pub documents: Vec<VerifiedDocument>,
+}
+
+#[derive(uniffi::Record, Debug)]
+pub struct VerifiedDocument {
+    /// The docType signed by the issuer in the MSO.
+    pub doc_type: String,
+    /// The data elements whose digest matches the MSO, by namespace and element identifier.
+    pub namespaces: HashMap<String, HashMap<String, MDocItem>>,
+    /// Outcome of issuer authentication, including the digests of the data elements.
+    pub issuer_authentication: AuthenticationStatus,
+    /// Outcome of device authentication.
+    pub device_authentication: AuthenticationStatus,
+    /// Errors that occurred while verifying the document.
+    pub errors: Vec<String>,
+}
+
+/// A session transcript received as CBOR, e.g. one computed by the caller for a DC API
+/// request, which is embedded as is in the `DeviceAuthentication`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+struct RawSessionTranscript(ciborium::Value);
+
+impl SessionTranscript for RawSessionTranscript {}
+
+/// Verify a base64url-encoded `DeviceResponse` received outside of an established session,
+/// such as through the DC API.
+///
+/// The document signer of each document must be issued by one of the PEM-encoded `roots_pem`,
+/// and the device signature must be over the CBOR-encoded `session_transcript`. Failures are
+/// reported per document, and data elements are only returned once their digest has been
+/// checked against the MSO.
+#[uniffi::export]
+pub fn verify_device_response(
+    device_response_b64: String,
+    session_transcript: Vec<u8>,
+    roots_pem: Vec<String>,
+) -> Result<VerifiedDocuments, MDLReaderResponseError> {
+    let device_response = BASE64_URL_SAFE_NO_PAD
+        .decode(device_response_b64.trim_end_matches('='))
+        .map_err(|_| MDLReaderResponseError::InvalidParsing)?;
+    let device_response: DeviceResponse = isomdl::cbor::from_slice(&device_response)
+        .map_err(|_| MDLReaderResponseError::InvalidParsing)?;
+    let session_transcript: RawSessionTranscript = isomdl::cbor::from_slice(&session_transcript)
+        .map_err(|_| MDLReaderResponseError::InvalidParsing)?;
+    let trusted_roots = parse_trusted_roots(roots_pem).map_err(|e| {
+        MDLReaderResponseError::InvalidTrustAnchors {
+            value: format!("{e:#}"),
+        }
+    })?;
+
+    let documents = device_response
+        .documents
+        .iter()
+        .flat_map(|documents| documents.iter())
+        .map(|document| verify_document(document, &session_transcript, &trusted_roots))
+        .collect();
+
+    Ok(VerifiedDocuments { documents })
+}
+
+fn verify_document(
+    document: &Document,
+    session_transcript: &RawSessionTranscript,
+    trusted_roots: &[Certificate],
+) -> VerifiedDocument {
+    let mut verified = VerifiedDocument {
+        doc_type: document.doc_type.clone(),
+        namespaces: HashMap::new(),
+        issuer_authentication: AuthenticationStatus::Invalid,
+        device_authentication: AuthenticationStatus::Invalid,
+        errors: vec![],
+    };
+
+    let mdoc =
+        match Mdoc::new_from_issuer_signed(KeyAlias(String::new()), document.issuer_signed.clone())
+        {
+            Ok(mdoc) => mdoc,
+            Err(e) => {
+                verified
+                    .errors
+                    .push(format!("invalid issuer signed data: {e}"));
+                return verified;
+            }
+        };
+
+    // Only the MSO docType is signed by the issuer.
+    let mso = &mdoc.document().mso;
+    verified.doc_type = mso.doc_type.clone();
+    let doc_type_matches = document.doc_type == mso.doc_type;
+    if !doc_type_matches {
+        verified.errors.push(format!(
+            "docType `{}` does not match the MSO docType `{}`",
+            document.doc_type, mso.doc_type
+        ));
+    }
+    let within_validity = check_mso_validity(&mdoc, &mut verified.errors);
+
+    let issuer_signature = mdoc.verify_issuer_signature_with_roots(&RustCrypto, trusted_roots);
+    if let Err(e) = &issuer_signature {
+        verified.errors.push(format!("issuer authentication: {e}"));
+    }
+    let (namespaces, digests_match) = verified_elements(&mdoc, document, &mut verified.errors);
+    verified.namespaces = namespaces;
+    if issuer_signature.is_ok() && digests_match && doc_type_matches && within_validity {
+        verified.issuer_authentication = AuthenticationStatus::Valid;
+    }
+
+    match verify_device_signature(&mdoc, document, session_transcript) {
+        Ok(()) => verified.device_authentication = AuthenticationStatus::Valid,
+        Err(e) => verified.errors.push(format!("device authentication: {e}")),
+    }
+
+    verified
+}
+
+/// Whether the current time is within the MSO `validityInfo`, reporting it in `errors` if not.
+fn check_mso_validity(mdoc: &Mdoc, errors: &mut Vec<String>) -> bool {
+    let validity_info = &mdoc.document().mso.validity_info;
+    let now = OffsetDateTime::now_utc();
+    if now < validity_info.valid_from {
+        errors.push(format!(
+            "the MSO is not valid until {}",
+            mdoc.validity_info().valid_from
+        ));
+        return false;
+    }
+    if now > validity_info.valid_until {
+        errors.push(format!(
+            "the MSO expired at {}",
+            mdoc.validity_info().valid_until
+        ));
+        return false;
+    }
+    true
+}
+
+/// The issuer-signed data elements of a document whose digest matches its MSO, reporting the
+/// others in `errors`, and whether every digest matched.
+fn verified_elements(
+    mdoc: &Mdoc,
+    document: &Document,
+    errors: &mut Vec<String>,
+) -> (HashMap<String, HashMap<String, MDocItem>>, bool) {
+    let mso = &mdoc.document().mso;
+    let digest = |bytes: &[u8]| match mso.digest_algorithm {
+        DigestAlgorithm::SHA256 => Sha256::digest(bytes).to_vec(),
+        DigestAlgorithm::SHA384 => Sha384::digest(bytes).to_vec(),
+        DigestAlgorithm::SHA512 => Sha512::digest(bytes).to_vec(),
+    };
+
+    let mut namespaces: HashMap<String, HashMap<String, MDocItem>> = HashMap::new();
+    let mut digests_match = true;
+    for (namespace, elements) in document
+        .issuer_signed
+        .namespaces
+        .iter()
+        .flat_map(|n| n.iter())
+    {
+        for element in elements.iter() {
+            let item = element.as_ref();
+            let expected = mso
+                .value_digests
+                .get(namespace)
+                .and_then(|digests| digests.get(&item.digest_id));
+            let matches = isomdl::cbor::to_vec(element).is_ok_and(|bytes| {
+                expected.is_some_and(|expected| expected.as_ref() == digest(&bytes))
+            });
+            if !matches {
+                digests_match = false;
+                errors.push(format!(
+                    "digest mismatch for {namespace}/{}",
+                    item.element_identifier
+                ));
+                continue;
+            }
+
+            match to_json_for_display(&item.element_value) {
+                Some(value) if is_mdoc_item(&value) => {
+                    namespaces
+                        .entry(namespace.clone())
+                        .or_default()
+                        .insert(item.element_identifier.clone(), value.into());
+                }
+                _ => errors.push(format!(
+                    "unsupported value for {namespace}/{}",
+                    item.element_identifier
+                )),
+            }
+        }
+    }
+    (namespaces, digests_match)
+}
+
+/// Whether a value can be converted to an [MDocItem], which has no null or fractional values.
+fn is_mdoc_item(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => false,
+        serde_json::Value::Number(n) => n.is_i64(),
+        serde_json::Value::Array(a) => a.iter().all(is_mdoc_item),
+        serde_json::Value::Object(m) => m.values().all(is_mdoc_item),
+        serde_json::Value::Bool(_) | serde_json::Value::String(_) => true,
+    }
+}
+
+/// Verify the `DeviceSignature` of a document with the device key of its MSO.
+fn verify_device_signature(
+    mdoc: &Mdoc,
+    document: &Document,
+    session_transcript: &RawSessionTranscript,
+) -> Result<(), String> {
+    let DeviceAuth::DeviceSignature(device_signature) = &document.device_signed.device_auth else {
+        return Err("only device signatures are supported".into());
+    };
+
+    let device_authentication = Tag24::new(DeviceAuthentication::new(
+        session_transcript.clone(),
+        document.doc_type.clone(),
+        document.device_signed.namespaces.clone(),
+    ))
+    .map_err(|e| e.to_string())?;
+    let device_authentication_bytes =
+        isomdl::cbor::to_vec(&device_authentication).map_err(|e| e.to_string())?;
+
+    let device_key: VerifyingKey =
+        p256::PublicKey::from_jwk_str(&mdoc.device_key_jwk().map_err(|e| e.to_string())?)
+            .map_err(|e| format!("device key is not a P-256 key: {e}"))?
+            .into();
+
+    device_signature.verify_detached_signature(
+        &device_authentication_bytes,
+        &[],
+        |signature, payload| {
+            let signature = Signature::from_slice(signature).map_err(|e| e.to_string())?;
+            device_key
+                .verify(payload, &signature)
+                .map_err(|_| "invalid device signature".to_string())
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use isomdl::definitions::{
+        device_response::Status, device_signed::DeviceNamespaces, helpers::NonEmptyVec,
+        DeviceSigned, IssuerSigned,
+    };
+
+    use super::*;
+    use crate::{
+        crypto::{KeyStore, SigningKey as _},
+        oid4vp::iso_18013_7::prepare_response::prepare_device_signature,
+    };
+
+    const IACA_CERTIFICATE: &str = include_str!("../../tests/res/mdl/iaca-certificate.pem");
+    const IACA_KEY: &str = include_str!("../../tests/res/mdl/iaca-key.pem");
+    const HANDOVER: &str = "test-handover";
+
+    /// Present every data element of a test mDL, as the holder would for the transcript
+    /// `[null, null, HANDOVER]`, claiming `doc_type` in place of the mDL docType if set.
+    async fn device_response(doc_type: Option<&str>) -> String {
+        let (key_manager, key_alias) = crate::mdl::util::test_device_key().await;
+        let mdoc = crate::mdl::util::generate_test_mdl_with_iaca(
+            key_manager.clone(),
+            key_alias.clone(),
+            IACA_CERTIFICATE,
+            IACA_KEY,
+        )
+        .unwrap();
+
+        let namespaces = mdoc
+            .document()
+            .namespaces
+            .iter()
+            .map(|(namespace, elements)| {
+                let elements = elements
+                    .iter()
+                    .map(|(_, element)| element.clone())
+                    .collect();
+                (namespace.clone(), NonEmptyVec::maybe_new(elements).unwrap())
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let doc_type = doc_type
+            .map(str::to_string)
+            .unwrap_or_else(|| mdoc.doctype());
+        let device_namespaces = Tag24::new(DeviceNamespaces::new()).unwrap();
+        let prepared = prepare_device_signature(
+            HANDOVER.to_string(),
+            doc_type.clone(),
+            device_namespaces.clone(),
+            ssi::claims::cose::coset::iana::Algorithm::ES256,
+        )
+        .unwrap();
+        let signature = key_manager
+            .get_signing_key(key_alias)
+            .unwrap()
+            .sign(prepared.signature_payload().to_vec())
+            .unwrap();
+
+        let document = Document {
+            doc_type,
+            issuer_signed: IssuerSigned {
+                issuer_auth: mdoc.document().issuer_auth.clone(),
+                namespaces: NonEmptyMap::maybe_new(namespaces),
+            },
+            device_signed: DeviceSigned {
+                namespaces: device_namespaces,
+                device_auth: DeviceAuth::DeviceSignature(prepared.finalize(signature)),
+            },
+            errors: None,
+        };
+        let device_response = DeviceResponse {
+            version: "1.0".into(),
+            documents: Some(NonEmptyVec::new(document)),
+            document_errors: None,
+            status: Status::OK,
+        };
+
+        BASE64_URL_SAFE_NO_PAD.encode(isomdl::cbor::to_vec(&device_response).unwrap())
+    }
+
+    fn session_transcript(handover: &str) -> Vec<u8> {
+        isomdl::cbor::to_vec(&ciborium::Value::Array(vec![
+            ciborium::Value::Null,
+            ciborium::Value::Null,
+            ciborium::Value::Text(handover.to_string()),
+        ]))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn verify_holder_device_response() {
+        let verified = verify_device_response(
+            device_response(None).await,
+            session_transcript(HANDOVER),
+            vec![IACA_CERTIFICATE.to_string()],
+        )
+        .unwrap();
+
+        let [document] = verified.documents.as_slice() else {
+            panic!("expected a single document");
+        };
+        assert_eq!(document.errors, Vec::<String>::new());
+        assert_eq!(document.issuer_authentication, AuthenticationStatus::Valid);
+        assert_eq!(document.device_authentication, AuthenticationStatus::Valid);
+        assert!(matches!(
+            &document.namespaces["org.iso.18013.5.1"]["family_name"],
+            MDocItem::Text(family_name) if family_name == "Smith"
+        ));
+    }
+
+    #[tokio::test]
+    async fn device_response_for_another_transcript() {
+        let verified = verify_device_response(
+            device_response(None).await,
+            session_transcript("another-handover"),
+            vec![IACA_CERTIFICATE.to_string()],
+        )
+        .unwrap();
+
+        let document = &verified.documents[0];
+        assert_eq!(document.issuer_authentication, AuthenticationStatus::Valid);
+        assert_eq!(
+            document.device_authentication,
+            AuthenticationStatus::Invalid
+        );
+    }
+
+    #[tokio::test]
+    async fn doc_type_mismatch() {
+        let verified = verify_device_response(
+            device_response(Some("org.iso.23220.photoid.1")).await,
+            session_transcript(HANDOVER),
+            vec![IACA_CERTIFICATE.to_string()],
+        )
+        .unwrap();
+
+        let document = &verified.documents[0];
+        assert_eq!(document.doc_type, "org.iso.18013.5.1.mDL");
+        assert_eq!(
+            document.issuer_authentication,
+            AuthenticationStatus::Invalid
+        );
+        assert_eq!(
+            document.errors,
+            vec![
+                "docType `org.iso.23220.photoid.1` does not match the MSO docType \
+                 `org.iso.18013.5.1.mDL`"
+                    .to_string()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_mso() {
+        let (_, key_alias, mdoc) = crate::mdl::util::test_mdl().await;
+        let mut document = mdoc.document().clone();
+        document.mso.validity_info.valid_until =
+            OffsetDateTime::now_utc() - time::Duration::days(1);
+        let mdoc = Mdoc::new_from_parts(document, key_alias);
+
+        let mut errors = vec![];
+        assert!(!check_mso_validity(&mdoc, &mut errors));
+        assert_eq!(
+            errors,
+            vec![format!(
+                "the MSO expired at {}",
+                mdoc.validity_info().valid_until
+            )]
+        );
+    }
+}
@@ -77,10 +77,90 @@ struct MinimalEcJwk {
 fn generate_test_mdl_inner(
     key_manager: Arc<dyn KeyStore>,
     key_alias: KeyAlias,
+) -> Result<crate::credential::mdoc::Mdoc> {
+    generate_test_mdl_with_iaca(
+        key_manager,
+        key_alias,
+        include_str!("../../tests/res/mdl/utrecht-certificate.pem"),
+        include_str!("../../tests/res/mdl/utrecht-key.pem"),
+    )
+}
+
+/// A test key store holding a newly generated P-256 device key, along with the key's alias.
+#[cfg(test)]
+pub(crate) async fn test_device_key() -> (Arc<crate::crypto::RustTestKeyManager>, KeyAlias) {
+    let key_manager = Arc::new(crate::crypto::RustTestKeyManager::default());
+    let key_alias = KeyAlias(uuid::Uuid::new_v4().to_string());
+    key_manager
+        .generate_p256_signing_key(key_alias.clone())
+        .await
+        .unwrap();
+    (key_manager, key_alias)
+}
+
+/// A test mDL, along with the key store and alias of its device key.
+#[cfg(test)]
+pub(crate) async fn test_mdl() -> (
+    Arc<crate::crypto::RustTestKeyManager>,
+    KeyAlias,
+    crate::credential::mdoc::Mdoc,
+) {
+    let (key_manager, key_alias) = test_device_key().await;
+    let mdoc = generate_test_mdl(key_manager.clone(), key_alias.clone()).unwrap();
+    (key_manager, key_alias, mdoc)
+}
+
+/// Generate a test mDL whose document signer certificate is issued by the supplied IACA.
+pub(crate) fn generate_test_mdl_with_iaca(
+    key_manager: Arc<dyn KeyStore>,
+    key_alias: KeyAlias,
+    iaca_cert_pem: &str,
+    iaca_key_pem: &str,
+) -> Result<crate::credential::mdoc::Mdoc> {
+    generate_test_mdl_with_signer_validity(
+        key_manager,
+        key_alias,
+        iaca_cert_pem,
+        iaca_key_pem,
+        // Document signer certificate valid for sixty days.
+        Duration::from_secs(60 * 60 * 24 * 60),
+    )
+}
+
+/// Generate a test mDL whose document signer certificate is issued by the supplied IACA, and
+/// is valid for `signer_validity` from now.
+pub(crate) fn generate_test_mdl_with_signer_validity(
+    key_manager: Arc<dyn KeyStore>,
+    key_alias: KeyAlias,
+    iaca_cert_pem: &str,
+    iaca_key_pem: &str,
+    signer_validity: Duration,
+) -> Result<crate::credential::mdoc::Mdoc> {
+    generate_test_mdl_with_validity(
+        key_manager,
+        key_alias,
+        iaca_cert_pem,
+        iaca_key_pem,
+        signer_validity,
+        // mDL valid for thirty days.
+        Duration::from_secs(60 * 60 * 24 * 30),
+    )
+}
+
+/// Generate a test mDL whose document signer certificate is valid for `signer_validity`, and
+/// whose MSO is valid for `mdoc_validity`, from now.
+pub(crate) fn generate_test_mdl_with_validity(
+    key_manager: Arc<dyn KeyStore>,
+    key_alias: KeyAlias,
+    iaca_cert_pem: &str,
+    iaca_key_pem: &str,
+    signer_validity: Duration,
+    mdoc_validity: Duration,
 ) -> Result<crate::credential::mdoc::Mdoc> {
     tracing::info!("Generating test mDL");
     let (certificate, signer) =
-        setup_certificate_chain().context("failed to setup certificate chain")?;
+        setup_certificate_chain(iaca_cert_pem, iaca_key_pem, signer_validity)
+            .context("failed to setup certificate chain")?;
     let key = key_manager
         .get_signing_key(key_alias.clone())
         .context("failed to get signing key")?;
@@ -93,7 +173,7 @@ fn generate_test_mdl_inner(
     )
     .context("failed to parse public key")?;
 
-    let mdoc_builder = prepare_mdoc(pk).context("failed to prepare mdoc")?;
+    let mdoc_builder = prepare_mdoc(pk, mdoc_validity).context("failed to prepare mdoc")?;
 
     let x5chain = X5Chain::builder()
         .with_certificate(certificate)
@@ -138,7 +218,7 @@ fn generate_test_mdl_inner(
     ))
 }
 
-fn prepare_mdoc(pub_key: PublicKey) -> Result<isomdl::issuance::mdoc::Builder> {
+fn prepare_mdoc(pub_key: PublicKey, validity: Duration) -> Result<isomdl::issuance::mdoc::Builder> {
     let isomdl_data = serde_json::json!(
         {
           "family_name":"Smith",
@@ -243,8 +323,7 @@ fn prepare_mdoc(pub_key: PublicKey) -> Result<isomdl::issuance::mdoc::Builder> {
     let validity_info = ValidityInfo {
         signed: OffsetDateTime::now_utc(),
         valid_from: OffsetDateTime::now_utc(),
-        // mDL valid for thirty days.
-        valid_until: OffsetDateTime::now_utc() + Duration::from_secs(60 * 60 * 24 * 30),
+        valid_until: OffsetDateTime::now_utc() + validity,
         expected_update: None,
     };
 
@@ -273,16 +352,18 @@ fn prepare_mdoc(pub_key: PublicKey) -> Result<isomdl::issuance::mdoc::Builder> {
         .device_key_info(device_key_info))
 }
 
-fn setup_certificate_chain() -> Result<(Certificate, p256::ecdsa::SigningKey)> {
-    let iaca_cert_pem = include_str!("../../tests/res/mdl/utrecht-certificate.pem");
+pub(crate) fn setup_certificate_chain(
+    iaca_cert_pem: &str,
+    iaca_key_pem: &str,
+    signer_validity: Duration,
+) -> Result<(Certificate, p256::ecdsa::SigningKey)> {
     let iaca_cert = Certificate::from_pem(iaca_cert_pem)?;
     let iaca_name: Name = iaca_cert.tbs_certificate.subject;
-    let key_pem = include_str!("../../tests/res/mdl/utrecht-key.pem");
-    let iaca_key = p256::ecdsa::SigningKey::from_pkcs8_pem(key_pem)?;
+    let iaca_key = p256::ecdsa::SigningKey::from_pkcs8_pem(iaca_key_pem)?;
 
     let ds_key = p256::ecdsa::SigningKey::random(&mut rand::thread_rng());
     let mut prepared_ds_certificate =
-        prepare_signer_certificate(&ds_key, &iaca_key, iaca_name.clone())?;
+        prepare_signer_certificate(&ds_key, &iaca_key, iaca_name.clone(), signer_validity)?;
     let signature: p256::ecdsa::Signature = iaca_key.sign(&prepared_ds_certificate.finalize()?);
     let ds_certificate: Certificate =
         prepared_ds_certificate.assemble(signature.to_der().to_bitstring()?)?;
@@ -294,6 +375,7 @@ fn prepare_signer_certificate<'s, S>(
     signer_key: &'s S,
     iaca_key: &'s S,
     iaca_name: Name,
+    validity: Duration,
 ) -> Result<CertificateBuilder<'s, S>>
 where
     S: KeypairRef + DynSignatureAlgorithmIdentifier,
@@ -312,8 +394,7 @@ where
             issuer: Some(iaca_name),
         },
         rand::random::<u64>().into(),
-        // Document signer certificate valid for sixty days.
-        Validity::from_now(Duration::from_secs(60 * 60 * 24 * 60))?,
+        Validity::from_now(validity)?,
         "CN=SpruceID Test DS,C=US,ST=NY,O=SpruceID".parse()?,
         spki,
         iaca_key,
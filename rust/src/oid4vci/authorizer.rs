@@ -0,0 +1,23 @@
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum Oid4vciAuthorizerError {
+    #[error("The user cancelled the authorization")]
+    Cancelled,
+    #[error("Failed to authorize: {reason}")]
+    Failed { reason: String },
+}
+
+/// Takes the user through the authorization endpoint of an issuer for offers using the
+/// authorization code grant, e.g. by opening the authorization URL in a browser tab.
+#[uniffi::export(with_foreign)]
+#[async_trait::async_trait]
+pub trait Oid4vciAuthorizer: Send + Sync {
+    /// Return the client id of the wallet at the authorization server.
+    fn client_id(&self) -> String;
+
+    /// Return the redirect URL the authorization server redirects to once the user is done.
+    fn redirect_url(&self) -> String;
+
+    /// Open `authorization_url` and return the full URL the authorization server redirected
+    /// to, which carries the authorization `code` and `state`.
+    async fn authorize(&self, authorization_url: String) -> Result<String, Oid4vciAuthorizerError>;
+}
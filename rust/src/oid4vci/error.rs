@@ -28,6 +28,11 @@ pub enum Oid4vciError {
     #[error("Failed to acquire lock for {_0}")]
     LockError(String),
 
+    #[error(
+        "proof of possession audience `{found}` does not match the credential issuer `{expected}`"
+    )]
+    PopAudienceMismatch { expected: String, found: String },
+
     #[error("{vp_request}")]
     VpRequestRequired { vp_request: serde_json::Value },
 
@@ -7,6 +7,9 @@ use oid4vci::oauth2::{
     AsyncHttpClient as ExtAsyncHttpClient, HttpRequest as ExtHttpRequest,
     HttpResponse as ExtHttpResponse, SyncHttpClient as ExtSyncHttpClient,
 };
+use openid4vp::core::util::AsyncHttpClient as Oid4vpAsyncHttpClient;
+
+use crate::{oid4vp::http_client::Oid4vpHttpClient, proxy::HttpProxyError};
 
 #[derive(thiserror::Error, uniffi::Error, Debug)]
 pub enum HttpClientError {
@@ -195,6 +198,45 @@ impl IHttpClient {
     }
 }
 
+/// [AsyncHttpClient] for flows that are driven entirely from Rust and so have no foreign
+/// client to use.
+///
+/// Requests go through an [Oid4vpHttpClient], and so honour the configured proxy.
+pub(crate) struct ReqwestHttpClient(Oid4vpHttpClient);
+
+impl ReqwestHttpClient {
+    pub(crate) fn new() -> Result<Self, HttpProxyError> {
+        Oid4vpHttpClient::new().map(Self)
+    }
+}
+
+#[async_trait]
+impl AsyncHttpClient for ReqwestHttpClient {
+    async fn http_client(&self, request: HttpRequest) -> Result<HttpResponse, HttpClientError> {
+        let mut builder = Request::builder()
+            .method(Method::from_str(&request.method).map_err(|_| HttpClientError::MethodParse)?)
+            .uri(Uri::from_str(&request.url).map_err(|_| HttpClientError::UrlParse)?);
+        for (key, value) in request.headers {
+            builder = builder.header(key, value);
+        }
+        let request = builder
+            .body(request.body)
+            .map_err(|_| HttpClientError::RequestBuilder)?;
+
+        let response = Oid4vpAsyncHttpClient::execute(&self.0, request)
+            .await
+            .map_err(|e| HttpClientError::Other {
+                error: format!("{e:#}"),
+            })?;
+
+        Ok(HttpResponse {
+            status_code: response.status().as_u16(),
+            headers: headermap_to_hashmap(response.headers())?,
+            body: response.into_body(),
+        })
+    }
+}
+
 /// Internal Arc Wrapper to be able to impl traits for it
 /// Examples include:
 ///  - `openidconnect::(As|S)yncHttpClient` for `uniffi`'s foreign trait
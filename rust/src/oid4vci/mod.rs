@@ -7,7 +7,10 @@ use oid4vci::{
     credential::ResponseEnum,
     credential_offer::CredentialOffer,
     metadata::{authorization_server::GrantType, AuthorizationServerMetadata, MetadataDiscovery},
-    oauth2::{ClientId, RedirectUrl, TokenResponse as ITokenResponse},
+    oauth2::{
+        AuthorizationCode, ClientId, CsrfToken, PkceCodeChallenge, RedirectUrl,
+        TokenResponse as ITokenResponse,
+    },
     profiles::{
         core::{
             self,
@@ -29,22 +32,30 @@ use ssi::{
 };
 use url::Url;
 
+pub use authorizer::*;
 pub use context_loader::context_loader_from_map;
 pub use error::*;
 pub use http_client::*;
 pub use metadata::*;
 pub use options::*;
 pub use session::*;
+pub use verify::*;
 pub use wrapper::*;
 
-use crate::credential::CredentialFormat;
+use crate::{
+    credential::{mdoc::Mdoc, CredentialFormat, ParsedCredential},
+    crypto::{KeyAlias, KeyStore},
+    proof_of_possession::create_pop_jwt,
+};
 
+mod authorizer;
 mod context_loader;
 mod error;
 mod http_client;
 mod metadata;
 mod options;
 mod session;
+mod verify;
 mod wrapper;
 
 #[uniffi::export(async_runtime = "tokio")]
@@ -87,32 +98,13 @@ pub async fn oid4vci_initiate_with_offer(
 
     let grants = credential_offer.grants().map(|g| g.to_owned());
 
-    let authorization_metadata =
+    // TODO: maybe offer a way for the wallet to pick grant ordering
+    // when multiple options are present
+    let (grant_type, authorization_server) =
         if let Some(grant) = credential_offer.pre_authorized_code_grant() {
-            // TODO: maybe offer a way for the wallet to pick grant ordering
-            // when multiple options are present
-
-            let authorization_server = grant.authorization_server();
-
-            match &http_client.0 {
-                Either::Left(sync_client) => {
-                    AuthorizationServerMetadata::discover_from_credential_issuer_metadata(
-                        sync_client,
-                        &issuer_metadata,
-                        Some(&GrantType::PreAuthorizedCode),
-                        authorization_server,
-                    )
-                }
-                Either::Right(async_client) => {
-                    AuthorizationServerMetadata::discover_from_credential_issuer_metadata_async(
-                        async_client,
-                        &issuer_metadata,
-                        Some(&GrantType::PreAuthorizedCode),
-                        authorization_server,
-                    )
-                    .await
-                }
-            }
+            (GrantType::PreAuthorizedCode, grant.authorization_server())
+        } else if let Some(grant) = credential_offer.authorization_code_grant() {
+            (GrantType::AuthorizationCode, grant.authorization_server())
         } else {
             // TODO: if grants isn't present in the credential offer
             // we must determine the grant type by using the metadata.
@@ -121,11 +113,31 @@ pub async fn oid4vci_initiate_with_offer(
             // metadata. Future solution must keep in mind that the
             // `authorization_servers` field is an array, so multiple
             // grant options from different servers may be available.
-            todo!("determine grant type with metadata")
+            return Err(Oid4vciError::UnsupportedGrantType);
+        };
+
+    let authorization_metadata = match &http_client.0 {
+        Either::Left(sync_client) => {
+            AuthorizationServerMetadata::discover_from_credential_issuer_metadata(
+                sync_client,
+                &issuer_metadata,
+                Some(&grant_type),
+                authorization_server,
+            )
         }
-        .map_err(|_| {
-            Oid4vciError::RequestError("failed to discover authorization server metadata".into())
-        })?;
+        Either::Right(async_client) => {
+            AuthorizationServerMetadata::discover_from_credential_issuer_metadata_async(
+                async_client,
+                &issuer_metadata,
+                Some(&grant_type),
+                authorization_server,
+            )
+            .await
+        }
+    }
+    .map_err(|_| {
+        Oid4vciError::RequestError("failed to discover authorization server metadata".into())
+    })?;
 
     let credential_requests: Vec<ProfilesCredentialRequest> = issuer_metadata
         .credential_configurations_supported()
@@ -340,6 +352,12 @@ pub async fn oid4vci_exchange_credential(
         ));
     }
 
+    log::trace!("check the audience of proofs_of_possession");
+    check_pop_audience(
+        &oid4vci_pop_audience(session.clone())?,
+        &proofs_of_possession,
+    )?;
+
     let credential_responses = if credential_requests.len() == 1 {
         log::trace!("processing single request");
 
@@ -533,3 +551,566 @@ pub async fn oid4vci_exchange_credential(
         .await
     }
 }
+
+/// Return the `aud` that proofs of possession must use for the session, i.e. the credential
+/// issuer identifier of the issuer metadata.
+#[uniffi::export]
+pub fn oid4vci_pop_audience(session: Arc<Oid4vciSession>) -> Result<String, Oid4vciError> {
+    Ok(session
+        .get_metadata()?
+        .credential_issuer()
+        .url()
+        .to_string())
+}
+
+/// Check that every proof of possession is meant for the credential issuer `audience`, as
+/// the issuer rejects proofs with any other `aud`.
+fn check_pop_audience(audience: &str, proofs_of_possession: &[String]) -> Result<(), Oid4vciError> {
+    let expected = Url::parse(audience).map_err(|e| {
+        Oid4vciError::InvalidSession(format!("invalid credential issuer identifier: {e}"))
+    })?;
+
+    for proof in proofs_of_possession {
+        let claims: serde_json::Value = proof
+            .split('.')
+            .nth(1)
+            .and_then(|payload| BASE64_URL_SAFE_NO_PAD.decode(payload).ok())
+            .and_then(|payload| serde_json::from_slice(&payload).ok())
+            .ok_or(Oid4vciError::InvalidParameter(
+                "invalid proof of possession: not a JWT".into(),
+            ))?;
+
+        let found: Vec<&str> = match &claims["aud"] {
+            serde_json::Value::String(aud) => vec![aud.as_str()],
+            serde_json::Value::Array(aud) => aud.iter().filter_map(|aud| aud.as_str()).collect(),
+            _ => vec![],
+        };
+
+        if !found
+            .iter()
+            .any(|aud| Url::parse(aud).is_ok_and(|aud| aud == expected))
+        {
+            return Err(Oid4vciError::PopAudienceMismatch {
+                expected: audience.to_string(),
+                found: found.join(", "),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Request a credential from an `openid-credential-offer://` URI, as returned by the
+/// issuance service once an issuance is ready.
+///
+/// The offer is resolved, the issuer and authorization server metadata discovered, the
+/// offered grant exchanged for an access token, and the credential requested with a proof of
+/// possession signed by the key stored under `key_alias` in `key_store`.
+///
+/// A pre-authorized code is exchanged directly. For the authorization code grant, `authorizer`
+/// takes the user through the authorization endpoint and hands back the authorization code;
+/// such offers are rejected with [Oid4vciError::UnsupportedGrantType] without an `authorizer`.
+///
+/// The credential is bound to `key_alias`, which `mso_mdoc` credentials later use for device
+/// authentication.
+#[uniffi::export(async_runtime = "tokio", default(authorizer = None))]
+pub async fn request_credential_from_offer(
+    offer_uri: String,
+    key_alias: KeyAlias,
+    key_store: Arc<dyn KeyStore>,
+    authorizer: Option<Arc<dyn Oid4vciAuthorizer>>,
+) -> Result<Arc<ParsedCredential>, Oid4vciError> {
+    let signer = key_store
+        .get_signing_key(key_alias.clone())
+        .map_err(|e| Oid4vciError::InvalidParameter(format!("invalid signing key: {e}")))?;
+
+    let http_client = ReqwestHttpClient::new()
+        .map_err(|e| Oid4vciError::Generic(format!("failed to build the HTTP client: {e}")))?;
+    let http_client: Arc<IHttpClient> =
+        Arc::new((Arc::new(http_client) as Arc<dyn AsyncHttpClient>).into());
+
+    // The client id and redirect url are only used by the authorization code flow.
+    let (client_id, redirect_url) = match &authorizer {
+        Some(authorizer) => (authorizer.client_id(), authorizer.redirect_url()),
+        None => ("wallet".to_string(), "openid4vci://callback".to_string()),
+    };
+    let session: Arc<Oid4vciSession> =
+        oid4vci_initiate_with_offer(offer_uri, client_id, redirect_url, http_client.clone())
+            .await?
+            .into();
+
+    let grants = session.get_grants()?;
+    let nonce = match (grants.pre_authorized_code(), authorizer) {
+        (Some(_), _) => oid4vci_exchange_token(session.clone(), http_client.clone()).await?,
+        (None, Some(authorizer)) => {
+            exchange_authorization_code(&session, authorizer.as_ref(), &http_client).await?
+        }
+        (None, None) => return Err(Oid4vciError::UnsupportedGrantType),
+    }
+    .unwrap_or_default();
+
+    let audience = oid4vci_pop_audience(session.clone())?;
+    let proofs_of_possession = (0..session.get_credential_requests()?.len())
+        .map(|_| create_pop_jwt(signer.as_ref(), audience.clone(), nonce.clone(), None))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Oid4vciError::Generic(e.to_string()))?;
+
+    let response = oid4vci_exchange_credential(
+        session,
+        proofs_of_possession,
+        Oid4vciExchangeOptions::default(),
+        None,
+        http_client,
+    )
+    .await?
+    .into_iter()
+    .next()
+    .ok_or(Oid4vciError::Generic(
+        "the issuer returned no credential".into(),
+    ))?;
+
+    if matches!(response.format, CredentialFormat::MsoMdoc) {
+        return Ok(ParsedCredential::new_mso_mdoc(parse_issued_mdoc(
+            response, key_alias,
+        )?));
+    }
+
+    let credential = String::from_utf8(response.payload)
+        .map_err(|_| Oid4vciError::InvalidParameter("credential is not valid UTF-8".into()))?;
+    ParsedCredential::new_from_string_with_format(
+        response.format.to_string(),
+        credential,
+        key_alias,
+    )
+    .map_err(|e| Oid4vciError::InvalidParameter(format!("invalid credential: {e}")))
+}
+
+/// Obtain an access token through the authorization code flow of the offer in `session`, and
+/// return the `c_nonce` of the token response.
+///
+/// The authorization request is protected with PKCE and a `state`, which must come back
+/// unchanged in the redirect returned by `authorizer`.
+async fn exchange_authorization_code(
+    session: &Oid4vciSession,
+    authorizer: &dyn Oid4vciAuthorizer,
+    http_client: &IHttpClient,
+) -> Result<Option<String>, Oid4vciError> {
+    let grants = session.get_grants()?;
+    let grant = grants
+        .authorization_code()
+        .ok_or(Oid4vciError::UnsupportedGrantType)?;
+
+    let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+    let mut request = session
+        .get_client()
+        .authorize_url(CsrfToken::new_random)
+        .map_err(|e| Oid4vciError::RequestError(format!("failed to build authorization url: {e}")))?
+        .set_pkce_challenge(pkce_challenge);
+    if let Some(issuer_state) = grant.issuer_state() {
+        request = request.set_issuer_state(issuer_state);
+    }
+    let (authorization_url, state) = request.url()?;
+
+    let redirect = authorizer
+        .authorize(authorization_url.to_string())
+        .await
+        .map_err(|e| Oid4vciError::RequestError(e.to_string()))?;
+    let redirect = Url::parse(&redirect).map_err(|_| {
+        Oid4vciError::InvalidParameter("invalid authorization response: failed to parse url".into())
+    })?;
+    let params: HashMap<_, _> = redirect.query_pairs().collect();
+
+    if let Some(error) = params.get("error") {
+        return Err(Oid4vciError::RequestError(format!(
+            "authorization failed: {error}"
+        )));
+    }
+    if params.get("state").map(|s| s.as_ref()) != Some(state.secret().as_str()) {
+        return Err(Oid4vciError::InvalidParameter(
+            "invalid authorization response: state mismatch".into(),
+        ));
+    }
+    let code = params
+        .get("code")
+        .map(|code| AuthorizationCode::new(code.to_string()))
+        .ok_or(Oid4vciError::InvalidParameter(
+            "invalid authorization response: missing code".into(),
+        ))?;
+
+    let token_response = match &http_client.0 {
+        Either::Left(sync_client) => session
+            .get_client()
+            .exchange_code(code)
+            .set_pkce_verifier(pkce_verifier)
+            .request(sync_client),
+        Either::Right(async_client) => {
+            session
+                .get_client()
+                .exchange_code(code)
+                .set_pkce_verifier(pkce_verifier)
+                .request_async(async_client)
+                .await
+        }
+    }
+    .map_err(|_| Oid4vciError::RequestError("failed to exchange code".into()))?;
+
+    let nonce = token_response
+        .extra_fields()
+        .c_nonce
+        .clone()
+        .map(|v| v.secret().to_owned());
+
+    session.set_token_response(token_response.into())?;
+
+    Ok(nonce)
+}
+
+/// Construct an [Mdoc] from an `mso_mdoc` credential response.
+///
+/// The `key_alias` must refer to the key used for the proof of possession in the
+/// credential request, as that key is bound to the mdoc as its device key.
+#[uniffi::export]
+pub fn parse_issued_mdoc(
+    response: CredentialResponse,
+    key_alias: KeyAlias,
+) -> Result<Arc<Mdoc>, Oid4vciError> {
+    if !matches!(response.format, CredentialFormat::MsoMdoc) {
+        return Err(Oid4vciError::InvalidParameter(format!(
+            "expected an mso_mdoc credential response, found: {}",
+            response.format
+        )));
+    }
+
+    // The payload is the base64url-encoded IssuerSigned structure.
+    let issuer_signed = String::from_utf8(response.payload).map_err(|_| {
+        Oid4vciError::InvalidParameter("mso_mdoc credential is not valid UTF-8".into())
+    })?;
+
+    Mdoc::new_from_base64url_encoded_issuer_signed(issuer_signed, key_alias)
+        .map_err(|e| Oid4vciError::InvalidParameter(format!("invalid mso_mdoc credential: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use isomdl::definitions::{
+        helpers::{NonEmptyMap, NonEmptyVec},
+        IssuerSigned,
+    };
+
+    use serde_json::json;
+    use wiremock::{
+        matchers::{body_string_contains, header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn parse_issued_mdoc_from_response() {
+        let (_, key_alias, mdoc) = crate::mdl::util::test_mdl().await;
+
+        let document = mdoc.document();
+        let namespaces = document
+            .namespaces
+            .clone()
+            .into_inner()
+            .into_iter()
+            .map(|(namespace, elements)| {
+                let elements =
+                    NonEmptyVec::maybe_new(elements.into_inner().into_values().collect()).unwrap();
+                (namespace, elements)
+            })
+            .collect::<BTreeMap<_, _>>();
+        let issuer_signed = IssuerSigned {
+            namespaces: NonEmptyMap::maybe_new(namespaces),
+            issuer_auth: document.issuer_auth.clone(),
+        };
+
+        let response = CredentialResponse {
+            format: CredentialFormat::MsoMdoc,
+            payload: BASE64_URL_SAFE_NO_PAD
+                .encode(isomdl::cbor::to_vec(&issuer_signed).unwrap())
+                .into_bytes(),
+        };
+
+        let issued = parse_issued_mdoc(response, key_alias.clone()).unwrap();
+
+        assert_eq!(issued.doctype(), mdoc.doctype());
+        assert_eq!(issued.key_alias(), key_alias);
+        assert_eq!(issued.details().len(), mdoc.details().len());
+    }
+
+    async fn proof_of_possession(audience: &str) -> String {
+        let jwk = ssi::JWK::generate_p256().to_public();
+        let signing_input = crate::proof_of_possession::generate_pop_prepare(
+            audience.to_string(),
+            Some("nonce".to_string()),
+            crate::did::DidMethod::Key,
+            serde_json::to_string(&jwk).unwrap(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        format!("{}.c2ln", String::from_utf8(signing_input).unwrap())
+    }
+
+    #[tokio::test]
+    async fn pop_audience_is_the_credential_issuer() {
+        let proof = proof_of_possession("https://issuer.example.com").await;
+
+        check_pop_audience("https://issuer.example.com/", &[proof]).unwrap();
+    }
+
+    #[tokio::test]
+    async fn pop_audience_mismatch() {
+        let proofs = [
+            proof_of_possession("https://issuer.example.com").await,
+            proof_of_possession("https://other.example.com").await,
+        ];
+
+        let error = check_pop_audience("https://issuer.example.com/", &proofs).unwrap_err();
+
+        assert!(
+            matches!(
+                &error,
+                Oid4vciError::PopAudienceMismatch { expected, found }
+                    if expected == "https://issuer.example.com/"
+                        && found.starts_with("https://other.example.com")
+            ),
+            "unexpected error: {error:?}"
+        );
+    }
+
+    /// Mount the issuer and authorization server metadata of an issuer on `server`.
+    async fn mount_metadata(server: &MockServer) {
+        let issuer = server.uri();
+
+        Mock::given(method("GET"))
+            .and(path("/.well-known/openid-credential-issuer"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "credential_issuer": issuer,
+                "credential_endpoint": format!("{issuer}/credential"),
+                "credential_configurations_supported": {
+                    "PermanentResidentCard": {
+                        "format": "ldp_vc",
+                        "credential_definition": {
+                            "@context": [
+                                "https://www.w3.org/2018/credentials/v1",
+                                "https://w3id.org/citizenship/v1"
+                            ],
+                            "type": ["VerifiableCredential", "PermanentResidentCard"]
+                        }
+                    }
+                }
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/.well-known/oauth-authorization-server"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "issuer": issuer,
+                "authorization_endpoint": format!("{issuer}/authorize"),
+                "token_endpoint": format!("{issuer}/token"),
+                "response_types_supported": ["code"],
+                "grant_types_supported": [
+                    "authorization_code",
+                    "urn:ietf:params:oauth:grant-type:pre-authorized_code"
+                ],
+                "code_challenge_methods_supported": ["S256"]
+            })))
+            .mount(server)
+            .await;
+    }
+
+    /// Mount an issuer on `server` whose token endpoint only accepts requests with a body
+    /// containing every part of `token_request`.
+    async fn mount_issuer(server: &MockServer, token_request: &[&str]) {
+        mount_metadata(server).await;
+
+        token_request
+            .iter()
+            .fold(
+                Mock::given(method("POST")).and(path("/token")),
+                |mock, part| mock.and(body_string_contains(*part)),
+            )
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "access-token",
+                "token_type": "bearer",
+                "expires_in": 86400,
+                "c_nonce": "c-nonce",
+                "c_nonce_expires_in": 86400
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+
+        let credential: serde_json::Value =
+            serde_json::from_str(include_str!("../../tests/res/vc")).unwrap();
+        Mock::given(method("POST"))
+            .and(path("/credential"))
+            .and(header("authorization", "Bearer access-token"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({ "credential": credential })),
+            )
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    fn credential_offer_uri(issuer: &str, grants: serde_json::Value) -> String {
+        let offer = json!({
+            "credential_issuer": issuer,
+            "credential_configuration_ids": ["PermanentResidentCard"],
+            "grants": grants
+        });
+        format!(
+            "openid-credential-offer://?credential_offer={}",
+            urlencoding::encode(&offer.to_string())
+        )
+    }
+
+    /// Authorizes every request as if the user consented, and records the authorization URL.
+    #[derive(Default)]
+    struct ConsentingAuthorizer {
+        authorization_url: std::sync::Mutex<Option<Url>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Oid4vciAuthorizer for ConsentingAuthorizer {
+        fn client_id(&self) -> String {
+            "wallet-client".to_string()
+        }
+
+        fn redirect_url(&self) -> String {
+            "https://wallet.example.com/callback".to_string()
+        }
+
+        async fn authorize(
+            &self,
+            authorization_url: String,
+        ) -> Result<String, Oid4vciAuthorizerError> {
+            let authorization_url = Url::parse(&authorization_url).unwrap();
+            let state = authorization_url
+                .query_pairs()
+                .find(|(name, _)| name == "state")
+                .map(|(_, value)| value.into_owned())
+                .unwrap();
+            *self.authorization_url.lock().unwrap() = Some(authorization_url);
+
+            Ok(format!(
+                "https://wallet.example.com/callback?code=AUTHCODE&state={state}"
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn request_credential_with_pre_authorized_code() {
+        let server = MockServer::start().await;
+        mount_issuer(&server, &["pre-authorized_code=ABCDEF"]).await;
+
+        let offer_uri = credential_offer_uri(
+            &server.uri(),
+            json!({
+                "urn:ietf:params:oauth:grant-type:pre-authorized_code": {
+                    "pre-authorized_code": "ABCDEF"
+                }
+            }),
+        );
+
+        let (key_manager, key_alias) = crate::mdl::util::test_device_key().await;
+
+        let credential =
+            request_credential_from_offer(offer_uri, key_alias.clone(), key_manager, None)
+                .await
+                .unwrap();
+
+        assert_eq!(credential.format(), CredentialFormat::LdpVc);
+        assert_eq!(credential.key_alias(), Some(key_alias));
+    }
+
+    #[tokio::test]
+    async fn request_credential_with_authorization_code() {
+        let server = MockServer::start().await;
+        mount_issuer(
+            &server,
+            &[
+                "grant_type=authorization_code",
+                "code=AUTHCODE",
+                "code_verifier=",
+            ],
+        )
+        .await;
+
+        let offer_uri = credential_offer_uri(
+            &server.uri(),
+            json!({ "authorization_code": { "issuer_state": "issuer-state" } }),
+        );
+
+        let (key_manager, key_alias) = crate::mdl::util::test_device_key().await;
+        let authorizer = Arc::new(ConsentingAuthorizer::default());
+
+        let credential = request_credential_from_offer(
+            offer_uri,
+            key_alias.clone(),
+            key_manager,
+            Some(authorizer.clone()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(credential.format(), CredentialFormat::LdpVc);
+        assert_eq!(credential.key_alias(), Some(key_alias));
+
+        let authorization_url = authorizer
+            .authorization_url
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap();
+        let params: HashMap<_, _> = authorization_url.query_pairs().into_owned().collect();
+        assert_eq!(
+            authorization_url.path(),
+            "/authorize",
+            "unexpected authorization url: {authorization_url}"
+        );
+        assert_eq!(params["response_type"], "code");
+        assert_eq!(params["client_id"], "wallet-client");
+        assert_eq!(params["issuer_state"], "issuer-state");
+        assert_eq!(params["code_challenge_method"], "S256");
+    }
+
+    #[tokio::test]
+    async fn authorization_code_offer_requires_an_authorizer() {
+        let server = MockServer::start().await;
+        mount_metadata(&server).await;
+
+        let offer_uri = credential_offer_uri(
+            &server.uri(),
+            json!({ "authorization_code": { "issuer_state": "issuer-state" } }),
+        );
+
+        let (key_manager, key_alias) = crate::mdl::util::test_device_key().await;
+
+        assert!(matches!(
+            request_credential_from_offer(offer_uri, key_alias, key_manager, None).await,
+            Err(Oid4vciError::UnsupportedGrantType)
+        ));
+    }
+
+    #[test]
+    fn parse_issued_mdoc_wrong_format() {
+        let response = CredentialResponse {
+            format: CredentialFormat::LdpVc,
+            payload: b"{}".to_vec(),
+        };
+
+        assert!(matches!(
+            parse_issued_mdoc(response, KeyAlias("key".to_string())),
+            Err(Oid4vciError::InvalidParameter(_))
+        ));
+    }
+}
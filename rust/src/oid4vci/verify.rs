@@ -0,0 +1,171 @@
+use oid4vci::credential_offer::CredentialOfferParameters as CredentialOffer;
+use url::Url;
+
+use crate::credential::{ParsedCredential, ParsedCredentialInner};
+
+use super::Oid4vciError;
+
+/// Sanity check that a freshly issued credential matches what the credential offer advertised.
+///
+/// This should be called before storing a credential received through OID4VCI, to detect
+/// issuer misbehavior or mix-ups between concurrent issuance flows.
+///
+/// The credential type must correspond to one of the offer's `credential_configuration_ids`,
+/// either exactly (e.g. an mdoc doctype) or as the prefix of a format-qualified configuration
+/// id (e.g. `UniversityDegreeCredential_ldp_vc`).
+///
+/// When the credential names its issuer as an HTTPS URL or a `did:web` DID, the host must
+/// match the host of the offer's `credential_issuer`. Issuers identified by other means (e.g.
+/// `did:key`, or the certificate chain of an mdoc) cannot be bound to the offer URL and are
+/// not checked here.
+pub fn verify_issued_against_offer(
+    credential: &ParsedCredential,
+    offer: &CredentialOffer,
+) -> Result<(), Oid4vciError> {
+    let configuration_ids: Vec<String> = offer
+        .credential_configuration_ids()
+        .iter()
+        .map(|id| id.to_string())
+        .collect();
+
+    let credential_type = credential.r#type().0;
+    if !type_matches_configuration_ids(&credential_type, &configuration_ids) {
+        return Err(Oid4vciError::InvalidParameter(format!(
+            "issued credential type '{credential_type}' does not match any offered configuration: {configuration_ids:?}"
+        )));
+    }
+
+    let offer_issuer = Url::parse(offer.issuer().as_str()).map_err(|_| {
+        Oid4vciError::InvalidParameter("invalid credential_offer: failed to parse issuer".into())
+    })?;
+
+    let Some(credential_issuer) = issuer_of(credential) else {
+        return Ok(());
+    };
+
+    match (issuer_host(&credential_issuer), offer_issuer.host_str()) {
+        (Some(credential_host), Some(offer_host))
+            if !credential_host.eq_ignore_ascii_case(offer_host) =>
+        {
+            Err(Oid4vciError::InvalidParameter(format!(
+                "issued credential issuer '{credential_issuer}' does not match the offer issuer '{offer_issuer}'"
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn type_matches_configuration_ids(credential_type: &str, configuration_ids: &[String]) -> bool {
+    credential_type
+        .split('+')
+        .chain(std::iter::once(credential_type))
+        .any(|ty| {
+            configuration_ids.iter().any(|id| {
+                id == ty
+                    || id
+                        .strip_prefix(ty)
+                        .is_some_and(|rest| rest.starts_with(['_', '-']))
+            })
+        })
+}
+
+/// The issuer identifier of the credential, if the format carries one in its claims.
+fn issuer_of(credential: &ParsedCredential) -> Option<String> {
+    let json = match &credential.inner {
+        ParsedCredentialInner::JwtVcJson(vc) | ParsedCredentialInner::JwtVcJsonLd(vc) => {
+            serde_json::from_str(&vc.credential_as_json_encoded_utf8_string()).ok()?
+        }
+        ParsedCredentialInner::LdpVc(vc) => vc.raw.clone(),
+        ParsedCredentialInner::VCDM2SdJwt(sd_jwt) => sd_jwt.revealed_claims_as_json().ok()?,
+        ParsedCredentialInner::MsoMdoc(_) | ParsedCredentialInner::Cwt(_) => return None,
+    };
+
+    match json.get("issuer")? {
+        serde_json::Value::String(issuer) => Some(issuer.clone()),
+        serde_json::Value::Object(issuer) => issuer.get("id")?.as_str().map(ToOwned::to_owned),
+        _ => None,
+    }
+}
+
+/// The host that the issuer identifier is bound to, if it is bound to one.
+fn issuer_host(issuer: &str) -> Option<String> {
+    if let Some(did_web) = issuer.strip_prefix("did:web:") {
+        let host = did_web.split([':', '#']).next()?;
+        // did:web encodes ports as `%3A`.
+        return Some(host.split("%3A").next()?.to_string());
+    }
+
+    let url = Url::parse(issuer).ok()?;
+    match url.scheme() {
+        "https" | "http" => url.host_str().map(ToOwned::to_owned),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::credential::json_vc::JsonVc;
+
+    use super::*;
+
+    fn offer(issuer: &str, configuration_ids: &[&str]) -> CredentialOffer {
+        serde_json::from_value(json!({
+            "credential_issuer": issuer,
+            "credential_configuration_ids": configuration_ids,
+        }))
+        .unwrap()
+    }
+
+    fn credential(issuer: serde_json::Value) -> std::sync::Arc<ParsedCredential> {
+        ParsedCredential::new_ldp_vc(
+            JsonVc::new_from_json(
+                json!({
+                    "@context": ["https://www.w3.org/2018/credentials/v1"],
+                    "type": ["VerifiableCredential", "AlumniCredential"],
+                    "issuer": issuer,
+                    "issuanceDate": "2024-01-01T00:00:00Z",
+                    "credentialSubject": { "id": "did:example:holder" },
+                })
+                .to_string(),
+            )
+            .unwrap(),
+        )
+    }
+
+    #[rstest]
+    #[case::exact("https://issuer.example.com", &["AlumniCredential"], json!("https://issuer.example.com/issuers/1"))]
+    #[case::qualified("https://issuer.example.com", &["AlumniCredential_ldp_vc"], json!("https://issuer.example.com"))]
+    #[case::did_web("https://issuer.example.com", &["AlumniCredential"], json!({ "id": "did:web:issuer.example.com" }))]
+    #[case::unbound_issuer("https://issuer.example.com", &["AlumniCredential"], json!("did:key:zDnaeS7MP4xpCcwkgez9FGixW5MwUp5E7QneZ7N63g3nWn5FQ"))]
+    fn issued_credential_matches_offer(
+        #[case] issuer: &str,
+        #[case] configuration_ids: &[&str],
+        #[case] credential_issuer: serde_json::Value,
+    ) {
+        verify_issued_against_offer(
+            &credential(credential_issuer),
+            &offer(issuer, configuration_ids),
+        )
+        .unwrap();
+    }
+
+    #[rstest]
+    #[case::wrong_type("https://issuer.example.com", &["UniversityDegreeCredential"], json!("https://issuer.example.com"))]
+    #[case::type_prefix_only("https://issuer.example.com", &["AlumniCredentialV2"], json!("https://issuer.example.com"))]
+    #[case::wrong_issuer("https://issuer.example.com", &["AlumniCredential"], json!("https://other.example.com"))]
+    #[case::wrong_did_web("https://issuer.example.com", &["AlumniCredential"], json!("did:web:other.example.com"))]
+    fn issued_credential_does_not_match_offer(
+        #[case] issuer: &str,
+        #[case] configuration_ids: &[&str],
+        #[case] credential_issuer: serde_json::Value,
+    ) {
+        assert!(verify_issued_against_offer(
+            &credential(credential_issuer),
+            &offer(issuer, configuration_ids),
+        )
+        .is_err());
+    }
+}
@@ -5,8 +5,9 @@ use std::{
 
 use super::{
     oid4vci_exchange_credential, oid4vci_exchange_token, oid4vci_get_metadata, oid4vci_initiate,
-    oid4vci_initiate_with_offer, AsyncHttpClient, CredentialResponse, IHttpClient, Oid4vciError,
-    Oid4vciExchangeOptions, Oid4vciMetadata, Oid4vciSession, SyncHttpClient,
+    oid4vci_initiate_with_offer, oid4vci_pop_audience, AsyncHttpClient, CredentialResponse,
+    IHttpClient, Oid4vciError, Oid4vciExchangeOptions, Oid4vciMetadata, Oid4vciSession,
+    SyncHttpClient,
 };
 
 #[derive(uniffi::Object)]
@@ -124,6 +125,11 @@ impl Oid4vci {
         oid4vci_get_metadata(self.session()?)
     }
 
+    /// Return the `aud` that proofs of possession must use, i.e. the credential issuer.
+    pub fn pop_audience(&self) -> Result<String, Oid4vciError> {
+        oid4vci_pop_audience(self.session()?)
+    }
+
     pub async fn initiate_with_offer(
         &self,
         credential_offer: String,
@@ -1,4 +1,5 @@
 use anyhow::{bail, Context, Result};
+use base64::prelude::*;
 use josekit::{jwk::Jwk, jwt::JwtPayload};
 use openid4vp::core::{
     authorization_request::{parameters::ResponseMode, AuthorizationRequestObject},
@@ -10,6 +11,27 @@ use crate::oid4vp::iso_18013_7::build_response::{
     build_jwe, get_jwk_from_client_metadata, get_state_from_request,
 };
 
+/// JWE `alg` values supported for encrypted responses.
+pub const SUPPORTED_ALGS: &[&str] = &["ECDH-ES", "ECDH-ES+A256KW"];
+/// JWE `enc` values supported for encrypted responses.
+pub const SUPPORTED_ENCS: &[&str] = &["A128GCM", "A256GCM"];
+
+fn check_supported_encryption(alg: &str, enc: &str) -> Result<()> {
+    if !SUPPORTED_ALGS.contains(&alg) || !SUPPORTED_ENCS.contains(&enc) {
+        bail!(
+            "unsupported response encryption: alg '{alg}' with enc '{enc}' was requested, supported alg values are {SUPPORTED_ALGS:?} and enc values are {SUPPORTED_ENCS:?}"
+        )
+    }
+    Ok(())
+}
+
+/// The JWE algorithms an encrypted response is encrypted with.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Record)]
+pub struct EncryptionParams {
+    pub alg: String,
+    pub enc: String,
+}
+
 pub enum Responder {
     Json {
         state: Option<String>,
@@ -34,17 +56,11 @@ impl Responder {
                     .authorization_encrypted_response_alg()
                     .parsing_error()?
                     .0;
-                if alg != "ECDH-ES" {
-                    bail!("unsupported encryption alg: {alg}")
-                }
-
                 let enc = client_metadata
                     .authorization_encrypted_response_enc()
                     .parsing_error()?
                     .0;
-                if enc != "A128GCM" {
-                    bail!("unsupported encryption scheme: {enc}")
-                }
+                check_supported_encryption(&alg, &enc)?;
 
                 Ok(Self::Jwe {
                     alg,
@@ -57,6 +73,17 @@ impl Responder {
         }
     }
 
+    /// The algorithms the response is encrypted with, or `None` if it is sent in plaintext.
+    pub fn encryption(&self) -> Option<EncryptionParams> {
+        match self {
+            Self::Json { .. } => None,
+            Self::Jwe { alg, enc, .. } => Some(EncryptionParams {
+                alg: alg.clone(),
+                enc: enc.clone(),
+            }),
+        }
+    }
+
     pub fn response(&self, vp_token: Json) -> Result<String> {
         match self {
             Self::Json { state } => {
@@ -92,4 +119,122 @@ impl Responder {
             }
         }
     }
+
+    /// Describe the response as pretty-printed JSON, for debugging.
+    ///
+    /// Alongside the response returned by [response](Self::response), the output contains
+    /// the decoded `vp_token`, the state and, for encrypted responses, the JWE header.
+    pub fn debug_response(&self, vp_token: Json, decoded_vp_token: Json) -> Result<String> {
+        let response = self.response(vp_token)?;
+
+        let debug = match self {
+            Self::Json { state } => json!({
+                "response_mode": "dc_api",
+                "state": state,
+                "response": serde_json::from_str::<Json>(&response)
+                    .context("failed to parse response")?,
+                "decoded_vp_token": decoded_vp_token,
+            }),
+            Self::Jwe { state, .. } => {
+                let header = response
+                    .split('.')
+                    .next()
+                    .context("response has no JWE header")?;
+                let header: Json = serde_json::from_slice(
+                    &BASE64_URL_SAFE_NO_PAD
+                        .decode(header)
+                        .context("failed to decode JWE header")?,
+                )
+                .context("failed to parse JWE header")?;
+
+                json!({
+                    "response_mode": "dc_api.jwt",
+                    "state": state,
+                    "jwe_header": header,
+                    "response": response,
+                    "decoded_vp_token": decoded_vp_token,
+                })
+            }
+        };
+
+        serde_json::to_string_pretty(&debug).context("failed to serialize debug response")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use josekit::jwk::Jwk;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+
+    fn decode_header(jwe: &str) -> Json {
+        use base64::prelude::*;
+
+        let header = jwe.split('.').next().unwrap();
+        serde_json::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(header).unwrap()).unwrap()
+    }
+
+    #[rstest]
+    #[case("ECDH-ES", "A128GCM")]
+    #[case("ECDH-ES", "A256GCM")]
+    #[case("ECDH-ES+A256KW", "A128GCM")]
+    #[case("ECDH-ES+A256KW", "A256GCM")]
+    fn encrypted_response(#[case] alg: &str, #[case] enc: &str) {
+        check_supported_encryption(alg, enc).unwrap();
+
+        let verifier_key = p256::SecretKey::random(&mut ssi::crypto::rand::thread_rng());
+        let verifier_jwk =
+            Jwk::from_bytes(verifier_key.public_key().to_jwk_string().as_bytes()).unwrap();
+
+        let responder = Responder::Jwe {
+            alg: alg.to_string(),
+            enc: enc.to_string(),
+            state: Some("state".to_string()),
+            verifier_jwk,
+        };
+
+        let jwe = responder.response(json!({ "mdl": "response" })).unwrap();
+
+        // Compact JWE serialization has five parts.
+        assert_eq!(jwe.split('.').count(), 5);
+        let header = decode_header(&jwe);
+        assert_eq!(header["alg"], alg);
+        assert_eq!(header["enc"], enc);
+    }
+
+    #[test]
+    fn encryption_of_responders() {
+        let plaintext = Responder::Json { state: None };
+        assert_eq!(plaintext.encryption(), None);
+
+        let verifier_key = p256::SecretKey::random(&mut ssi::crypto::rand::thread_rng());
+        let encrypted = Responder::Jwe {
+            alg: "ECDH-ES".to_string(),
+            enc: "A256GCM".to_string(),
+            state: None,
+            verifier_jwk: Jwk::from_bytes(verifier_key.public_key().to_jwk_string().as_bytes())
+                .unwrap(),
+        };
+        assert_eq!(
+            encrypted.encryption(),
+            Some(EncryptionParams {
+                alg: "ECDH-ES".to_string(),
+                enc: "A256GCM".to_string(),
+            })
+        );
+    }
+
+    #[rstest]
+    #[case("RSA-OAEP", "A128GCM")]
+    #[case("ECDH-ES", "A128CBC-HS256")]
+    #[case("ECDH-ES+A128KW", "A256GCM")]
+    fn unsupported_encryption(#[case] alg: &str, #[case] enc: &str) {
+        let error = check_supported_encryption(alg, enc)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains(alg));
+        assert!(error.contains(enc));
+    }
 }
@@ -0,0 +1,196 @@
+use openid4vp::core::dcql_query::DcqlQuery;
+use serde_json::Value as Json;
+
+/// The options of a DCQL credential set that can be satisfied by the wallet.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct CredentialSetOptions {
+    /// Whether the verifier requires this credential set to be satisfied.
+    pub required: bool,
+    /// The purpose the verifier gave for requesting this credential set, if any.
+    pub purpose: Option<String>,
+    /// The options that can be satisfied, each a list of DCQL credential query ids that must
+    /// all be presented together.
+    pub satisfying_options: Vec<Vec<String>>,
+}
+
+/// The result of evaluating the credential sets of a DCQL query.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct CredentialSetEvaluation {
+    /// Whether every required credential set has at least one satisfying option.
+    pub satisfiable: bool,
+    /// The evaluated credential sets, in the order given in the query.
+    pub credential_sets: Vec<CredentialSetOptions>,
+}
+
+/// Evaluate the `credential_sets` of a DCQL query against the credential queries the wallet
+/// has a matching credential for.
+///
+/// If the query has no `credential_sets`, every credential query is required, which is
+/// represented as a single required set with one option containing all credential queries.
+pub fn evaluate_credential_sets(
+    query: &DcqlQuery,
+    available_credential_ids: &[String],
+) -> CredentialSetEvaluation {
+    let satisfying_options = |options: Vec<Vec<String>>| -> Vec<Vec<String>> {
+        options
+            .into_iter()
+            .filter(|option| {
+                option
+                    .iter()
+                    .all(|id| available_credential_ids.contains(id))
+            })
+            .collect()
+    };
+
+    let credential_sets: Vec<CredentialSetOptions> = match query.credential_sets() {
+        Some(sets) => sets
+            .iter()
+            .map(|set| CredentialSetOptions {
+                required: set.is_required(),
+                purpose: set.purpose().map(purpose_to_string),
+                satisfying_options: satisfying_options(
+                    set.options()
+                        .iter()
+                        .map(|option| option.iter().cloned().collect())
+                        .collect(),
+                ),
+            })
+            .collect(),
+        None => vec![CredentialSetOptions {
+            required: true,
+            purpose: None,
+            satisfying_options: satisfying_options(vec![query
+                .credentials()
+                .iter()
+                .map(|credential| credential.id().to_string())
+                .collect()]),
+        }],
+    };
+
+    let satisfiable = credential_sets
+        .iter()
+        .filter(|set| set.required)
+        .all(|set| !set.satisfying_options.is_empty());
+
+    CredentialSetEvaluation {
+        satisfiable,
+        credential_sets,
+    }
+}
+
+/// A DCQL purpose, which may be any JSON value, as text.
+pub(super) fn purpose_to_string(purpose: &Json) -> String {
+    match purpose {
+        Json::String(purpose) => purpose.clone(),
+        purpose => purpose.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn credential(id: &str) -> Json {
+        json!({
+            "id": id,
+            "format": "mso_mdoc",
+            "meta": { "doctype_value": format!("org.example.{id}") }
+        })
+    }
+
+    /// Parse a query as the request object does, so that anything the typed query drops is
+    /// lost to the tests as well.
+    fn parse(query: Json) -> DcqlQuery {
+        serde_json::from_value(query).unwrap()
+    }
+
+    fn query() -> DcqlQuery {
+        parse(json!({
+            "credentials": [
+                credential("mdl"),
+                credential("pid"),
+                credential("address"),
+                credential("loyalty")
+            ],
+            "credential_sets": [
+                {
+                    "purpose": "Identification",
+                    "options": [["mdl"], ["pid", "address"]]
+                },
+                {
+                    "required": false,
+                    "options": [["loyalty"]]
+                }
+            ]
+        }))
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn first_alternative_satisfies() {
+        let evaluation = evaluate_credential_sets(&query(), &ids(&["mdl"]));
+
+        assert!(evaluation.satisfiable);
+        assert_eq!(
+            evaluation.credential_sets[0].satisfying_options,
+            vec![ids(&["mdl"])]
+        );
+        assert_eq!(
+            evaluation.credential_sets[0].purpose.as_deref(),
+            Some("Identification")
+        );
+        assert!(evaluation.credential_sets[1].satisfying_options.is_empty());
+    }
+
+    #[test]
+    fn second_alternative_satisfies() {
+        let evaluation = evaluate_credential_sets(&query(), &ids(&["pid", "address", "loyalty"]));
+
+        assert!(evaluation.satisfiable);
+        assert_eq!(
+            evaluation.credential_sets[0].satisfying_options,
+            vec![ids(&["pid", "address"])]
+        );
+        assert_eq!(
+            evaluation.credential_sets[1].satisfying_options,
+            vec![ids(&["loyalty"])]
+        );
+    }
+
+    #[test]
+    fn incomplete_alternative_does_not_satisfy() {
+        let evaluation = evaluate_credential_sets(&query(), &ids(&["pid", "loyalty"]));
+
+        assert!(!evaluation.satisfiable);
+        assert!(evaluation.credential_sets[0].satisfying_options.is_empty());
+    }
+
+    #[test]
+    fn without_credential_sets_all_credentials_are_required() {
+        let query = parse(json!({
+            "credentials": [credential("mdl"), credential("pid")]
+        }));
+
+        assert!(!evaluate_credential_sets(&query, &ids(&["mdl"])).satisfiable);
+        assert!(evaluate_credential_sets(&query, &ids(&["mdl", "pid"])).satisfiable);
+    }
+
+    #[test]
+    fn credential_sets_are_kept_by_the_typed_query() {
+        let evaluation = evaluate_credential_sets(&query(), &ids(&["loyalty"]));
+
+        // Were the sets dropped, the single implicit set would require every credential.
+        assert_eq!(evaluation.credential_sets.len(), 2);
+        assert!(evaluation.credential_sets[0].required);
+        assert!(!evaluation.credential_sets[1].required);
+        assert_eq!(
+            evaluation.credential_sets[1].satisfying_options,
+            vec![ids(&["loyalty"])]
+        );
+    }
+}
@@ -1,37 +1,52 @@
 mod build_response;
+mod credential_sets;
 mod prepare_response;
 mod requested_values;
+mod verifier_certificate;
 
-use std::{fmt, sync::Arc};
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+};
 
 use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
+use base64::prelude::*;
+pub use build_response::EncryptionParams;
 use build_response::Responder;
+use credential_sets::{evaluate_credential_sets, purpose_to_string};
+pub use credential_sets::{CredentialSetEvaluation, CredentialSetOptions};
+use isomdl::definitions::DeviceResponse;
 use openid4vp::{
     core::{
         authorization_request::{
-            parameters::ExpectedOrigins,
+            parameters::{ExpectedOrigins, ResponseMode},
             verification::{verifier::P256Verifier, x509_san, RequestVerifier},
             AuthorizationRequest, AuthorizationRequestObject,
         },
         dcql_query::DcqlQuery,
         metadata::WalletMetadata,
         object::ParsingErrorContext,
-        util::ReqwestClient,
+        util::AsyncHttpClient,
     },
     verifier::client::X509SanVariant,
     wallet::Wallet,
 };
-use prepare_response::{vp_token, Handover};
+use prepare_response::{device_response_for_display, vp_token, Handover};
 use requested_values::find_match;
 use serde_json::json;
 use ssi::{claims::JwsBuf, jwk::Algorithm};
+use url::Url;
+use uuid::Uuid;
+use verifier_certificate::VerifierCertificate;
+
+use crate::{common::Origin, credential::mdoc::Mdoc, crypto::KeyStore, oid4vp::error::OID4VPError};
 
-use crate::{credential::mdoc::Mdoc, crypto::KeyStore};
+use super::http_client::Oid4vpHttpClient;
 
 use super::iso_18013_7::{
-    prepare_response::prepare_response,
-    requested_values::{FieldId180137, RequestMatch180137},
+    prepare_response::{prepare_response, RawRedirectUri, RawResponseUri},
+    requested_values::{FieldId180137, MissingField180137, RequestMatch180137},
 };
 
 #[derive(uniffi::Object)]
@@ -43,17 +58,62 @@ pub struct InProgressRequestDcApi {
     request: AuthorizationRequest,
     request_object: AuthorizationRequestObject,
     request_match: RequestMatch180137,
+    purpose: Option<String>,
     wallet_activity: WalletActivity,
 }
 
 struct WalletActivity {
-    http_client: ReqwestClient,
+    http_client: Oid4vpHttpClient,
     origin: String,
+    /// The calling origin, normalized to be compared with the origins a request expects.
+    normalized_origin: Origin,
+    /// Whether localhost verifiers are allowed, which may then serve request objects over
+    /// plain http.
+    allow_localhost: bool,
     wallet_metadata: WalletMetadata,
+    verifier_certificate: OnceLock<VerifierCertificate>,
+    /// Set when the request is rejected because of an origin mismatch, so that it can be
+    /// reported as [DcApiError::OriginMismatch].
+    origin_mismatch: OnceLock<String>,
+}
+
+/// Request objects must be fetched over https, unless localhost is allowed and the request
+/// object is served by a localhost verifier.
+fn check_request_uri_scheme(request_uri: &Url, allow_localhost: bool) -> Result<(), DcApiError> {
+    let localhost = Origin::parse(request_uri.as_str()).is_ok_and(|origin| origin.is_localhost());
+    if request_uri.scheme() == "https" || (allow_localhost && localhost) {
+        return Ok(());
+    }
+    Err(DcApiError::RequestUri(format!(
+        "request_uri must use https: {request_uri}"
+    )))
+}
+
+/// Check that a request object fetched with a `wallet_nonce` carries the same nonce.
+fn check_wallet_nonce(request_jwt: &str, wallet_nonce: &str) -> Result<()> {
+    let payload = request_jwt
+        .split('.')
+        .nth(1)
+        .context("the request object is not a JWT")?;
+    let claims: serde_json::Value = serde_json::from_slice(
+        &BASE64_URL_SAFE_NO_PAD
+            .decode(payload)
+            .context("the request object payload is not base64url")?,
+    )
+    .context("the request object payload is not JSON")?;
+
+    if claims
+        .get("wallet_nonce")
+        .and_then(serde_json::Value::as_str)
+        != Some(wallet_nonce)
+    {
+        bail!("the request object does not carry the wallet_nonce");
+    }
+    Ok(())
 }
 
 impl Wallet for WalletActivity {
-    type HttpClient = ReqwestClient;
+    type HttpClient = Oid4vpHttpClient;
 
     fn http_client(&self) -> &Self::HttpClient {
         &self.http_client
@@ -65,25 +125,190 @@ impl Wallet for WalletActivity {
 }
 
 impl WalletActivity {
+    fn new(origin: String, allow_localhost: bool) -> Result<Self, DcApiError> {
+        let normalized_origin = Origin::parse(&origin).map_err(DcApiError::invalid_origin)?;
+        normalized_origin
+            .require_secure(allow_localhost)
+            .map_err(DcApiError::invalid_origin)?;
+
+        Ok(Self {
+            http_client: Oid4vpHttpClient::new().map_err(DcApiError::internal_error)?,
+            origin,
+            normalized_origin,
+            allow_localhost,
+            wallet_metadata: default_metadata(),
+            verifier_certificate: OnceLock::new(),
+            origin_mismatch: OnceLock::new(),
+        })
+    }
+
+    /// Parse a request, and verify it with its client id scheme.
+    ///
+    /// A request passed by reference, with a `request_uri`, is first fetched and replaced by
+    /// the request object it points to.
+    async fn validate_request(
+        &self,
+        request_json: String,
+    ) -> Result<(AuthorizationRequest, AuthorizationRequestObject), DcApiError> {
+        let request: serde_json::Value = serde_json::from_str(&request_json)
+            .context(request_json)
+            .context("failed to parse the request")
+            .map_err(DcApiError::invalid_request)?;
+
+        let request: AuthorizationRequest =
+            serde_json::from_value(self.resolve_request_uri(request).await?)
+                .context("failed to parse the request")
+                .map_err(DcApiError::invalid_request)?;
+
+        let request_object = request
+            .clone()
+            .validate(self)
+            .await
+            .context("the request is could not be verified")
+            .map_err(|e| match self.origin_mismatch.get() {
+                Some(mismatch) => DcApiError::OriginMismatch(mismatch.clone()),
+                None => DcApiError::invalid_request(e),
+            })?;
+
+        Ok((request, request_object))
+    }
+
+    /// Replace the `request_uri` of a request by the request object JWT it points to.
+    ///
+    /// The request object is fetched over https, with a `GET` unless the `request_uri_method`
+    /// is `post`, in which case the wallet metadata and a `wallet_nonce` are posted, and the
+    /// request object must carry the same `wallet_nonce`.
+    ///
+    /// This is not subject to an [OfflinePolicy](crate::verifier::offline::OfflinePolicy),
+    /// which only governs verification: the request object is needed to present anything.
+    async fn resolve_request_uri(
+        &self,
+        mut request: serde_json::Value,
+    ) -> Result<serde_json::Value, DcApiError> {
+        let Some(object) = request.as_object_mut() else {
+            return Ok(request);
+        };
+        let Some(request_uri) = object.remove("request_uri") else {
+            return Ok(request);
+        };
+
+        let request_uri: Url = request_uri
+            .as_str()
+            .context("request_uri is not a string")
+            .and_then(|uri| Url::parse(uri).context("request_uri is not a valid URL"))
+            .map_err(DcApiError::request_uri)?;
+
+        if object.contains_key("request") {
+            return Err(DcApiError::RequestUri(
+                "a request cannot have both request and request_uri".to_string(),
+            ));
+        }
+        check_request_uri_scheme(&request_uri, self.allow_localhost)?;
+
+        let request_uri_method = object.remove("request_uri_method");
+        let request_jwt = match request_uri_method.as_ref().map(|m| m.as_str()) {
+            None | Some(Some("get")) => self.fetch_request_object(&request_uri, None).await,
+            Some(Some("post")) => {
+                let wallet_nonce = Uuid::new_v4().simple().to_string();
+                self.fetch_request_object(&request_uri, Some(&wallet_nonce))
+                    .await
+                    .and_then(|request_jwt| {
+                        check_wallet_nonce(&request_jwt, &wallet_nonce)?;
+                        Ok(request_jwt)
+                    })
+            }
+            Some(_) => {
+                return Err(DcApiError::RequestUri(format!(
+                    "unsupported request_uri_method: {}",
+                    request_uri_method.unwrap_or_default()
+                )))
+            }
+        }
+        .with_context(|| format!("failed to fetch the request object from {request_uri}"))
+        .map_err(DcApiError::request_uri)?;
+
+        object.insert("request".to_string(), json!(request_jwt));
+        Ok(request)
+    }
+
+    /// Fetch the request object, posting the wallet metadata and `wallet_nonce` if given.
+    async fn fetch_request_object(
+        &self,
+        request_uri: &Url,
+        wallet_nonce: Option<&str>,
+    ) -> Result<String> {
+        let request = http::Request::builder()
+            .uri(request_uri.as_str())
+            .header("Accept", "application/oauth-authz-req+jwt");
+        let request = match wallet_nonce {
+            None => request.method("GET").body(vec![])?,
+            Some(wallet_nonce) => {
+                let body = url::form_urlencoded::Serializer::new(String::new())
+                    .append_pair(
+                        "wallet_metadata",
+                        &serde_json::to_string(self.metadata())
+                            .context("failed to encode the wallet metadata")?,
+                    )
+                    .append_pair("wallet_nonce", wallet_nonce)
+                    .finish();
+                request
+                    .method("POST")
+                    .header("Content-Type", "application/x-www-form-urlencoded")
+                    .body(body.into_bytes())?
+            }
+        };
+
+        let response = self.http_client().execute(request).await?;
+        if !response.status().is_success() {
+            bail!("unexpected status: {}", response.status());
+        }
+
+        let request_jwt =
+            String::from_utf8(response.into_body()).context("the request object is not UTF-8")?;
+        Ok(request_jwt.trim().to_string())
+    }
+
     fn check_expected_origins(&self, request: &AuthorizationRequestObject) -> Result<()> {
         let expected_origins: ExpectedOrigins = request.get().parsing_error()?;
         // This occurs if the request has been forwarded by an attacker, or if the verifier is misconfigured.
-        if !expected_origins.0.contains(&self.origin) {
-            bail!("expected origin not found in request");
+        if !expected_origins
+            .0
+            .iter()
+            .filter_map(|expected| Origin::parse(expected).ok())
+            .any(|expected| expected.matches(&self.normalized_origin))
+        {
+            return Err(self.reject_origin(format!(
+                "expected origin '{}' not found in request",
+                self.origin
+            )));
         }
         Ok(())
     }
 
+    /// Record an origin mismatch, returning the error to reject the request with.
+    fn reject_origin(&self, message: String) -> anyhow::Error {
+        let _ = self.origin_mismatch.set(message.clone());
+        anyhow::anyhow!(message)
+    }
+
+    /// Record the certificate of a request JWT that has passed x509_san verification.
+    fn set_verifier_certificate(&self, request_jwt: &str) -> Result<()> {
+        let certificate = VerifierCertificate::from_request_jwt(request_jwt)?;
+        // The request is only verified once, so the certificate is never already set.
+        let _ = self.verifier_certificate.set(certificate);
+        Ok(())
+    }
+
     async fn effective_client_id(&self, request: &AuthorizationRequest) -> Result<String> {
         let (aro, jws) = request.resolve_request(self.http_client()).await?;
         if let Some(jws) = jws {
             let jws = JwsBuf::new(jws).context("failed to decode JWS")?;
             let jwt = jws.into_decoded().context("failed to decode JWT")?;
             if jwt.header().algorithm == Algorithm::None {
-                return Ok(format!("web-origin:{}", self.origin));
+                return Ok(format!("web-origin:{}", self.normalized_origin));
             }
         } else {
-            return Ok(format!("web-origin:{}", self.origin));
+            return Ok(format!("web-origin:{}", self.normalized_origin));
         }
 
         Ok(aro
@@ -104,6 +329,53 @@ impl RequestVerifier for WalletActivity {
         Ok(())
     }
 
+    async fn redirect_uri(
+        &self,
+        decoded_request: &AuthorizationRequestObject,
+        request_jwt: Option<String>,
+    ) -> Result<()> {
+        // Requests using the redirect_uri client id scheme cannot be signed.
+        if let Some(jws) = request_jwt {
+            let jws = JwsBuf::new(jws).context("failed to decode JWS")?;
+            let jwt = jws.into_decoded().context("failed to decode JWT")?;
+            if jwt.header().algorithm != Algorithm::None {
+                bail!("requests using the redirect_uri client id scheme must not be signed")
+            }
+        }
+
+        let client_id = &decoded_request
+            .client_id()
+            .context("request missing client_id")?
+            .0;
+        let client_id = client_id.strip_prefix("redirect_uri:").unwrap_or(client_id);
+        let client_origin = Url::parse(client_id)
+            .context("client_id is not a valid URI")?
+            .origin();
+
+        // The response is delivered to the response_uri (or redirect_uri) if one is given,
+        // otherwise it is returned to the calling web origin.
+        let response_target = match decoded_request.get::<RawResponseUri>() {
+            Some(response_uri) => response_uri.parsing_error()?.0,
+            None => match decoded_request.get::<RawRedirectUri>() {
+                Some(redirect_uri) => redirect_uri.parsing_error()?.0,
+                None => self.origin.clone(),
+            },
+        };
+        let response_origin = Url::parse(&response_target)
+            .context("response URI is not a valid URI")?
+            .origin();
+
+        if client_origin != response_origin {
+            return Err(self.reject_origin(format!(
+                "client_id origin '{}' does not match the response origin '{}'",
+                client_origin.ascii_serialization(),
+                response_origin.ascii_serialization()
+            )));
+        }
+
+        Ok(())
+    }
+
     async fn x509_san_dns(
         &self,
         decoded_request: &AuthorizationRequestObject,
@@ -117,9 +389,10 @@ impl RequestVerifier for WalletActivity {
             X509SanVariant::Dns,
             self.metadata(),
             decoded_request,
-            request_jwt,
+            request_jwt.clone(),
             None,
-        )
+        )?;
+        self.set_verifier_certificate(&request_jwt)
     }
 
     async fn x509_san_uri(
@@ -135,39 +408,106 @@ impl RequestVerifier for WalletActivity {
             X509SanVariant::Uri,
             self.metadata(),
             decoded_request,
-            request_jwt,
+            request_jwt.clone(),
             None,
-        )
+        )?;
+        self.set_verifier_certificate(&request_jwt)
     }
 }
 
+/// The response modes of a DC API request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum DcApiResponseMode {
+    /// `dc_api`: the response is returned in plaintext.
+    DcApi,
+    /// `dc_api.jwt`: the response is returned as an encrypted JWT.
+    DcApiJwt,
+}
+
+impl DcApiResponseMode {
+    fn from_response_mode(response_mode: &ResponseMode) -> Option<Self> {
+        match response_mode {
+            ResponseMode::DcApi => Some(Self::DcApi),
+            ResponseMode::DcApiJwt => Some(Self::DcApiJwt),
+            _ => None,
+        }
+    }
+}
+
+/// A requested field, as shown to the user when asking for their consent.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct DcApiConsentField {
+    /// The id to approve the field with in [InProgressRequestDcApi::respond].
+    pub id: FieldId180137,
+    pub label: String,
+    pub value: Option<String>,
+    /// Whether the verifier requires the field, or only asks for it in some claim sets.
+    pub required: bool,
+    /// Whether the verifier intends to retain the value.
+    pub intent_to_retain: bool,
+    /// Why the verifier asked for the field.
+    pub purpose: Option<String>,
+}
+
+/// How claim paths that cannot address an mdoc element are handled.
+///
+/// mdoc claims are addressed by exactly a namespace and an element identifier, so deeper
+/// paths, or paths with non-string segments, cannot be matched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, uniffi::Enum)]
+pub enum ClaimPathMode {
+    /// Log a warning and skip the claim, or ignore any segments after the element identifier.
+    #[default]
+    Lenient,
+    /// Reject the request, listing the unsupported claim paths.
+    Strict,
+}
+
 /// Handle a DC API request.
 ///
 /// Supports OpenID4VP Draft 24 using DCQL for mDL only.
-#[uniffi::export(async_runtime = "tokio")]
+///
+/// If `accepted_response_modes` is given, requests using any other response mode are
+/// rejected, e.g. to only accept encrypted responses.
+///
+/// `claim_path_mode` defaults to [ClaimPathMode::Lenient].
+///
+/// The `origin` must use https, and localhost origins are rejected unless `allow_localhost`
+/// is set, e.g. for development builds. A `request_uri` must use https too, unless it points
+/// to localhost and `allow_localhost` is set.
+///
+/// A `request_uri` is always fetched, whatever the
+/// [OfflinePolicy](crate::verifier::offline::OfflinePolicy) of the wallet, as the request
+/// cannot be answered without its request object.
+#[uniffi::export(
+    async_runtime = "tokio",
+    default(
+        accepted_response_modes = None,
+        claim_path_mode = None,
+        allow_localhost = None
+    )
+)]
 pub async fn handle_dc_api_request(
     dcql_credential_id: String,
     mdoc: Arc<Mdoc>,
     origin: String,
     request_json: String,
+    accepted_response_modes: Option<Vec<DcApiResponseMode>>,
+    claim_path_mode: Option<ClaimPathMode>,
+    allow_localhost: Option<bool>,
 ) -> Result<InProgressRequestDcApi, DcApiError> {
-    let wallet_activity = WalletActivity {
-        http_client: ReqwestClient::new().map_err(DcApiError::internal_error)?,
-        origin: origin.clone(),
-        wallet_metadata: default_metadata(),
-    };
-
-    let request: AuthorizationRequest = serde_json::from_str(&request_json)
-        .context(request_json)
-        .context("failed to parse the request")
-        .map_err(DcApiError::invalid_request)?;
+    let wallet_activity = WalletActivity::new(origin.clone(), allow_localhost.unwrap_or_default())?;
+    let (request, request_object) = wallet_activity.validate_request(request_json).await?;
 
-    let request_object = request
-        .clone()
-        .validate(&wallet_activity)
-        .await
-        .context("the request is could not be verified")
-        .map_err(DcApiError::invalid_request)?;
+    if let Some(accepted_response_modes) = accepted_response_modes {
+        let response_mode = request_object.response_mode();
+        if !DcApiResponseMode::from_response_mode(response_mode)
+            .is_some_and(|mode| accepted_response_modes.contains(&mode))
+        {
+            return Err(DcApiError::UnsupportedResponseMode(format!(
+                "{response_mode:?}"
+            )));
+        }
+    }
 
     let responder = Responder::new(&request_object)
         .context("could not build a responder for the request")
@@ -182,12 +522,25 @@ pub async fn handle_dc_api_request(
         .credentials()
         .iter()
         .find(|c| c.id() == dcql_credential_id)
-        .context("requested credential not found")
-        .map_err(DcApiError::invalid_request)?;
+        .ok_or_else(|| DcApiError::CredentialNotFound(dcql_credential_id.clone()))?;
 
-    let request_match = find_match(credential_query, &mdoc)
-        .context("the selected credential does not match the request")
-        .map_err(DcApiError::invalid_request)?;
+    let mut request_match =
+        find_match(credential_query, &mdoc, claim_path_mode.unwrap_or_default())
+            .context("the selected credential does not match the request")
+            .map_err(DcApiError::match_failed)?;
+
+    if request_match.doctype_mismatch {
+        return Err(DcApiError::MatchFailed(format!(
+            "the request is not for a {}",
+            mdoc.doctype()
+        )));
+    }
+
+    let purpose = find_purpose(&query, &dcql_credential_id);
+    // DCQL has no per-claim purpose, so every field is requested for the credential's purpose.
+    for field in &mut request_match.requested_fields {
+        field.purpose = purpose.clone();
+    }
 
     Ok(InProgressRequestDcApi {
         dcql_credential_id,
@@ -197,20 +550,341 @@ pub async fn handle_dc_api_request(
         request,
         request_object,
         request_match,
+        purpose,
         wallet_activity,
     })
 }
 
+/// What a request asks for, shown before any stored credential is considered.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct Oid4vpRequestSummary {
+    /// The client id the response would be bound to, e.g. `web-origin:<origin>` for unsigned
+    /// requests.
+    pub client_id: String,
+    pub purpose: Option<String>,
+    pub response_mode: String,
+    /// The requested credential formats, e.g. `mso_mdoc` or `ldp_vc`, without duplicates.
+    pub requested_formats: Vec<String>,
+    /// The requested claims, as JSON-encoded DCQL claim paths or presentation definition
+    /// field paths.
+    pub requested_claims: Vec<String>,
+}
+
+/// Inspect a DC API request without matching it against any credential.
+///
+/// The request is verified as in [handle_dc_api_request], and may use either a DCQL query
+/// or a presentation definition.
+#[uniffi::export(async_runtime = "tokio", default(allow_localhost = None))]
+pub async fn inspect_oid4vp_request(
+    request_json: String,
+    origin: String,
+    allow_localhost: Option<bool>,
+) -> Result<Oid4vpRequestSummary, OID4VPError> {
+    let validation_error = |e: DcApiError| OID4VPError::RequestValidation(e.to_string());
+
+    let wallet_activity = WalletActivity::new(origin, allow_localhost.unwrap_or_default())
+        .map_err(validation_error)?;
+    let (request, request_object) = wallet_activity
+        .validate_request(request_json)
+        .await
+        .map_err(validation_error)?;
+
+    let client_id = wallet_activity
+        .effective_client_id(&request)
+        .await
+        .map_err(|e| OID4VPError::RequestValidation(format!("{e:#}")))?;
+    let response_mode = request_object.response_mode().to_string();
+
+    let (purpose, requested_formats, requested_claims) = match request_object.get::<DcqlQuery>() {
+        Some(_) => {
+            let query: DcqlQuery = request_object
+                .get()
+                .parsing_error()
+                .map_err(|e| OID4VPError::RequestValidation(format!("{e:#}")))?;
+            summarize_dcql_query(&query)
+        }
+        None => {
+            let definition = request_object
+                .resolve_presentation_definition(wallet_activity.http_client())
+                .await
+                .map_err(|e| OID4VPError::PresentationDefinitionResolution(format!("{e:?}")))?
+                .ok_or_else(|| {
+                    OID4VPError::RequestValidation(
+                        "request contains neither a DCQL query nor a presentation definition"
+                            .to_string(),
+                    )
+                })?
+                .into_parsed();
+            let definition = serde_json::to_value(&definition)
+                .map_err(|e| OID4VPError::JsonSyntaxParse(e.to_string()))?;
+            summarize_presentation_definition(&definition)
+        }
+    };
+
+    Ok(Oid4vpRequestSummary {
+        client_id,
+        purpose,
+        response_mode,
+        requested_formats,
+        requested_claims,
+    })
+}
+
+/// The purpose, formats and claim paths of a DCQL query.
+fn summarize_dcql_query(query: &DcqlQuery) -> (Option<String>, Vec<String>, Vec<String>) {
+    let purpose = query
+        .credentials()
+        .iter()
+        .find_map(|credential| find_purpose(query, credential.id()));
+
+    let query = serde_json::to_value(query).unwrap_or_default();
+    let credentials = query
+        .get("credentials")
+        .and_then(|credentials| credentials.as_array())
+        .into_iter()
+        .flatten();
+
+    let mut formats = Vec::new();
+    let mut claims = Vec::new();
+    for credential in credentials {
+        if let Some(format) = credential.get("format").and_then(|f| f.as_str()) {
+            push_unique(&mut formats, format.to_string());
+        }
+        credential
+            .get("claims")
+            .and_then(|claims| claims.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|claim| claim.get("path"))
+            .for_each(|path| push_unique(&mut claims, path.to_string()));
+    }
+
+    (purpose, formats, claims)
+}
+
+/// The purpose, formats and field paths of a presentation definition.
+fn summarize_presentation_definition(
+    definition: &serde_json::Value,
+) -> (Option<String>, Vec<String>, Vec<String>) {
+    let input_descriptors = || {
+        definition
+            .get("input_descriptors")
+            .and_then(|descriptors| descriptors.as_array())
+            .into_iter()
+            .flatten()
+    };
+
+    let purpose = definition
+        .get("purpose")
+        .into_iter()
+        .chain(input_descriptors().filter_map(|descriptor| descriptor.get("purpose")))
+        .find_map(|purpose| purpose.as_str())
+        .map(str::to_string);
+
+    let mut formats = Vec::new();
+    let mut claims = Vec::new();
+    for format in definition
+        .get("format")
+        .into_iter()
+        .chain(input_descriptors().filter_map(|descriptor| descriptor.get("format")))
+        .filter_map(|format| format.as_object())
+        .flat_map(|format| format.keys())
+    {
+        push_unique(&mut formats, format.clone());
+    }
+    input_descriptors()
+        .filter_map(|descriptor| descriptor.get("constraints")?.get("fields")?.as_array())
+        .flatten()
+        .filter_map(|field| field.get("path"))
+        .for_each(|path| push_unique(&mut claims, path.to_string()));
+
+    (purpose, formats, claims)
+}
+
+fn push_unique(values: &mut Vec<String>, value: String) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+/// Find the purpose the verifier gave for requesting a credential.
+///
+/// This is taken from the first credential set that includes the credential as an option,
+/// falling back to a `purpose` in the credential query's `meta`.
+fn find_purpose(query: &DcqlQuery, dcql_credential_id: &str) -> Option<String> {
+    let credential_set_purpose = query
+        .credential_sets()
+        .into_iter()
+        .flat_map(|sets| sets.iter())
+        .filter(|set| {
+            set.options()
+                .iter()
+                .flatten()
+                .any(|id| id == dcql_credential_id)
+        })
+        .find_map(|set| set.purpose().map(purpose_to_string));
+
+    credential_set_purpose.or_else(|| {
+        query
+            .credentials()
+            .iter()
+            .find(|credential| credential.id() == dcql_credential_id)?
+            .meta()?
+            .get("purpose")
+            .map(purpose_to_string)
+    })
+}
+
 #[uniffi::export]
 impl InProgressRequestDcApi {
     pub fn get_match(&self) -> RequestMatch180137 {
         self.request_match.clone()
     }
 
+    /// Whether the response will be encrypted, i.e. the request uses `dc_api.jwt`.
+    pub fn response_will_be_encrypted(&self) -> bool {
+        self.responder.encryption().is_some()
+    }
+
+    /// The algorithms the response will be encrypted with, if it is encrypted.
+    pub fn negotiated_encryption(&self) -> Option<EncryptionParams> {
+        self.responder.encryption()
+    }
+
     pub fn get_origin(&self) -> String {
         self.origin.clone()
     }
 
+    /// The nonce the verifier bound the request to, e.g. to build a key binding outside of
+    /// [respond](Self::respond).
+    pub fn request_nonce(&self) -> String {
+        self.request_object.nonce().to_string()
+    }
+
+    /// The audience the verifier expects the response to be bound to: its client id for
+    /// signed requests, or `web-origin:<origin>` for unsigned ones.
+    pub async fn effective_audience(&self) -> Result<String, DcApiError> {
+        self.wallet_activity
+            .effective_client_id(&self.request)
+            .await
+            .context("failed to determine the effective client id")
+            .map_err(DcApiError::invalid_request)
+    }
+
+    /// The requested elements that are not present in the selected credential, with the
+    /// reason why, e.g. to explain to the user why a request cannot be fully satisfied.
+    pub fn missing_field_details(&self) -> Vec<MissingField180137> {
+        self.request_match.missing_fields.clone()
+    }
+
+    /// The requested elements that are not present in the selected credential,
+    /// formatted as `<namespace>/<element identifier>`.
+    pub fn missing_fields(&self) -> Vec<String> {
+        self.request_match
+            .missing_fields
+            .iter()
+            .map(|field| format!("{}/{}", field.namespace, field.element_identifier))
+            .collect()
+    }
+
+    /// Every field the verifier asked for, with what is needed to ask the user for consent.
+    pub fn consent_fields(&self) -> Vec<DcApiConsentField> {
+        self.request_match
+            .requested_fields
+            .iter()
+            .map(|field| DcApiConsentField {
+                id: field.id.clone(),
+                label: field.displayable_name.clone(),
+                value: field.displayable_value.clone(),
+                required: field.required,
+                intent_to_retain: field.intent_to_retain,
+                purpose: field.purpose.clone(),
+            })
+            .collect()
+    }
+
+    /// The names of the requested fields that the verifier intends to retain, e.g. to warn
+    /// the user that they will be kept.
+    pub fn retained_fields(&self) -> Vec<String> {
+        self.request_match
+            .requested_fields
+            .iter()
+            .filter(|field| field.intent_to_retain)
+            .map(|field| field.displayable_name.clone())
+            .collect()
+    }
+
+    /// The purpose the verifier gave for requesting the credential, if any.
+    pub fn purpose(&self) -> Option<String> {
+        self.purpose.clone()
+    }
+
+    /// The subject common name (CN) of the certificate the request was signed with.
+    ///
+    /// This is only available for requests verified with the `x509_san_dns` or
+    /// `x509_san_uri` client id schemes.
+    pub fn verifier_common_name(&self) -> Option<String> {
+        self.wallet_activity
+            .verifier_certificate
+            .get()?
+            .common_name
+            .clone()
+    }
+
+    /// The subject organization (O) of the certificate the request was signed with.
+    ///
+    /// This is only available for requests verified with the `x509_san_dns` or
+    /// `x509_san_uri` client id schemes.
+    pub fn verifier_organization(&self) -> Option<String> {
+        self.wallet_activity
+            .verifier_certificate
+            .get()?
+            .organization
+            .clone()
+    }
+
+    /// The DNS names, URIs and email addresses in the subject alternative name extension of
+    /// the certificate the request was signed with.
+    ///
+    /// This is empty for requests that were not verified with the `x509_san_dns` or
+    /// `x509_san_uri` client id schemes.
+    pub fn verifier_subject_alternative_names(&self) -> Vec<String> {
+        self.wallet_activity
+            .verifier_certificate
+            .get()
+            .map(|certificate| certificate.subject_alternative_names.clone())
+            .unwrap_or_default()
+    }
+
+    /// Evaluate the request's `credential_sets` against the mdoc of this request, which is
+    /// available for every DCQL credential query for its doctype.
+    ///
+    /// The result lists, for each credential set, the options that can be satisfied, so the
+    /// wallet can tell whether the mdoc is enough or which other credentials are needed.
+    pub fn credential_set_options(&self) -> Result<CredentialSetEvaluation, DcApiError> {
+        let query: DcqlQuery = self
+            .request_object
+            .get()
+            .parsing_error()
+            .map_err(DcApiError::invalid_request)?;
+
+        let doctype = self.mdoc.doctype();
+        let available_credential_ids: Vec<String> = query
+            .credentials()
+            .iter()
+            .filter(|credential_query| {
+                credential_query
+                    .meta()
+                    .and_then(|meta| meta.get("doctype_value"))
+                    .and_then(|value| value.as_str())
+                    == Some(doctype.as_str())
+            })
+            .map(|credential_query| credential_query.id().to_string())
+            .collect();
+
+        Ok(evaluate_credential_sets(&query, &available_credential_ids))
+    }
+
     /// Generate a response for the request.
     ///
     /// The response is either a JWE or a serialized JSON Object.
@@ -219,19 +893,67 @@ impl InProgressRequestDcApi {
         keystore: Arc<dyn KeyStore>,
         approved_fields: Vec<FieldId180137>,
     ) -> Result<String, DcApiError> {
+        let device_response = self.device_response(keystore, approved_fields).await?;
+
+        let vp_token = vp_token(self.dcql_credential_id.clone(), device_response)
+            .context("failed to create a VP token")
+            .map_err(DcApiError::internal_error)?;
+
+        self.responder
+            .response(vp_token)
+            .context("failed to create a response")
+            .map_err(DcApiError::internal_error)
+    }
+
+    /// Generate a response for the request, described as pretty-printed JSON to help debug
+    /// interoperability issues.
+    ///
+    /// Alongside the response, the output contains the decoded device response and, for
+    /// encrypted responses, the JWE header. It is not meant to be sent to the verifier, use
+    /// [respond](Self::respond) instead.
+    pub async fn respond_debug(
+        &self,
+        keystore: Arc<dyn KeyStore>,
+        approved_fields: Vec<FieldId180137>,
+    ) -> Result<String, DcApiError> {
+        let device_response = self.device_response(keystore, approved_fields).await?;
+
+        let decoded_vp_token = serde_json::Value::Object(
+            [(
+                self.dcql_credential_id.clone(),
+                device_response_for_display(&device_response)
+                    .map_err(DcApiError::internal_error)?,
+            )]
+            .into_iter()
+            .collect(),
+        );
+
+        let vp_token = vp_token(self.dcql_credential_id.clone(), device_response)
+            .context("failed to create a VP token")
+            .map_err(DcApiError::internal_error)?;
+
+        self.responder
+            .debug_response(vp_token, decoded_vp_token)
+            .context("failed to create a debug response")
+            .map_err(DcApiError::internal_error)
+    }
+}
+
+impl InProgressRequestDcApi {
+    async fn device_response(
+        &self,
+        keystore: Arc<dyn KeyStore>,
+        approved_fields: Vec<FieldId180137>,
+    ) -> Result<DeviceResponse, DcApiError> {
         let handover = Handover::new(
             self.origin.clone(),
-            self.wallet_activity
-                .effective_client_id(&self.request)
-                .await
-                .context("failed to determine the effective client id")
-                .map_err(DcApiError::invalid_request)?,
-            self.request_object.nonce().to_string(),
+            self.effective_audience().await?,
+            self.request_nonce(),
         )
         .context("failed to create a handover")
         .map_err(DcApiError::internal_error)?;
 
-        let device_response = prepare_response(
+        prepare_response(
             keystore,
             &self.mdoc,
             approved_fields,
@@ -240,23 +962,27 @@ impl InProgressRequestDcApi {
             handover,
         )
         .context("failed to prepare the device response")
-        .map_err(DcApiError::internal_error)?;
-
-        let vp_token = vp_token(self.dcql_credential_id.clone(), device_response)
-            .context("failed to create a VP token")
-            .map_err(DcApiError::internal_error)?;
-
-        self.responder
-            .response(vp_token)
-            .context("failed to create a response")
-            .map_err(DcApiError::internal_error)
+        .map_err(DcApiError::internal_error)
     }
 }
 
 #[derive(Debug, uniffi::Error)]
 pub enum DcApiError {
     InvalidRequest(String),
+    /// Any other failure, e.g. to build the response.
     InternalError(String),
+    UnsupportedResponseMode(String),
+    /// The request was not made for the calling origin, or its client id and response
+    /// targets belong to different origins.
+    OriginMismatch(String),
+    /// The request has no credential query with the selected DCQL credential id.
+    CredentialNotFound(String),
+    /// The selected credential does not satisfy the credential query.
+    MatchFailed(String),
+    /// The calling origin is malformed, does not use HTTPS, or is a localhost origin.
+    InvalidOrigin(String),
+    /// The request object could not be fetched from the `request_uri` of the request.
+    RequestUri(String),
 }
 
 impl DcApiError {
@@ -268,10 +994,28 @@ impl DcApiError {
         Self::InternalError(format!("{error:#}"))
     }
 
+    fn match_failed<E: fmt::Display>(error: E) -> Self {
+        Self::MatchFailed(format!("{error:#}"))
+    }
+
+    fn invalid_origin<E: fmt::Display>(error: E) -> Self {
+        Self::InvalidOrigin(format!("{error:#}"))
+    }
+
+    fn request_uri<E: fmt::Display>(error: E) -> Self {
+        Self::RequestUri(format!("{error:#}"))
+    }
+
     fn inner(&self) -> &str {
         match self {
             DcApiError::InvalidRequest(s) => s,
             DcApiError::InternalError(s) => s,
+            DcApiError::UnsupportedResponseMode(s) => s,
+            DcApiError::OriginMismatch(s) => s,
+            DcApiError::CredentialNotFound(s) => s,
+            DcApiError::MatchFailed(s) => s,
+            DcApiError::InvalidOrigin(s) => s,
+            DcApiError::RequestUri(s) => s,
         }
     }
 
@@ -279,6 +1023,12 @@ impl DcApiError {
         match self {
             DcApiError::InvalidRequest(_) => "InvalidRequest",
             DcApiError::InternalError(_) => "InternalError",
+            DcApiError::UnsupportedResponseMode(_) => "UnsupportedResponseMode",
+            DcApiError::OriginMismatch(_) => "OriginMismatch",
+            DcApiError::CredentialNotFound(_) => "CredentialNotFound",
+            DcApiError::MatchFailed(_) => "MatchFailed",
+            DcApiError::InvalidOrigin(_) => "InvalidOrigin",
+            DcApiError::RequestUri(_) => "RequestUri",
         }
     }
 }
@@ -301,13 +1051,16 @@ fn default_metadata() -> WalletMetadata {
         },
         "client_id_schemes_supported": [
             "x509_san_dns",
-            "x509_san_uri"
+            "x509_san_uri",
+            "redirect_uri"
         ],
         "authorization_encryption_alg_values_supported": [
-            "ECDH-ES"
+            "ECDH-ES",
+            "ECDH-ES+A256KW"
         ],
         "authorization_encryption_enc_values_supported": [
-            "A128GCM"
+            "A128GCM",
+            "A256GCM"
         ],
         // Missing from the default wallet metadata in the specification, but necessary to support signed authorization requests.
         "request_object_signing_alg_values_supported": ["ES256"]
@@ -319,6 +1072,12 @@ fn default_metadata() -> WalletMetadata {
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::crypto::RustTestKeyManager;
 
     #[test]
     fn default_metadata() {
@@ -328,4 +1087,833 @@ mod test {
             "mdoc-openid4vp://"
         );
     }
+
+    fn redirect_uri_request(client_id: &str) -> String {
+        request_with_query(
+            client_id,
+            json!({
+                "credentials": [{
+                    "id": "mdl",
+                    "format": "mso_mdoc",
+                    "meta": { "doctype_value": "org.iso.18013.5.1.mDL" },
+                    "claims": [{ "path": ["org.iso.18013.5.1", "family_name"] }]
+                }]
+            }),
+        )
+    }
+
+    fn request_with_query(client_id: &str, dcql_query: serde_json::Value) -> String {
+        json!({
+            "client_id": client_id,
+            "client_id_scheme": "redirect_uri",
+            "response_type": "vp_token",
+            "response_mode": "dc_api",
+            "nonce": "exc7gBkxjx1rdc9udRrveKvSsJIq80avlXeLHhGwqtA",
+            "dcql_query": dcql_query,
+        })
+        .to_string()
+    }
+
+    async fn test_mdoc() -> (Arc<RustTestKeyManager>, Arc<crate::credential::mdoc::Mdoc>) {
+        let (key_manager, _, mdoc) = crate::mdl::util::test_mdl().await;
+        (key_manager, Arc::new(mdoc))
+    }
+
+    #[rstest]
+    #[case::matching_origin("https://verifier.example.com/callback", true)]
+    #[case::prefixed_client_id("redirect_uri:https://verifier.example.com", true)]
+    #[case::mismatched_origin("https://attacker.example.com/callback", false)]
+    #[tokio::test]
+    async fn redirect_uri_client_id_scheme(#[case] client_id: &str, #[case] valid: bool) {
+        let (key_manager, mdoc) = test_mdoc().await;
+
+        let result = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            redirect_uri_request(client_id),
+            None,
+            None,
+            None,
+        )
+        .await;
+
+        let in_progress = match result {
+            Ok(in_progress) => {
+                assert!(valid, "request with mismatched origin was accepted");
+                in_progress
+            }
+            Err(e) => {
+                assert!(!valid, "request was rejected: {e}");
+                return;
+            }
+        };
+
+        let approved_fields = in_progress
+            .get_match()
+            .requested_fields()
+            .into_iter()
+            .map(|field| field.id)
+            .collect();
+
+        let response = in_progress
+            .respond(key_manager, approved_fields)
+            .await
+            .unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert!(response["vp_token"]["mdl"].is_string());
+    }
+
+    #[tokio::test]
+    async fn debug_response_decodes_device_response() {
+        let (key_manager, mdoc) = test_mdoc().await;
+
+        let in_progress = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            redirect_uri_request("https://verifier.example.com/callback"),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let approved_fields = in_progress
+            .get_match()
+            .requested_fields()
+            .into_iter()
+            .map(|field| field.id)
+            .collect();
+
+        let debug = in_progress
+            .respond_debug(key_manager, approved_fields)
+            .await
+            .unwrap();
+        assert!(debug.contains('\n'), "debug output is not pretty-printed");
+
+        let debug: serde_json::Value = serde_json::from_str(&debug).unwrap();
+        assert!(debug["response"]["vp_token"]["mdl"].is_string());
+        assert_eq!(
+            debug["decoded_vp_token"]["mdl"]["documents"][0]["docType"],
+            "org.iso.18013.5.1.mDL"
+        );
+    }
+
+    #[tokio::test]
+    async fn match_details_before_responding() {
+        let (_, mdoc) = test_mdoc().await;
+
+        let request = request_with_query(
+            "https://verifier.example.com/callback",
+            json!({
+                "credentials": [{
+                    "id": "mdl",
+                    "format": "mso_mdoc",
+                    "meta": { "doctype_value": "org.iso.18013.5.1.mDL" },
+                    "claims": [
+                        { "path": ["org.iso.18013.5.1", "family_name"] },
+                        { "path": ["org.iso.18013.5.1", "not_an_element"] }
+                    ]
+                }],
+                "credential_sets": [{
+                    "options": [["mdl"]],
+                    "purpose": "Verify your identity"
+                }]
+            }),
+        );
+
+        let in_progress = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            request,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            in_progress.missing_fields(),
+            vec!["org.iso.18013.5.1/not_an_element".to_string()]
+        );
+        assert_eq!(
+            in_progress.purpose().as_deref(),
+            Some("Verify your identity")
+        );
+    }
+
+    #[tokio::test]
+    async fn consent_fields_carry_retention_and_purpose() {
+        let (_, mdoc) = test_mdoc().await;
+
+        let request = request_with_query(
+            "https://verifier.example.com/callback",
+            json!({
+                "credentials": [{
+                    "id": "mdl",
+                    "format": "mso_mdoc",
+                    "meta": {
+                        "doctype_value": "org.iso.18013.5.1.mDL",
+                        "purpose": "Rent a car"
+                    },
+                    "claims": [
+                        {
+                            "id": "name",
+                            "path": ["org.iso.18013.5.1", "family_name"],
+                            "intent_to_retain": true
+                        },
+                        { "id": "place", "path": ["org.iso.18013.5.1", "birth_place"] }
+                    ],
+                    "claim_sets": [["name", "place"], ["name"]]
+                }]
+            }),
+        );
+
+        let in_progress = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            request,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let fields = in_progress.consent_fields();
+        assert_eq!(fields.len(), 2);
+
+        let family_name = fields.iter().find(|f| f.label == "Family Name").unwrap();
+        assert!(family_name.required);
+        assert!(family_name.intent_to_retain);
+        assert_eq!(family_name.purpose.as_deref(), Some("Rent a car"));
+
+        let birth_place = fields.iter().find(|f| f.label == "Birth Place").unwrap();
+        assert_eq!(birth_place.value.as_deref(), Some("Canada"));
+        assert!(!birth_place.required);
+        assert!(!birth_place.intent_to_retain);
+        assert_eq!(birth_place.purpose.as_deref(), Some("Rent a car"));
+
+        assert_eq!(in_progress.retained_fields(), vec!["Family Name"]);
+    }
+
+    #[tokio::test]
+    async fn credential_set_with_alternatives() {
+        let (_, mdoc) = test_mdoc().await;
+
+        let mdoc_query = |id: &str, doctype: &str| {
+            json!({
+                "id": id,
+                "format": "mso_mdoc",
+                "meta": { "doctype_value": doctype },
+                "claims": [{ "path": ["org.iso.18013.5.1", "family_name"] }]
+            })
+        };
+
+        let request = request_with_query(
+            "https://verifier.example.com/callback",
+            json!({
+                "credentials": [
+                    mdoc_query("mdl", "org.iso.18013.5.1.mDL"),
+                    mdoc_query("pid", "eu.europa.ec.eudi.pid.1")
+                ],
+                "credential_sets": [{
+                    "options": [["pid"], ["mdl"]]
+                }]
+            }),
+        );
+
+        let in_progress = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            request,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let evaluation = in_progress.credential_set_options().unwrap();
+
+        assert!(evaluation.satisfiable);
+        assert_eq!(
+            evaluation.credential_sets[0].satisfying_options,
+            vec![vec!["mdl".to_string()]]
+        );
+    }
+
+    #[rstest]
+    #[case::any_mode(None, true)]
+    #[case::plaintext_accepted(Some(vec![super::DcApiResponseMode::DcApi]), true)]
+    #[case::encrypted_only(Some(vec![super::DcApiResponseMode::DcApiJwt]), false)]
+    #[tokio::test]
+    async fn accepted_response_modes(
+        #[case] accepted_response_modes: Option<Vec<super::DcApiResponseMode>>,
+        #[case] accepted: bool,
+    ) {
+        let (_, mdoc) = test_mdoc().await;
+
+        let result = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            redirect_uri_request("https://verifier.example.com/callback"),
+            accepted_response_modes,
+            None,
+            None,
+        )
+        .await;
+
+        match result {
+            Ok(_) => assert!(accepted, "plaintext response mode was accepted"),
+            Err(super::DcApiError::UnsupportedResponseMode(_)) => assert!(!accepted),
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+
+    /// A request signed with a self-signed certificate for `verifier.example.com`, using the
+    /// `x509_san_dns` client id scheme.
+    fn x509_san_dns_request() -> String {
+        use base64::{
+            prelude::{BASE64_STANDARD, BASE64_URL_SAFE_NO_PAD},
+            Engine,
+        };
+        use p256::ecdsa::{DerSignature, Signature, SigningKey};
+        use signature::Signer;
+        use ssi::crypto::rand;
+        use x509_cert::{
+            builder::{Builder, CertificateBuilder, Profile},
+            der::Encode,
+            ext::pkix::{name::GeneralName, SubjectAltName},
+            spki::SubjectPublicKeyInfoOwned,
+            time::Validity,
+        };
+
+        let key = SigningKey::random(&mut rand::thread_rng());
+        let mut builder = CertificateBuilder::new(
+            Profile::Root,
+            1u32.into(),
+            Validity::from_now(std::time::Duration::from_secs(60 * 60)).unwrap(),
+            "CN=Example Verifier,O=Example Org,C=US".parse().unwrap(),
+            SubjectPublicKeyInfoOwned::from_key(*key.verifying_key()).unwrap(),
+            &key,
+        )
+        .unwrap();
+        builder
+            .add_extension(&SubjectAltName(vec![GeneralName::DnsName(
+                "verifier.example.com".to_string().try_into().unwrap(),
+            )]))
+            .unwrap();
+        let certificate = builder.build::<DerSignature>().unwrap();
+
+        let header = json!({
+            "alg": "ES256",
+            "typ": "oauth-authz-req+jwt",
+            "x5c": [BASE64_STANDARD.encode(certificate.to_der().unwrap())],
+        });
+        let claims = json!({
+            "client_id": "verifier.example.com",
+            "client_id_scheme": "x509_san_dns",
+            "response_type": "vp_token",
+            "response_mode": "dc_api",
+            "nonce": "exc7gBkxjx1rdc9udRrveKvSsJIq80avlXeLHhGwqtA",
+            "expected_origins": ["https://verifier.example.com"],
+            "dcql_query": {
+                "credentials": [{
+                    "id": "mdl",
+                    "format": "mso_mdoc",
+                    "meta": { "doctype_value": "org.iso.18013.5.1.mDL" },
+                    "claims": [{ "path": ["org.iso.18013.5.1", "family_name"] }]
+                }]
+            },
+        });
+        let signing_input = format!(
+            "{}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(header.to_string()),
+            BASE64_URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature: Signature = key.sign(signing_input.as_bytes());
+        let request_jwt = format!(
+            "{signing_input}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        );
+
+        json!({
+            "client_id": "verifier.example.com",
+            "request": request_jwt,
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn verifier_certificate_subject() {
+        let (_, mdoc) = test_mdoc().await;
+
+        let in_progress = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            x509_san_dns_request(),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            in_progress.verifier_common_name().as_deref(),
+            Some("Example Verifier")
+        );
+        assert_eq!(
+            in_progress.verifier_organization().as_deref(),
+            Some("Example Org")
+        );
+        assert_eq!(
+            in_progress.verifier_subject_alternative_names(),
+            vec!["verifier.example.com".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn request_passed_by_request_uri() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        let (_, mdoc) = test_mdoc().await;
+        let signed_request: serde_json::Value =
+            serde_json::from_str(&x509_san_dns_request()).unwrap();
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/request/1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(signed_request["request"].as_str().unwrap()),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let request_json = json!({
+            "client_id": signed_request["client_id"],
+            "request_uri": format!("{}/request/1", server.uri()),
+        })
+        .to_string();
+
+        let in_progress = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            request_json,
+            None,
+            None,
+            Some(true),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            in_progress.verifier_common_name().as_deref(),
+            Some("Example Verifier")
+        );
+    }
+
+    #[tokio::test]
+    async fn unreachable_request_uri() {
+        use wiremock::MockServer;
+
+        let (_, mdoc) = test_mdoc().await;
+        let server = MockServer::start().await;
+
+        let request_json = json!({
+            "client_id": "x509_san_dns:verifier.example.com",
+            "request_uri": format!("{}/request/missing", server.uri()),
+        })
+        .to_string();
+
+        let result = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            request_json,
+            None,
+            None,
+            Some(true),
+        )
+        .await;
+
+        assert!(
+            matches!(result, Err(super::DcApiError::RequestUri(_))),
+            "unexpected result: {:?}",
+            result.err()
+        );
+    }
+
+    #[tokio::test]
+    async fn request_uri_posted_without_wallet_nonce_in_response() {
+        use wiremock::{
+            matchers::{body_string_contains, header, method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        let (_, mdoc) = test_mdoc().await;
+        let signed_request: serde_json::Value =
+            serde_json::from_str(&x509_san_dns_request()).unwrap();
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/request/1"))
+            .and(header("Content-Type", "application/x-www-form-urlencoded"))
+            .and(body_string_contains("wallet_metadata="))
+            .and(body_string_contains("wallet_nonce="))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(signed_request["request"].as_str().unwrap()),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let request_json = json!({
+            "client_id": signed_request["client_id"],
+            "request_uri": format!("{}/request/1", server.uri()),
+            "request_uri_method": "post",
+        })
+        .to_string();
+
+        let result = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            request_json,
+            None,
+            None,
+            Some(true),
+        )
+        .await;
+
+        assert!(
+            matches!(
+                result,
+                Err(super::DcApiError::RequestUri(ref e)) if e.contains("wallet_nonce")
+            ),
+            "unexpected result: {:?}",
+            result.err()
+        );
+    }
+
+    #[rstest]
+    #[case::remote("http://verifier.example.com/request/1", Some(true))]
+    #[case::localhost_not_allowed("http://127.0.0.1:8080/request/1", None)]
+    #[tokio::test]
+    async fn request_uri_over_http(
+        #[case] request_uri: &str,
+        #[case] allow_localhost: Option<bool>,
+    ) {
+        let (_, mdoc) = test_mdoc().await;
+
+        let request_json = json!({
+            "client_id": "x509_san_dns:verifier.example.com",
+            "request_uri": request_uri,
+        })
+        .to_string();
+
+        let result = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            request_json,
+            None,
+            None,
+            allow_localhost,
+        )
+        .await;
+
+        assert!(
+            matches!(result, Err(super::DcApiError::RequestUri(ref e)) if e.contains("https")),
+            "unexpected result: {:?}",
+            result.err()
+        );
+    }
+
+    #[tokio::test]
+    async fn expected_origin_matches_case_insensitively() {
+        let (_, mdoc) = test_mdoc().await;
+
+        let result = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://Verifier.Example.com:443".to_string(),
+            x509_san_dns_request(),
+            None,
+            None,
+            None,
+        )
+        .await;
+
+        assert!(result.is_ok(), "request was rejected: {:?}", result.err());
+    }
+
+    #[tokio::test]
+    async fn request_from_http_origin() {
+        let (_, mdoc) = test_mdoc().await;
+
+        let result = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "http://verifier.example.com".to_string(),
+            redirect_uri_request("http://verifier.example.com/callback"),
+            None,
+            None,
+            None,
+        )
+        .await;
+
+        assert!(
+            matches!(result, Err(super::DcApiError::InvalidOrigin(_))),
+            "unexpected result: {:?}",
+            result.err()
+        );
+    }
+
+    #[rstest]
+    #[case::rejected_by_default(None)]
+    #[case::allowed(Some(true))]
+    #[tokio::test]
+    async fn request_from_localhost_origin(#[case] allow_localhost: Option<bool>) {
+        let (_, mdoc) = test_mdoc().await;
+
+        let result = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "http://localhost:8080".to_string(),
+            redirect_uri_request("http://localhost:8080/callback"),
+            None,
+            None,
+            allow_localhost,
+        )
+        .await;
+
+        match allow_localhost {
+            Some(true) => assert!(result.is_ok(), "request was rejected: {:?}", result.err()),
+            _ => assert!(
+                matches!(result, Err(super::DcApiError::InvalidOrigin(_))),
+                "unexpected result: {:?}",
+                result.err()
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn request_for_another_origin() {
+        let (_, mdoc) = test_mdoc().await;
+
+        let result = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://attacker.example.com".to_string(),
+            x509_san_dns_request(),
+            None,
+            None,
+            None,
+        )
+        .await;
+
+        assert!(
+            matches!(result, Err(super::DcApiError::OriginMismatch(_))),
+            "unexpected result: {:?}",
+            result.err()
+        );
+    }
+
+    #[tokio::test]
+    async fn request_without_selected_credential() {
+        let (_, mdoc) = test_mdoc().await;
+
+        let result = super::handle_dc_api_request(
+            "pid".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            redirect_uri_request("https://verifier.example.com/callback"),
+            None,
+            None,
+            None,
+        )
+        .await;
+
+        assert!(
+            matches!(result, Err(super::DcApiError::CredentialNotFound(ref id)) if id == "pid"),
+            "unexpected result: {:?}",
+            result.err()
+        );
+    }
+
+    #[tokio::test]
+    async fn claimless_request_for_another_doctype() {
+        let (_, mdoc) = test_mdoc().await;
+
+        let result = super::handle_dc_api_request(
+            "pid".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            request_with_query(
+                "https://verifier.example.com/callback",
+                json!({
+                    "credentials": [{
+                        "id": "pid",
+                        "format": "mso_mdoc",
+                        "meta": { "doctype_value": "eu.europa.ec.eudi.pid.1" },
+                    }]
+                }),
+            ),
+            None,
+            None,
+            None,
+        )
+        .await;
+
+        assert!(
+            matches!(result, Err(super::DcApiError::MatchFailed(_))),
+            "unexpected result: {:?}",
+            result.err()
+        );
+    }
+
+    #[rstest]
+    #[case::signed(x509_san_dns_request(), "verifier.example.com")]
+    #[case::unsigned(
+        redirect_uri_request("https://verifier.example.com/callback"),
+        "web-origin:https://verifier.example.com"
+    )]
+    #[tokio::test]
+    async fn key_binding_nonce_and_audience(#[case] request: String, #[case] audience: &str) {
+        let (_, mdoc) = test_mdoc().await;
+
+        let in_progress = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            request,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            in_progress.request_nonce(),
+            "exc7gBkxjx1rdc9udRrveKvSsJIq80avlXeLHhGwqtA"
+        );
+        assert_eq!(in_progress.effective_audience().await.unwrap(), audience);
+    }
+
+    #[tokio::test]
+    async fn no_verifier_certificate_for_redirect_uri() {
+        let (_, mdoc) = test_mdoc().await;
+
+        let in_progress = super::handle_dc_api_request(
+            "mdl".to_string(),
+            mdoc,
+            "https://verifier.example.com".to_string(),
+            redirect_uri_request("https://verifier.example.com/callback"),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(in_progress.verifier_common_name(), None);
+        assert!(in_progress.verifier_subject_alternative_names().is_empty());
+    }
+
+    #[tokio::test]
+    async fn inspect_dcql_mdl_request() {
+        let request = request_with_query(
+            "https://verifier.example.com/callback",
+            json!({
+                "credentials": [{
+                    "id": "mdl",
+                    "format": "mso_mdoc",
+                    "meta": {
+                        "doctype_value": "org.iso.18013.5.1.mDL",
+                        "purpose": "Age verification"
+                    },
+                    "claims": [
+                        { "path": ["org.iso.18013.5.1", "age_over_21"] },
+                        { "path": ["org.iso.18013.5.1", "portrait"] }
+                    ]
+                }]
+            }),
+        );
+
+        let summary = super::inspect_oid4vp_request(
+            request,
+            "https://Verifier.Example.com:443".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(summary.client_id, "web-origin:https://verifier.example.com");
+        assert_eq!(summary.purpose.as_deref(), Some("Age verification"));
+        assert_eq!(summary.response_mode, "dc_api");
+        assert_eq!(summary.requested_formats, vec!["mso_mdoc"]);
+        assert_eq!(
+            summary.requested_claims,
+            vec![
+                r#"["org.iso.18013.5.1","age_over_21"]"#,
+                r#"["org.iso.18013.5.1","portrait"]"#
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn inspect_ldp_presentation_definition_request() {
+        let request = json!({
+            "client_id": "https://verifier.example.com/callback",
+            "client_id_scheme": "redirect_uri",
+            "response_type": "vp_token",
+            "response_mode": "dc_api",
+            "nonce": "exc7gBkxjx1rdc9udRrveKvSsJIq80avlXeLHhGwqtA",
+            "presentation_definition": {
+                "id": "alumni",
+                "purpose": "Proof of alumni status",
+                "input_descriptors": [{
+                    "id": "alumni_credential",
+                    "format": { "ldp_vc": { "proof_type": ["Ed25519Signature2020"] } },
+                    "constraints": {
+                        "fields": [{ "path": ["$.credentialSubject.alumniOf"] }]
+                    }
+                }]
+            },
+        })
+        .to_string();
+
+        let summary = super::inspect_oid4vp_request(
+            request,
+            "https://verifier.example.com".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(summary.purpose.as_deref(), Some("Proof of alumni status"));
+        assert_eq!(summary.requested_formats, vec!["ldp_vc"]);
+        assert_eq!(
+            summary.requested_claims,
+            vec![r#"["$.credentialSubject.alumniOf"]"#]
+        );
+    }
 }
@@ -8,6 +8,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as Json;
 use sha2::{Digest, Sha256};
 
+use crate::credential::mdoc::to_json_for_display;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Handover(String, ByteStr);
 
@@ -37,3 +39,41 @@ pub fn vp_token(request_id: String, device_response: DeviceResponse) -> Result<J
     );
     Ok(vp_token)
 }
+
+/// The device response as JSON, for debugging.
+///
+/// Embedded CBOR (tag 24) is decoded, so that e.g. the MSO and the issuer signed items are
+/// shown in full.
+pub fn device_response_for_display(device_response: &DeviceResponse) -> Result<Json> {
+    let bytes =
+        cbor::to_vec(device_response).context("failed to encode device response as CBOR")?;
+    let value: ciborium::Value =
+        ciborium::from_reader(bytes.as_slice()).context("failed to decode device response")?;
+    to_json_for_display(&decode_embedded_cbor(value))
+        .context("failed to convert device response to JSON")
+}
+
+fn decode_embedded_cbor(value: ciborium::Value) -> ciborium::Value {
+    use ciborium::Value;
+
+    match value {
+        Value::Tag(24, inner) => match *inner {
+            Value::Bytes(bytes) => match ciborium::from_reader::<Value, _>(bytes.as_slice()) {
+                Ok(decoded) => Value::Tag(24, Box::new(decode_embedded_cbor(decoded))),
+                Err(_) => Value::Tag(24, Box::new(Value::Bytes(bytes))),
+            },
+            inner => Value::Tag(24, Box::new(decode_embedded_cbor(inner))),
+        },
+        Value::Tag(tag, inner) => Value::Tag(tag, Box::new(decode_embedded_cbor(*inner))),
+        Value::Array(values) => {
+            Value::Array(values.into_iter().map(decode_embedded_cbor).collect())
+        }
+        Value::Map(entries) => Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, decode_embedded_cbor(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
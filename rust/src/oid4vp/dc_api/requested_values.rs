@@ -2,19 +2,34 @@ use std::collections::BTreeMap;
 
 use anyhow::{bail, Result};
 use itertools::Itertools;
-use openid4vp::core::dcql_query::{DcqlCredentialClaimsQueryPath, DcqlCredentialQuery};
+use openid4vp::core::dcql_query::{
+    DcqlCredentialClaimsQuery, DcqlCredentialClaimsQueryPath, DcqlCredentialQuery,
+};
 use uuid::Uuid;
 
+use super::ClaimPathMode;
 use crate::{
     credential::mdoc::Mdoc,
+    mdl::age_over::{age_over_attestations, limit_age_over_attestations, AGE_OVER_NAMESPACE},
     oid4vp::iso_18013_7::requested_values::{
-        calculate_age_over_mapping, cbor_to_string, FieldId180137, FieldMap, RequestMatch180137,
-        RequestedField180137,
+        cbor_to_string, FieldId180137, FieldMap, MissingField180137, MissingFieldReason,
+        RequestMatch180137, RequestedField180137,
     },
 };
 
 /// Find the match between a query and a credential.
-pub fn find_match(query: &DcqlCredentialQuery, credential: &Mdoc) -> Result<RequestMatch180137> {
+///
+/// mdoc claims are addressed by a namespace and an element identifier, so any other claim
+/// path is unsupported, and is handled according to `claim_path_mode`.
+///
+/// Requested elements that the credential cannot provide are reported in `missing_fields`
+/// with the reason why. If the query is for another doctype, every requested element is
+/// missing, as another credential may match.
+pub fn find_match(
+    query: &DcqlCredentialQuery,
+    credential: &Mdoc,
+    claim_path_mode: ClaimPathMode,
+) -> Result<RequestMatch180137> {
     let mdoc = credential.document();
 
     if let Some(doc_type) = query
@@ -23,11 +38,17 @@ pub fn find_match(query: &DcqlCredentialQuery, credential: &Mdoc) -> Result<Requ
         .and_then(|value| value.as_str())
     {
         if doc_type != mdoc.mso.doc_type {
-            bail!("the request was not for an mDL: {}", doc_type)
+            return Ok(RequestMatch180137 {
+                credential_id: credential.id(),
+                field_map: FieldMap::new(),
+                requested_fields: vec![],
+                missing_fields: doctype_mismatch(query),
+                doctype_mismatch: true,
+            });
         }
     }
 
-    let mut age_over_mapping = calculate_age_over_mapping(&mdoc.namespaces);
+    let mut age_over_mapping = age_over_attestations(&mdoc.namespaces);
 
     let mut field_map = FieldMap::new();
 
@@ -49,7 +70,7 @@ pub fn find_match(query: &DcqlCredentialQuery, credential: &Mdoc) -> Result<Requ
                                 // If there are other age attestations that this element
                                 // should respond to, insert virtual elements for each
                                 // of those mappings.
-                                if namespace == "org.iso.18013.5.1" {
+                                if namespace == AGE_OVER_NAMESPACE {
                                     age_over_mapping.remove(element_identifier)
                                 } else {
                                     None
@@ -66,14 +87,27 @@ pub fn find_match(query: &DcqlCredentialQuery, credential: &Mdoc) -> Result<Requ
         })
         .collect();
 
+    let required_claim_ids = required_claim_ids(query);
+
     let mut requested_fields = BTreeMap::new();
-    let mut missing_fields = BTreeMap::new();
+    let mut missing_fields = Vec::new();
+    let mut unsupported_paths = Vec::new();
 
     'fields: for field in query
         .claims()
         .into_iter()
         .flat_map(|queries| queries.iter())
     {
+        if claim_path_mode == ClaimPathMode::Strict && !is_supported_path(field.path()) {
+            unsupported_paths.push(format!("{:?}", field.path()));
+            continue 'fields;
+        }
+        if field.path().len() > 2 {
+            tracing::warn!(
+                "ignoring claim path segments beyond the element identifier: {:?}",
+                &field.path()[2..]
+            );
+        }
         let Some(DcqlCredentialClaimsQueryPath::String(namespace)) = field.path().first() else {
             tracing::warn!(
                 "no valid namespace provided in query: {:?}",
@@ -89,11 +123,20 @@ pub fn find_match(query: &DcqlCredentialQuery, credential: &Mdoc) -> Result<Requ
             );
             continue 'fields;
         };
-        let Some(field_id) = elements_map
-            .get(namespace)
-            .and_then(|elements| elements.get(element_identifier))
-        else {
-            missing_fields.insert(namespace.clone(), element_identifier.clone());
+        let Some(elements) = elements_map.get(namespace) else {
+            missing_fields.push(MissingField180137 {
+                namespace: namespace.clone(),
+                element_identifier: element_identifier.clone(),
+                reason: MissingFieldReason::NamespaceAbsent,
+            });
+            continue 'fields;
+        };
+        let Some(field_id) = elements.get(element_identifier) else {
+            missing_fields.push(MissingField180137 {
+                namespace: namespace.clone(),
+                element_identifier: element_identifier.clone(),
+                reason: MissingFieldReason::ElementAbsent,
+            });
             continue 'fields;
         };
         let displayable_value = field_map
@@ -119,31 +162,218 @@ pub fn find_match(query: &DcqlCredentialQuery, credential: &Mdoc) -> Result<Requ
                 displayable_value,
                 selectively_disclosable: true,
                 intent_to_retain: field.intent_to_retain().unwrap_or(false),
-                required: true,
+                required: is_required(required_claim_ids.as_deref(), field),
                 purpose: None,
             },
         );
     }
 
-    let mut seen_age_over_attestations = 0;
-    let requested_fields = requested_fields
-        .into_values()
-        // According to the rules in ISO/IEC 18013-5 Section 7.2.5, don't respond with more
-        // than 2 age over attestations.
-        .filter(|field| {
-            if field.displayable_name.starts_with("age_over_") {
-                seen_age_over_attestations += 1;
-                seen_age_over_attestations < 3
-            } else {
-                true
-            }
-        })
-        .collect();
+    if !unsupported_paths.is_empty() {
+        bail!(
+            "unsupported claim paths in query: {}",
+            unsupported_paths.join(", ")
+        )
+    }
+
+    // According to the rules in ISO/IEC 18013-5 Section 7.2.5, don't respond with more
+    // than 2 age over attestations.
+    let requested_fields = limit_age_over_attestations(requested_fields.into_values(), |field| {
+        field_map
+            .get(&field.id)
+            .map(|(_, item)| item.as_ref().element_identifier.as_str())
+            .unwrap_or_default()
+    });
 
     Ok(RequestMatch180137 {
         credential_id: credential.id(),
         field_map,
         requested_fields,
         missing_fields,
+        doctype_mismatch: false,
     })
 }
+
+/// Every element requested by a query for another doctype, as missing fields.
+fn doctype_mismatch(query: &DcqlCredentialQuery) -> Vec<MissingField180137> {
+    query
+        .claims()
+        .into_iter()
+        .flat_map(|queries| queries.iter())
+        .filter_map(|field| {
+            let (
+                Some(DcqlCredentialClaimsQueryPath::String(namespace)),
+                Some(DcqlCredentialClaimsQueryPath::String(element_identifier)),
+            ) = (field.path().first(), field.path().get(1))
+            else {
+                return None;
+            };
+            Some(MissingField180137 {
+                namespace: namespace.clone(),
+                element_identifier: element_identifier.clone(),
+                reason: MissingFieldReason::DoctypeMismatch,
+            })
+        })
+        .collect()
+}
+
+/// The ids of the claims that are in every claim set of the query.
+///
+/// Returns `None` if the query has no claim sets, in which case every claim is required.
+fn required_claim_ids(query: &DcqlCredentialQuery) -> Option<Vec<String>> {
+    let (first, rest) = query.claim_sets()?.split_first()?;
+    Some(
+        first
+            .iter()
+            .filter(|id| rest.iter().all(|claim_set| claim_set.contains(id)))
+            .cloned()
+            .collect(),
+    )
+}
+
+/// Whether a claim must be disclosed, i.e. it is in every claim set of the query.
+fn is_required(required_claim_ids: Option<&[String]>, claim: &DcqlCredentialClaimsQuery) -> bool {
+    let Some(required_claim_ids) = required_claim_ids else {
+        return true;
+    };
+
+    claim
+        .id()
+        .is_some_and(|id| required_claim_ids.iter().any(|required| required == id))
+}
+
+/// Whether a claim path is exactly a namespace followed by an element identifier.
+fn is_supported_path(path: &[DcqlCredentialClaimsQueryPath]) -> bool {
+    matches!(
+        path,
+        [
+            DcqlCredentialClaimsQueryPath::String(_),
+            DcqlCredentialClaimsQueryPath::String(_)
+        ]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+    use crate::mdl::util::test_mdl;
+
+    fn query_with_nested_path() -> DcqlCredentialQuery {
+        serde_json::from_value(json!({
+            "id": "mdl",
+            "format": "mso_mdoc",
+            "meta": { "doctype_value": "org.iso.18013.5.1.mDL" },
+            "claims": [
+                { "path": ["org.iso.18013.5.1", "family_name"] },
+                { "path": ["org.iso.18013.5.1", "driving_privileges", "vehicle_category_code"] }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn lenient_mode_ignores_extra_path_segments() {
+        let (_, _, mdoc) = test_mdl().await;
+
+        let request_match =
+            find_match(&query_with_nested_path(), &mdoc, ClaimPathMode::Lenient).unwrap();
+
+        assert!(request_match
+            .requested_fields
+            .iter()
+            .any(|field| field.displayable_name == "Family Name"));
+    }
+
+    #[rstest]
+    #[case::namespace_absent(
+        "org.iso.18013.5.1.mDL",
+        ["org.example.absent", "family_name"],
+        MissingFieldReason::NamespaceAbsent
+    )]
+    #[case::element_absent(
+        "org.iso.18013.5.1.mDL",
+        ["org.iso.18013.5.1", "not_present"],
+        MissingFieldReason::ElementAbsent
+    )]
+    #[case::doctype_mismatch(
+        "org.example.other",
+        ["org.iso.18013.5.1", "family_name"],
+        MissingFieldReason::DoctypeMismatch
+    )]
+    #[tokio::test]
+    async fn missing_field_reason(
+        #[case] doctype: &str,
+        #[case] path: [&str; 2],
+        #[case] reason: MissingFieldReason,
+    ) {
+        let (_, _, mdoc) = test_mdl().await;
+        let query: DcqlCredentialQuery = serde_json::from_value(json!({
+            "id": "mdl",
+            "format": "mso_mdoc",
+            "meta": { "doctype_value": doctype },
+            "claims": [{ "path": path }]
+        }))
+        .unwrap();
+
+        let request_match = find_match(&query, &mdoc, ClaimPathMode::Lenient).unwrap();
+
+        assert!(request_match.requested_fields.is_empty());
+        assert_eq!(
+            request_match.missing_fields,
+            vec![MissingField180137 {
+                namespace: path[0].to_string(),
+                element_identifier: path[1].to_string(),
+                reason,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn doctype_mismatch_without_claims() {
+        let (_, _, mdoc) = test_mdl().await;
+        let query: DcqlCredentialQuery = serde_json::from_value(json!({
+            "id": "pid",
+            "format": "mso_mdoc",
+            "meta": { "doctype_value": "eu.europa.ec.eudi.pid.1" },
+        }))
+        .unwrap();
+
+        let request_match = find_match(&query, &mdoc, ClaimPathMode::Lenient).unwrap();
+
+        assert!(request_match.doctype_mismatch);
+        assert!(request_match.requested_fields.is_empty());
+        assert!(request_match.missing_fields.is_empty());
+    }
+
+    #[test]
+    fn claims_in_every_claim_set_are_required() {
+        let query: DcqlCredentialQuery = serde_json::from_value(json!({
+            "id": "mdl",
+            "format": "mso_mdoc",
+            "meta": { "doctype_value": "org.iso.18013.5.1.mDL" },
+            "claims": [
+                { "id": "name", "path": ["org.iso.18013.5.1", "family_name"] },
+                { "id": "place", "path": ["org.iso.18013.5.1", "birth_place"] }
+            ],
+            "claim_sets": [["name", "place"], ["name"]]
+        }))
+        .unwrap();
+
+        // Were the claim sets dropped by the typed query, every claim would be required.
+        assert_eq!(required_claim_ids(&query), Some(vec!["name".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn strict_mode_rejects_three_segment_path() {
+        let (_, _, mdoc) = test_mdl().await;
+
+        let error = find_match(&query_with_nested_path(), &mdoc, ClaimPathMode::Strict)
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("vehicle_category_code"), "{error}");
+        assert!(!error.contains("family_name"), "{error}");
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use ssi::claims::JwsBuf;
+use x509_cert::{
+    der::{
+        asn1::{Any, PrintableStringRef, Utf8StringRef},
+        oid::{db::rfc4519, AssociatedOid},
+        Decode,
+    },
+    ext::pkix::{name::GeneralName, SubjectAltName},
+    Certificate,
+};
+
+/// The subject of the certificate a verifier signed its request with.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct VerifierCertificate {
+    pub common_name: Option<String>,
+    pub organization: Option<String>,
+    pub subject_alternative_names: Vec<String>,
+}
+
+impl VerifierCertificate {
+    /// Parse the leaf certificate of the `x5c` header of a request JWT.
+    ///
+    /// This does not verify the certificate or the JWT, and must only be used once the request
+    /// has been verified.
+    pub fn from_request_jwt(request_jwt: &str) -> Result<Self> {
+        let jws = JwsBuf::new(request_jwt.to_string()).context("failed to decode JWS")?;
+        let jwt = jws.into_decoded().context("failed to decode JWT")?;
+        let leaf = jwt
+            .header()
+            .x509_certificate_chain
+            .as_ref()
+            .and_then(|x5c| x5c.first())
+            .context("request JWT has no x5c header")?;
+        let der = BASE64_STANDARD
+            .decode(leaf)
+            .context("x5c certificate is not valid base64")?;
+        let certificate =
+            Certificate::from_der(&der).context("x5c certificate could not be parsed")?;
+
+        Self::from_certificate(&certificate)
+    }
+
+    fn from_certificate(certificate: &Certificate) -> Result<Self> {
+        let subject_attribute = |oid| {
+            certificate
+                .tbs_certificate
+                .subject
+                .0
+                .iter()
+                .flat_map(|rdn| rdn.0.iter())
+                .find(|attribute| attribute.oid == oid)
+                .and_then(|attribute| directory_string(&attribute.value))
+        };
+
+        let mut subject_alternative_names = vec![];
+        for extension in certificate.tbs_certificate.extensions.iter().flatten() {
+            if extension.extn_id != SubjectAltName::OID {
+                continue;
+            }
+            let san = SubjectAltName::from_der(extension.extn_value.as_bytes())
+                .context("unable to parse 'subject alternative name' extension")?;
+            subject_alternative_names.extend(san.0.into_iter().filter_map(|name| match name {
+                GeneralName::DnsName(name) => Some(name.to_string()),
+                GeneralName::UniformResourceIdentifier(uri) => Some(uri.to_string()),
+                GeneralName::Rfc822Name(email) => Some(email.to_string()),
+                _ => None,
+            }));
+        }
+
+        Ok(Self {
+            common_name: subject_attribute(rfc4519::CN),
+            organization: subject_attribute(rfc4519::O),
+            subject_alternative_names,
+        })
+    }
+}
+
+fn directory_string(value: &Any) -> Option<String> {
+    Utf8StringRef::try_from(value)
+        .map(|s| s.to_string())
+        .or_else(|_| PrintableStringRef::try_from(value).map(|s| s.to_string()))
+        .ok()
+}
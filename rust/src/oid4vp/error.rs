@@ -74,6 +74,10 @@ pub enum OID4VPError {
     SelectiveDisclosureInvalidFields,
     #[error("Selected fields cannot be empty")]
     SelectiveDisclosureEmptySelection,
+    #[error("The verifier does not accept a presentation format the credential can be presented in: {0}")]
+    FormatNotAccepted(String),
+    #[error("Requested fields are not supported for the credential format: {0}")]
+    UnsupportedCredentialFormat(String),
     #[error("Failed to initialize metadata: {0}")]
     Debug(String),
 }
@@ -1,7 +1,9 @@
 use super::error::OID4VPError;
+use super::http_client::Oid4vpHttpClient;
 use super::permission_request::*;
 use super::presentation::PresentationSigner;
 use crate::credential::*;
+use crate::crypto::KeyStore;
 use crate::vdc_collection::VdcCollection;
 
 use std::collections::HashMap;
@@ -72,7 +74,7 @@ pub struct Holder {
     pub(crate) metadata: WalletMetadata,
 
     /// HTTP Request Client
-    pub(crate) client: openid4vp::core::util::ReqwestClient,
+    pub(crate) client: Oid4vpHttpClient,
 
     /// A list of trusted DIDs.
     pub(crate) trusted_dids: Vec<String>,
@@ -85,19 +87,25 @@ pub struct Holder {
 
     /// Optional context map for resolving specific contexts
     pub(crate) context_map: Option<HashMap<String, String>>,
+
+    /// Key store holding the device keys of mdocs, required to present mdocs.
+    pub(crate) key_store: Option<Arc<dyn KeyStore>>,
 }
 
 #[uniffi::export(async_runtime = "tokio")]
 impl Holder {
     /// Uses VDC collection to retrieve the credentials for a given presentation definition.
-    #[uniffi::constructor]
+    ///
+    /// The `key_store` holds the device keys of mdocs, and is required to present them.
+    #[uniffi::constructor(default(key_store = None))]
     pub async fn new(
         vdc_collection: Arc<VdcCollection>,
         trusted_dids: Vec<String>,
         signer: Box<dyn PresentationSigner>,
         context_map: Option<HashMap<String, String>>,
+        key_store: Option<Arc<dyn KeyStore>>,
     ) -> Result<Arc<Self>, OID4VPError> {
-        let client = openid4vp::core::util::ReqwestClient::new()
+        let client = Oid4vpHttpClient::new()
             .map_err(|e| OID4VPError::HttpClientInitialization(format!("{e:?}")))?;
 
         Ok(Arc::new(Self {
@@ -108,6 +116,7 @@ impl Holder {
             provided_credentials: None,
             signer: Arc::new(signer),
             context_map,
+            key_store,
         }))
     }
 
@@ -116,14 +125,15 @@ impl Holder {
     ///
     /// This constructor will use the provided credentials for the presentation,
     /// instead of searching for credentials in the VDC collection.
-    #[uniffi::constructor]
+    #[uniffi::constructor(default(key_store = None))]
     pub async fn new_with_credentials(
         provided_credentials: Vec<Arc<ParsedCredential>>,
         trusted_dids: Vec<String>,
         signer: Box<dyn PresentationSigner>,
         context_map: Option<HashMap<String, String>>,
+        key_store: Option<Arc<dyn KeyStore>>,
     ) -> Result<Arc<Self>, OID4VPError> {
-        let client = openid4vp::core::util::ReqwestClient::new()
+        let client = Oid4vpHttpClient::new()
             .map_err(|e| OID4VPError::HttpClientInitialization(format!("{e:?}")))?;
 
         Ok(Arc::new(Self {
@@ -134,6 +144,7 @@ impl Holder {
             provided_credentials: Some(provided_credentials),
             signer: Arc::new(signer),
             context_map,
+            key_store,
         }))
     }
 
@@ -198,7 +209,7 @@ impl Holder {
         // Insert support for the JSON-LD format.
         metadata.vp_formats_supported_mut().0.insert(
             ClaimFormatDesignation::LdpVp,
-            ClaimFormatPayload::ProofType(vec!["ecdsa-rdfc-2019".into()]),
+            ClaimFormatPayload::ProofType(vec!["ecdsa-rdfc-2019".into(), "eddsa-rdfc-2022".into()]),
         );
 
         // Insert support for JwtVpJson format.
@@ -234,7 +245,7 @@ impl Holder {
             None => match &self.vdc_collection {
                 None => vec![],
                 Some(vdc_collection) => {
-                    futures::stream::iter(vdc_collection.all_entries().await?.into_iter())
+                    futures::stream::iter(vdc_collection.all_ids().await?.into_iter())
                         .filter_map(|id| async move {
                             vdc_collection
                                 .get(id)
@@ -284,23 +295,6 @@ impl Holder {
             ));
         }
 
-        // TODO: Add full support for limit_disclosure, probably this should be thrown at OID4VP
-        if presentation_definition
-            .input_descriptors()
-            .iter()
-            .any(|id| {
-                id.constraints
-                    .limit_disclosure()
-                    .is_some_and(|ld| matches!(ld, ConstraintsLimitDisclosure::Required))
-            })
-        {
-            log::debug!("Limit disclosure required for input descriptor.");
-
-            return Err(OID4VPError::LimitDisclosure(
-                "Limit disclosure required for input descriptor.".to_string(),
-            ));
-        }
-
         let credentials = credentials
             .into_iter()
             .map(|c| {
@@ -326,6 +320,7 @@ impl Holder {
             request,
             self.signer.clone(),
             self.context_map.clone(),
+            self.key_store.clone(),
         ))
     }
 }
@@ -395,7 +390,7 @@ impl RequestVerifier for Holder {
 }
 
 impl OID4VPWallet for Holder {
-    type HttpClient = openid4vp::core::util::ReqwestClient;
+    type HttpClient = Oid4vpHttpClient;
 
     fn http_client(&self) -> &Self::HttpClient {
         &self.client
@@ -527,6 +522,7 @@ pub(crate) mod tests {
             vec!["did:web:localhost%3A3000:oid4vp:client".into()],
             Box::new(key_signer),
             None,
+            None,
         )
         .await?;
 
@@ -592,6 +588,7 @@ pub(crate) mod tests {
             vec![],
             Box::new(key_signer),
             Some(context),
+            None,
         )
         .await
         .expect("Failed to create oid4vp holder");
@@ -638,6 +635,7 @@ pub(crate) mod tests {
             vec![],
             Box::new(key_signer),
             Some(default_ld_json_context()),
+            None,
         )
         .await?;
 
@@ -711,6 +709,7 @@ pub(crate) mod tests {
             vec!["did:web:localhost%3A3000:oid4vp:client".into()],
             Box::new(key_signer),
             None,
+            None,
         )
         .await?;
 
@@ -779,6 +778,7 @@ pub(crate) mod tests {
             vec!["did:web:localhost%3A3000:oid4vp:client".into()],
             Box::new(signer),
             Some(default_ld_json_context()),
+            None,
         )
         .await?;
 
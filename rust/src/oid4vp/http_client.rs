@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use openid4vp::core::util::AsyncHttpClient;
+use url::Url;
+
+use crate::proxy::{self, HttpProxyError};
+
+/// HTTP client used to resolve OID4VP request objects and submit responses.
+///
+/// Unlike `openid4vp::core::util::ReqwestClient`, this client honours the
+/// proxy configured with [proxy::configure_http_proxy].
+#[derive(Debug, Clone)]
+pub struct Oid4vpHttpClient(reqwest::Client);
+
+impl AsRef<reqwest::Client> for Oid4vpHttpClient {
+    fn as_ref(&self) -> &reqwest::Client {
+        &self.0
+    }
+}
+
+impl Oid4vpHttpClient {
+    pub fn new() -> Result<Self, HttpProxyError> {
+        Self::from_builder(proxy::client_builder()?)
+    }
+
+    pub fn with_proxy(proxy_url: Option<&Url>) -> Result<Self, HttpProxyError> {
+        Self::from_builder(proxy::client_builder_with_proxy(proxy_url)?)
+    }
+
+    fn from_builder(builder: reqwest::ClientBuilder) -> Result<Self, HttpProxyError> {
+        builder
+            .build()
+            .map(Self)
+            .map_err(|e| HttpProxyError::InvalidProxy(format!("{e:?}")))
+    }
+}
+
+#[async_trait]
+impl AsyncHttpClient for Oid4vpHttpClient {
+    async fn execute(
+        &self,
+        request: http::Request<Vec<u8>>,
+    ) -> anyhow::Result<http::Response<Vec<u8>>> {
+        let response = self.0.execute(request.try_into()?).await?;
+
+        let mut builder = http::Response::builder()
+            .status(response.status())
+            .version(response.version());
+
+        for (name, value) in response.headers() {
+            builder = builder.header(name, value);
+        }
+
+        Ok(builder.body(response.bytes().await?.to_vec())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use openid4vp::core::util::AsyncHttpClient;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn requests_are_sent_through_proxy() {
+        let proxy = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/request-object"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("proxied"))
+            .expect(1)
+            .mount(&proxy)
+            .await;
+
+        let proxy_url: Url = proxy.uri().parse().unwrap();
+        let client = Oid4vpHttpClient::with_proxy(Some(&proxy_url)).unwrap();
+
+        let request = http::Request::get("http://verifier.invalid/request-object")
+            .body(vec![])
+            .unwrap();
+        let response = client.execute(request).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.body(), b"proxied");
+    }
+}
@@ -128,7 +128,12 @@ pub fn build_jwe(
         jwe_header.set_key_id(kid);
     }
 
-    let encrypter: EcdhEsJweEncrypter<NistP256> = josekit::jwe::ECDH_ES.encrypter_from_jwk(jwk)?;
+    let algorithm = match alg {
+        "ECDH-ES" => josekit::jwe::ECDH_ES,
+        "ECDH-ES+A256KW" => josekit::jwe::ECDH_ES_A256KW,
+        _ => bail!("unsupported encryption alg: {alg}"),
+    };
+    let encrypter: EcdhEsJweEncrypter<NistP256> = algorithm.encrypter_from_jwk(jwk)?;
 
     let jwe = encode_with_encrypter(payload, &jwe_header, &encrypter)?;
     Ok(jwe)
@@ -20,7 +20,6 @@ use openid4vp::{
         },
         metadata::WalletMetadata,
         presentation_definition::PresentationDefinition,
-        util::ReqwestClient,
     },
     wallet::Wallet as OpenID4VPWallet,
 };
@@ -31,7 +30,12 @@ use ssi::crypto::rand::{thread_rng, Rng};
 use url::Url;
 use uuid::Uuid;
 
-use crate::{credential::mdoc::Mdoc, crypto::KeyStore};
+use crate::{
+    credential::mdoc::Mdoc,
+    crypto::{CryptoError, KeyStore},
+};
+
+use super::http_client::Oid4vpHttpClient;
 
 /// Handler for OpenID4VP requests according to the profile in ISO/IEC 18013-7 Annex B.
 ///
@@ -39,7 +43,7 @@ use crate::{credential::mdoc::Mdoc, crypto::KeyStore};
 #[derive(uniffi::Object, Clone)]
 pub struct OID4VP180137 {
     credentials: Vec<Arc<Mdoc>>,
-    http_client: ReqwestClient,
+    http_client: Oid4vpHttpClient,
     keystore: Arc<dyn KeyStore>,
     metadata: WalletMetadata,
 }
@@ -63,19 +67,23 @@ pub enum OID4VP180137Error {
     Initialization(String),
     InvalidRequest(String),
     ResponseProcessing(String),
+    UserAuthenticationRequired,
+    UserAuthenticationFailed(String),
 }
 
 impl fmt::Display for OID4VP180137Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                OID4VP180137Error::Initialization(s) => s,
-                OID4VP180137Error::InvalidRequest(s) => s,
-                OID4VP180137Error::ResponseProcessing(s) => s,
+        match self {
+            OID4VP180137Error::Initialization(s) => write!(f, "{s}"),
+            OID4VP180137Error::InvalidRequest(s) => write!(f, "{s}"),
+            OID4VP180137Error::ResponseProcessing(s) => write!(f, "{s}"),
+            OID4VP180137Error::UserAuthenticationRequired => {
+                write!(f, "user authentication is required to sign the response")
             }
-        )
+            OID4VP180137Error::UserAuthenticationFailed(s) => {
+                write!(f, "user authentication failed: {s}")
+            }
+        }
     }
 }
 
@@ -89,7 +97,15 @@ impl OID4VP180137Error {
     }
 
     fn response_processing(error: anyhow::Error) -> Self {
-        Self::ResponseProcessing(format!("{error:#}"))
+        // Surface user authentication failures of the device key separately, so that the
+        // wallet can prompt the user again rather than report a generic failure.
+        match error.downcast_ref::<CryptoError>() {
+            Some(CryptoError::UserAuthenticationRequired) => Self::UserAuthenticationRequired,
+            Some(CryptoError::UserAuthenticationFailed(reason)) => {
+                Self::UserAuthenticationFailed(reason.clone())
+            }
+            _ => Self::ResponseProcessing(format!("{error:#}")),
+        }
     }
 }
 
@@ -103,8 +119,8 @@ impl OID4VP180137 {
         Ok(Self {
             credentials,
             keystore,
-            http_client: openid4vp::core::util::ReqwestClient::new()
-                .map_err(OID4VP180137Error::initialization)?,
+            http_client: Oid4vpHttpClient::new()
+                .map_err(|e| OID4VP180137Error::initialization(e.into()))?,
             metadata: default_metadata(),
         })
     }
@@ -219,7 +235,7 @@ impl InProgressRequest180137 {
 }
 
 impl OpenID4VPWallet for OID4VP180137 {
-    type HttpClient = ReqwestClient;
+    type HttpClient = Oid4vpHttpClient;
 
     fn metadata(&self) -> &WalletMetadata {
         &self.metadata
@@ -6,11 +6,13 @@ use isomdl::{
     cbor,
     cose::sign1::PreparedCoseSign1,
     definitions::{
+        device_key::cose_key::OKPCurve,
         device_response::DocumentErrorCode,
         device_signed::{DeviceAuthentication, DeviceNamespaces},
         helpers::{ByteStr, NonEmptyMap, NonEmptyVec, Tag24},
         session::SessionTranscript as SessionTranscriptTrait,
-        DeviceResponse, DeviceSigned, Document, IssuerSigned, IssuerSignedItem,
+        CoseKey, DeviceResponse, DeviceSigned, Document, EC2Curve, IssuerSigned, IssuerSignedItem,
+        Mso,
     },
 };
 use openid4vp::core::{
@@ -22,10 +24,10 @@ use serde_json::Value as Json;
 use sha2::{Digest, Sha256};
 use ssi::claims::cose::coset::{self, CoseSign1Builder};
 
-use crate::crypto::KeyStore;
+use crate::crypto::{CryptoCurveUtils, KeyStore};
 
 use super::{
-    requested_values::{FieldId180137, FieldMap},
+    requested_values::{FieldId180137, FieldMap, MissingField180137},
     Mdoc,
 };
 
@@ -78,6 +80,47 @@ impl Handover {
     }
 }
 
+/// The handover of OpenID4VP 1.0 (Appendix B.2.6.1), for responses that are not encrypted to
+/// the verifier and are therefore not bound to an `mdoc_generated_nonce`.
+///
+/// The handover is bound to the `response_uri` of the request, or to its `redirect_uri` for
+/// response modes that redirect to the verifier, such as `fragment`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenID4VPHandover(String, ByteStr);
+
+impl OpenID4VPHandover {
+    pub fn new(request: &AuthorizationRequestObject) -> Result<Self> {
+        let client_id = request.client_id().context("missing client_id")?.0.clone();
+        let response_uri = match request.get::<RawResponseUri>() {
+            Some(response_uri) => response_uri.parsing_error()?.0,
+            None => {
+                request
+                    .get::<RawRedirectUri>()
+                    .context("missing response_uri and redirect_uri")?
+                    .parsing_error()?
+                    .0
+            }
+        };
+        let nonce = request.nonce().to_string();
+
+        // The JWK thumbprint is null, as the response is not encrypted.
+        let handover_info = Cbor::Array(vec![
+            Cbor::Text(client_id),
+            Cbor::Text(nonce),
+            Cbor::Null,
+            Cbor::Text(response_uri),
+        ]);
+        tracing::debug!("handover_info CBOR: {handover_info:#?}");
+
+        let handover_info_hash = Sha256::digest(cbor::to_vec(&handover_info)?).to_vec();
+
+        Ok(Self(
+            "OpenID4VPHandover".to_string(),
+            handover_info_hash.into(),
+        ))
+    }
+}
+
 impl<H> SessionTranscript<H> {
     fn new(handover: H) -> Self {
         Self(Cbor::Null, Cbor::Null, handover)
@@ -111,11 +154,37 @@ impl From<RawResponseUri> for Json {
     }
 }
 
+/// Unprocessed redirect_uri, for the same reasons as [RawResponseUri].
+#[derive(Debug, Clone)]
+pub struct RawRedirectUri(pub String);
+
+impl TypedParameter for RawRedirectUri {
+    const KEY: &'static str = "redirect_uri";
+}
+
+impl TryFrom<Json> for RawRedirectUri {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Json) -> std::result::Result<Self, Self::Error> {
+        let Json::String(uri) = value else {
+            bail!("unexpected type")
+        };
+
+        Ok(Self(uri))
+    }
+}
+
+impl From<RawRedirectUri> for Json {
+    fn from(value: RawRedirectUri) -> Self {
+        Json::String(value.0)
+    }
+}
+
 pub fn prepare_response<H: Serialize + DeserializeOwned + Debug>(
     key_store: Arc<dyn KeyStore>,
     credential: &Mdoc,
     approved_fields: Vec<FieldId180137>,
-    missing_fields: &BTreeMap<String, String>,
+    missing_fields: &[MissingField180137],
     mut field_map: FieldMap,
     handover: H,
 ) -> Result<DeviceResponse> {
@@ -148,34 +217,13 @@ pub fn prepare_response<H: Serialize + DeserializeOwned + Debug>(
     let device_namespaces = Tag24::new(DeviceNamespaces::new())
         .context("failed to encode device namespaces as CBOR")?;
 
-    let session_transcript = SessionTranscript::new(handover);
-
-    let device_authentication_payload = Tag24::new(DeviceAuthentication::new(
-        session_transcript,
+    let algorithm = device_key_algorithm(&mdoc.mso)?;
+    let prepared_cose_sign1 = prepare_device_signature(
+        handover,
         mdoc.mso.doc_type.clone(),
         device_namespaces.clone(),
-    ))
-    .context("failed to encode device auth payload as CBOR")?;
-
-    tracing::debug!("device authentication payload: {device_authentication_payload:?}");
-
-    let device_authentication_bytes = isomdl::cbor::to_vec(&device_authentication_payload)
-        .context("failed to encode device auth payload as CBOR bytes")?;
-
-    tracing::debug!("device authentication payload bytes: {device_authentication_bytes:?}");
-
-    let header = coset::HeaderBuilder::new()
-        .algorithm(coset::iana::Algorithm::ES256)
-        .build();
-
-    let cose_sign1_builder = CoseSign1Builder::new().protected(header);
-    let prepared_cose_sign1 = PreparedCoseSign1::new(
-        cose_sign1_builder,
-        Some(&device_authentication_bytes),
-        None,
-        false,
-    )
-    .context("failed to prepare CoseSign1")?;
+        algorithm,
+    )?;
 
     let device_key = key_store
         .get_signing_key(credential.key_alias())
@@ -185,6 +233,14 @@ pub fn prepare_response<H: Serialize + DeserializeOwned + Debug>(
         .sign(prepared_cose_sign1.signature_payload().to_vec())
         .context("failed to generate device_signature")?;
 
+    // The signature encoding of a SigningKey is unknown, but COSE requires raw signatures.
+    let signature = match algorithm {
+        coset::iana::Algorithm::ES256 => CryptoCurveUtils::secp256r1()
+            .ensure_raw_fixed_width_signature_encoding(signature)
+            .context("unsupported device signature encoding")?,
+        _ => signature,
+    };
+
     let device_signature = prepared_cose_sign1.finalize(signature);
 
     let device_auth = isomdl::definitions::DeviceAuth::DeviceSignature(device_signature);
@@ -195,7 +251,12 @@ pub fn prepare_response<H: Serialize + DeserializeOwned + Debug>(
     };
 
     let mut errors: BTreeMap<String, NonEmptyMap<String, DocumentErrorCode>> = BTreeMap::new();
-    for (namespace, element_identifier) in missing_fields {
+    for MissingField180137 {
+        namespace,
+        element_identifier,
+        ..
+    } in missing_fields
+    {
         if let Some(elems) = errors.get_mut(namespace) {
             elems.insert(
                 element_identifier.clone(),
@@ -231,3 +292,62 @@ pub fn prepare_response<H: Serialize + DeserializeOwned + Debug>(
 
     Ok(response)
 }
+
+/// Prepare the `DeviceSignature` over the `DeviceAuthentication` of a document, to be finalized
+/// with the device key signature of [PreparedCoseSign1::signature_payload].
+pub(crate) fn prepare_device_signature<H: Serialize + DeserializeOwned + Debug>(
+    handover: H,
+    doc_type: String,
+    device_namespaces: Tag24<DeviceNamespaces>,
+    algorithm: coset::iana::Algorithm,
+) -> Result<PreparedCoseSign1> {
+    let session_transcript = SessionTranscript::new(handover);
+
+    let device_authentication_payload = Tag24::new(DeviceAuthentication::new(
+        session_transcript,
+        doc_type,
+        device_namespaces,
+    ))
+    .context("failed to encode device auth payload as CBOR")?;
+
+    tracing::debug!("device authentication payload: {device_authentication_payload:?}");
+
+    let device_authentication_bytes = isomdl::cbor::to_vec(&device_authentication_payload)
+        .context("failed to encode device auth payload as CBOR bytes")?;
+
+    tracing::debug!("device authentication payload bytes: {device_authentication_bytes:?}");
+
+    let header = coset::HeaderBuilder::new().algorithm(algorithm).build();
+
+    let cose_sign1_builder = CoseSign1Builder::new().protected(header);
+    PreparedCoseSign1::new(
+        cose_sign1_builder,
+        Some(&device_authentication_bytes),
+        None,
+        false,
+    )
+    .context("failed to prepare CoseSign1")
+}
+
+/// The COSE algorithm of signatures by the device key of an MSO.
+pub(crate) fn device_key_algorithm(mso: &Mso) -> Result<coset::iana::Algorithm> {
+    match &mso.device_key_info.device_key {
+        CoseKey::EC2 {
+            crv: EC2Curve::P256,
+            ..
+        } => Ok(coset::iana::Algorithm::ES256),
+        CoseKey::EC2 {
+            crv: EC2Curve::P384,
+            ..
+        } => Ok(coset::iana::Algorithm::ES384),
+        CoseKey::EC2 {
+            crv: EC2Curve::P521,
+            ..
+        } => Ok(coset::iana::Algorithm::ES512),
+        CoseKey::OKP {
+            crv: OKPCurve::Ed25519,
+            ..
+        } => Ok(coset::iana::Algorithm::EdDSA),
+        key => bail!("unsupported device key: {key:?}"),
+    }
+}
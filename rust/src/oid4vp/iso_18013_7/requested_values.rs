@@ -2,9 +2,7 @@ use std::{collections::BTreeMap, sync::Arc};
 
 use anyhow::{bail, Result};
 use ciborium::Value as Cbor;
-use isomdl::definitions::{
-    device_request::NameSpace, helpers::NonEmptyMap, issuer_signed::IssuerSignedItemBytes,
-};
+use isomdl::definitions::{device_request::NameSpace, issuer_signed::IssuerSignedItemBytes};
 use openid4vp::core::{
     input_descriptor::InputDescriptor, presentation_definition::PresentationDefinition,
 };
@@ -12,7 +10,10 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as Json;
 use uuid::Uuid;
 
-use crate::credential::mdoc::Mdoc;
+use crate::{
+    credential::mdoc::Mdoc,
+    mdl::age_over::{age_over_attestations, limit_age_over_attestations, AGE_OVER_NAMESPACE},
+};
 
 #[derive(Debug, Clone, uniffi::Object)]
 /// A viable match for the credential request.
@@ -20,7 +21,29 @@ pub struct RequestMatch180137 {
     pub credential_id: Uuid,
     pub field_map: FieldMap,
     pub requested_fields: Vec<RequestedField180137>,
-    pub missing_fields: BTreeMap<String, String>,
+    pub missing_fields: Vec<MissingField180137>,
+    /// Whether the request is for another doctype than that of the credential, even if it
+    /// requests no element.
+    pub doctype_mismatch: bool,
+}
+
+/// Why a requested element cannot be returned from a credential.
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum MissingFieldReason {
+    /// The credential has no element in the requested namespace.
+    NamespaceAbsent,
+    /// The credential has the requested namespace, but not the requested element.
+    ElementAbsent,
+    /// The request is for another doctype than that of the credential.
+    DoctypeMismatch,
+}
+
+/// A requested element that is not returned, with the reason why.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct MissingField180137 {
+    pub namespace: String,
+    pub element_identifier: String,
+    pub reason: MissingFieldReason,
 }
 
 uniffi::custom_newtype!(FieldId180137, String);
@@ -50,6 +73,14 @@ impl RequestMatch180137 {
     pub fn requested_fields(&self) -> Vec<RequestedField180137> {
         self.requested_fields.clone()
     }
+
+    pub fn missing_fields(&self) -> Vec<MissingField180137> {
+        self.missing_fields.clone()
+    }
+
+    pub fn doctype_mismatch(&self) -> bool {
+        self.doctype_mismatch
+    }
 }
 
 pub fn parse_request<'l, C>(
@@ -94,7 +125,7 @@ fn find_match(input_descriptor: &InputDescriptor, credential: &Mdoc) -> Result<R
         bail!("the request was not for an mDL: {}", input_descriptor.id)
     }
 
-    let mut age_over_mapping = calculate_age_over_mapping(&mdoc.namespaces);
+    let mut age_over_mapping = age_over_attestations(&mdoc.namespaces);
 
     let mut field_map = FieldMap::new();
 
@@ -119,7 +150,7 @@ fn find_match(input_descriptor: &InputDescriptor, credential: &Mdoc) -> Result<R
                                         // If there are other age attestations that this element
                                         // should respond to, insert virtual elements for each
                                         // of those mappings.
-                                        if namespace == "org.iso.18013.5.1" {
+                                        if namespace == AGE_OVER_NAMESPACE {
                                             age_over_mapping.remove(element_identifier)
                                         } else {
                                             None
@@ -146,7 +177,7 @@ fn find_match(input_descriptor: &InputDescriptor, credential: &Mdoc) -> Result<R
     );
 
     let mut requested_fields = BTreeMap::new();
-    let mut missing_fields = BTreeMap::new();
+    let mut missing_fields = Vec::new();
 
     let elements_json_ref = &elements_json;
 
@@ -208,7 +239,16 @@ fn find_match(input_descriptor: &InputDescriptor, credential: &Mdoc) -> Result<R
             None => {
                 let json_path = field.path.as_ref()[0].to_string();
                 if let Some((namespace, element_identifier)) = split_json_path(&json_path) {
-                    missing_fields.insert(namespace, element_identifier);
+                    let reason = if mdoc.namespaces.contains_key(&namespace) {
+                        MissingFieldReason::ElementAbsent
+                    } else {
+                        MissingFieldReason::NamespaceAbsent
+                    };
+                    missing_fields.push(MissingField180137 {
+                        namespace,
+                        element_identifier,
+                        reason,
+                    });
                 } else {
                     tracing::warn!("invalid JSON path expression: {json_path}")
                 }
@@ -216,26 +256,21 @@ fn find_match(input_descriptor: &InputDescriptor, credential: &Mdoc) -> Result<R
         }
     }
 
-    let mut seen_age_over_attestations = 0;
-    let requested_fields = requested_fields
-        .into_values()
-        // According to the rules in ISO/IEC 18013-5 Section 7.2.5, don't respond with more
-        // than 2 age over attestations.
-        .filter(|field| {
-            if field.displayable_name.starts_with("age_over_") {
-                seen_age_over_attestations += 1;
-                seen_age_over_attestations < 3
-            } else {
-                true
-            }
-        })
-        .collect();
+    // According to the rules in ISO/IEC 18013-5 Section 7.2.5, don't respond with more
+    // than 2 age over attestations.
+    let requested_fields = limit_age_over_attestations(requested_fields.into_values(), |field| {
+        field_map
+            .get(&field.id)
+            .map(|(_, item)| item.as_ref().element_identifier.as_str())
+            .unwrap_or_default()
+    });
 
     Ok(RequestMatch180137 {
         credential_id: credential.id(),
         field_map,
         requested_fields,
         missing_fields,
+        doctype_mismatch: false,
     })
 }
 
@@ -270,144 +305,14 @@ fn cbor_to_string_inner(cbor: &Cbor, allowed_depth: u8) -> Option<String> {
     }
 }
 
-fn age_from_str(s: &str) -> Option<u8> {
-    let mut chars = s.chars();
-    let first = match chars.next() {
-        Some(d @ '0'..='9') => d,
-        _ => return None,
-    };
-    match chars.next() {
-        Some(_d @ '0'..='9') => (),
-        _ => return None,
-    };
-    if chars.next().is_some() {
-        return None;
-    }
-
-    if first == '0' {
-        s[1..].parse().ok()
-    } else {
-        s.parse().ok()
-    }
-}
-
-pub fn calculate_age_over_mapping(
-    namespaces: &NonEmptyMap<String, NonEmptyMap<String, IssuerSignedItemBytes>>,
-) -> BTreeMap<String, Vec<String>> {
-    let mut age_over_x_elements: Vec<(u8, bool)> = namespaces
-        .iter()
-        .filter(|(ns, _)| *ns == "org.iso.18013.5.1")
-        .flat_map(|(_, elems)| elems.iter())
-        .filter_map(|(id, elem)| {
-            id.strip_prefix("age_over_")
-                .and_then(age_from_str)
-                .and_then(|age| elem.as_ref().element_value.as_bool().map(|b| (age, b)))
-        })
-        .collect();
-
-    age_over_x_elements.sort_by(|a, b| a.0.cmp(&b.0));
-
-    // Transform this mapping from (requested_age -> responded_age) into
-    // (responded_age -> requested_age[]]) so that virtual elements for every possible requested_age
-    // can be constructed.
-    reverse_mapping(age_over_x_elements)
-        .into_iter()
-        .map(|(request, response)| {
-            (
-                format!("age_over_{request:02}"),
-                format!("age_over_{response:02}"),
-            )
-        })
-        .fold(BTreeMap::new(), |mut acc, (request, response)| {
-            if let Some(arr) = acc.get_mut(&response) {
-                arr.push(request);
-            } else {
-                acc.insert(response, vec![request]);
-            }
-            acc
-        })
-}
-
-/// Create a reverse mapping of age_over attestation responses, where the key is the requested
-/// age and the value is the responding age.
-///
-/// For example, if we had "age_over_18: true", "age_over_21: true", "age_over_30: false" and
-/// "age_over_60: false", then we would want to construct the following mapping:
-///
-/// 0..=18 -> 18 (requests for age over 0-18 are responded with age_over_18: true)
-/// 19..=21 -> 21 (requests for age over 19-21 are responded with age_over_21: true)
-/// 22..=29 -> None (requests for age over 22-29 have no response)
-/// 30..=59 -> 30 (requests for age over 30-59 are responded with age_over_30: false)
-/// 60..=99 -> 30 (requests for age over 30-59 are responded with age_over_30: false)
-///
-/// This follows the rules defined in ISO/IEC 18013-5 Section 7.2.5.
-fn reverse_mapping(age_over_x_elements: Vec<(u8, bool)>) -> BTreeMap<u8, u8> {
-    let mut reverse_mapping = BTreeMap::<u8, u8>::new();
-
-    // Starting with the lowest age_over_XX: false claims.
-    //
-    // Using the above example, before the first iteration the mapping will be:
-    // 0..=99 -> None
-    //
-    // After the first iteration, the mapping will be:
-    // 0..=29 -> None
-    // 30..=99 -> 30
-    //
-    // After the second and final iteration, the mapping will be:
-    // 0..=29 -> None
-    // 30..=59 -> 30
-    // 60..=99 -> 60
-    for age in age_over_x_elements
-        .iter()
-        .filter_map(|(age, b)| if !b { Some(age) } else { None })
-    {
-        for xx in *age..=99 {
-            reverse_mapping.insert(xx, *age);
-        }
-    }
-
-    // Starting with the highest age_over_XX: true claims.
-    //
-    // Using the above example, before the first iteration the mapping will be:
-    // 0..=29 -> None
-    // 30..=59 -> 30
-    // 60..=99 -> 60
-    //
-    // After the first iteration, the mapping will be:
-    // 0..=21 -> 21
-    // 22..=29 -> None
-    // 30..=59 -> 30
-    // 60..=99 -> 60
-    //
-    // After the second and final iteration, the mapping will be:
-    // 0..=18 -> 18
-    // 19..=21 -> 21
-    // 22..=29 -> None
-    // 30..=59 -> 30
-    // 60..=99 -> 60
-    for age in age_over_x_elements
-        .iter()
-        .rev()
-        .filter_map(|(age, b)| if *b { Some(age) } else { None })
-    {
-        for xx in 0..=*age {
-            reverse_mapping.insert(xx, *age);
-        }
-    }
-
-    reverse_mapping
-}
-
 #[cfg(test)]
 mod test {
-    use std::{fs::File, sync::Arc};
+    use std::fs::File;
 
     use openid4vp::core::presentation_definition::PresentationDefinition;
     use rstest::rstest;
 
-    use crate::crypto::{KeyAlias, RustTestKeyManager};
-
-    use super::{parse_request, reverse_mapping};
+    use super::parse_request;
 
     #[rstest]
     #[case::valid("tests/examples/18013_7_presentation_definition.json", 0)]
@@ -417,16 +322,8 @@ mod test {
         #[case] filepath: &str,
         #[case] missing_fields: usize,
     ) {
-        let key_manager = Arc::new(RustTestKeyManager::default());
-        let key_alias = KeyAlias("".to_string());
-
-        key_manager
-            .generate_p256_signing_key(key_alias.clone())
-            .await
-            .unwrap();
-
-        let credentials =
-            vec![crate::mdl::util::generate_test_mdl(key_manager, key_alias).unwrap()];
+        let (_, _, mdoc) = crate::mdl::util::test_mdl().await;
+        let credentials = vec![mdoc];
 
         let presentation_definition: PresentationDefinition =
             serde_json::from_reader(File::open(filepath).unwrap()).unwrap();
@@ -440,23 +337,6 @@ mod test {
         assert_eq!(request.missing_fields.len(), missing_fields);
     }
 
-    #[test]
-    fn age_attestation_mapping() {
-        let reverse_mapping =
-            reverse_mapping(vec![(18, true), (21, true), (30, false), (60, false)]);
-        assert_eq!(reverse_mapping.len(), 92);
-
-        reverse_mapping
-            .into_iter()
-            .for_each(|(request, response)| match request {
-                0..=18 => assert_eq!(response, 18),
-                19..=21 => assert_eq!(response, 21),
-                30..=59 => assert_eq!(response, 30),
-                60..=99 => assert_eq!(response, 60),
-                _ => panic!("unexpected value"),
-            })
-    }
-
     #[rstest]
     #[case::valid("$['namespace']['element_id']", true)]
     #[case::invalid("$.namespace.element_id", false)]
@@ -1,6 +1,7 @@
 pub mod dc_api;
 pub mod error;
 pub mod holder;
+pub mod http_client;
 pub mod iso_18013_7;
 pub mod permission_request;
 pub mod presentation;
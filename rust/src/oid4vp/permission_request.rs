@@ -1,12 +1,21 @@
 use super::error::OID4VPError;
-use super::presentation::{PresentationError, PresentationOptions, PresentationSigner};
-use crate::credential::{Credential, ParsedCredential, PresentableCredential};
+use super::presentation::{
+    DryRunKeyStore, DryRunSigner, PresentationError, PresentationOptions, PresentationSigner,
+};
+use crate::common::CredentialType;
+use crate::credential::{
+    Credential, CredentialFormat, ParsedCredential, ParsedCredentialInner, PresentableCredential,
+};
+use crate::crypto::KeyStore;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::{Arc, RwLock};
 
-use base64::{engine::general_purpose::URL_SAFE, Engine as _};
+use base64::{
+    engine::general_purpose::{URL_SAFE, URL_SAFE_NO_PAD},
+    Engine as _,
+};
 use itertools::Itertools;
 use openid4vp::core::authorization_request::AuthorizationRequestObject;
 use openid4vp::core::presentation_definition::PresentationDefinition;
@@ -45,9 +54,19 @@ pub enum PermissionRequestError {
     #[error("Input descriptor not found for input descriptor id: {0}")]
     InputDescriptorNotFound(String),
 
+    /// No credentials were selected for presentation.
+    #[error("No credentials were selected for presentation.")]
+    NoSelectedCredentials,
+
+    /// The number of selected field lists does not match the number of selected credentials.
+    #[error(
+        "Selected fields were given for {fields} credential(s), but {credentials} credential(s) were selected."
+    )]
+    FieldCredentialLengthMismatch { fields: u64, credentials: u64 },
+
     /// Invalid selected credential for requested field. Selected
-    /// credential does not match optional credentials.
-    #[error("Selected credential type, {0}, does not match requested credential types: {1}")]
+    /// credential, or one of its selected fields, does not match the request.
+    #[error("Selected value, {0}, does not match the requested values: {1}")]
     InvalidSelectedCredential(String, String),
 
     /// Credential Presentation Error
@@ -157,6 +176,71 @@ impl RequestedField {
     }
 }
 
+/// The requested fields of a single credential, for display on a consent screen.
+#[derive(Debug, uniffi::Record)]
+pub struct CredentialConsent {
+    /// The credential the fields are requested from.
+    pub credential: Arc<PresentableCredential>,
+    /// Fields that must be shared for the presentation to succeed.
+    pub required_fields: Vec<Arc<RequestedField>>,
+    /// Fields that the holder may choose not to share.
+    pub optional_fields: Vec<Arc<RequestedField>>,
+}
+
+/// A summary of everything requested by a verifier across all matching credentials.
+#[derive(Debug, uniffi::Record)]
+pub struct ConsentSummary {
+    /// The client ID of the verifier.
+    pub client_id: Option<String>,
+    /// The domain the presentation will be sent to.
+    pub domain: Option<String>,
+    /// The purpose of the presentation request.
+    pub purpose: Option<String>,
+    /// Whether the verifier intends to retain any of the requested fields.
+    pub retained: bool,
+    /// The requested fields, grouped by credential.
+    pub credentials: Vec<CredentialConsent>,
+}
+
+/// The requested fields for the holder's current credential selection.
+#[derive(Debug, uniffi::Record)]
+pub struct SelectionRequestedFields {
+    /// The requested fields, grouped by selected credential.
+    pub credentials: Vec<CredentialConsent>,
+    /// Fields requested by input descriptors that no selected credential satisfies.
+    pub missing_fields: Vec<Arc<RequestedField>>,
+}
+
+/// The claims of a selected credential that will be disclosed to the verifier.
+#[derive(Debug, uniffi::Record)]
+pub struct CredentialDisclosurePreview {
+    /// The selected credential, with its selected fields.
+    pub credential: Arc<PresentableCredential>,
+    /// Whether the whole credential is disclosed, because its format does not support
+    /// selective disclosure.
+    pub full_disclosure: bool,
+    /// The disclosed claims, as a JSON-encoded string.
+    pub claims: String,
+}
+
+/// The fields of a selected credential that a permission response would disclose.
+#[derive(Debug, uniffi::Record)]
+pub struct CredentialFieldsPreview {
+    /// The selected credential, with its selected fields.
+    pub credential: Arc<PresentableCredential>,
+    /// The names of the disclosed requested fields, or their paths if they have no name.
+    pub field_names: Vec<String>,
+}
+
+/// A permission response as it would be created for a selection, before it is signed.
+#[derive(Debug, uniffi::Record)]
+pub struct PermissionResponsePreview {
+    /// The disclosed fields, grouped by selected credential.
+    pub credentials: Vec<CredentialFieldsPreview>,
+    /// The descriptor map of the presentation submission, as a JSON-encoded string.
+    pub descriptor_map: String,
+}
+
 #[derive(Debug, Clone, uniffi::Object)]
 pub struct PermissionRequest {
     pub(crate) definition: PresentationDefinition,
@@ -164,6 +248,7 @@ pub struct PermissionRequest {
     pub(crate) request: AuthorizationRequestObject,
     pub(crate) signer: Arc<Box<dyn PresentationSigner>>,
     pub(crate) context_map: Option<HashMap<String, String>>,
+    pub(crate) key_store: Option<Arc<dyn KeyStore>>,
 }
 
 impl PermissionRequest {
@@ -173,6 +258,7 @@ impl PermissionRequest {
         request: AuthorizationRequestObject,
         signer: Arc<Box<dyn PresentationSigner>>,
         context_map: Option<HashMap<String, String>>,
+        key_store: Option<Arc<dyn KeyStore>>,
     ) -> Arc<Self> {
         Arc::new(Self {
             definition,
@@ -180,8 +266,120 @@ impl PermissionRequest {
             request,
             signer,
             context_map,
+            key_store,
         })
     }
+
+    /// Split the requested fields of a credential into required and optional fields.
+    fn credential_consent(&self, credential: &Arc<PresentableCredential>) -> CredentialConsent {
+        let (required_fields, optional_fields) = self
+            .requested_fields(credential)
+            .into_iter()
+            .partition(|field| field.required);
+
+        CredentialConsent {
+            credential: credential.clone(),
+            required_fields,
+            optional_fields,
+        }
+    }
+
+    /// Pair each selected credential with the fields selected for it, ensuring the
+    /// selection can be presented.
+    fn select_credentials(
+        &self,
+        selected_credentials: Vec<Arc<PresentableCredential>>,
+        selected_fields: Vec<Vec<String>>,
+    ) -> Result<Vec<Arc<PresentableCredential>>, PermissionRequestError> {
+        // Ensure that the selected credentials are not empty.
+        if selected_credentials.is_empty() {
+            return Err(PermissionRequestError::NoSelectedCredentials);
+        }
+
+        // Ensure that there are selected fields for all credentials.
+        if selected_fields.len() != selected_credentials.len() {
+            return Err(PermissionRequestError::FieldCredentialLengthMismatch {
+                fields: selected_fields.len() as u64,
+                credentials: selected_credentials.len() as u64,
+            });
+        }
+
+        selected_credentials
+            .iter()
+            .zip(selected_fields)
+            .map(|(sc, sf)| {
+                // Disclosure can only be limited to the selected fields of selectively
+                // disclosable credentials, otherwise drop the connection.
+                if sc.limit_disclosure && !sc.selective_disclosable() {
+                    return Err(PermissionRequestError::LimitDisclosure);
+                }
+
+                // Ensure that every selected field was requested from the credential.
+                if !sf.is_empty() {
+                    let requested_paths = self
+                        .requested_fields(sc)
+                        .iter()
+                        .map(|field| field.path())
+                        .collect::<Vec<_>>();
+                    if let Some(field) = sf.iter().find(|field| !requested_paths.contains(field)) {
+                        return Err(PermissionRequestError::InvalidSelectedCredential(
+                            field.clone(),
+                            requested_paths.join(", "),
+                        ));
+                    }
+                }
+
+                Ok(PresentableCredential {
+                    inner: sc.inner.clone(),
+                    limit_disclosure: sc.limit_disclosure,
+                    selected_fields: Some(sf),
+                }
+                .into())
+            })
+            .collect()
+    }
+
+    /// Sign the presentations of a permission response with `signer`, and the device
+    /// authentication of mdocs with the keys of `key_store`.
+    async fn permission_response(
+        &self,
+        signer: Arc<Box<dyn PresentationSigner>>,
+        key_store: Option<Arc<dyn KeyStore>>,
+        selected_credentials: Vec<Arc<PresentableCredential>>,
+        selected_fields: Vec<Vec<String>>,
+        response_options: ResponseOptions,
+    ) -> Result<Arc<PermissionResponse>, OID4VPError> {
+        let selected_credentials =
+            self.select_credentials(selected_credentials, selected_fields)?;
+
+        // Set options for constructing a verifiable presentation.
+        let options = PresentationOptions {
+            request: &self.request,
+            signer,
+            context_map: self.context_map.clone(),
+            response_options: &response_options,
+            key_store,
+            user_authentication_error: Default::default(),
+        };
+
+        let token_items = futures::future::try_join_all(
+            selected_credentials
+                .iter()
+                .map(|cred: &Arc<_>| cred.as_vp_token(&options)),
+        )
+        .await?;
+
+        let vp_token = VpToken(token_items);
+
+        Ok(Arc::new(PermissionResponse {
+            selected_credentials,
+            presentation_definition: self.definition.clone(),
+            authorization_request: self.request.clone(),
+            vp_token,
+            options: response_options,
+            presentation_submission_id: Uuid::new_v4(),
+        }))
+    }
 }
 
 #[uniffi::export(async_runtime = "tokio")]
@@ -192,6 +390,34 @@ impl PermissionRequest {
         self.credentials.clone()
     }
 
+    /// Return the matched credentials of the given format, e.g. to group them by format.
+    pub fn credentials_for_format(
+        &self,
+        format: CredentialFormat,
+    ) -> Vec<Arc<PresentableCredential>> {
+        self.credentials
+            .iter()
+            .filter(|credential| credential.as_parsed_credential().format() == format)
+            .cloned()
+            .collect()
+    }
+
+    /// Return the types of the matched credentials, without duplicates, e.g. the doctype of
+    /// an mdoc.
+    pub fn credential_types(&self) -> Vec<CredentialType> {
+        let mut types: Vec<CredentialType> = Vec::new();
+        for credential_type in self
+            .credentials
+            .iter()
+            .map(|credential| credential.as_parsed_credential().r#type())
+        {
+            if !types.contains(&credential_type) {
+                types.push(credential_type);
+            }
+        }
+        types
+    }
+
     /// Return the requested fields for a given credential.
     ///
     /// NOTE: This will return only the requested fields for a given credential.
@@ -235,71 +461,243 @@ impl PermissionRequest {
     ) -> Result<Arc<PermissionResponse>, OID4VPError> {
         log::debug!("Creating Permission Response");
 
-        // Ensure that the selected credentials are not empty.
-        if selected_credentials.is_empty() {
-            return Err(PermissionRequestError::InvalidSelectedCredential(
-                "No selected credentials".to_string(),
-                self.definition.credential_types_hint().join(", "),
-            )
-            .into());
-        }
+        self.permission_response(
+            self.signer.clone(),
+            self.key_store.clone(),
+            selected_credentials,
+            selected_fields,
+            response_options,
+        )
+        .await
+    }
 
-        // Ensure that there are selected fields for all credentials.
-        if selected_fields.len() != selected_credentials.len() {
-            return Err(PermissionRequestError::InvalidSelectedCredential(
-                "Selected credentials length must match selected fields length".to_string(),
-                self.definition.credential_types_hint().join(", "),
+    /// Check that a permission response can be created for the given selection, without
+    /// asking the holder's signer to sign it.
+    ///
+    /// The presentations, and the device authentication of mdocs, are signed with throwaway
+    /// keys instead, and the authorization response is encoded, so that structural and
+    /// encoding errors are reported before the holder is prompted to sign, e.g. by a
+    /// biometric prompt.
+    pub async fn dry_run_permission_response(
+        &self,
+        selected_credentials: Vec<Arc<PresentableCredential>>,
+        selected_fields: Vec<Vec<String>>,
+        response_options: ResponseOptions,
+    ) -> Result<(), OID4VPError> {
+        let signer: Box<dyn PresentationSigner> = Box::new(DryRunSigner::new(self.signer.clone())?);
+        let response = self
+            .permission_response(
+                Arc::new(signer),
+                self.key_store
+                    .as_ref()
+                    .map(|_| Arc::new(DryRunKeyStore) as Arc<dyn KeyStore>),
+                selected_credentials,
+                selected_fields,
+                response_options,
             )
-            .into());
-        }
+            .await?;
 
-        let selected_credentials = selected_credentials
-            .iter()
-            .zip(selected_fields)
-            .map(|(sc, sf)| {
-                // If limit disclosure is `required` drop connection
-                if sc.limit_disclosure {
-                    return Err(PermissionRequestError::LimitDisclosure);
-                }
-                Ok(PresentableCredential {
-                    inner: sc.inner.clone(),
-                    limit_disclosure: sc.limit_disclosure,
-                    selected_fields: Some(sf),
-                }
-                .into())
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        response.authorization_response()?;
+        response.vp_token()?;
+        Ok(())
+    }
 
-        // Set options for constructing a verifiable presentation.
-        let options = PresentationOptions {
-            request: &self.request,
-            signer: self.signer.clone(),
-            context_map: self.context_map.clone(),
-            response_options: &response_options,
-        };
+    /// Preview the claims that [create_permission_response](Self::create_permission_response)
+    /// would disclose for the given selection, without signing a presentation.
+    ///
+    /// This can be used to show the holder exactly what they are about to share.
+    pub fn preview_disclosure(
+        &self,
+        selected_credentials: Vec<Arc<PresentableCredential>>,
+        selected_fields: Vec<Vec<String>>,
+    ) -> Result<Vec<CredentialDisclosurePreview>, OID4VPError> {
+        self.select_credentials(selected_credentials, selected_fields)?
+            .into_iter()
+            .map(|credential| {
+                let (claims, full_disclosure) = credential.disclosed_claims()?;
+                Ok(CredentialDisclosurePreview {
+                    credential,
+                    full_disclosure,
+                    claims: claims.to_string(),
+                })
+            })
+            .collect()
+    }
 
-        let token_items = futures::future::try_join_all(
-            selected_credentials
-                .iter()
-                .map(|cred: &Arc<_>| cred.as_vp_token(&options)),
-        )
-        .await?;
+    /// Preview the permission response that
+    /// [create_permission_response](Self::create_permission_response) would create for the
+    /// given selection, e.g. for a final confirmation screen, without signing a presentation.
+    ///
+    /// Every requested field of a credential that does not support selective disclosure, or
+    /// for which no field was selected, is disclosed.
+    pub fn preview_permission_response(
+        &self,
+        selected_credentials: Vec<Arc<PresentableCredential>>,
+        selected_fields: Vec<Vec<String>>,
+        response_options: ResponseOptions,
+    ) -> Result<PermissionResponsePreview, OID4VPError> {
+        let selected_credentials =
+            self.select_credentials(selected_credentials, selected_fields)?;
 
-        let vp_token = VpToken(token_items);
+        let credentials = selected_credentials
+            .iter()
+            .map(|credential| {
+                let selection = credential
+                    .selected_fields
+                    .as_ref()
+                    .filter(|fields| !fields.is_empty() && credential.selective_disclosable());
+                let field_names = self
+                    .requested_fields(credential)
+                    .into_iter()
+                    .filter(|field| match selection {
+                        Some(fields) => fields.contains(&field.path),
+                        None => true,
+                    })
+                    .map(|field| field.name.clone().unwrap_or_else(|| field.path.clone()))
+                    .collect();
+                CredentialFieldsPreview {
+                    credential: credential.clone(),
+                    field_names,
+                }
+            })
+            .collect();
 
-        Ok(Arc::new(PermissionResponse {
+        // The descriptor map does not depend on the vp token, so it is left unsigned.
+        let response = PermissionResponse {
             selected_credentials,
             presentation_definition: self.definition.clone(),
             authorization_request: self.request.clone(),
-            vp_token,
+            vp_token: VpToken(vec![]),
             options: response_options,
-        }))
+            presentation_submission_id: Uuid::new_v4(),
+        };
+        let descriptor_map = serde_json::to_string(&response.create_descriptor_map()?)
+            .map_err(|e| OID4VPError::PresentationSubmissionCreation(format!("{e:?}")))?;
+
+        Ok(PermissionResponsePreview {
+            credentials,
+            descriptor_map,
+        })
     }
 
     /// Return the purpose of the presentation request.
     pub fn purpose(&self) -> Option<String> {
         self.definition.purpose().map(ToOwned::to_owned)
     }
+
+    /// Return a summary of the request for the consent screen, combining the
+    /// verifier identity with the requested fields of every matching credential.
+    pub fn consent_summary(&self) -> ConsentSummary {
+        let credentials: Vec<CredentialConsent> = self
+            .credentials
+            .iter()
+            .map(|credential| self.credential_consent(credential))
+            .collect();
+
+        let retained = credentials.iter().any(|credential| {
+            credential
+                .required_fields
+                .iter()
+                .chain(&credential.optional_fields)
+                .any(|field| field.retained)
+        });
+
+        ConsentSummary {
+            client_id: self.client_id(),
+            domain: self.domain(),
+            purpose: self.purpose(),
+            retained,
+            credentials,
+        }
+    }
+
+    /// Return the names of the requested fields that the verifier intends to retain, across
+    /// every matching credential, e.g. to warn the holder that they will be kept.
+    ///
+    /// Fields without a name are identified by their path.
+    pub fn retained_fields(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for field in self
+            .credentials
+            .iter()
+            .flat_map(|credential| self.requested_fields(credential))
+            .filter(|field| field.retained)
+        {
+            let name = field.name.clone().unwrap_or_else(|| field.path.clone());
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Recompute the requested fields for the holder's current credential selection, e.g.
+    /// as credentials are toggled on a multi-credential consent screen.
+    ///
+    /// Fields requested by input descriptors that none of the selected credentials
+    /// satisfy are returned as missing.
+    pub fn requested_fields_for_selection(
+        &self,
+        selected_credentials: Vec<Arc<PresentableCredential>>,
+    ) -> SelectionRequestedFields {
+        let credentials: Vec<CredentialConsent> = selected_credentials
+            .iter()
+            .map(|credential| self.credential_consent(credential))
+            .collect();
+
+        let selected_descriptor_ids: HashSet<String> = credentials
+            .iter()
+            .flat_map(|credential| {
+                credential
+                    .required_fields
+                    .iter()
+                    .chain(&credential.optional_fields)
+            })
+            .map(|field| field.input_descriptor_id.clone())
+            .collect();
+
+        let mut missing_fields: Vec<Arc<RequestedField>> = Vec::new();
+        for field in self
+            .credentials
+            .iter()
+            .flat_map(|credential| self.requested_fields(credential))
+        {
+            let is_missing = !selected_descriptor_ids.contains(&field.input_descriptor_id)
+                && !missing_fields.iter().any(|missing| {
+                    missing.input_descriptor_id == field.input_descriptor_id
+                        && missing.path == field.path
+                });
+            if is_missing {
+                missing_fields.push(field);
+            }
+        }
+
+        SelectionRequestedFields {
+            credentials,
+            missing_fields,
+        }
+    }
+}
+
+/// Return the fields of `credential` requested by a JSON-encoded presentation definition.
+///
+/// Unlike [PermissionRequest::requested_fields], this does not require an authorization
+/// request or a signer, so it can be used to preview a consent screen.
+#[uniffi::export]
+pub fn preview_requested_fields(
+    definition_json: String,
+    credential: &Arc<ParsedCredential>,
+) -> Result<Vec<Arc<RequestedField>>, OID4VPError> {
+    let definition: PresentationDefinition = serde_json::from_str(&definition_json)
+        .map_err(|e| OID4VPError::JsonSyntaxParse(e.to_string()))?;
+
+    if let ParsedCredentialInner::Cwt(_) = credential.inner {
+        return Err(OID4VPError::UnsupportedCredentialFormat(
+            credential.format().to_string(),
+        ));
+    }
+
+    Ok(credential.requested_fields(&definition))
 }
 
 /// Non-normative response options used to provide configurable interface
@@ -327,6 +725,21 @@ pub struct ResponseOptions {
     /// Remove the `$.vp` path prefix for the descriptor map for the verifiable credential.
     /// This is non-normative option, e.g. `$.vp` -> `$`
     pub remove_vp_path_prefix: bool,
+    /// Override the top-level path of the descriptor map entry, keyed by input descriptor id,
+    /// e.g. `$` or `$.vp`. Takes precedence over `remove_vp_path_prefix`.
+    ///
+    /// This is a non-normative option for verifiers that expect different path prefixes for
+    /// different credential formats within the same submission.
+    pub path_prefix_overrides: HashMap<String, String>,
+}
+
+impl ResponseOptions {
+    /// The overridden top-level descriptor map path for the given input descriptor, if any.
+    pub(crate) fn path_prefix_override(&self, input_descriptor_id: &str) -> Option<&str> {
+        self.path_prefix_overrides
+            .get(input_descriptor_id)
+            .map(String::as_str)
+    }
 }
 
 /// This struct is used to represent the response to a permission request.
@@ -344,6 +757,9 @@ pub struct PermissionResponse {
     pub authorization_request: AuthorizationRequestObject,
     pub vp_token: VpToken,
     pub options: ResponseOptions,
+    /// The id of the presentation submission, kept stable so that the submission returned by
+    /// [PermissionResponse::presentation_submission_json] matches the submitted one.
+    pub presentation_submission_id: Uuid,
 }
 
 #[uniffi::export]
@@ -360,41 +776,172 @@ impl PermissionResponse {
     pub fn vp_token(&self) -> Result<String, OID4VPError> {
         serde_json::to_string(&self.vp_token).map_err(|e| OID4VPError::Token(format!("{e:?}")))
     }
+
+    /// Return each verifiable presentation of the vp token as standalone W3C VP JSON,
+    /// without the OID4VP framing, e.g. to display or store it.
+    ///
+    /// Data-integrity presentations are returned with their proof, while JWT presentations
+    /// are returned as the signed compact JWT. Vp token items that are not W3C presentations,
+    /// such as SD-JWTs and mdoc device responses, are skipped.
+    pub fn verifiable_presentations_json(&self) -> Result<Vec<String>, OID4VPError> {
+        let mut presentations = Vec::new();
+        for item in &self.vp_token.0 {
+            let item =
+                serde_json::to_value(item).map_err(|e| OID4VPError::Token(format!("{e:?}")))?;
+            presentations.extend(verifiable_presentation(item));
+        }
+        Ok(presentations)
+    }
+
+    /// Return the presentation submission as a JSON-encoded utf-8 string.
+    ///
+    /// This is the same submission that is sent alongside the vp token, and is
+    /// helpful for debugging or for verifiers that process it separately.
+    pub fn presentation_submission_json(&self) -> Result<String, OID4VPError> {
+        serde_json::to_string(&self.create_presentation_submission()?)
+            .map_err(|e| OID4VPError::PresentationSubmissionCreation(format!("{e:?}")))
+    }
+
+    /// Return the response as pretty-printed JSON, to help debug interoperability issues.
+    ///
+    /// Unlike [vp_token](Self::vp_token), JWT and SD-JWT tokens are decoded and the
+    /// presentation submission is included. It is not meant to be sent to the verifier.
+    pub fn debug_json(&self) -> Result<String, OID4VPError> {
+        let vp_token = serde_json::to_value(&self.vp_token)
+            .map_err(|e| OID4VPError::Token(format!("{e:?}")))?;
+        let decoded_vp_token = match vp_token {
+            serde_json::Value::Array(items) => {
+                serde_json::Value::Array(items.into_iter().map(decode_token_for_display).collect())
+            }
+            item => decode_token_for_display(item),
+        };
+
+        let debug = serde_json::json!({
+            "vp_token": decoded_vp_token,
+            "presentation_submission": self.create_presentation_submission()?,
+        });
+
+        serde_json::to_string_pretty(&debug).map_err(|e| OID4VPError::Debug(format!("{e:?}")))
+    }
+}
+
+/// Extract the W3C verifiable presentation of a vp token item, if it is one.
+///
+/// A JWT presentation is kept as the signed JWT, so that it can still be verified.
+fn verifiable_presentation(item: serde_json::Value) -> Option<String> {
+    match item {
+        serde_json::Value::Object(_) => Some(item.to_string()),
+        serde_json::Value::String(token) => {
+            let payload = token.split('.').nth(1)?;
+            let payload: serde_json::Value =
+                serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+            payload.get("vp")?;
+            Some(token)
+        }
+        _ => None,
+    }
+}
+
+/// Decode a JWT or SD-JWT vp token item for display, keeping the encoded form alongside.
+///
+/// Any other item is returned unchanged.
+fn decode_token_for_display(item: serde_json::Value) -> serde_json::Value {
+    let decode = |segment: &str| -> Option<serde_json::Value> {
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(segment).ok()?).ok()
+    };
+
+    let Some(token) = item.as_str() else {
+        return item;
+    };
+
+    let mut parts = token.split('~');
+    let Some((header, payload)) = parts.next().and_then(|jwt| {
+        let mut segments = jwt.split('.');
+        Some((decode(segments.next()?)?, decode(segments.next()?)?))
+    }) else {
+        return item;
+    };
+
+    let mut decoded = serde_json::json!({
+        "encoded": token,
+        "header": header,
+        "payload": payload,
+    });
+
+    let disclosures: Vec<serde_json::Value> = parts
+        .filter(|part| !part.is_empty() && !part.contains('.'))
+        .filter_map(decode)
+        .collect();
+    if !disclosures.is_empty() {
+        decoded["disclosures"] = serde_json::Value::Array(disclosures);
+    }
+
+    decoded
 }
 
 impl PermissionResponse {
     // Construct a DescriptorMap for the presentation submission based on the
     // credentials returned from the VDC collection.
+    //
+    // Each selected credential is given its own descriptor map, associated with the input
+    // descriptor it was selected for. When an input descriptor is satisfied by several
+    // credentials, each of its descriptor maps uses an indexed nested path.
     pub fn create_descriptor_map(&self) -> Result<Vec<DescriptorMap>, OID4VPError> {
-        self.presentation_definition
-            .input_descriptors()
-            // TODO: It is possible for an input descriptor to have multiple credentials,
-            // in which case, it may be expected that the descriptor map will have a nested
-            // path. When creating a descriptor map, it may be better to use a mapping of input descriptor
-            // id to a list of credentials, whereby each descriptor id is mapped to a descriptor map,
-            // with a nested path for each credential it maps onto.
-            //
-            // Currently, each selected credential is provided its own descriptor map associated with
-            // the corresponding input descriptor. It is assumed that each input descriptor corresponds
-            // to a single verifiable credential.
+        let descriptor_ids = self.selected_descriptor_ids()?;
+
+        self.selected_credentials
             .iter()
-            .zip(self.selected_credentials.iter())
+            .zip(descriptor_ids)
             .enumerate()
-            .map(|(idx, (descriptor, cred))| {
-                // NOTE: If the iterator only includes a single credential, then
+            .map(|(idx, (cred, descriptor_id))| {
+                // NOTE: If only a single credential is presented, then
                 // do not provide an index for the descriptor map.
                 //
                 // This will inform the descriptor map to use the credential as a
                 // root path, instead of a indexed path.
-                if idx == 0 && idx == self.presentation_definition.input_descriptors().len() - 1 {
-                    return cred.create_descriptor_map(
-                        self.options.clone(),
-                        descriptor.id.clone(),
-                        None,
-                    );
-                }
+                let index = (self.selected_credentials.len() > 1).then_some(idx);
+                cred.create_descriptor_map(
+                    &self.authorization_request,
+                    self.options.clone(),
+                    descriptor_id,
+                    index,
+                )
+            })
+            .collect()
+    }
+
+    /// Return the id of the input descriptor each selected credential was selected for.
+    ///
+    /// Credentials are matched 1:1 with input descriptors when there is one credential per
+    /// descriptor. Otherwise, every credential is matched with the sole input descriptor, or
+    /// with the input descriptor of its requested fields.
+    fn selected_descriptor_ids(&self) -> Result<Vec<String>, OID4VPError> {
+        let descriptors = self.presentation_definition.input_descriptors();
+
+        if descriptors.len() == self.selected_credentials.len() {
+            return Ok(descriptors
+                .iter()
+                .map(|descriptor| descriptor.id.clone())
+                .collect());
+        }
 
-                cred.create_descriptor_map(self.options.clone(), descriptor.id.clone(), Some(idx))
+        if descriptors.len() == 1 {
+            return Ok(vec![
+                descriptors[0].id.clone();
+                self.selected_credentials.len()
+            ]);
+        }
+
+        self.selected_credentials
+            .iter()
+            .map(|cred| {
+                ParsedCredential {
+                    inner: cred.inner.clone(),
+                }
+                .requested_fields(&self.presentation_definition)
+                .first()
+                .map(|field| field.input_descriptor_id())
+                .ok_or(OID4VPError::InputDescriptorNotFound)
             })
             .collect()
     }
@@ -418,9 +965,1076 @@ impl PermissionResponse {
     /// Create a presentation submission based on the selected credentials returned in the permission response.
     fn create_presentation_submission(&self) -> Result<PresentationSubmission, OID4VPError> {
         Ok(PresentationSubmission::new(
-            Uuid::new_v4(),
+            self.presentation_submission_id,
             self.presentation_definition.id().clone(),
             self.create_descriptor_map()?,
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::credential::json_vc::JsonVc;
+
+    use super::*;
+
+    fn credential(subject: serde_json::Value) -> Arc<PresentableCredential> {
+        let json_vc = JsonVc::new_from_json(
+            json!({
+                "@context": ["https://www.w3.org/2018/credentials/v1"],
+                "type": ["VerifiableCredential"],
+                "issuer": "did:example:issuer",
+                "issuanceDate": "2024-01-01T00:00:00Z",
+                "credentialSubject": subject,
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        Arc::new(PresentableCredential {
+            inner: crate::credential::ParsedCredentialInner::LdpVc(json_vc),
+            limit_disclosure: false,
+            selected_fields: None,
+        })
+    }
+
+    fn two_credential_definition() -> PresentationDefinition {
+        serde_json::from_value(json!({
+            "id": "two-credentials",
+            "purpose": "Verify your education and employment",
+            "input_descriptors": [
+                {
+                    "id": "alumni",
+                    "constraints": {
+                        "fields": [{
+                            "path": ["$.credentialSubject.alumniOf"],
+                            "name": "School",
+                            "intent_to_retain": true
+                        }]
+                    }
+                },
+                {
+                    "id": "employment",
+                    "constraints": {
+                        "fields": [{
+                            "path": ["$.credentialSubject.employer"],
+                            "name": "Employer",
+                            "purpose": "Confirm current employment",
+                            "optional": true
+                        }]
+                    }
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn authorization_request(definition: &PresentationDefinition) -> AuthorizationRequestObject {
+        serde_json::from_value(json!({
+            "client_id": "https://verifier.example.com",
+            "client_id_scheme": "redirect_uri",
+            "nonce": "nonce",
+            "response_type": "vp_token",
+            "response_mode": "direct_post",
+            "response_uri": "https://verifier.example.com/response",
+            "presentation_definition": definition,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn retained_fields_of_two_credentials() {
+        let definition = two_credential_definition();
+        let request = authorization_request(&definition);
+
+        let signer: Box<dyn PresentationSigner> = Box::new(crate::tests::load_signer());
+        let permission_request = PermissionRequest::new(
+            definition,
+            vec![
+                credential(json!({ "alumniOf": "Example University" })),
+                credential(json!({ "employer": "Example Corp" })),
+            ],
+            request,
+            Arc::new(signer),
+            None,
+            None,
+        );
+
+        assert_eq!(permission_request.retained_fields(), vec!["School"]);
+    }
+
+    #[tokio::test]
+    async fn credentials_by_format_and_type() {
+        let (_, _, mdoc) = crate::mdl::util::test_mdl().await;
+        let mdoc = Arc::new(PresentableCredential {
+            inner: crate::credential::ParsedCredentialInner::MsoMdoc(Arc::new(mdoc)),
+            limit_disclosure: false,
+            selected_fields: None,
+        });
+        let alumni = credential(json!({ "alumniOf": "Example University" }));
+        let employment = credential(json!({ "employer": "Example Corp" }));
+
+        let definition = two_credential_definition();
+        let signer: Box<dyn PresentationSigner> = Box::new(crate::tests::load_signer());
+        let permission_request = PermissionRequest::new(
+            definition.clone(),
+            vec![alumni.clone(), mdoc.clone(), employment.clone()],
+            authorization_request(&definition),
+            Arc::new(signer),
+            None,
+            None,
+        );
+
+        let ldp_vcs = permission_request.credentials_for_format(CredentialFormat::LdpVc);
+        assert_eq!(ldp_vcs.len(), 2);
+        assert!(Arc::ptr_eq(&ldp_vcs[0], &alumni));
+        assert!(Arc::ptr_eq(&ldp_vcs[1], &employment));
+
+        let mdocs = permission_request.credentials_for_format(CredentialFormat::MsoMdoc);
+        assert_eq!(mdocs.len(), 1);
+        assert!(Arc::ptr_eq(&mdocs[0], &mdoc));
+
+        assert!(permission_request
+            .credentials_for_format(CredentialFormat::Cwt)
+            .is_empty());
+        assert_eq!(
+            permission_request.credential_types(),
+            vec![
+                alumni.as_parsed_credential().r#type(),
+                CredentialType("org.iso.18013.5.1.mDL".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn consent_summary_for_two_credentials() {
+        let definition = two_credential_definition();
+        let request = authorization_request(&definition);
+
+        let signer: Box<dyn PresentationSigner> = Box::new(crate::tests::load_signer());
+        let permission_request = PermissionRequest::new(
+            definition,
+            vec![
+                credential(json!({ "alumniOf": "Example University" })),
+                credential(json!({ "employer": "Example Corp" })),
+            ],
+            request,
+            Arc::new(signer),
+            None,
+            None,
+        );
+
+        let summary = permission_request.consent_summary();
+
+        assert_eq!(
+            summary.client_id.as_deref(),
+            Some("https://verifier.example.com")
+        );
+        assert_eq!(summary.domain.as_deref(), Some("verifier.example.com"));
+        assert_eq!(
+            summary.purpose.as_deref(),
+            Some("Verify your education and employment")
+        );
+        assert!(summary.retained);
+        assert_eq!(summary.credentials.len(), 2);
+
+        let alumni = &summary.credentials[0];
+        assert_eq!(alumni.required_fields.len(), 1);
+        assert!(alumni.optional_fields.is_empty());
+        assert_eq!(alumni.required_fields[0].name().as_deref(), Some("School"));
+
+        let employment = &summary.credentials[1];
+        assert!(employment.required_fields.is_empty());
+        assert_eq!(employment.optional_fields.len(), 1);
+        assert_eq!(
+            employment.optional_fields[0].purpose().as_deref(),
+            Some("Confirm current employment")
+        );
+    }
+
+    #[test]
+    fn preview_requested_fields_of_cwt() {
+        use std::collections::BTreeMap;
+
+        use serde_cbor::Value;
+
+        use crate::credential::cwt::{compress_cwt, encode_base10, Cwt};
+
+        let claims = serde_cbor::to_vec(&Value::Map(BTreeMap::from([(
+            Value::Integer(1),
+            Value::Text("did:example:issuer".into()),
+        )])))
+        .unwrap();
+        let cwt_bytes = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Bytes(vec![0xa0]),
+            Value::Map(BTreeMap::new()),
+            Value::Bytes(claims),
+            Value::Bytes(vec![0; 64]),
+        ]))
+        .unwrap();
+        let cwt = Cwt::new_from_base10(encode_base10(compress_cwt(cwt_bytes, 6))).unwrap();
+
+        assert!(matches!(
+            preview_requested_fields(
+                serde_json::to_string(&two_credential_definition()).unwrap(),
+                &ParsedCredential::new_cwt(cwt),
+            ),
+            Err(OID4VPError::UnsupportedCredentialFormat(format)) if format == "cwt"
+        ));
+    }
+
+    #[test]
+    fn preview_requested_fields_without_signer() {
+        let definition = two_credential_definition();
+        let credential = credential(json!({ "alumniOf": "Example University" }));
+
+        let fields = preview_requested_fields(
+            serde_json::to_string(&definition).unwrap(),
+            &credential.as_parsed_credential(),
+        )
+        .unwrap();
+
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].name().as_deref(), Some("School"));
+        assert_eq!(fields[0].input_descriptor_id(), "alumni");
+
+        assert!(matches!(
+            preview_requested_fields("{".into(), &credential.as_parsed_credential()),
+            Err(OID4VPError::JsonSyntaxParse(_))
+        ));
+    }
+
+    #[test]
+    fn requested_fields_after_deselecting_a_credential() {
+        let definition = two_credential_definition();
+        let alumni = credential(json!({ "alumniOf": "Example University" }));
+        let employment = credential(json!({ "employer": "Example Corp" }));
+
+        let signer: Box<dyn PresentationSigner> = Box::new(crate::tests::load_signer());
+        let permission_request = PermissionRequest::new(
+            definition.clone(),
+            vec![alumni.clone(), employment.clone()],
+            authorization_request(&definition),
+            Arc::new(signer),
+            None,
+            None,
+        );
+
+        let both = permission_request
+            .requested_fields_for_selection(vec![alumni.clone(), employment.clone()]);
+        assert_eq!(both.credentials.len(), 2);
+        assert!(both.missing_fields.is_empty());
+
+        let alumni_only = permission_request.requested_fields_for_selection(vec![alumni]);
+        assert_eq!(alumni_only.credentials.len(), 1);
+        assert_eq!(
+            alumni_only.credentials[0].required_fields[0]
+                .name()
+                .as_deref(),
+            Some("School")
+        );
+        assert_eq!(alumni_only.missing_fields.len(), 1);
+        assert_eq!(
+            alumni_only.missing_fields[0].input_descriptor_id(),
+            "employment"
+        );
+        assert!(!alumni_only.missing_fields[0].required());
+    }
+
+    #[test]
+    fn presentation_submission_matches_descriptor_map() {
+        let definition = two_credential_definition();
+        let response = PermissionResponse {
+            selected_credentials: vec![
+                credential(json!({ "alumniOf": "Example University" })),
+                credential(json!({ "employer": "Example Corp" })),
+            ],
+            authorization_request: authorization_request(&definition),
+            presentation_definition: definition,
+            vp_token: VpToken(vec![]),
+            options: ResponseOptions::default(),
+            presentation_submission_id: Uuid::new_v4(),
+        };
+
+        let submission: serde_json::Value =
+            serde_json::from_str(&response.presentation_submission_json().unwrap()).unwrap();
+
+        assert_eq!(
+            submission["id"],
+            json!(response.presentation_submission_id.to_string())
+        );
+        assert_eq!(submission["definition_id"], json!("two-credentials"));
+        assert_eq!(
+            submission["descriptor_map"],
+            serde_json::to_value(response.create_descriptor_map().unwrap()).unwrap()
+        );
+        assert_eq!(submission["descriptor_map"][1]["id"], json!("employment"));
+
+        // The submission is stable across calls, so it matches the submitted one.
+        assert_eq!(
+            response.presentation_submission_json().unwrap(),
+            serde_json::to_string(&response.create_presentation_submission().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn verifiable_presentations_without_vp_token_framing() {
+        let definition = two_credential_definition();
+        let credential = json!({
+            "@context": ["https://www.w3.org/2018/credentials/v1"],
+            "type": ["VerifiableCredential"],
+            "issuer": "did:example:issuer",
+            "credentialSubject": { "alumniOf": "Example University" },
+        });
+        let ldp_vp = json!({
+            "@context": ["https://www.w3.org/2018/credentials/v1"],
+            "type": ["VerifiablePresentation"],
+            "holder": "did:example:holder",
+            "verifiableCredential": [credential],
+            "proof": { "type": "DataIntegrityProof" },
+        });
+        let jwt_vp = json!({
+            "type": ["VerifiablePresentation"],
+            "holder": "did:example:holder",
+            "verifiableCredential": ["eyJhbGciOiJFUzI1NiJ9.e30.c2ln"],
+        });
+        let jwt = format!(
+            "{}.{}.c2ln",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"ES256"}"#),
+            URL_SAFE_NO_PAD.encode(json!({ "nonce": "nonce", "vp": jwt_vp }).to_string()),
+        );
+
+        let response = PermissionResponse {
+            selected_credentials: vec![],
+            authorization_request: authorization_request(&definition),
+            presentation_definition: definition,
+            vp_token: serde_json::from_value(json!([ldp_vp, jwt, "b2RkIG1kb2M"])).unwrap(),
+            options: ResponseOptions::default(),
+            presentation_submission_id: Uuid::new_v4(),
+        };
+
+        let presentations = response.verifiable_presentations_json().unwrap();
+        assert_eq!(presentations.len(), 2);
+
+        let presentation: serde_json::Value = serde_json::from_str(&presentations[0]).unwrap();
+        assert_eq!(presentation, ldp_vp);
+        assert_eq!(presentation["holder"], json!("did:example:holder"));
+        assert_eq!(presentation["verifiableCredential"][0], credential);
+
+        // The JWT presentation is returned signed, not as its bare `vp` claim.
+        assert_eq!(presentations[1], jwt);
+    }
+
+    fn permission_request() -> Arc<PermissionRequest> {
+        let definition = two_credential_definition();
+        let signer: Box<dyn PresentationSigner> = Box::new(crate::tests::load_signer());
+        PermissionRequest::new(
+            definition.clone(),
+            vec![],
+            authorization_request(&definition),
+            Arc::new(signer),
+            None,
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn no_selected_credentials() {
+        let error = permission_request()
+            .create_permission_response(vec![], vec![], ResponseOptions::default())
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(
+                error,
+                OID4VPError::PermissionRequest(PermissionRequestError::NoSelectedCredentials)
+            ),
+            "unexpected error: {error:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn field_credential_length_mismatch() {
+        let error = permission_request()
+            .create_permission_response(
+                vec![
+                    credential(json!({ "alumniOf": "Example University" })),
+                    credential(json!({ "employer": "Example Corp" })),
+                ],
+                vec![vec![]],
+                ResponseOptions::default(),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(
+                error,
+                OID4VPError::PermissionRequest(
+                    PermissionRequestError::FieldCredentialLengthMismatch {
+                        fields: 1,
+                        credentials: 2
+                    }
+                )
+            ),
+            "unexpected error: {error:?}"
+        );
+    }
+
+    #[test]
+    fn preview_disclosure_length_mismatch() {
+        let error = permission_request()
+            .preview_disclosure(
+                vec![credential(json!({ "alumniOf": "Example University" }))],
+                vec![],
+            )
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            OID4VPError::PermissionRequest(PermissionRequestError::FieldCredentialLengthMismatch {
+                fields: 0,
+                credentials: 1
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn invalid_selected_field() {
+        let credential = credential(json!({ "alumniOf": "Example University" }));
+        let selected_field = URL_SAFE.encode("$.credentialSubject.employer");
+
+        let error = permission_request()
+            .create_permission_response(
+                vec![credential],
+                vec![vec![selected_field.clone()]],
+                ResponseOptions::default(),
+            )
+            .await
+            .unwrap_err();
+
+        match error {
+            OID4VPError::PermissionRequest(PermissionRequestError::InvalidSelectedCredential(
+                field,
+                _,
+            )) => assert_eq!(field, selected_field),
+            error => panic!("unexpected error: {error:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn preview_matches_presented_sd_jwt() {
+        use crate::credential::vcdm2_sd_jwt::{tests::generate_sd_jwt, VCDM2SdJwt};
+        use openid4vp::core::response::parameters::VpTokenItem;
+
+        let definition: PresentationDefinition = serde_json::from_value(json!({
+            "id": "achievement",
+            "input_descriptors": [{
+                "id": "achievement",
+                "constraints": {
+                    "fields": [{ "path": ["$.credentialSubject.achievement"] }]
+                }
+            }]
+        }))
+        .unwrap();
+        let sd_jwt =
+            VCDM2SdJwt::new_from_compact_sd_jwt(generate_sd_jwt().await.to_string()).unwrap();
+        let credential = Arc::new(PresentableCredential {
+            inner: crate::credential::ParsedCredentialInner::VCDM2SdJwt(sd_jwt),
+            limit_disclosure: false,
+            selected_fields: None,
+        });
+
+        let signer: Box<dyn PresentationSigner> = Box::new(crate::tests::load_signer());
+        let permission_request = PermissionRequest::new(
+            definition.clone(),
+            vec![credential.clone()],
+            authorization_request(&definition),
+            Arc::new(signer),
+            None,
+            None,
+        );
+
+        let selected_fields = vec![vec![URL_SAFE.encode("$.credentialSubject.achievement")]];
+
+        let preview = permission_request
+            .preview_disclosure(vec![credential.clone()], selected_fields.clone())
+            .unwrap();
+        let response = permission_request
+            .create_permission_response(
+                vec![credential],
+                selected_fields,
+                ResponseOptions::default(),
+            )
+            .await
+            .unwrap();
+
+        let VpTokenItem::String(presented) = &response.vp_token.0[0] else {
+            panic!("expected a compact SD-JWT");
+        };
+        let presented = VCDM2SdJwt::new_from_compact_sd_jwt(presented.clone())
+            .unwrap()
+            .revealed_claims_as_json()
+            .unwrap();
+
+        assert_eq!(preview.len(), 1);
+        assert!(!preview[0].full_disclosure);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&preview[0].claims).unwrap(),
+            presented
+        );
+        // The concealed identity was not selected, so it is not disclosed.
+        assert!(!preview[0].claims.contains("John Smith"));
+    }
+
+    #[tokio::test]
+    async fn permission_response_preview_matches_response() {
+        use crate::credential::vcdm2_sd_jwt::{tests::generate_sd_jwt, VCDM2SdJwt};
+        use openid4vp::core::response::parameters::VpTokenItem;
+
+        let definition: PresentationDefinition = serde_json::from_value(json!({
+            "id": "achievement",
+            "input_descriptors": [{
+                "id": "achievement",
+                "constraints": {
+                    "fields": [{
+                        "path": ["$.credentialSubject.achievement"],
+                        "name": "Achievement"
+                    }]
+                }
+            }]
+        }))
+        .unwrap();
+        let sd_jwt =
+            VCDM2SdJwt::new_from_compact_sd_jwt(generate_sd_jwt().await.to_string()).unwrap();
+        let credential = Arc::new(PresentableCredential {
+            inner: crate::credential::ParsedCredentialInner::VCDM2SdJwt(sd_jwt),
+            limit_disclosure: false,
+            selected_fields: None,
+        });
+
+        let signer: Box<dyn PresentationSigner> = Box::new(crate::tests::load_signer());
+        let permission_request = PermissionRequest::new(
+            definition.clone(),
+            vec![credential.clone()],
+            authorization_request(&definition),
+            Arc::new(signer),
+            None,
+            None,
+        );
+
+        let selected_fields = vec![vec![URL_SAFE.encode("$.credentialSubject.achievement")]];
+
+        let preview = permission_request
+            .preview_permission_response(
+                vec![credential.clone()],
+                selected_fields.clone(),
+                ResponseOptions::default(),
+            )
+            .unwrap();
+        let response = permission_request
+            .create_permission_response(
+                vec![credential],
+                selected_fields,
+                ResponseOptions::default(),
+            )
+            .await
+            .unwrap();
+
+        let VpTokenItem::String(presented) = &response.vp_token.0[0] else {
+            panic!("expected a compact SD-JWT");
+        };
+        let presented = VCDM2SdJwt::new_from_compact_sd_jwt(presented.clone())
+            .unwrap()
+            .revealed_claims_as_json()
+            .unwrap();
+
+        assert_eq!(preview.credentials.len(), 1);
+        assert_eq!(preview.credentials[0].field_names, vec!["Achievement"]);
+        assert!(presented["credentialSubject"].get("achievement").is_some());
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&preview.descriptor_map).unwrap(),
+            serde_json::to_value(response.create_descriptor_map().unwrap()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn sd_jwt_presentation_discloses_selection_with_key_binding() {
+        use crate::credential::vcdm2_sd_jwt::VCDM2SdJwt;
+        use openid4vp::core::response::parameters::VpTokenItem;
+        use sha2::{Digest, Sha256};
+        use ssi::{
+            claims::{sd_jwt::SdAlg, vc_jose_cose::SdJwtVc},
+            json_pointer, JWK,
+        };
+
+        let definition: PresentationDefinition = serde_json::from_value(json!({
+            "id": "alumni",
+            "input_descriptors": [{
+                "id": "alumni",
+                "constraints": {
+                    "fields": [{ "path": ["$.credentialSubject.alumniOf"] }]
+                }
+            }]
+        }))
+        .unwrap();
+
+        // Bound to the key of the holder's signer.
+        let claims: SdJwtVc = serde_json::from_value(json!({
+            "@context": ["https://www.w3.org/ns/credentials/v2"],
+            "type": ["VerifiableCredential"],
+            "issuer": "did:example:issuer",
+            "credentialSubject": {
+                "alumniOf": "Example University",
+                "employer": "Example Corp",
+            },
+            "cnf": { "jwk": crate::tests::load_jwk().to_public() },
+        }))
+        .unwrap();
+        let sd_jwt = claims
+            .conceal_and_sign(
+                SdAlg::Sha256,
+                &[
+                    json_pointer!("/credentialSubject/alumniOf"),
+                    json_pointer!("/credentialSubject/employer"),
+                ],
+                &JWK::generate_ed25519().unwrap(),
+            )
+            .await
+            .unwrap();
+        let credential = Arc::new(PresentableCredential {
+            inner: crate::credential::ParsedCredentialInner::VCDM2SdJwt(
+                VCDM2SdJwt::new_from_compact_sd_jwt(sd_jwt.to_string()).unwrap(),
+            ),
+            limit_disclosure: false,
+            selected_fields: None,
+        });
+
+        let signer: Box<dyn PresentationSigner> = Box::new(crate::tests::load_signer());
+        let permission_request = PermissionRequest::new(
+            definition.clone(),
+            vec![credential.clone()],
+            authorization_request(&definition),
+            Arc::new(signer),
+            None,
+            None,
+        );
+
+        let response = permission_request
+            .create_permission_response(
+                vec![credential],
+                vec![vec![URL_SAFE.encode("$.credentialSubject.alumniOf")]],
+                ResponseOptions::default(),
+            )
+            .await
+            .unwrap();
+
+        let VpTokenItem::String(presented) = &response.vp_token.0[0] else {
+            panic!("expected a compact SD-JWT");
+        };
+        let (sd_jwt, key_binding_jwt) = presented.rsplit_once('~').unwrap();
+        let disclosures = sd_jwt.split('~').skip(1).collect::<Vec<_>>();
+
+        // Only the selected claim is disclosed.
+        assert_eq!(disclosures.len(), 1);
+        let disclosure: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(disclosures[0]).unwrap()).unwrap();
+        assert_eq!(disclosure[1], "alumniOf");
+
+        let key_binding_claims: serde_json::Value = serde_json::from_slice(
+            &URL_SAFE_NO_PAD
+                .decode(key_binding_jwt.split('.').nth(1).unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(key_binding_claims["nonce"], "nonce");
+        assert_eq!(key_binding_claims["aud"], "https://verifier.example.com");
+        assert_eq!(
+            key_binding_claims["sd_hash"],
+            URL_SAFE_NO_PAD.encode(Sha256::digest(format!("{sd_jwt}~")))
+        );
+    }
+
+    #[rstest]
+    #[case::direct_post(
+        json!({
+            "response_mode": "direct_post",
+            "response_uri": "https://verifier.example.com/response",
+        }),
+        "https://verifier.example.com/response"
+    )]
+    #[case::fragment(
+        json!({
+            "response_mode": "fragment",
+            "redirect_uri": "https://verifier.example.com/callback",
+        }),
+        "https://verifier.example.com/callback"
+    )]
+    #[tokio::test]
+    async fn limit_disclosure_of_mdoc(
+        #[case] response_parameters: serde_json::Value,
+        #[case] handover_uri: &str,
+    ) {
+        use crate::mdl::reader::{verify_device_response, AuthenticationStatus};
+        use ciborium::Value as Cbor;
+        use isomdl::definitions::DeviceResponse;
+        use openid4vp::core::response::parameters::VpTokenItem;
+        use sha2::{Digest, Sha256};
+
+        const IACA_CERTIFICATE: &str = include_str!("../../tests/res/mdl/iaca-certificate.pem");
+        const IACA_KEY: &str = include_str!("../../tests/res/mdl/iaca-key.pem");
+
+        let definition: PresentationDefinition = serde_json::from_value(json!({
+            "id": "mdl",
+            "input_descriptors": [{
+                "id": "org.iso.18013.5.1.mDL",
+                "format": { "mso_mdoc": { "alg": ["ES256"] } },
+                "constraints": {
+                    "limit_disclosure": "required",
+                    "fields": [
+                        { "path": ["$['org.iso.18013.5.1']['family_name']"] },
+                        { "path": ["$['org.iso.18013.5.1']['given_name']"] },
+                        { "path": ["$['org.iso.18013.5.1']['birth_date']"] }
+                    ]
+                }
+            }]
+        }))
+        .unwrap();
+
+        let (key_manager, key_alias) = crate::mdl::util::test_device_key().await;
+        let mdoc = crate::mdl::util::generate_test_mdl_with_iaca(
+            key_manager.clone(),
+            key_alias,
+            IACA_CERTIFICATE,
+            IACA_KEY,
+        )
+        .unwrap();
+        let credential = Arc::new(PresentableCredential {
+            inner: crate::credential::ParsedCredentialInner::MsoMdoc(Arc::new(mdoc)),
+            limit_disclosure: true,
+            selected_fields: None,
+        });
+
+        let mut request = json!({
+            "client_id": "https://verifier.example.com",
+            "client_id_scheme": "redirect_uri",
+            "nonce": "nonce",
+            "response_type": "vp_token",
+            "presentation_definition": definition,
+        });
+        request
+            .as_object_mut()
+            .unwrap()
+            .extend(response_parameters.as_object().unwrap().clone());
+        let request: AuthorizationRequestObject = serde_json::from_value(request).unwrap();
+        let signer: Box<dyn PresentationSigner> = Box::new(crate::tests::load_signer());
+        let permission_request = PermissionRequest::new(
+            definition.clone(),
+            vec![credential.clone()],
+            request.clone(),
+            Arc::new(signer),
+            None,
+            Some(key_manager),
+        );
+        assert_eq!(permission_request.requested_fields(&credential).len(), 3);
+
+        let response = permission_request
+            .create_permission_response(
+                vec![credential],
+                vec![vec![
+                    URL_SAFE.encode("$['org.iso.18013.5.1']['family_name']"),
+                    URL_SAFE.encode("$['org.iso.18013.5.1']['given_name']"),
+                ]],
+                ResponseOptions::default(),
+            )
+            .await
+            .unwrap();
+
+        let VpTokenItem::String(device_response) = &response.vp_token.0[0] else {
+            panic!("expected a base64url-encoded DeviceResponse");
+        };
+        let encoded_device_response = device_response.clone();
+        let device_response: DeviceResponse =
+            isomdl::cbor::from_slice(&URL_SAFE_NO_PAD.decode(device_response).unwrap()).unwrap();
+        let documents = device_response.documents.unwrap();
+        let namespaces = documents[0].issuer_signed.namespaces.as_ref().unwrap();
+
+        assert_eq!(namespaces.len(), 1);
+        let mut disclosed = namespaces["org.iso.18013.5.1"]
+            .iter()
+            .map(|element| element.as_ref().element_identifier.clone())
+            .collect::<Vec<_>>();
+        disclosed.sort();
+        assert_eq!(disclosed, ["family_name", "given_name"]);
+
+        // The device authentication is signed by the device key of the MSO, over the session
+        // transcript a verifier builds from its own request (OpenID4VP 1.0 Appendix B.2.6.1).
+        let handover_info = isomdl::cbor::to_vec(&Cbor::Array(vec![
+            Cbor::Text("https://verifier.example.com".into()),
+            Cbor::Text("nonce".into()),
+            Cbor::Null,
+            Cbor::Text(handover_uri.into()),
+        ]))
+        .unwrap();
+        let session_transcript = isomdl::cbor::to_vec(&Cbor::Array(vec![
+            Cbor::Null,
+            Cbor::Null,
+            Cbor::Array(vec![
+                Cbor::Text("OpenID4VPHandover".into()),
+                Cbor::Bytes(Sha256::digest(handover_info).to_vec()),
+            ]),
+        ]))
+        .unwrap();
+        let verified = verify_device_response(
+            encoded_device_response,
+            session_transcript,
+            vec![IACA_CERTIFICATE.to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            verified.documents[0].device_authentication,
+            AuthenticationStatus::Valid
+        );
+    }
+
+    /// Signer that delegates to [KeySigner](crate::tests::load_signer) but fails to sign,
+    /// as if the user cancelled the biometric prompt guarding the key.
+    #[derive(Debug)]
+    struct CancelledAuthSigner(crate::oid4vp::holder::tests::KeySigner);
+
+    #[async_trait::async_trait]
+    impl PresentationSigner for CancelledAuthSigner {
+        async fn sign(&self, _payload: Vec<u8>) -> Result<Vec<u8>, PresentationError> {
+            Err(PresentationError::UserAuthenticationFailed(
+                "biometric prompt cancelled".into(),
+            ))
+        }
+
+        fn algorithm(&self) -> ssi::crypto::Algorithm {
+            self.0.algorithm()
+        }
+
+        async fn verification_method(&self) -> String {
+            self.0.verification_method().await
+        }
+
+        fn did(&self) -> String {
+            self.0.did()
+        }
+
+        fn cryptosuite(&self) -> ssi::claims::data_integrity::CryptosuiteString {
+            self.0.cryptosuite()
+        }
+
+        fn jwk(&self) -> String {
+            self.0.jwk()
+        }
+    }
+
+    #[tokio::test]
+    async fn signing_reports_user_authentication_failure() {
+        let definition = two_credential_definition();
+        let jwt_vc = crate::credential::jwt_vc::JwtVc::new_from_compact_jws(
+            include_str!("../../tests/examples/vvt.jwt").trim().into(),
+        )
+        .unwrap();
+
+        let signer: Box<dyn PresentationSigner> =
+            Box::new(CancelledAuthSigner(crate::tests::load_signer()));
+        let permission_request = PermissionRequest::new(
+            definition.clone(),
+            vec![],
+            authorization_request(&definition),
+            Arc::new(signer),
+            None,
+            None,
+        );
+
+        let error = permission_request
+            .create_permission_response(
+                vec![Arc::new(PresentableCredential {
+                    inner: crate::credential::ParsedCredentialInner::JwtVcJson(jwt_vc),
+                    limit_disclosure: false,
+                    selected_fields: None,
+                })],
+                vec![vec![]],
+                ResponseOptions::default(),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(
+                error,
+                OID4VPError::Presentation(PresentationError::UserAuthenticationFailed(_))
+            ),
+            "unexpected error: {error:?}"
+        );
+    }
+
+    /// Signer that claims to sign with EdDSA, which is not supported for JWT presentations,
+    /// and must not be asked to sign.
+    #[derive(Debug)]
+    struct UntouchedEdDsaSigner(crate::oid4vp::holder::tests::KeySigner);
+
+    #[async_trait::async_trait]
+    impl PresentationSigner for UntouchedEdDsaSigner {
+        async fn sign(&self, _payload: Vec<u8>) -> Result<Vec<u8>, PresentationError> {
+            panic!("the holder's key must not be used for a dry run")
+        }
+
+        fn algorithm(&self) -> ssi::crypto::Algorithm {
+            ssi::crypto::Algorithm::EdDSA
+        }
+
+        async fn verification_method(&self) -> String {
+            self.0.verification_method().await
+        }
+
+        fn did(&self) -> String {
+            self.0.did()
+        }
+
+        fn cryptosuite(&self) -> ssi::claims::data_integrity::CryptosuiteString {
+            self.0.cryptosuite()
+        }
+
+        fn jwk(&self) -> String {
+            self.0.jwk()
+        }
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_signature_encoding_error() {
+        let definition = two_credential_definition();
+        let jwt_vc = crate::credential::jwt_vc::JwtVc::new_from_compact_jws(
+            include_str!("../../tests/examples/vvt.jwt").trim().into(),
+        )
+        .unwrap();
+
+        let signer: Box<dyn PresentationSigner> =
+            Box::new(UntouchedEdDsaSigner(crate::tests::load_signer()));
+        let permission_request = PermissionRequest::new(
+            definition.clone(),
+            vec![],
+            authorization_request(&definition),
+            Arc::new(signer),
+            None,
+            None,
+        );
+
+        // The signature of a JWT presentation is only re-encoded once it has been signed.
+        let error = permission_request
+            .dry_run_permission_response(
+                vec![Arc::new(PresentableCredential {
+                    inner: crate::credential::ParsedCredentialInner::JwtVcJson(jwt_vc),
+                    limit_disclosure: false,
+                    selected_fields: None,
+                })],
+                vec![vec![]],
+                ResponseOptions::default(),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(
+                error,
+                OID4VPError::Presentation(PresentationError::CryptographicSuite(_))
+            ),
+            "unexpected error: {error:?}"
+        );
+    }
+
+    #[test]
+    fn descriptor_with_multiple_credentials() {
+        let definition: PresentationDefinition = serde_json::from_value(json!({
+            "id": "alumni-credentials",
+            "input_descriptors": [{
+                "id": "alumni",
+                "constraints": {
+                    "fields": [{ "path": ["$.credentialSubject.alumniOf"] }]
+                }
+            }]
+        }))
+        .unwrap();
+        let response = PermissionResponse {
+            selected_credentials: vec![
+                credential(json!({ "alumniOf": "Example University" })),
+                credential(json!({ "alumniOf": "Example College" })),
+            ],
+            authorization_request: authorization_request(&definition),
+            presentation_definition: definition,
+            vp_token: VpToken(vec![]),
+            options: ResponseOptions::default(),
+            presentation_submission_id: Uuid::new_v4(),
+        };
+
+        let descriptor_map =
+            serde_json::to_value(response.create_descriptor_map().unwrap()).unwrap();
+
+        assert_eq!(descriptor_map.as_array().unwrap().len(), 2);
+        for (idx, map) in descriptor_map.as_array().unwrap().iter().enumerate() {
+            assert_eq!(map["id"], json!("alumni"));
+            assert_eq!(
+                map["path_nested"]["path"],
+                json!(format!("$.verifiableCredential[{idx}]"))
+            );
+        }
+    }
+
+    #[test]
+    fn mixed_submission_path_prefix_overrides() {
+        let definition = two_credential_definition();
+        let jwt_vc = crate::credential::jwt_vc::JwtVc::new_from_compact_jws(
+            include_str!("../../tests/examples/vvt.jwt").trim().into(),
+        )
+        .unwrap();
+        let response = PermissionResponse {
+            selected_credentials: vec![
+                credential(json!({ "alumniOf": "Example University" })),
+                Arc::new(PresentableCredential {
+                    inner: crate::credential::ParsedCredentialInner::JwtVcJson(jwt_vc),
+                    limit_disclosure: false,
+                    selected_fields: None,
+                }),
+            ],
+            authorization_request: authorization_request(&definition),
+            presentation_definition: definition,
+            vp_token: VpToken(vec![]),
+            options: ResponseOptions {
+                path_prefix_overrides: [
+                    ("alumni".to_string(), "$.vp".to_string()),
+                    ("employment".to_string(), "$".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+                ..Default::default()
+            },
+            presentation_submission_id: Uuid::new_v4(),
+        };
+
+        let descriptor_map =
+            serde_json::to_value(response.create_descriptor_map().unwrap()).unwrap();
+
+        assert_eq!(descriptor_map[0]["id"], json!("alumni"));
+        assert_eq!(descriptor_map[0]["format"], json!("ldp_vp"));
+        assert_eq!(descriptor_map[0]["path"], json!("$.vp"));
+        assert_eq!(
+            descriptor_map[0]["path_nested"]["path"],
+            json!("$.verifiableCredential[0]")
+        );
+
+        assert_eq!(descriptor_map[1]["id"], json!("employment"));
+        assert_eq!(descriptor_map[1]["format"], json!("jwt_vp_json"));
+        assert_eq!(descriptor_map[1]["path"], json!("$"));
+        assert_eq!(
+            descriptor_map[1]["path_nested"]["path"],
+            json!("$.verifiableCredential[1]")
+        );
+    }
+}
@@ -1,9 +1,17 @@
-use crate::crypto::CryptoCurveUtils;
+use crate::crypto::{parse_jwk, CryptoCurveUtils, CryptoError, KeyAlias, KeyStore, SigningKey};
+use crate::did::dids_equal;
+
+use crate::credential::CredentialEncodingError;
 
 use super::{error::OID4VPError, RequestedField, ResponseOptions};
 
-use std::{collections::HashMap, ops::Deref, str::FromStr, sync::Arc};
+use std::{
+    collections::HashMap,
+    ops::Deref,
+    sync::{Arc, Mutex},
+};
 
+use base64::prelude::*;
 use openid4vp::core::{
     authorization_request::AuthorizationRequestObject, credential_format::ClaimFormatDesignation,
     presentation_definition::PresentationDefinition, presentation_submission::DescriptorMap,
@@ -13,6 +21,7 @@ use serde::Serialize;
 use ssi::{
     claims::{
         data_integrity::{suites::JsonWebSignature2020, AnyProtocol, CryptosuiteString},
+        jws::JwsSigner,
         MessageSignatureError, SignatureEnvironment,
     },
     crypto::{Algorithm, AlgorithmInstance},
@@ -40,6 +49,25 @@ pub enum PresentationError {
 
     #[error("Failed to parse public JsonWebKey: {0}")]
     JWK(String),
+
+    /// The signing key requires user presence, and the user has not yet been authenticated.
+    #[error("User authentication is required to sign the presentation")]
+    UserAuthenticationRequired,
+
+    /// The user could not be authenticated, e.g. a biometric prompt was failed or cancelled.
+    #[error("User authentication failed: {0}")]
+    UserAuthenticationFailed(String),
+}
+
+impl PresentationError {
+    /// Whether the error was caused by the signer failing to authenticate the user, as
+    /// opposed to a failure of the signature itself.
+    pub fn is_user_authentication_error(&self) -> bool {
+        matches!(
+            self,
+            Self::UserAuthenticationRequired | Self::UserAuthenticationFailed(_)
+        )
+    }
 }
 /// Credential Presentation trait defines the set of standard methods
 /// each credential format must implement.
@@ -188,7 +216,7 @@ pub trait PresentationSigner: Send + Sync + std::fmt::Debug {
     /// `ldp_vp`, `jwt_vp`.
     ///
     ///
-    /// E.g., JsonWebSignature2020, ecdsa-rdfc-2019
+    /// E.g., JsonWebSignature2020, ecdsa-rdfc-2019, eddsa-rdfc-2022
     fn cryptosuite(&self) -> CryptosuiteString;
 
     /// Return the public JWK of the signing key.
@@ -196,6 +224,94 @@ pub trait PresentationSigner: Send + Sync + std::fmt::Debug {
     fn jwk(&self) -> String;
 }
 
+/// A [PresentationSigner] for dry runs, which signs with a throwaway key of the same
+/// algorithm as the holder's signer, without ever asking it to sign.
+///
+/// The DID, verification method and JWK of the holder's signer are reused, so that the
+/// presentation is structured exactly as it will be when signed for real. Its signature
+/// does not verify.
+#[derive(Debug)]
+pub(crate) struct DryRunSigner {
+    signer: Arc<Box<dyn PresentationSigner>>,
+    key: JWK,
+}
+
+impl DryRunSigner {
+    pub(crate) fn new(signer: Arc<Box<dyn PresentationSigner>>) -> Result<Self, PresentationError> {
+        let key = match signer.algorithm() {
+            Algorithm::ES256 => JWK::generate_p256(),
+            Algorithm::ES384 => JWK::generate_p384(),
+            Algorithm::EdDSA => {
+                JWK::generate_ed25519().map_err(|e| PresentationError::Signing(format!("{e:?}")))?
+            }
+            alg => {
+                return Err(PresentationError::Signing(format!(
+                    "Dry runs are not supported for algorithm: {alg:?}"
+                )))
+            }
+        };
+
+        Ok(Self { signer, key })
+    }
+}
+
+#[async_trait::async_trait]
+impl PresentationSigner for DryRunSigner {
+    async fn sign(&self, payload: Vec<u8>) -> Result<Vec<u8>, PresentationError> {
+        self.key
+            .sign_bytes(&payload)
+            .await
+            .map_err(|e| PresentationError::Signing(format!("{e:?}")))
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        self.signer.algorithm()
+    }
+
+    async fn verification_method(&self) -> String {
+        self.signer.verification_method().await
+    }
+
+    fn did(&self) -> String {
+        self.signer.did()
+    }
+
+    fn cryptosuite(&self) -> CryptosuiteString {
+        self.signer.cryptosuite()
+    }
+
+    fn jwk(&self) -> String {
+        self.signer.jwk()
+    }
+}
+
+/// A [KeyStore] for dry runs, which holds a throwaway P-256 key under every alias, so that
+/// mdoc device authentication never asks for the holder's device key.
+pub(crate) struct DryRunKeyStore;
+
+impl KeyStore for DryRunKeyStore {
+    fn get_signing_key(&self, _: KeyAlias) -> Result<Arc<dyn SigningKey>, CryptoError> {
+        Ok(Arc::new(DryRunKey(p256::SecretKey::random(
+            &mut ssi::crypto::rand::thread_rng(),
+        ))))
+    }
+}
+
+struct DryRunKey(p256::SecretKey);
+
+impl SigningKey for DryRunKey {
+    fn jwk(&self) -> Result<String, CryptoError> {
+        Ok(self.0.public_key().to_jwk_string())
+    }
+
+    fn sign(&self, payload: Vec<u8>) -> Result<Vec<u8>, CryptoError> {
+        use p256::ecdsa::signature::Signer;
+        let signature: p256::ecdsa::Signature =
+            p256::ecdsa::SigningKey::from(&self.0).sign(&payload);
+        Ok(signature.to_vec())
+    }
+}
+
 /// Internal options for constructing a VP Token, and optionally signing it.
 ///
 /// PresentationOptions provides a means to pass metadata about the verifiable presentation
@@ -209,6 +325,11 @@ pub struct PresentationOptions<'a> {
     /// Optional context map for the presentation.
     pub(crate) context_map: Option<HashMap<String, String>>,
     pub(crate) response_options: &'a ResponseOptions,
+    /// Key store holding the device keys of mdocs, used for their device authentication.
+    pub(crate) key_store: Option<Arc<dyn KeyStore>>,
+    /// User authentication error raised by the signer while signing through ssi, which
+    /// only carries signer errors as strings.
+    pub(crate) user_authentication_error: Arc<Mutex<Option<PresentationError>>>,
 }
 
 impl MessageSigner<WithProtocol<ssi::crypto::Algorithm, AnyProtocol>> for PresentationOptions<'_> {
@@ -233,11 +354,15 @@ impl MessageSigner<WithProtocol<ssi::crypto::Algorithm, AnyProtocol>> for Presen
             ));
         }
 
-        let signature_bytes = self
-            .signer
-            .sign(message.to_vec())
-            .await
-            .map_err(|e| MessageSignatureError::signature_failed(format!("{e:?}")))?;
+        let signature_bytes = self.signer.sign(message.to_vec()).await.map_err(|e| {
+            let message = MessageSignatureError::signature_failed(format!("{e:?}"));
+            if e.is_user_authentication_error() {
+                if let Ok(mut slot) = self.user_authentication_error.lock() {
+                    *slot = Some(e);
+                }
+            }
+            message
+        })?;
 
         match self.signer.cryptosuite().as_ref() {
             "ecdsa-rdfc-2019" => self
@@ -247,6 +372,8 @@ impl MessageSigner<WithProtocol<ssi::crypto::Algorithm, AnyProtocol>> for Presen
                 .ok_or(MessageSignatureError::UnsupportedAlgorithm(
                     "Unsupported signature encoding".into(),
                 )),
+            // Ed25519 signatures are always the raw 64-byte encoding.
+            "eddsa-rdfc-2022" => Ok(signature_bytes),
             _ => Err(MessageSignatureError::UnsupportedAlgorithm(
                 self.signer.cryptosuite().to_string(),
             )),
@@ -267,12 +394,47 @@ where
     ) -> Result<Option<Self::MessageSigner>, ssi::claims::SignatureError> {
         Ok(method
             .controller()
-            .filter(|ctrl| **ctrl == self.signer.did())
+            .filter(|ctrl| dids_equal(ctrl.as_str(), &self.signer.did()))
             .map(|_| self.clone()))
     }
 }
 
+/// Choose the format in which to present a credential whose own presentation format is
+/// `native`.
+///
+/// The native format is used if the verifier accepts it, or does not state the formats it
+/// accepts. Otherwise, the first of the `wrapping` formats accepted by the verifier is used,
+/// which must be formats the credential can be embedded in without loss.
+pub(crate) fn negotiate_presentation_format(
+    request: &AuthorizationRequestObject,
+    native: ClaimFormatDesignation,
+    wrapping: &[ClaimFormatDesignation],
+) -> Result<ClaimFormatDesignation, OID4VPError> {
+    let Ok(vp_formats) = request.vp_formats() else {
+        return Ok(native);
+    };
+
+    if vp_formats.0.is_empty() || vp_formats.0.contains_key(&native) {
+        return Ok(native);
+    }
+
+    wrapping
+        .iter()
+        .find(|format| vp_formats.0.contains_key(format))
+        .cloned()
+        .ok_or_else(|| OID4VPError::FormatNotAccepted(format!("{native:?}")))
+}
+
 impl PresentationOptions<'_> {
+    /// Choose the format in which to present a credential, see [negotiate_presentation_format].
+    pub fn presentation_format(
+        &self,
+        native: ClaimFormatDesignation,
+        wrapping: &[ClaimFormatDesignation],
+    ) -> Result<ClaimFormatDesignation, OID4VPError> {
+        negotiate_presentation_format(self.request, native, wrapping)
+    }
+
     pub async fn verification_method_id(&self) -> Result<IriBuf, PresentationError> {
         self.signer
             .verification_method()
@@ -298,7 +460,46 @@ impl PresentationOptions<'_> {
     }
 
     pub fn jwk(&self) -> Result<JWK, PresentationError> {
-        JWK::from_str(&self.signer.jwk()).map_err(|e| PresentationError::JWK(format!("{e:?}")))
+        parse_jwk(&self.signer.jwk()).map_err(|e| PresentationError::JWK(e.to_string()))
+    }
+
+    /// Sign a compact JWT with the holder's signer, e.g. the key binding JWT of an SD-JWT.
+    ///
+    /// The `alg` header is set to the algorithm of the signer, whose signature is converted
+    /// to the fixed-width encoding of JWS.
+    pub async fn sign_jwt(
+        &self,
+        mut header: serde_json::Value,
+        claims: serde_json::Value,
+    ) -> Result<String, OID4VPError> {
+        header["alg"] = self.signer.algorithm().to_string().into();
+
+        let encode = |value: &serde_json::Value| {
+            serde_json::to_vec(value)
+                .map(|bytes| BASE64_URL_SAFE_NO_PAD.encode(bytes))
+                .map_err(|e| CredentialEncodingError::VpToken(format!("{e:?}")))
+        };
+        let unsigned_jwt = format!("{}.{}", encode(&header)?, encode(&claims)?);
+
+        let signature = self
+            .signer
+            .sign(unsigned_jwt.as_bytes().to_vec())
+            .await
+            .map_err(|e| match e {
+                e if e.is_user_authentication_error() => OID4VPError::Presentation(e),
+                e => CredentialEncodingError::VpToken(format!("{e:?}")).into(),
+            })?;
+        let signature = self
+            .curve_utils()
+            .map(|utils| utils.ensure_raw_fixed_width_signature_encoding(signature))?
+            .ok_or(OID4VPError::Presentation(PresentationError::Signing(
+                "Unsupported signature encoding.".into(),
+            )))?;
+
+        Ok(format!(
+            "{unsigned_jwt}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(signature)
+        ))
     }
 
     /// Return the crypto curve utils based on the signing algorithm, e.g. ES256.
@@ -334,11 +535,18 @@ impl PresentationOptions<'_> {
     }
 
     /// Sign a JSON presentation type for a v1 OR v2 credential.
-    pub async fn sign_presentation(
+    ///
+    /// The credentials of the presentation are usually JSON credentials, but may also be
+    /// enveloped credentials, such as a JWT VC string.
+    pub async fn sign_presentation<C1, C2>(
         &self,
         // NOTE: the presentation is `unsecured` at this point.
-        presentation: AnyJsonPresentation,
-    ) -> Result<DataIntegrity<AnyJsonPresentation, AnySuite>, PresentationError> {
+        presentation: AnyJsonPresentation<C1, C2>,
+    ) -> Result<DataIntegrity<AnyJsonPresentation<C1, C2>, AnySuite>, PresentationError>
+    where
+        C1: Serialize,
+        C2: Serialize,
+    {
         let resolver = VerificationMethodDIDResolver::new(AnyDidMethod::default());
 
         let mut proof_options = ProofOptions::new(
@@ -397,6 +605,18 @@ impl PresentationOptions<'_> {
                     )
                     .await
             }
+            "eddsa-rdfc-2022" => {
+                AnySuite::EdDsaRdfc2022
+                    .sign_with(
+                        &env,
+                        presentation,
+                        resolver,
+                        self,
+                        proof_options,
+                        Default::default(),
+                    )
+                    .await
+            }
             JsonWebSignature2020::NAME => {
                 AnySuite::JsonWebSignature2020
                     .sign_with(
@@ -411,6 +631,12 @@ impl PresentationOptions<'_> {
             }
             _ => return Err(PresentationError::CryptographicSuite(suite.to_string())),
         }
-        .map_err(|e| PresentationError::Signing(format!("{e:?}")))
+        .map_err(|e| {
+            self.user_authentication_error
+                .lock()
+                .ok()
+                .and_then(|mut slot| slot.take())
+                .unwrap_or_else(|| PresentationError::Signing(format!("{e:?}")))
+        })
     }
 }
@@ -1,6 +1,19 @@
+use base64::prelude::*;
+use openid4vp::core::presentation_definition::PresentationDefinition;
+use p256::ecdsa::{signature::Verifier as _, Signature, VerifyingKey};
 use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use ssi::{claims::JwsString, dids::DIDURL};
 use std::sync::Arc;
 use url::Url;
+use x509_cert::{der::Decode as _, Certificate};
+
+use crate::{
+    credential::json_vc::{did_verification_parameters, DidVerificationParameters, JsonVc},
+    did::{CachingDidResolver, ConfiguredDidResolver},
+    trusted_roots::parse_trusted_roots,
+    verifier::{crypto::RustCrypto, helpers::validate_signer_certificate},
+};
 
 #[derive(Debug, thiserror::Error, uniffi::Error)]
 pub enum Oid4vpVerifierError {
@@ -8,13 +21,333 @@ pub enum Oid4vpVerifierError {
     HttpClient(String),
     #[error("Invalid URL: {0}")]
     Url(String),
+    #[error("Invalid vp_token: {0}")]
+    InvalidVpToken(String),
+    #[error("Invalid presentation definition: {0}")]
+    InvalidPresentationDefinition(String),
+    #[error("Failed to load the trust store: {0}")]
+    TrustStore(String),
+    #[error("Presentation signature verification failed: {0}")]
+    PresentationSignature(String),
+    #[error("Nonce mismatch: expected {expected}, found {found}")]
+    NonceMismatch { expected: String, found: String },
+    #[error("Holder binding failed: {0}")]
+    HolderBinding(String),
+    #[error("Credential {index} failed verification: {reason}")]
+    CredentialVerification { index: u32, reason: String },
+    #[error("Input descriptor {0} is not satisfied by the presented credentials")]
+    UnsatisfiedDescriptor(String),
+}
+
+/// A `vp_token` that has passed [validate_vp_token].
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct VpValidationResult {
+    /// The DID of the holder, who signed the presentation.
+    pub holder: String,
+    /// The JSON-encoded claims of each presented credential, in presentation order.
+    pub credentials: Vec<String>,
+    /// The ids of the input descriptors satisfied by the presented credentials.
+    pub satisfied_descriptors: Vec<String>,
+}
+
+/// Validate a `jwt_vp_json` `vp_token` received by a relying party.
+///
+/// The presentation must be signed by the holder's DID, carry `expected_nonce`, and only
+/// contain credentials whose subject is the holder. Embedded `ldp_vc` credentials and DID-signed
+/// JWT credentials are verified by resolving their issuer, while JWT credentials with an `x5c`
+/// header must chain to one of the PEM-encoded `roots_pem`. The `exp` and `nbf` claims of the
+/// presentation and of JWT credentials are checked against the current time.
+///
+/// Every input descriptor of the presentation definition must be satisfied by at least one of
+/// the presented credentials.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn validate_vp_token(
+    vp_token: String,
+    presentation_definition_json: String,
+    expected_nonce: String,
+    roots_pem: Vec<String>,
+) -> Result<VpValidationResult, Oid4vpVerifierError> {
+    let descriptors = input_descriptors(&presentation_definition_json)?;
+    let trusted_roots = parse_trusted_roots(roots_pem)
+        .map_err(|e| Oid4vpVerifierError::TrustStore(format!("{e:#}")))?;
+    let params = did_verification_parameters(CachingDidResolver::new(
+        ConfiguredDidResolver::default(),
+        None,
+    ));
+
+    let (header, claims) = decode_jwt(&vp_token).map_err(Oid4vpVerifierError::InvalidVpToken)?;
+
+    verify_did_signed_jwt(&vp_token, &params)
+        .await
+        .map_err(Oid4vpVerifierError::PresentationSignature)?;
+    check_jwt_validity(&claims).map_err(Oid4vpVerifierError::InvalidVpToken)?;
+
+    let nonce = claims
+        .get("nonce")
+        .and_then(Json::as_str)
+        .unwrap_or_default();
+    if nonce != expected_nonce {
+        return Err(Oid4vpVerifierError::NonceMismatch {
+            expected: expected_nonce,
+            found: nonce.to_string(),
+        });
+    }
+
+    let holder = holder_did(&header, &claims)?;
+
+    let presented = match claims
+        .get("vp")
+        .ok_or_else(|| Oid4vpVerifierError::InvalidVpToken("missing vp claim".into()))?
+        .get("verifiableCredential")
+    {
+        Some(Json::Array(credentials)) => credentials.clone(),
+        Some(credential) => vec![credential.clone()],
+        None => vec![],
+    };
+
+    let mut credentials = Vec::with_capacity(presented.len());
+    for (index, credential) in presented.iter().enumerate() {
+        let (credential, subject) = verify_credential(credential, &params, &trusted_roots)
+            .await
+            .map_err(|reason| Oid4vpVerifierError::CredentialVerification {
+                index: index as u32,
+                reason,
+            })?;
+
+        if !is_bound_to(&credential, subject.as_deref(), &holder) {
+            return Err(Oid4vpVerifierError::HolderBinding(format!(
+                "the subject of credential {index} is not the holder {holder}"
+            )));
+        }
+
+        credentials.push(credential);
+    }
+
+    let mut satisfied_descriptors = Vec::with_capacity(descriptors.len());
+    for (id, definition) in descriptors {
+        if !credentials
+            .iter()
+            .any(|credential| definition.is_credential_match(credential))
+        {
+            return Err(Oid4vpVerifierError::UnsatisfiedDescriptor(id));
+        }
+        satisfied_descriptors.push(id);
+    }
+
+    Ok(VpValidationResult {
+        holder,
+        credentials: credentials.iter().map(Json::to_string).collect(),
+        satisfied_descriptors,
+    })
+}
+
+/// Split a presentation definition into single-descriptor definitions, keyed by descriptor id,
+/// so that each descriptor can be matched on its own.
+fn input_descriptors(
+    presentation_definition_json: &str,
+) -> Result<Vec<(String, PresentationDefinition)>, Oid4vpVerifierError> {
+    let invalid = Oid4vpVerifierError::InvalidPresentationDefinition;
+
+    let definition: Json =
+        serde_json::from_str(presentation_definition_json).map_err(|e| invalid(e.to_string()))?;
+    let descriptors = definition
+        .get("input_descriptors")
+        .and_then(Json::as_array)
+        .ok_or_else(|| invalid("missing input_descriptors".into()))?;
+
+    descriptors
+        .iter()
+        .map(|descriptor| {
+            let id = descriptor
+                .get("id")
+                .and_then(Json::as_str)
+                .ok_or_else(|| invalid("input descriptor without an id".into()))?;
+
+            let mut single = definition.clone();
+            single["input_descriptors"] = Json::Array(vec![descriptor.clone()]);
+            let single = serde_json::from_value(single)
+                .map_err(|e: serde_json::Error| invalid(e.to_string()))?;
+
+            Ok((id.to_string(), single))
+        })
+        .collect()
+}
+
+/// Decode the header and claims of a compact JWS, without verifying it.
+fn decode_jwt(jwt: &str) -> Result<(Json, Json), String> {
+    let mut parts = jwt.split('.');
+    let (Some(header), Some(payload), Some(_), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err("not a compact JWS".into());
+    };
+
+    let decode = |part: &str| -> Result<Json, String> {
+        let bytes = BASE64_URL_SAFE_NO_PAD
+            .decode(part)
+            .map_err(|e| e.to_string())?;
+        serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+    };
+
+    Ok((decode(header)?, decode(payload)?))
+}
+
+/// Verify a JWT signed by the verification method of its `kid` header.
+async fn verify_did_signed_jwt(
+    jwt: &str,
+    params: &DidVerificationParameters,
+) -> Result<(), String> {
+    JwsString::from_string(jwt.to_string())
+        .map_err(|e| e.to_string())?
+        .verify(params)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// The holder of a presentation is the DID of its signing key, which must agree with the `iss`
+/// claim and the `holder` of the presentation, when they are given.
+fn holder_did(header: &Json, claims: &Json) -> Result<String, Oid4vpVerifierError> {
+    let kid = header.get("kid").and_then(Json::as_str).ok_or_else(|| {
+        Oid4vpVerifierError::HolderBinding("the presentation has no kid header".into())
+    })?;
+    let holder = DIDURL::new(kid.as_bytes())
+        .map_err(|_| Oid4vpVerifierError::HolderBinding(format!("kid {kid} is not a DID URL")))?
+        .did()
+        .as_str()
+        .to_string();
+
+    let claimed = [
+        claims.get("iss"),
+        claims.get("vp").and_then(|vp| vp.get("holder")),
+    ];
+    for claimed in claimed.into_iter().flatten() {
+        if claimed.as_str() != Some(holder.as_str()) {
+            return Err(Oid4vpVerifierError::HolderBinding(format!(
+                "the presentation is signed by {holder}, but claims to be from {claimed}"
+            )));
+        }
+    }
+
+    Ok(holder)
+}
+
+/// Verify an embedded credential, returning its claims and, for a JWT credential, its `sub`
+/// claim.
+async fn verify_credential(
+    credential: &Json,
+    params: &DidVerificationParameters,
+    trusted_roots: &[Certificate],
+) -> Result<(Json, Option<String>), String> {
+    match credential {
+        Json::Object(_) => {
+            let json_vc =
+                JsonVc::new_from_json(credential.to_string()).map_err(|e| e.to_string())?;
+            json_vc
+                .verify_with(params, time::Duration::ZERO)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok((credential.clone(), None))
+        }
+        Json::String(jwt) => {
+            let (header, claims) = decode_jwt(jwt)?;
+            match header.get("x5c") {
+                Some(x5c) => verify_x5c_signed_jwt(jwt, x5c, trusted_roots)?,
+                None => verify_did_signed_jwt(jwt, params).await?,
+            }
+            check_jwt_validity(&claims)?;
+            let subject = claims.get("sub").and_then(Json::as_str).map(str::to_string);
+            Ok((claims.get("vc").cloned().unwrap_or(claims), subject))
+        }
+        _ => Err("unsupported credential encoding".into()),
+    }
+}
+
+/// Check the `exp` and `nbf` claims of a JWT, which must be NumericDates, against now.
+fn check_jwt_validity(claims: &Json) -> Result<(), String> {
+    let date = |name: &str| match claims.get(name) {
+        Some(date) => date
+            .as_f64()
+            .map(Some)
+            .ok_or_else(|| format!("the {name} claim is not a NumericDate")),
+        None => Ok(None),
+    };
+    let now = time::OffsetDateTime::now_utc().unix_timestamp() as f64;
+
+    if let Some(exp) = date("exp")? {
+        if now >= exp {
+            return Err(format!("the JWT expired at {exp}"));
+        }
+    }
+    if let Some(nbf) = date("nbf")? {
+        if now < nbf {
+            return Err(format!("the JWT is not valid before {nbf}"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Verify an ES256 JWT signed by the leaf certificate of its `x5c` header, which must be
+/// issued by one of the trusted roots.
+fn verify_x5c_signed_jwt(
+    jwt: &str,
+    x5c: &Json,
+    trusted_roots: &[Certificate],
+) -> Result<(), String> {
+    let leaf = x5c
+        .get(0)
+        .and_then(Json::as_str)
+        .ok_or("x5c header has no certificate")?;
+    let leaf = BASE64_STANDARD
+        .decode(leaf)
+        .map_err(|e| format!("x5c certificate is not valid base64: {e}"))?;
+    let leaf = Certificate::from_der(&leaf)
+        .map_err(|e| format!("x5c certificate could not be parsed: {e}"))?;
+
+    validate_signer_certificate(&RustCrypto, trusted_roots, &leaf)
+        .map_err(|e| format!("x5c certificate is not trusted: {e}"))?;
+
+    let (signing_input, signature) = jwt.rsplit_once('.').ok_or("not a compact JWS")?;
+    let signature = BASE64_URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|e| e.to_string())?;
+    let signature = Signature::from_slice(&signature).map_err(|e| e.to_string())?;
+    VerifyingKey::from_sec1_bytes(
+        leaf.tbs_certificate
+            .subject_public_key_info
+            .subject_public_key
+            .raw_bytes(),
+    )
+    .map_err(|e| format!("certificate key is not a P-256 key: {e}"))?
+    .verify(signing_input.as_bytes(), &signature)
+    .map_err(|_| "invalid JWT signature".to_string())
+}
+
+/// Whether the subject of a credential is `holder`: its JWT `sub` claim if given, and every
+/// `credentialSubject` id otherwise.
+fn is_bound_to(credential: &Json, jwt_subject: Option<&str>, holder: &str) -> bool {
+    if let Some(subject) = jwt_subject {
+        return subject == holder;
+    }
+
+    let subjects = match credential.get("credentialSubject") {
+        Some(Json::Array(subjects)) => subjects.iter().collect(),
+        Some(subject) => vec![subject],
+        None => vec![],
+    };
+
+    !subjects.is_empty()
+        && subjects
+            .iter()
+            .all(|subject| subject.get("id").and_then(Json::as_str) == Some(holder))
 }
 
 #[derive(Debug, uniffi::Object)]
 pub struct DelegatedVerifier {
     base_url: Url,
     /// HTTP Request Client
-    pub(crate) client: openid4vp::core::util::ReqwestClient,
+    pub(crate) client: super::http_client::Oid4vpHttpClient,
 }
 
 #[derive(Debug, Serialize, Deserialize, uniffi::Enum, PartialEq)]
@@ -57,7 +390,7 @@ pub struct DelegateInitializationResponse {
 impl DelegatedVerifier {
     #[uniffi::constructor]
     pub async fn new_client(base_url: Url) -> Result<Arc<Self>, Oid4vpVerifierError> {
-        let client = openid4vp::core::util::ReqwestClient::new()
+        let client = super::http_client::Oid4vpHttpClient::new()
             .map_err(|e| Oid4vpVerifierError::HttpClient(format!("{e:?}")))?;
 
         Ok(Arc::new(Self { base_url, client }))
@@ -114,7 +447,18 @@ impl DelegatedVerifier {
 
 #[cfg(test)]
 mod tests {
-    use ssi::JWK;
+    use serde_json::json;
+    use ssi::{
+        claims::{
+            data_integrity::{AnySuite, CryptographicSuite, ProofOptions},
+            jwt::{AnyClaims, ExpirationTime, Issuer, NumericDate, StringOrURI},
+            vc::v2::JsonCredential,
+            JWTClaims,
+        },
+        dids::{DIDKey, DIDResolver as _, DIDJWK},
+        verification_methods::SingleSecretSigner,
+        JWK,
+    };
 
     use super::*;
     use crate::credential::vcdm2_sd_jwt::VCDM2SdJwt;
@@ -125,6 +469,7 @@ mod tests {
     // NOTE: This requires an instance of credible to be accessible
     const BASE_URL: &str = "http://localhost:3003";
     const DELEGATED_VERIFIER_URL: &str = "/api2/verifier/1/delegate";
+    const IACA_CERTIFICATE: &str = include_str!("../../tests/res/mdl/iaca-certificate.pem");
 
     #[tokio::test]
     #[ignore]
@@ -161,6 +506,7 @@ mod tests {
             trusted_dids,
             Box::new(key_signer),
             None,
+            None,
         )
         .await
         .expect("failed to create oid4vp holder");
@@ -200,4 +546,255 @@ mod tests {
 
         Ok(())
     }
+
+    fn alumni_definition() -> String {
+        json!({
+            "id": "alumni",
+            "input_descriptors": [{
+                "id": "alumni",
+                "constraints": {
+                    "fields": [{ "path": ["$.credentialSubject.alumniOf"] }]
+                }
+            }]
+        })
+        .to_string()
+    }
+
+    /// The key of the holder, with its `did:jwk` verification method as key id, and its DID.
+    fn holder() -> (JWK, String) {
+        let mut holder_jwk = crate::tests::load_jwk();
+        let holder_vm = DIDJWK::generate_url(&holder_jwk.to_public());
+        let holder = holder_vm.did().as_str().to_string();
+        holder_jwk.key_id = Some(holder_vm.into());
+        (holder_jwk, holder)
+    }
+
+    /// Present an alumni credential about the holder, signed by a fresh `did:key` issuer.
+    async fn alumni_vp_token(nonce: &str) -> String {
+        let (holder_jwk, holder) = holder();
+
+        let issuer_jwk = JWK::generate_ed25519().unwrap();
+        let issuer_vm = DIDKey::generate_url(&issuer_jwk).unwrap();
+        let credential: JsonCredential = serde_json::from_value(json!({
+            "@context": ["https://www.w3.org/ns/credentials/v2"],
+            "type": ["VerifiableCredential"],
+            "issuer": issuer_vm.did().as_str(),
+            "credentialSubject": { "id": holder, "alumniOf": "Example University" },
+        }))
+        .unwrap();
+        let credential = AnySuite::EdDsaRdfc2022
+            .sign(
+                credential,
+                DIDKey.into_vm_resolver(),
+                SingleSecretSigner::new(issuer_jwk).into_local(),
+                ProofOptions::from_method(issuer_vm.into_iri().into()),
+            )
+            .await
+            .unwrap();
+
+        vp_token(&holder_jwk, &holder, nonce, json!(credential), None).await
+    }
+
+    /// Present a credential as the holder, in a VP JWT expiring at `expires_at` if given.
+    async fn vp_token(
+        holder_jwk: &JWK,
+        holder: &str,
+        nonce: &str,
+        credential: Json,
+        expires_at: Option<i32>,
+    ) -> String {
+        let mut claims: JWTClaims<AnyClaims> = JWTClaims::default();
+        claims
+            .registered
+            .set(Issuer(StringOrURI::String(holder.to_string())));
+        if let Some(expires_at) = expires_at {
+            claims
+                .registered
+                .set(ExpirationTime(NumericDate::from(expires_at)));
+        }
+        claims.private.set("nonce".to_string(), json!(nonce));
+        claims.private.set(
+            "vp".to_string(),
+            json!({
+                "@context": ["https://www.w3.org/2018/credentials/v1"],
+                "type": ["VerifiablePresentation"],
+                "holder": holder,
+                "verifiableCredential": [credential],
+            }),
+        );
+
+        claims.sign(holder_jwk).await.unwrap().to_string()
+    }
+
+    /// An alumni JWT credential about `subject`, identified by its `sub` claim only, signed by a
+    /// document signer certificate issued by the test IACA.
+    fn x5c_alumni_credential(subject: &str, expires_at: i64) -> String {
+        use p256::ecdsa::signature::Signer as _;
+
+        let (certificate, signing_key) = crate::mdl::util::setup_certificate_chain(
+            IACA_CERTIFICATE,
+            include_str!("../../tests/res/mdl/iaca-key.pem"),
+            std::time::Duration::from_secs(60 * 60 * 24),
+        )
+        .unwrap();
+        let header = json!({
+            "alg": "ES256",
+            "x5c": [BASE64_STANDARD.encode(x509_cert::der::Encode::to_der(&certificate).unwrap())],
+        });
+        let claims = json!({
+            "sub": subject,
+            "exp": expires_at,
+            "vc": {
+                "@context": ["https://www.w3.org/2018/credentials/v1"],
+                "type": ["VerifiableCredential"],
+                "credentialSubject": { "alumniOf": "Example University" },
+            },
+        });
+
+        let signing_input = format!(
+            "{}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(header.to_string()),
+            BASE64_URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature: Signature = signing_key.sign(signing_input.as_bytes());
+        format!(
+            "{signing_input}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        )
+    }
+
+    #[tokio::test]
+    async fn validate_alumni_presentation() {
+        let vp_token = alumni_vp_token("nonce-123").await;
+
+        let result = validate_vp_token(
+            vp_token,
+            alumni_definition(),
+            "nonce-123".to_string(),
+            vec![],
+        )
+        .await
+        .unwrap();
+
+        assert!(result.holder.starts_with("did:jwk:"));
+        assert_eq!(result.satisfied_descriptors, vec!["alumni".to_string()]);
+        assert_eq!(result.credentials.len(), 1);
+        let credential: Json = serde_json::from_str(&result.credentials[0]).unwrap();
+        assert_eq!(
+            credential["credentialSubject"]["alumniOf"],
+            "Example University"
+        );
+    }
+
+    #[tokio::test]
+    async fn reject_nonce_mismatch() {
+        let vp_token = alumni_vp_token("nonce-123").await;
+
+        let result = validate_vp_token(
+            vp_token,
+            alumni_definition(),
+            "another-nonce".to_string(),
+            vec![],
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(Oid4vpVerifierError::NonceMismatch { found, .. }) if found == "nonce-123"
+        ));
+    }
+
+    #[tokio::test]
+    async fn validate_x5c_credential_bound_by_sub() {
+        let (holder_jwk, holder) = holder();
+        let expires_at = time::OffsetDateTime::now_utc().unix_timestamp() + 3600;
+        let credential = x5c_alumni_credential(&holder, expires_at);
+        let vp_token = vp_token(&holder_jwk, &holder, "nonce-123", json!(credential), None).await;
+
+        let result = validate_vp_token(
+            vp_token,
+            alumni_definition(),
+            "nonce-123".to_string(),
+            vec![IACA_CERTIFICATE.to_string()],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.holder, holder);
+        assert_eq!(result.satisfied_descriptors, vec!["alumni".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn reject_expired_x5c_credential() {
+        let (holder_jwk, holder) = holder();
+        let expired_at = time::OffsetDateTime::now_utc().unix_timestamp() - 60;
+        let credential = x5c_alumni_credential(&holder, expired_at);
+        let vp_token = vp_token(&holder_jwk, &holder, "nonce-123", json!(credential), None).await;
+
+        let result = validate_vp_token(
+            vp_token,
+            alumni_definition(),
+            "nonce-123".to_string(),
+            vec![IACA_CERTIFICATE.to_string()],
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(Oid4vpVerifierError::CredentialVerification { index: 0, reason })
+                if reason.starts_with("the JWT expired")
+        ));
+    }
+
+    #[tokio::test]
+    async fn reject_x5c_credential_from_untrusted_root() {
+        let (holder_jwk, holder) = holder();
+        let expires_at = time::OffsetDateTime::now_utc().unix_timestamp() + 3600;
+        let credential = x5c_alumni_credential(&holder, expires_at);
+        let vp_token = vp_token(&holder_jwk, &holder, "nonce-123", json!(credential), None).await;
+
+        let result = validate_vp_token(
+            vp_token,
+            alumni_definition(),
+            "nonce-123".to_string(),
+            vec![include_str!("../../tests/res/mdl/utrecht-certificate.pem").to_string()],
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(Oid4vpVerifierError::CredentialVerification { index: 0, reason })
+                if reason.starts_with("x5c certificate is not trusted")
+        ));
+    }
+
+    #[tokio::test]
+    async fn reject_expired_presentation() {
+        let (holder_jwk, holder) = holder();
+        let expires_at = time::OffsetDateTime::now_utc().unix_timestamp() + 3600;
+        let credential = x5c_alumni_credential(&holder, expires_at);
+        let expired_at = time::OffsetDateTime::now_utc().unix_timestamp() as i32 - 60;
+        let vp_token = vp_token(
+            &holder_jwk,
+            &holder,
+            "nonce-123",
+            json!(credential),
+            Some(expired_at),
+        )
+        .await;
+
+        let result = validate_vp_token(
+            vp_token,
+            alumni_definition(),
+            "nonce-123".to_string(),
+            vec![IACA_CERTIFICATE.to_string()],
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(Oid4vpVerifierError::InvalidVpToken(reason))
+                if reason.starts_with("the JWT expired")
+        ));
+    }
 }
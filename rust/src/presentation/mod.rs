@@ -15,6 +15,7 @@ pub use error::*;
 use crate::{
     credential::{ParsedCredential, ParsedCredentialInner},
     crypto::CryptoCurveUtils,
+    did::{did_key_matches_algorithm, dids_equal, CachingDidResolver, DidResolutionCache},
     oid4vp::PresentationSigner,
 };
 
@@ -31,6 +32,8 @@ pub struct JsonLdPresentationBuilder {
 
     pub(crate) signer: Arc<Box<dyn PresentationSigner>>,
     pub(crate) context_map: Option<HashMap<String, String>>,
+    /// Cache of the holder's DID document, shared across builders. No caching by default.
+    pub(crate) resolver_cache: Option<Arc<DidResolutionCache>>,
 }
 
 #[uniffi::export]
@@ -57,6 +60,17 @@ impl JsonLdPresentationBuilder {
             domain,
             signer: Arc::new(signer),
             context_map,
+            resolver_cache: None,
+        }
+        .into()
+    }
+
+    /// Resolve the holder's DID through `cache`, so that it is only resolved once across
+    /// the presentations issued by builders sharing the cache.
+    pub fn with_resolver_cache(&self, cache: Arc<DidResolutionCache>) -> Arc<Self> {
+        Self {
+            resolver_cache: Some(cache),
+            ..self.clone()
         }
         .into()
     }
@@ -103,7 +117,10 @@ impl JsonLdPresentationBuilder {
         params.challenge = self.challenge.to_owned();
         params.domains = self.domain.to_owned().map(|d| vec![d]).unwrap_or_default();
 
-        let resolver = VerificationMethodDIDResolver::new(AnyDidMethod::default());
+        let resolver = VerificationMethodDIDResolver::new(CachingDidResolver::new(
+            AnyDidMethod::default(),
+            self.resolver_cache.clone(),
+        ));
         let suite = AnySuite::pick(&key, params.verification_method.as_ref())
             .ok_or(PresentationBuilderError::SigningSuitePickError)?;
 
@@ -188,7 +205,109 @@ where
     ) -> Result<Option<Self::MessageSigner>, ssi::claims::SignatureError> {
         Ok(method
             .controller()
-            .filter(|ctrl| **ctrl == self.signer.did())
+            .filter(|ctrl| dids_equal(ctrl.as_str(), &self.signer.did()))
+            .filter(|ctrl| did_key_matches_algorithm(ctrl.as_str(), self.signer.algorithm()))
             .map(|_| self.clone()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use ssi::{
+        jwk::Algorithm as JwkAlgorithm, verification_methods::GenericVerificationMethod, JWK,
+    };
+
+    use super::*;
+    use crate::oid4vp::holder::tests::KeySigner;
+
+    fn builder(signer: KeySigner) -> Arc<JsonLdPresentationBuilder> {
+        let holder = signer.did();
+        JsonLdPresentationBuilder::new(
+            format!("urn:uuid:{}", uuid::Uuid::new_v4()),
+            holder,
+            "authentication".to_string(),
+            Some("challenge".to_string()),
+            None,
+            Box::new(signer),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn holder_is_resolved_once_with_cache() {
+        let cache = DidResolutionCache::new(60);
+        let builder = builder(crate::tests::load_signer()).with_resolver_cache(cache.clone());
+
+        builder.issue_presentation(vec![]).await.unwrap();
+        builder.issue_presentation(vec![]).await.unwrap();
+
+        assert_eq!(cache.resolutions(), 1);
+    }
+
+    #[tokio::test]
+    async fn holder_is_resolved_again_after_ttl() {
+        let cache = DidResolutionCache::new(0);
+        let builder = builder(crate::tests::load_signer()).with_resolver_cache(cache.clone());
+
+        builder.issue_presentation(vec![]).await.unwrap();
+        builder.issue_presentation(vec![]).await.unwrap();
+
+        assert_eq!(cache.resolutions(), 2);
+    }
+
+    /// A signer for `jwk`, signing with `algorithm`.
+    fn key_signer(jwk: JWK, algorithm: JwkAlgorithm) -> KeySigner {
+        KeySigner {
+            jwk: JWK {
+                algorithm: Some(algorithm),
+                ..jwk
+            },
+        }
+    }
+
+    /// Whether `builder` signs for a verification method controlled by `controller`.
+    async fn signs_for(builder: &JsonLdPresentationBuilder, controller: &str) -> bool {
+        let method: GenericVerificationMethod = serde_json::from_value(serde_json::json!({
+            "id": format!("{controller}-vm"),
+            "type": "Multikey",
+            "controller": controller,
+        }))
+        .unwrap();
+
+        ssi::verification_methods::Signer::for_method(builder, std::borrow::Cow::Owned(method))
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    #[rstest]
+    #[case::ed25519(JWK::generate_ed25519().unwrap(), JwkAlgorithm::EdDSA)]
+    #[case::secp256k1(JWK::generate_secp256k1(), JwkAlgorithm::ES256K)]
+    #[tokio::test]
+    async fn did_key_controller_with_fragment(#[case] jwk: JWK, #[case] algorithm: JwkAlgorithm) {
+        let signer = key_signer(jwk, algorithm);
+        let did = signer.did();
+        let fragment = did.trim_start_matches("did:key:").to_string();
+        let builder = builder(signer);
+
+        assert!(signs_for(&builder, &format!("{did}#{fragment}")).await);
+        assert!(signs_for(&builder, &did).await);
+    }
+
+    #[rstest]
+    #[case::ed25519_key_signing_es256k(JWK::generate_ed25519().unwrap(), JwkAlgorithm::ES256K)]
+    #[case::secp256k1_key_signing_eddsa(JWK::generate_secp256k1(), JwkAlgorithm::EdDSA)]
+    #[tokio::test]
+    async fn did_key_controller_of_other_algorithm(
+        #[case] jwk: JWK,
+        #[case] algorithm: JwkAlgorithm,
+    ) {
+        let signer = key_signer(jwk, algorithm);
+        let did = signer.did();
+        let fragment = did.trim_start_matches("did:key:").to_string();
+        let builder = builder(signer);
+
+        assert!(!signs_for(&builder, &format!("{did}#{fragment}")).await);
+    }
+}
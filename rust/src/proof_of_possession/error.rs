@@ -15,4 +15,13 @@ pub enum PopError {
 
     #[error("{_0}")]
     ConversionError(#[from] oid4vci::proof_of_possession::ConversionError),
+
+    #[error("{_0}")]
+    CryptoError(#[from] crate::crypto::CryptoError),
+
+    #[error("unsupported proof of possession key: {_0}")]
+    UnsupportedKey(String),
+
+    #[error("unsupported signature encoding")]
+    SignatureEncoding,
 }
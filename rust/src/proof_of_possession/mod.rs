@@ -7,12 +7,16 @@ use oid4vci::{
     },
     types::Nonce,
 };
-use ssi::jwk::JWK;
+use ssi::jwk::{Params, JWK};
 use url::Url;
 
 pub use error::*;
 
-use crate::{did, oid4vci::Oid4vciError};
+use crate::{
+    crypto::{parse_jwk, CryptoCurveUtils, SigningKey},
+    did,
+    oid4vci::Oid4vciError,
+};
 
 mod error;
 
@@ -68,3 +72,115 @@ pub fn generate_pop_complete(
     ]
     .join("."))
 }
+
+/// The `typ` header of an OID4VCI proof of possession JWT.
+const POP_JWT_TYPE: &str = "openid4vci-proof+jwt";
+
+/// Create an OID4VCI proof of possession JWT, signed by `signer`.
+///
+/// The public key of `signer` is carried in the `jwk` header, for issuers that bind the
+/// credential to a key rather than a DID. Only P-256 keys are supported. `issued_at` is a
+/// UNIX timestamp in seconds, and defaults to now.
+#[uniffi::export]
+pub fn create_pop_jwt(
+    signer: &dyn SigningKey,
+    audience: String,
+    nonce: String,
+    issued_at: Option<i64>,
+) -> Result<String, PopError> {
+    let jwk = parse_jwk(&signer.jwk()?)?.to_public();
+    match &jwk.params {
+        Params::EC(ec) if ec.curve.as_deref() == Some("P-256") => {}
+        _ => {
+            return Err(PopError::UnsupportedKey(
+                "only P-256 keys are supported".into(),
+            ))
+        }
+    }
+
+    let header = serde_json::json!({
+        "alg": "ES256",
+        "typ": POP_JWT_TYPE,
+        "jwk": jwk,
+    });
+    let claims = serde_json::json!({
+        "aud": audience,
+        "iat": issued_at.unwrap_or_else(|| time::OffsetDateTime::now_utc().unix_timestamp()),
+        "nonce": nonce,
+    });
+
+    let signing_input = format!(
+        "{}.{}",
+        BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header)?),
+        BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims)?),
+    );
+
+    let signature = CryptoCurveUtils::secp256r1()
+        .ensure_raw_fixed_width_signature_encoding(signer.sign(signing_input.as_bytes().to_vec())?)
+        .ok_or(PopError::SignatureEncoding)?;
+
+    Ok(format!(
+        "{signing_input}.{}",
+        BASE64_URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+
+    use super::*;
+    use crate::crypto::{KeyAlias, KeyStore, RustTestKeyManager};
+
+    #[tokio::test]
+    async fn pop_jwt_header_and_claims() {
+        let key_manager = RustTestKeyManager::default();
+        let key_alias = KeyAlias("pop".to_string());
+        key_manager
+            .generate_p256_signing_key(key_alias.clone())
+            .await
+            .unwrap();
+        let signer = key_manager.get_signing_key(key_alias).unwrap();
+
+        let jwt = create_pop_jwt(
+            signer.as_ref(),
+            "https://issuer.example.com".to_string(),
+            "c_nonce".to_string(),
+            Some(1_700_000_000),
+        )
+        .unwrap();
+
+        let decode = |segment: &str| -> serde_json::Value {
+            serde_json::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(segment).unwrap()).unwrap()
+        };
+        let [header, claims, signature] = jwt.split('.').collect::<Vec<_>>()[..] else {
+            panic!("not a compact JWS: {jwt}");
+        };
+        let (header, claims) = (decode(header), decode(claims));
+
+        assert_eq!(header["alg"], "ES256");
+        assert_eq!(header["typ"], POP_JWT_TYPE);
+        assert!(header["jwk"].get("d").is_none());
+        assert_eq!(claims["aud"], "https://issuer.example.com");
+        assert_eq!(claims["nonce"], "c_nonce");
+        assert_eq!(claims["iat"], 1_700_000_000);
+
+        let jwk: JWK = serde_json::from_value(header["jwk"].clone()).unwrap();
+        let Params::EC(ec) = jwk.params else {
+            panic!("not an EC key: {jwk}");
+        };
+        let point = p256::EncodedPoint::from_affine_coordinates(
+            ec.x_coordinate.unwrap().0.as_slice().into(),
+            ec.y_coordinate.unwrap().0.as_slice().into(),
+            false,
+        );
+        let signing_input = jwt.rsplit_once('.').unwrap().0;
+        VerifyingKey::from_encoded_point(&point)
+            .unwrap()
+            .verify(
+                signing_input.as_bytes(),
+                &Signature::from_slice(&BASE64_URL_SAFE_NO_PAD.decode(signature).unwrap()).unwrap(),
+            )
+            .unwrap();
+    }
+}
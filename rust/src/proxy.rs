@@ -0,0 +1,60 @@
+//! HTTP proxy configuration shared by the HTTP clients built in this crate.
+
+use std::sync::RwLock;
+
+use url::Url;
+
+static HTTP_PROXY: RwLock<Option<Url>> = RwLock::new(None);
+
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum HttpProxyError {
+    #[error("invalid proxy URL: {0}")]
+    InvalidProxy(String),
+    #[error("failed to acquire lock on the HTTP proxy configuration")]
+    Lock,
+}
+
+/// Route all HTTP clients created after this call through the given proxy,
+/// or disable proxying by passing `None`.
+///
+/// The proxy is used for both HTTP and HTTPS traffic. Clients that were
+/// created before this call keep the configuration they were built with.
+#[uniffi::export]
+pub fn configure_http_proxy(proxy_url: Option<Url>) -> Result<(), HttpProxyError> {
+    if let Some(url) = &proxy_url {
+        reqwest::Proxy::all(url.as_str())
+            .map_err(|e| HttpProxyError::InvalidProxy(format!("{e:?}")))?;
+    }
+
+    *HTTP_PROXY.write().map_err(|_| HttpProxyError::Lock)? = proxy_url;
+
+    Ok(())
+}
+
+/// The currently configured HTTP proxy, if any.
+#[uniffi::export]
+pub fn http_proxy() -> Option<Url> {
+    HTTP_PROXY.read().ok().and_then(|proxy| proxy.clone())
+}
+
+/// Returns a client builder that uses the globally configured proxy.
+pub(crate) fn client_builder() -> Result<reqwest::ClientBuilder, HttpProxyError> {
+    client_builder_with_proxy(http_proxy().as_ref())
+}
+
+/// Returns a client builder that uses the given proxy, ignoring the global
+/// configuration.
+pub(crate) fn client_builder_with_proxy(
+    proxy_url: Option<&Url>,
+) -> Result<reqwest::ClientBuilder, HttpProxyError> {
+    let builder = reqwest::Client::builder().use_rustls_tls();
+
+    let Some(url) = proxy_url else {
+        return Ok(builder);
+    };
+
+    let proxy = reqwest::Proxy::all(url.as_str())
+        .map_err(|e| HttpProxyError::InvalidProxy(format!("{e:?}")))?;
+
+    Ok(builder.proxy(proxy))
+}
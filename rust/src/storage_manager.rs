@@ -32,6 +32,13 @@ pub enum StorageManagerError {
     InternalError,
 }
 
+/// A key-value pair to be stored with [StorageManagerInterface::add_many].
+#[derive(Debug, PartialEq, uniffi::Record)]
+pub struct StorageEntry {
+    pub key: Key,
+    pub value: Value,
+}
+
 /// Interface: StorageManagerInterface
 ///
 /// The StorageManagerInterface provides access to functions defined in Kotlin and Swift for
@@ -63,9 +70,22 @@ pub trait StorageManagerInterface: Send + Sync + Debug {
 
     /// Function: list
     ///
-    /// Callback function pointer for listing available keys.
+    /// Callback function pointer for listing available keys, without loading their values.
+    /// Callers interested in a subset of keys can filter them with [Key::strip_prefix].
     async fn list(&self) -> Result<Vec<Key>, StorageManagerError>;
 
+    /// Function: contains
+    ///
+    /// Checks whether a value is stored under a key.  Defaults to retrieving the value with
+    /// [StorageManagerInterface::get], implementations should override it when the existence
+    /// of a key can be checked without loading its value.
+    ///
+    /// Arguments:
+    /// key - The key to look up
+    async fn contains(&self, key: Key) -> Result<bool, StorageManagerError> {
+        Ok(self.get(key).await?.is_some())
+    }
+
     /// Function: remove
     ///
     /// Callback function pointer to native (kotlin/swift) code for
@@ -73,4 +93,33 @@ pub trait StorageManagerInterface: Send + Sync + Debug {
     /// particular, it must treat removing a non-existent key as a normal and
     /// expected circumstance, simply returning () and not an error.
     async fn remove(&self, key: Key) -> Result<(), StorageManagerError>;
+
+    /// Function: add_many
+    ///
+    /// Adds several key-value pairs to storage.  Implementations should apply the entries
+    /// atomically, so a failure leaves none of them stored.  Defaults to adding each entry in
+    /// turn, which is not atomic.
+    ///
+    /// Arguments:
+    /// entries - The key-value pairs to add
+    async fn add_many(&self, entries: Vec<StorageEntry>) -> Result<(), StorageManagerError> {
+        for StorageEntry { key, value } in entries {
+            self.add(key, value).await?;
+        }
+        Ok(())
+    }
+
+    /// Function: remove_many
+    ///
+    /// Removes several keys from storage.  Implementations should apply the removals
+    /// atomically.  Defaults to removing each key in turn, which is not atomic.
+    ///
+    /// Arguments:
+    /// keys - The keys to remove
+    async fn remove_many(&self, keys: Vec<Key>) -> Result<(), StorageManagerError> {
+        for key in keys {
+            self.remove(key).await?;
+        }
+        Ok(())
+    }
 }
@@ -260,6 +260,7 @@ pub async fn test_vc_playground_oid4vp() {
         trusted_dids,
         Box::new(signer),
         Some(default_ld_json_context()),
+        None,
     )
     .await
     .expect("Failed to create holder");
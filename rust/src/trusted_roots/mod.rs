@@ -15,6 +15,34 @@ pub fn trusted_roots() -> uniffi::deps::anyhow::Result<Vec<Certificate>> {
     .collect()
 }
 
+/// The built-in trusted roots, followed by the caller-supplied PEM certificates.
+pub fn trusted_roots_with(
+    additional_pem: Vec<String>,
+) -> uniffi::deps::anyhow::Result<Vec<Certificate>> {
+    let mut roots = trusted_roots()?;
+    roots.extend(parse_trusted_roots(additional_pem)?);
+    Ok(roots)
+}
+
+/// Only the caller-supplied PEM certificates, without the built-in trusted roots.
+///
+/// Each entry may contain one or more PEM-encoded certificates.
+pub fn parse_trusted_roots(pem: Vec<String>) -> uniffi::deps::anyhow::Result<Vec<Certificate>> {
+    pem.iter()
+        .enumerate()
+        .map(|(i, pem)| {
+            let certificates = Certificate::load_pem_chain(pem.as_bytes()).map_err(|e| {
+                anyhow::anyhow!("could not load trusted root certificate {i} from PEM: {e}")
+            })?;
+            if certificates.is_empty() {
+                anyhow::bail!("no certificate found in trusted root PEM {i}")
+            }
+            Ok(certificates)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map(|certificates| certificates.into_iter().flatten().collect())
+}
+
 fn load_spruce_county_prod_root_certificate() -> anyhow::Result<Certificate> {
     Certificate::from_der(SPRUCE_COUNTY_PROD_ROOT_CERTIFICATE_DER)
         .map_err(|e| anyhow::anyhow!("could not load the root certificate: {e}"))
@@ -29,3 +57,37 @@ fn load_spruce_county_dev_root_certificate() -> anyhow::Result<Certificate> {
     Certificate::from_der(SPRUCE_COUNTY_DEV_ROOT_CERTIFICATE_DER)
         .map_err(|e| anyhow::anyhow!("could not load the root certificate: {e}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPRUCE_COUNTY_DEV_ROOT_CERTIFICATE_PEM: &str = include_str!("./spruce_county_dev.pem");
+
+    #[test]
+    fn parse_valid_pem() {
+        let roots =
+            parse_trusted_roots(vec![SPRUCE_COUNTY_DEV_ROOT_CERTIFICATE_PEM.into()]).unwrap();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0], load_spruce_county_dev_root_certificate().unwrap());
+    }
+
+    #[test]
+    fn trusted_roots_with_appends_to_built_in() {
+        let roots =
+            trusted_roots_with(vec![SPRUCE_COUNTY_DEV_ROOT_CERTIFICATE_PEM.into()]).unwrap();
+        assert_eq!(roots.len(), trusted_roots().unwrap().len() + 1);
+    }
+
+    #[test]
+    fn reject_malformed_pem() {
+        let truncated = SPRUCE_COUNTY_DEV_ROOT_CERTIFICATE_PEM
+            .lines()
+            .take(3)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert!(parse_trusted_roots(vec!["not a certificate".into()]).is_err());
+        assert!(parse_trusted_roots(vec![truncated]).is_err());
+    }
+}
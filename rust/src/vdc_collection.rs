@@ -9,9 +9,6 @@ use thiserror::Error;
 use tracing::info;
 use uuid::Uuid;
 
-/// Internal prefix for credential keys.
-const KEY_PREFIX: &str = "Credential.";
-
 #[derive(uniffi::Object)]
 /// Verifiable Digital Credential Collection
 ///
@@ -91,8 +88,8 @@ impl VdcCollection {
         }
     }
 
-    /// Get a list of all the credentials.
-    pub async fn all_entries(&self) -> Result<Vec<Uuid>, VdcCollectionError> {
+    /// Get a list of the ids of all the credentials.
+    pub async fn all_ids(&self) -> Result<Vec<Uuid>, VdcCollectionError> {
         self.storage
             .list()
             .await
@@ -100,13 +97,26 @@ impl VdcCollection {
             .map_err(VdcCollectionError::LoadFailed)
     }
 
+    /// Get a list of all the credentials.
+    ///
+    /// Fails if any of the credentials cannot be loaded.
+    pub async fn all_entries(&self) -> Result<Vec<Credential>, VdcCollectionError> {
+        let mut credentials = vec![];
+        for id in self.all_ids().await? {
+            if let Some(credential) = self.get(id).await? {
+                credentials.push(credential);
+            }
+        }
+        Ok(credentials)
+    }
+
     /// Get a list of all the credentials that match a specified type.
     pub async fn all_entries_by_type(
         &self,
         ctype: &CredentialType,
     ) -> Result<Vec<Uuid>, VdcCollectionError> {
-        let all_entries = self.all_entries().await?;
-        Ok(futures::stream::iter(all_entries.into_iter())
+        let all_ids = self.all_ids().await?;
+        Ok(futures::stream::iter(all_ids.into_iter())
             .filter_map(|id| async move { self.get(id).await.ok().flatten() })
             .collect::<Vec<Credential>>()
             .await
@@ -116,9 +126,23 @@ impl VdcCollection {
             .collect::<Vec<Uuid>>())
     }
 
+    /// Remove all the credentials that match a specified type.
+    ///
+    /// Returns the number of credentials removed.
+    pub async fn delete_by_type(
+        &self,
+        credential_type: CredentialType,
+    ) -> Result<u32, VdcCollectionError> {
+        let ids = self.all_entries_by_type(&credential_type).await?;
+        for id in &ids {
+            self.delete(*id).await?;
+        }
+        Ok(ids.len() as u32)
+    }
+
     /// Dump the contents of the credential set to the logger.
     pub async fn dump(&self) {
-        match self.all_entries().await {
+        match self.all_ids().await {
             Ok(list) => {
                 for key in list {
                     if let Ok(x) = self.get(key).await {
@@ -134,14 +158,14 @@ impl VdcCollection {
 impl VdcCollection {
     /// Convert a UUID to a storage key.
     fn id_to_key(id: Uuid) -> Key {
-        Key(format!("{}{}", KEY_PREFIX, id))
+        Key::namespaced(KeyNamespace::Credential, &id.to_string())
     }
 
     /// Convert a string ref to a storage key.
     ///
     /// Returns `None` if it's not the right format.
     fn key_to_id(key: &Key) -> Option<Uuid> {
-        key.strip_prefix(KEY_PREFIX)
+        key.strip_prefix(KeyNamespace::Credential.prefix())
             .map(|id| Uuid::parse_str(&id))
             .transpose()
             .ok()
@@ -158,7 +182,7 @@ mod tests {
     async fn test_vdc() {
         let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
         let vdc = VdcCollection::new(smi);
-        for id in vdc.all_entries().await.unwrap() {
+        for id in vdc.all_ids().await.unwrap() {
             vdc.delete(id).await.unwrap();
         }
         let payload_1: Vec<u8> = "Some random collection of bytes. ⚛".into();
@@ -211,13 +235,13 @@ mod tests {
             .await
             .expect("Failed to get the third value");
 
-        assert!(vdc.all_entries().await.unwrap().len() == 3);
+        assert!(vdc.all_ids().await.unwrap().len() == 3);
 
         vdc.delete(credential_2.id)
             .await
             .expect("Failed to delete the second value.");
 
-        assert!(vdc.all_entries().await.unwrap().len() == 2);
+        assert!(vdc.all_ids().await.unwrap().len() == 2);
 
         vdc.delete(credential_1.id)
             .await
@@ -226,6 +250,48 @@ mod tests {
             .await
             .expect("Failed to delete the third value.");
 
-        assert!(vdc.all_entries().await.unwrap().is_empty());
+        assert!(vdc.all_ids().await.unwrap().is_empty());
+    }
+
+    fn credential(credential_type: &str) -> Credential {
+        Credential {
+            id: Uuid::new_v4(),
+            format: CredentialFormat::MsoMdoc,
+            r#type: CredentialType(credential_type.into()),
+            payload: Uuid::new_v4().as_bytes().to_vec(),
+            key_alias: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn list_and_delete_by_type() {
+        let smi: Arc<dyn StorageManagerInterface> = Arc::new(LocalStore::new());
+        let vdc = VdcCollection::new(smi);
+
+        let mdl_1 = credential("org.iso.18013.5.1.mDL");
+        let mdl_2 = credential("org.iso.18013.5.1.mDL");
+        let pid = credential("eu.europa.ec.eudi.pid.1");
+        for credential in [&mdl_1, &mdl_2, &pid] {
+            vdc.add(credential).await.unwrap();
+        }
+
+        let mut ids = vdc.all_ids().await.unwrap();
+        ids.sort();
+        let mut expected = vec![mdl_1.id, mdl_2.id, pid.id];
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        let entries = vdc.all_entries().await.unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries
+            .iter()
+            .any(|entry| entry.id == pid.id && entry.payload == pid.payload));
+
+        let removed = vdc
+            .delete_by_type(CredentialType("org.iso.18013.5.1.mDL".into()))
+            .await
+            .unwrap();
+        assert_eq!(removed, 2);
+        assert_eq!(vdc.all_ids().await.unwrap(), vec![pid.id]);
     }
 }
@@ -0,0 +1,314 @@
+use std::sync::Arc;
+
+use crate::{
+    credential::{
+        cwt::leeway_from_secs,
+        json_vc::{did_verification_parameters, DidVerificationParameters},
+        ParsedCredential, ParsedCredentialInner,
+    },
+    did::{CachingDidResolver, ConfiguredDidResolver, DidResolutionCache, DidResolverConfig},
+    trusted_roots,
+    verifier::{crypto::Crypto, offline::OfflinePolicy, outcome::VerificationReport},
+};
+use time::Duration;
+use x509_cert::Certificate;
+
+/// The time-to-live of DID documents cached during a batch, when no [DidResolutionCache] is
+/// given.
+const BATCH_DID_CACHE_TTL_SECS: u64 = 300;
+
+/// Options for [verify_credentials_batch].
+#[derive(uniffi::Record, Debug, Clone, Default)]
+pub struct BatchVerificationOptions {
+    /// Trust the built-in root certificates in addition to the provided trust store.
+    pub include_built_in_roots: bool,
+    /// Seconds by which validity periods, e.g. CWT `exp` and `nbf` claims, may be off from
+    /// the device clock.
+    pub leeway_secs: u64,
+    /// Whether DIDs may be resolved over the network. Offline, only credentials trusted
+    /// through a certificate chain can be verified.
+    pub offline_policy: OfflinePolicy,
+    /// How issuer DIDs are resolved, e.g. from cached `did:web` documents.
+    pub did_resolver_config: DidResolverConfig,
+    /// A cache of resolved DID documents to share with other batches. Each batch otherwise
+    /// has its own cache, so that an issuer shared by several credentials is resolved once.
+    pub did_resolution_cache: Option<Arc<DidResolutionCache>>,
+}
+
+#[derive(thiserror::Error, uniffi::Error, Debug)]
+pub enum BatchVerificationError {
+    #[error("failed to load the trust store: {0}")]
+    TrustStore(String),
+    #[error("invalid DID resolver configuration: {0}")]
+    DidResolverConfig(String),
+}
+
+/// The outcome of verifying one credential of a batch.
+#[derive(uniffi::Enum, Debug, Clone, PartialEq)]
+pub enum CredentialVerification {
+    /// The credential was verified.
+    Verified { report: VerificationReport },
+    /// The credential is in a format that cannot be verified in a batch.
+    UnsupportedFormat { format: String },
+    /// The credential failed verification.
+    Failed { reason: String },
+}
+
+/// Verify several credentials, e.g. those of a single presentation.
+///
+/// The trust store is parsed and the DID resolver set up once, and are then shared by every
+/// credential, resolved DID documents being cached. `trust_store` contains PEM-encoded root
+/// certificates, used for mdocs and CWTs.
+///
+/// Every credential is verified, even after one fails, and their outcomes are returned in
+/// the order of `credentials`.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn verify_credentials_batch(
+    credentials: Vec<Arc<ParsedCredential>>,
+    crypto: &dyn Crypto,
+    trust_store: Vec<String>,
+    options: BatchVerificationOptions,
+) -> Result<Vec<CredentialVerification>, BatchVerificationError> {
+    let verifier = BatchVerifier::new(trust_store, options)?;
+
+    let mut outcomes = Vec::with_capacity(credentials.len());
+    for credential in &credentials {
+        outcomes.push(verifier.verify(credential, crypto).await);
+    }
+    Ok(outcomes)
+}
+
+/// Trust and resolution state shared across the credentials of a batch.
+struct BatchVerifier {
+    trusted_roots: Vec<Certificate>,
+    did_resolver: CachingDidResolver<ConfiguredDidResolver>,
+    params: DidVerificationParameters,
+    leeway: Duration,
+    offline_policy: OfflinePolicy,
+}
+
+impl BatchVerifier {
+    fn new(
+        trust_store: Vec<String>,
+        options: BatchVerificationOptions,
+    ) -> Result<Self, BatchVerificationError> {
+        let trusted_roots = if options.include_built_in_roots {
+            trusted_roots::trusted_roots_with(trust_store)
+        } else {
+            trusted_roots::parse_trusted_roots(trust_store)
+        }
+        .map_err(|e| BatchVerificationError::TrustStore(format!("{e:#}")))?;
+
+        let did_resolver = CachingDidResolver::new(
+            ConfiguredDidResolver::try_from(options.did_resolver_config)
+                .map_err(|e| BatchVerificationError::DidResolverConfig(e.to_string()))?,
+            Some(
+                options
+                    .did_resolution_cache
+                    .unwrap_or_else(|| DidResolutionCache::new(BATCH_DID_CACHE_TTL_SECS)),
+            ),
+        );
+
+        Ok(Self {
+            trusted_roots,
+            params: did_verification_parameters(did_resolver.clone()),
+            did_resolver,
+            leeway: leeway_from_secs(options.leeway_secs),
+            offline_policy: options.offline_policy,
+        })
+    }
+
+    async fn verify(
+        &self,
+        credential: &ParsedCredential,
+        crypto: &dyn Crypto,
+    ) -> CredentialVerification {
+        let report = match &credential.inner {
+            ParsedCredentialInner::LdpVc(json_vc) => {
+                match json_vc.check_offline_policy(self.offline_policy) {
+                    Ok(()) => json_vc
+                        .verify_with(&self.params, self.leeway)
+                        .await
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            ParsedCredentialInner::MsoMdoc(mdoc) => mdoc
+                .verify_issuer_signature_with_roots(crypto, &self.trusted_roots)
+                .map_err(|e| e.to_string()),
+            ParsedCredentialInner::Cwt(cwt) => cwt
+                .validate(
+                    crypto,
+                    &self.trusted_roots,
+                    &self.did_resolver,
+                    self.leeway,
+                    self.offline_policy,
+                )
+                .await
+                .map_err(|e| e.to_string()),
+            ParsedCredentialInner::JwtVcJson(_)
+            | ParsedCredentialInner::JwtVcJsonLd(_)
+            | ParsedCredentialInner::VCDM2SdJwt(_) => {
+                return CredentialVerification::UnsupportedFormat {
+                    format: credential.format().to_string(),
+                }
+            }
+        };
+
+        match report {
+            Ok(report) => CredentialVerification::Verified { report },
+            Err(reason) => CredentialVerification::Failed { reason },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::Value as Json;
+    use ssi::{
+        claims::{
+            data_integrity::{AnySuite, CryptographicSuite, ProofOptions},
+            vc::v2::JsonCredential,
+        },
+        dids::{DIDKey, DIDResolver as _},
+        json_ld::IriBuf,
+        verification_methods::SingleSecretSigner,
+        JWK,
+    };
+
+    use super::*;
+    use crate::{credential::json_vc::JsonVc, verifier::crypto::VerificationResult};
+
+    const ISSUER_DID: &str = "did:web:issuer.example.com";
+
+    /// Data-integrity proofs are verified without [Crypto], so every call is refused.
+    struct NoCrypto;
+
+    impl Crypto for NoCrypto {
+        fn p256_verify(&self, _: Vec<u8>, _: Vec<u8>, _: Vec<u8>) -> VerificationResult {
+            VerificationResult::Failure {
+                cause: "unsupported".into(),
+            }
+        }
+
+        fn k256_verify(&self, _: Vec<u8>, _: Vec<u8>, _: Vec<u8>) -> VerificationResult {
+            VerificationResult::Failure {
+                cause: "unsupported".into(),
+            }
+        }
+
+        fn ed25519_verify(&self, _: Vec<u8>, _: Vec<u8>, _: Vec<u8>) -> VerificationResult {
+            VerificationResult::Failure {
+                cause: "unsupported".into(),
+            }
+        }
+    }
+
+    /// Issue `count` credentials signed by the same `did:web` issuer, whose DID document is
+    /// given by the returned configuration.
+    async fn credentials_from_one_issuer(count: usize) -> (Vec<Json>, DidResolverConfig) {
+        let issuer_jwk = JWK::generate_ed25519().unwrap();
+        let did_key = DIDKey::generate(&issuer_jwk).unwrap();
+        let vm = format!("{ISSUER_DID}#key-1");
+        let document = serde_json::json!({
+            "@context": [
+                "https://www.w3.org/ns/did/v1",
+                "https://w3id.org/security/multikey/v1"
+            ],
+            "id": ISSUER_DID,
+            "verificationMethod": [{
+                "id": vm,
+                "type": "Multikey",
+                "controller": ISSUER_DID,
+                "publicKeyMultibase": did_key.as_str().trim_start_matches("did:key:"),
+            }],
+            "assertionMethod": [vm],
+        });
+        let config = DidResolverConfig {
+            did_web_documents: HashMap::from([(ISSUER_DID.to_string(), document.to_string())]),
+        };
+        let resolver = ConfiguredDidResolver::try_from(config.clone()).unwrap();
+
+        let mut credentials = Vec::with_capacity(count);
+        for i in 0..count {
+            let credential: JsonCredential = serde_json::from_value(serde_json::json!({
+                "@context": ["https://www.w3.org/ns/credentials/v2"],
+                "type": ["VerifiableCredential"],
+                "issuer": ISSUER_DID,
+                "credentialSubject": { "name": format!("Subject {i}") },
+            }))
+            .unwrap();
+
+            let signed = AnySuite::EdDsaRdfc2022
+                .sign(
+                    credential,
+                    resolver.clone().into_vm_resolver(),
+                    SingleSecretSigner::new(issuer_jwk.clone()).into_local(),
+                    ProofOptions::from_method(IriBuf::new(vm.clone()).unwrap().into()),
+                )
+                .await
+                .unwrap();
+            credentials.push(serde_json::to_value(&signed).unwrap());
+        }
+        (credentials, config)
+    }
+
+    fn parsed(json: &Json) -> Arc<ParsedCredential> {
+        ParsedCredential::new_ldp_vc(JsonVc::new_from_json(json.to_string()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn verify_credentials_sharing_an_issuer() {
+        let (credentials, did_resolver_config) = credentials_from_one_issuer(3).await;
+        let cache = DidResolutionCache::new(BATCH_DID_CACHE_TTL_SECS);
+
+        let outcomes = verify_credentials_batch(
+            credentials.iter().map(parsed).collect(),
+            &NoCrypto,
+            vec![],
+            BatchVerificationOptions {
+                did_resolver_config,
+                did_resolution_cache: Some(cache.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcomes.len(), 3);
+        assert!(outcomes.iter().all(|outcome| matches!(
+            outcome,
+            CredentialVerification::Verified { report } if report.warnings.is_empty()
+        )));
+        assert_eq!(cache.resolutions(), 1);
+    }
+
+    #[tokio::test]
+    async fn failing_credential_does_not_stop_the_batch() {
+        let (mut credentials, did_resolver_config) = credentials_from_one_issuer(3).await;
+        credentials[1]["credentialSubject"]["name"] = Json::String("Tampered".into());
+
+        let outcomes = verify_credentials_batch(
+            credentials.iter().map(parsed).collect(),
+            &NoCrypto,
+            vec![],
+            BatchVerificationOptions {
+                did_resolver_config,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            outcomes.as_slice(),
+            [
+                CredentialVerification::Verified { .. },
+                CredentialVerification::Failed { .. },
+                CredentialVerification::Verified { .. },
+            ]
+        ));
+    }
+}
@@ -1,4 +1,6 @@
-use uniffi::deps::anyhow::{anyhow, Context};
+use cose_rs::CoseSign1;
+use ssi::claims::cose::coset;
+use uniffi::deps::anyhow::{self, anyhow, bail, Context};
 use x509_cert::der::{asn1, Encode};
 
 #[uniffi::export(with_foreign)]
@@ -9,6 +11,22 @@ pub trait Crypto: Send + Sync {
         payload: Vec<u8>,
         signature: Vec<u8>,
     ) -> VerificationResult;
+
+    /// Verify a DER-encoded ECDSA signature over secp256k1 with SHA-256.
+    fn k256_verify(
+        &self,
+        certificate_der: Vec<u8>,
+        payload: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> VerificationResult;
+
+    /// Verify a raw 64-byte Ed25519 signature.
+    fn ed25519_verify(
+        &self,
+        certificate_der: Vec<u8>,
+        payload: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> VerificationResult;
 }
 
 impl Crypto for Box<dyn Crypto> {
@@ -20,6 +38,24 @@ impl Crypto for Box<dyn Crypto> {
     ) -> VerificationResult {
         Crypto::p256_verify(self.as_ref(), certificate_der, payload, signature)
     }
+
+    fn k256_verify(
+        &self,
+        certificate_der: Vec<u8>,
+        payload: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> VerificationResult {
+        Crypto::k256_verify(self.as_ref(), certificate_der, payload, signature)
+    }
+
+    fn ed25519_verify(
+        &self,
+        certificate_der: Vec<u8>,
+        payload: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> VerificationResult {
+        Crypto::ed25519_verify(self.as_ref(), certificate_der, payload, signature)
+    }
 }
 
 #[derive(Debug, uniffi::Enum)]
@@ -37,67 +73,342 @@ impl VerificationResult {
     }
 }
 
-/// A verifier for CoseSign objects with ECDSA + P-256 signatures.
-pub struct CoseP256Verifier<'a> {
+/// The COSE signature algorithms supported by [CoseVerifier].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoseAlgorithm {
+    /// ECDSA over P-256 with SHA-256.
+    ES256,
+    /// ECDSA over secp256k1 with SHA-256.
+    ES256K,
+    /// EdDSA over Ed25519.
+    EdDSA,
+}
+
+impl CoseAlgorithm {
+    /// Resolve the algorithm from its COSE (IANA) identifier.
+    pub fn from_identifier(alg: i128) -> Option<Self> {
+        match alg {
+            -7 => Some(Self::ES256),
+            -47 => Some(Self::ES256K),
+            -8 => Some(Self::EdDSA),
+            _ => None,
+        }
+    }
+
+    /// Resolve the algorithm from the `alg` (label 1) of a cose-rs protected header.
+    pub fn from_cose_sign1(cose_sign1: &CoseSign1) -> anyhow::Result<Self> {
+        match cose_sign1
+            .protected()
+            .get_i(1)
+            .context("alg (label '1') is not in the protected header")?
+        {
+            serde_cbor::Value::Integer(alg) => Self::from_identifier(*alg)
+                .with_context(|| format!("unsupported COSE algorithm: {alg}")),
+            v => bail!("unexpected format for alg: {v:?}"),
+        }
+    }
+
+    /// Resolve the algorithm from the `alg` of a coset header.
+    pub fn from_coset_header(header: &coset::Header) -> anyhow::Result<Self> {
+        match &header.alg {
+            Some(coset::RegisteredLabelWithPrivate::Assigned(alg)) => {
+                Self::from_identifier(*alg as i128)
+                    .with_context(|| format!("unsupported COSE algorithm: {alg:?}"))
+            }
+            Some(alg) => bail!("unsupported COSE algorithm: {alg:?}"),
+            None => bail!("alg is not in the protected header"),
+        }
+    }
+}
+
+/// A verifier for CoseSign objects, dispatching to the [Crypto] verification method
+/// matching the algorithm of the COSE header.
+pub struct CoseVerifier<'a> {
     pub crypto: &'a dyn Crypto,
     pub certificate_der: Vec<u8>,
+    pub algorithm: CoseAlgorithm,
 }
 
-/// A CoseSign ECDSA + P-256 signature.
-pub struct CoseP256Signature {
-    r: [u8; 32],
-    s: [u8; 32],
-}
+/// A CoseSign signature, in the raw encoding of its algorithm.
+pub struct CoseSignature(Vec<u8>);
 
-impl TryFrom<&[u8]> for CoseP256Signature {
+impl TryFrom<&[u8]> for CoseSignature {
     type Error = signature::Error;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let (r, s) = value.split_at(32);
-        Ok(Self {
-            r: r.try_into().map_err(|e| {
-                Self::Error::from_source(anyhow!("failed to parse 'r' parameter from slice: {e}"))
-            })?,
-            s: s.try_into().map_err(|e| {
-                Self::Error::from_source(anyhow!("failed to parse 's' parameter from slice: {e}"))
-            })?,
-        })
+        Ok(Self(value.to_vec()))
     }
 }
 
-impl cose_rs::algorithm::SignatureAlgorithm for CoseP256Verifier<'_> {
+impl cose_rs::algorithm::SignatureAlgorithm for CoseVerifier<'_> {
     fn algorithm(&self) -> cose_rs::algorithm::Algorithm {
-        cose_rs::algorithm::Algorithm::ES256
+        match self.algorithm {
+            CoseAlgorithm::ES256 => cose_rs::algorithm::Algorithm::ES256,
+            CoseAlgorithm::ES256K => cose_rs::algorithm::Algorithm::ES256K,
+            CoseAlgorithm::EdDSA => cose_rs::algorithm::Algorithm::EdDSA,
+        }
+    }
+}
+
+impl signature::Verifier<CoseSignature> for CoseVerifier<'_> {
+    fn verify(&self, msg: &[u8], signature: &CoseSignature) -> Result<(), signature::Error> {
+        let certificate_der = self.certificate_der.clone();
+        let signature = &signature.0;
+
+        let result = match self.algorithm {
+            CoseAlgorithm::ES256 => self.crypto.p256_verify(
+                certificate_der,
+                msg.to_vec(),
+                ecdsa_der_signature(signature).map_err(signature::Error::from_source)?,
+            ),
+            CoseAlgorithm::ES256K => self.crypto.k256_verify(
+                certificate_der,
+                msg.to_vec(),
+                ecdsa_der_signature(signature).map_err(signature::Error::from_source)?,
+            ),
+            CoseAlgorithm::EdDSA => {
+                if signature.len() != 64 {
+                    return Err(signature::Error::from_source(anyhow!(
+                        "expected a 64 byte Ed25519 signature, found {} bytes",
+                        signature.len()
+                    )));
+                }
+                self.crypto
+                    .ed25519_verify(certificate_der, msg.to_vec(), signature.clone())
+            }
+        };
+
+        result.into_result().map_err(signature::Error::from_source)
     }
 }
 
-impl signature::Verifier<CoseP256Signature> for CoseP256Verifier<'_> {
-    fn verify(&self, msg: &[u8], signature: &CoseP256Signature) -> Result<(), signature::Error> {
-        // Construct DER signature.
-        let mut seq: asn1::SequenceOf<asn1::Uint, 2> = asn1::SequenceOf::new();
-        seq.add(
-            asn1::Uint::new(&signature.r)
-                .context("unable to construct integer from signature parameter 'r'")
-                .map_err(signature::Error::from_source)?,
-        )
-        .context("unable to add signature parameter 'r' to the sequence")
-        .map_err(signature::Error::from_source)?;
-        seq.add(
-            asn1::Uint::new(&signature.s)
-                .context("unable to construct integer from signature parameter 's'")
-                .map_err(signature::Error::from_source)?,
-        )
-        .context("unable to add signature parameter 's' to the sequence")
-        .map_err(signature::Error::from_source)?;
-
-        let der_signature = seq
-            .to_der()
-            .context("unable to encode DER sequence")
-            .map_err(signature::Error::from_source)?;
-
-        self.crypto
-            .p256_verify(self.certificate_der.clone(), msg.to_vec(), der_signature)
-            .into_result()
-            .map_err(signature::Error::from_source)
+/// The `id-Ed25519` algorithm identifier of Ed25519 subject public keys (RFC 8410).
+const ED25519_OID: asn1::ObjectIdentifier = asn1::ObjectIdentifier::new_unwrap("1.3.101.112");
+
+/// A [Crypto] verifying P-256 and Ed25519 signatures in Rust, for verification that is not
+/// delegated to the platform. secp256k1 is refused.
+pub(crate) struct RustCrypto;
+
+impl Crypto for RustCrypto {
+    fn p256_verify(
+        &self,
+        certificate_der: Vec<u8>,
+        payload: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> VerificationResult {
+        use p256::ecdsa::{signature::Verifier as _, Signature, VerifyingKey};
+        use x509_cert::{der::Decode as _, Certificate};
+
+        let verify = || -> anyhow::Result<()> {
+            let certificate = Certificate::from_der(&certificate_der)?;
+            let key = VerifyingKey::from_sec1_bytes(
+                certificate
+                    .tbs_certificate
+                    .subject_public_key_info
+                    .subject_public_key
+                    .raw_bytes(),
+            )?;
+            key.verify(&payload, &Signature::from_der(&signature)?)?;
+            Ok(())
+        };
+        verification_result(verify())
+    }
+
+    fn k256_verify(&self, _: Vec<u8>, _: Vec<u8>, _: Vec<u8>) -> VerificationResult {
+        VerificationResult::Failure {
+            cause: "secp256k1 is not supported".into(),
+        }
+    }
+
+    fn ed25519_verify(
+        &self,
+        certificate_der: Vec<u8>,
+        payload: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> VerificationResult {
+        use ed25519_dalek::{Signature, Verifier as _, VerifyingKey};
+        use x509_cert::{der::Decode as _, Certificate};
+
+        let verify = || -> anyhow::Result<()> {
+            let certificate = Certificate::from_der(&certificate_der)?;
+            let spki = &certificate.tbs_certificate.subject_public_key_info;
+            if spki.algorithm.oid != ED25519_OID {
+                bail!(
+                    "expected an Ed25519 public key, found algorithm {}",
+                    spki.algorithm.oid
+                );
+            }
+            let public_key: [u8; 32] = spki
+                .subject_public_key
+                .raw_bytes()
+                .try_into()
+                .context("expected a 32 byte Ed25519 public key")?;
+            let key = VerifyingKey::from_bytes(&public_key)?;
+            key.verify(&payload, &Signature::from_slice(&signature)?)?;
+            Ok(())
+        };
+        verification_result(verify())
+    }
+}
+
+fn verification_result(result: anyhow::Result<()>) -> VerificationResult {
+    match result {
+        Ok(()) => VerificationResult::Success,
+        Err(e) => VerificationResult::Failure {
+            cause: e.to_string(),
+        },
+    }
+}
+
+/// Convert a raw `r || s` ECDSA signature over a 256-bit curve, as used by COSE,
+/// into its DER encoding.
+fn ecdsa_der_signature(signature: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if signature.len() != 64 {
+        bail!(
+            "expected a 64 byte ECDSA signature, found {} bytes",
+            signature.len()
+        );
+    }
+    let (r, s) = signature.split_at(32);
+
+    let mut seq: asn1::SequenceOf<asn1::Uint, 2> = asn1::SequenceOf::new();
+    seq.add(
+        asn1::Uint::new(r).context("unable to construct integer from signature parameter 'r'")?,
+    )
+    .context("unable to add signature parameter 'r' to the sequence")?;
+    seq.add(
+        asn1::Uint::new(s).context("unable to construct integer from signature parameter 's'")?,
+    )
+    .context("unable to add signature parameter 's' to the sequence")?;
+
+    seq.to_der().context("unable to encode DER sequence")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use cose_rs::sign1::VerificationResult as CoseVerificationResult;
+    use k256::ecdsa::{signature::Signer, Signature, SigningKey, VerifyingKey};
+    use serde_cbor::Value;
+
+    use super::*;
+
+    /// Verifies secp256k1 signatures with a known key, ignoring the certificate.
+    struct K256Crypto(VerifyingKey);
+
+    impl Crypto for K256Crypto {
+        fn p256_verify(&self, _: Vec<u8>, _: Vec<u8>, _: Vec<u8>) -> VerificationResult {
+            VerificationResult::Failure {
+                cause: "unsupported".into(),
+            }
+        }
+
+        fn k256_verify(
+            &self,
+            _certificate_der: Vec<u8>,
+            payload: Vec<u8>,
+            signature: Vec<u8>,
+        ) -> VerificationResult {
+            match Signature::from_der(&signature)
+                .and_then(|signature| signature::Verifier::verify(&self.0, &payload, &signature))
+            {
+                Ok(()) => VerificationResult::Success,
+                Err(e) => VerificationResult::Failure {
+                    cause: e.to_string(),
+                },
+            }
+        }
+
+        fn ed25519_verify(&self, _: Vec<u8>, _: Vec<u8>, _: Vec<u8>) -> VerificationResult {
+            VerificationResult::Failure {
+                cause: "unsupported".into(),
+            }
+        }
+    }
+
+    /// A COSE_Sign1 over `payload`, signed with ES256K.
+    fn es256k_cose_sign1(signing_key: &SigningKey, payload: &[u8]) -> CoseSign1 {
+        let protected = serde_cbor::to_vec(&Value::Map(BTreeMap::from([(
+            Value::Integer(1),
+            Value::Integer(-47),
+        )])))
+        .unwrap();
+        let sig_structure = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Text("Signature1".into()),
+            Value::Bytes(protected.clone()),
+            Value::Bytes(vec![]),
+            Value::Bytes(payload.to_vec()),
+        ]))
+        .unwrap();
+        let signature: Signature = signing_key.sign(&sig_structure);
+
+        let cose_sign1 = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(BTreeMap::new()),
+            Value::Bytes(payload.to_vec()),
+            Value::Bytes(signature.to_bytes().to_vec()),
+        ]))
+        .unwrap();
+        serde_cbor::from_slice(&cose_sign1).unwrap()
+    }
+
+    #[test]
+    fn verify_es256k_cose_sign1() {
+        let signing_key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let cose_sign1 = es256k_cose_sign1(&signing_key, b"payload");
+
+        let crypto = K256Crypto(*signing_key.verifying_key());
+        let verifier = CoseVerifier {
+            crypto: &crypto,
+            certificate_der: vec![],
+            algorithm: CoseAlgorithm::from_cose_sign1(&cose_sign1).unwrap(),
+        };
+
+        assert_eq!(verifier.algorithm, CoseAlgorithm::ES256K);
+        assert!(matches!(
+            cose_sign1.verify(&verifier, None, None),
+            CoseVerificationResult::Success
+        ));
+    }
+
+    #[test]
+    fn reject_es256k_cose_sign1_from_other_key() {
+        let cose_sign1 = es256k_cose_sign1(&SigningKey::from_slice(&[7; 32]).unwrap(), b"payload");
+
+        let other_key = SigningKey::from_slice(&[8; 32]).unwrap();
+        let crypto = K256Crypto(*other_key.verifying_key());
+        let verifier = CoseVerifier {
+            crypto: &crypto,
+            certificate_der: vec![],
+            algorithm: CoseAlgorithm::ES256K,
+        };
+
+        assert!(!matches!(
+            cose_sign1.verify(&verifier, None, None),
+            CoseVerificationResult::Success
+        ));
+    }
+
+    #[test]
+    fn reject_ed25519_signature_from_non_ed25519_certificate() {
+        use x509_cert::der::DecodePem as _;
+
+        let certificate = x509_cert::Certificate::from_pem(include_str!(
+            "../../tests/res/mdl/iaca-certificate.pem"
+        ))
+        .unwrap();
+
+        let result = RustCrypto.ed25519_verify(
+            certificate.to_der().unwrap(),
+            b"payload".to_vec(),
+            vec![0; 64],
+        );
+
+        assert!(matches!(
+            result,
+            VerificationResult::Failure { cause }
+                if cause.starts_with("expected an Ed25519 public key")
+        ));
     }
 }
@@ -1,15 +1,19 @@
 use std::time::SystemTime;
 
-use crate::verifier::outcome::{ClaimValue, Failure};
+use crate::verifier::{
+    crypto::Crypto,
+    outcome::{ClaimValue, Failure, VerificationWarning},
+};
 use cose_rs::{cwt::ClaimsSet, CoseSign1};
 use log::debug;
 use serde_cbor::Value;
+use ssi::claims::cose::coset;
 use time::Date;
 use time_macros::format_description;
 use uniffi::deps::anyhow::{bail, Context, Result};
 use x509_cert::{
-    der::{oid::AssociatedOid, Decode},
-    ext::pkix::{CrlDistributionPoints, KeyUsage},
+    der::{oid::AssociatedOid, Decode, Encode},
+    ext::pkix::{BasicConstraints, CrlDistributionPoints, KeyUsage},
     time::Validity,
     Certificate,
 };
@@ -34,6 +38,32 @@ pub fn get_signer_certificate(cwt: &CoseSign1) -> Result<Certificate> {
     Certificate::from_der(cert_der).context("signer certificate could not be parsed")
 }
 
+/// Extract the signer certificate from the x5chain (label 33) of a coset `COSE_Sign1`,
+/// as used by mdoc `IssuerAuth`, where the chain may be in either header and the signer
+/// certificate comes first.
+pub fn get_x5chain_signer_certificate(sign1: &coset::CoseSign1) -> Result<Certificate> {
+    let x5chain = sign1
+        .protected
+        .header
+        .rest
+        .iter()
+        .chain(sign1.unprotected.rest.iter())
+        .find_map(|(label, value)| (*label == coset::Label::Int(33)).then_some(value))
+        .context("x5chain (label '33') is not in the COSE_Sign1 headers")?;
+
+    let cert_der = match x5chain {
+        ciborium::Value::Bytes(der) => der,
+        ciborium::Value::Array(x5c) => match x5c.first() {
+            Some(ciborium::Value::Bytes(der)) => der,
+            Some(v) => bail!("unexpected format for x509 certificate: {v:?}"),
+            None => bail!("x5chain is empty"),
+        },
+        v => bail!("unexpected format for x5chain: {v:?}"),
+    };
+
+    Certificate::from_der(cert_der).context("signer certificate could not be parsed")
+}
+
 pub fn extract_extensions(certificate: &Certificate) -> Result<(KeyUsage, CrlDistributionPoints)> {
     let mut key_usage = None;
     let mut crl_dp = None;
@@ -48,6 +78,8 @@ pub fn extract_extensions(certificate: &Certificate) -> Result<(KeyUsage, CrlDis
         match extension.extn_id {
             KeyUsage::OID => key_usage = Some(&extension.extn_value),
             CrlDistributionPoints::OID => crl_dp = Some(&extension.extn_value),
+            // CA certificates mark basic constraints as critical, which is understood here.
+            BasicConstraints::OID => {}
             oid if extension.critical => bail!("unexpected critical extension: {oid}"),
             oid => {
                 debug!("skipping certificate extension {oid}")
@@ -101,6 +133,104 @@ pub trait Claim: Sized {
     fn from_value(value: &Value) -> crate::verifier::outcome::Result<Self>;
 }
 
+/// Certificates that expire within this many days produce a [VerificationWarning].
+pub const CERTIFICATE_EXPIRY_WARNING_DAYS: u64 = 30;
+
+/// Warn if a certificate is still valid, but expires within
+/// [CERTIFICATE_EXPIRY_WARNING_DAYS].
+pub fn expiry_warning(certificate: &Certificate) -> Option<VerificationWarning> {
+    let remaining = certificate
+        .tbs_certificate
+        .validity
+        .not_after
+        .to_system_time()
+        .duration_since(SystemTime::now())
+        .ok()?;
+    let expires_in_days = remaining.as_secs() / (60 * 60 * 24);
+
+    (expires_in_days < CERTIFICATE_EXPIRY_WARNING_DAYS).then(|| {
+        VerificationWarning::CertificateExpiringSoon {
+            subject: certificate.tbs_certificate.subject.to_string(),
+            expires_in_days,
+        }
+    })
+}
+
+/// Why a signer certificate does not chain to a trusted root.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum CertificateChainError {
+    #[error("{0}")]
+    UntrustedChain(String),
+    #[error("root certificate is expired or not yet valid")]
+    RootCertificateExpired,
+    #[error("signer certificate is expired or not yet valid")]
+    SignerCertificateExpired,
+    #[error("{0}")]
+    SignerCertificateInvalid(String),
+}
+
+/// Validate that the signer certificate is issued by one of the trusted roots, that both are
+/// within their validity period, and that their key usages permit signing certificates and
+/// digital signatures respectively. Returns the issuing root.
+pub(crate) fn validate_signer_certificate<'a>(
+    crypto: &dyn Crypto,
+    trusted_roots: &'a [Certificate],
+    signer_certificate: &Certificate,
+) -> Result<&'a Certificate, CertificateChainError> {
+    // Root validation.
+    let signer_issuer = &signer_certificate.tbs_certificate.issuer;
+    let root_certificate = trusted_roots
+        .iter()
+        .find(|root| &root.tbs_certificate.subject == signer_issuer)
+        .ok_or_else(|| {
+            CertificateChainError::UntrustedChain(format!(
+                "no trusted root certificate has the subject {signer_issuer}"
+            ))
+        })?;
+
+    check_validity(&root_certificate.tbs_certificate.validity)
+        .map_err(|_| CertificateChainError::RootCertificateExpired)?;
+
+    let (key_usage, _crl_dp) = extract_extensions(root_certificate)
+        .map_err(|e| CertificateChainError::UntrustedChain(format!("{e:#}")))?;
+    if !key_usage.key_cert_sign() {
+        return Err(CertificateChainError::UntrustedChain(
+            "root certificate cannot be used for verifying certificate signatures".into(),
+        ));
+    }
+
+    // Validate that Root issued Signer.
+    let root_der = root_certificate
+        .to_der()
+        .map_err(|e| CertificateChainError::UntrustedChain(e.to_string()))?;
+    let signer_tbs_der = signer_certificate
+        .tbs_certificate
+        .to_der()
+        .map_err(|e| CertificateChainError::SignerCertificateInvalid(e.to_string()))?;
+    crypto
+        .p256_verify(
+            root_der,
+            signer_tbs_der,
+            signer_certificate.signature.raw_bytes().to_vec(),
+        )
+        .into_result()
+        .map_err(CertificateChainError::UntrustedChain)?;
+
+    // Signer validation.
+    check_validity(&signer_certificate.tbs_certificate.validity)
+        .map_err(|_| CertificateChainError::SignerCertificateExpired)?;
+
+    let (key_usage, _crl_dp) = extract_extensions(signer_certificate)
+        .map_err(|e| CertificateChainError::SignerCertificateInvalid(format!("{e:#}")))?;
+    if !key_usage.digital_signature() {
+        return Err(CertificateChainError::SignerCertificateInvalid(
+            "certificate not for digital signature".into(),
+        ));
+    }
+
+    Ok(root_certificate)
+}
+
 pub fn check_validity(validity: &Validity) -> Result<()> {
     let nbf = validity.not_before.to_system_time();
     let exp = validity.not_after.to_system_time();
@@ -1,12 +1,14 @@
+pub mod batch;
 pub mod crypto;
 pub mod helpers;
+pub mod offline;
 pub mod outcome;
 
 use std::collections::HashMap;
 
 use crate::verifier::{
-    crypto::{CoseP256Verifier, Crypto},
-    outcome::{ClaimValue, CredentialInfo, Failure, Outcome, Result},
+    crypto::{CoseAlgorithm, CoseVerifier, Crypto},
+    outcome::{ClaimValue, CredentialInfo, Failure, Outcome, Result, VerificationWarning},
 };
 use cose_rs::{
     cwt::{claim::ExpirationTime, ClaimsSet},
@@ -88,20 +90,20 @@ pub trait Verifiable: Credential {
         crypto: &C,
         cwt: CoseSign1,
         trusted_roots: Vec<Certificate>,
-    ) -> Result<()> {
+    ) -> Result<Vec<VerificationWarning>> {
         let signer_certificate = helpers::get_signer_certificate(&cwt).map_err(Failure::trust)?;
 
         // We want to manually handle the Err to get all errors, so try_fold would not work
         #[allow(clippy::manual_try_fold)]
-        trusted_roots
+        let warnings = trusted_roots
             .into_iter()
             .filter(|cert| {
                 cert.tbs_certificate.subject == signer_certificate.tbs_certificate.issuer
             })
             .fold(Result::Err("\n".to_string()), |res, cert| match res {
-                Ok(_) => Ok(()),
+                Ok(warnings) => Ok(warnings),
                 Err(err) => match self.validate_certificate_chain(crypto, &cwt, cert.clone()) {
-                    Ok(_) => Ok(()),
+                    Ok(warnings) => Ok(warnings),
                     Err(e) => Err(format!("{}\n--------------\n{}", err, e)),
                 },
             })
@@ -114,7 +116,9 @@ pub trait Verifiable: Credential {
             })
             .map_err(Failure::trust)?;
 
-        self.validate_cwt(cwt)
+        self.validate_cwt(cwt)?;
+
+        Ok(warnings)
     }
 
     fn validate_cwt(&self, cwt: CoseSign1) -> Result<()> {
@@ -145,7 +149,7 @@ pub trait Verifiable: Credential {
         crypto: &dyn Crypto,
         cwt: &CoseSign1,
         root_certificate: CertificateInner,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Vec<VerificationWarning>> {
         let signer_certificate = helpers::get_signer_certificate(cwt)?;
 
         // Root validation.
@@ -200,14 +204,18 @@ pub trait Verifiable: Credential {
         }
 
         // Validate that Signer issued CWT.
-        let verifier = CoseP256Verifier {
+        let verifier = CoseVerifier {
             crypto,
             certificate_der: signer_certificate
                 .to_der()
                 .context("unable to encode signer certificate as der")?,
+            algorithm: CoseAlgorithm::from_cose_sign1(cwt)?,
         };
         match cwt.verify(&verifier, None, None) {
-            VerificationResult::Success => Ok(()),
+            VerificationResult::Success => Ok([&root_certificate, &signer_certificate]
+                .into_iter()
+                .filter_map(helpers::expiry_warning)
+                .collect()),
             VerificationResult::Failure(e) => {
                 bail!("failed to verify the CWT signature: {e}")
             }
@@ -234,7 +242,10 @@ pub trait Verifiable: Credential {
         };
 
         match self.validate(crypto, cwt, trusted_roots) {
-            Ok(()) => Outcome::Verified { credential_info },
+            Ok(warnings) => Outcome::Verified {
+                credential_info,
+                warnings,
+            },
             Err(f) => Outcome::Unverified {
                 credential_info: Some(credential_info),
                 failure: f,
@@ -0,0 +1,16 @@
+/// Whether verification may use the network, e.g. to resolve DIDs or fetch status lists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, uniffi::Enum)]
+pub enum OfflinePolicy {
+    /// DIDs and status lists are resolved over the network as needed.
+    #[default]
+    Online,
+    /// Nothing is fetched, e.g. on air-gapped devices: statuses are reported as unknown, and
+    /// only certificate-based trust is accepted.
+    Offline,
+}
+
+impl OfflinePolicy {
+    pub(crate) fn is_offline(self) -> bool {
+        self == Self::Offline
+    }
+}
@@ -7,7 +7,10 @@ pub type Result<T, E = Failure> = ::std::result::Result<T, E>;
 #[derive(uniffi::Enum)]
 pub enum Outcome {
     /// The credential was successfully verified.
-    Verified { credential_info: CredentialInfo },
+    Verified {
+        credential_info: CredentialInfo,
+        warnings: Vec<VerificationWarning>,
+    },
     /// The credential could not be verified.
     Unverified {
         credential_info: Option<CredentialInfo>,
@@ -15,6 +18,39 @@ pub enum Outcome {
     },
 }
 
+/// A caveat of an otherwise successful verification, which should be displayed to the user
+/// without failing the verification.
+#[derive(uniffi::Enum, Debug, Clone, PartialEq)]
+pub enum VerificationWarning {
+    /// A certificate in the trust chain expires soon.
+    CertificateExpiringSoon {
+        /// The subject of the certificate.
+        subject: String,
+        /// The number of whole days until the certificate expires.
+        expires_in_days: u64,
+    },
+    /// A proof was ignored because its cryptosuite is not supported.
+    UnsupportedProofIgnored { cryptosuite: String },
+}
+
+/// How the issuer of a credential was established as trusted.
+#[derive(uniffi::Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustMechanism {
+    /// The signer certificate chains to a trusted root certificate.
+    CertificateChain,
+    /// The signing key was resolved from the issuer DID, without a certificate chain.
+    IssuerDid,
+}
+
+/// The report of a successful verification.
+#[derive(uniffi::Record, Debug, Clone, Default, PartialEq)]
+pub struct VerificationReport {
+    /// Soft warnings, that do not fail the verification.
+    pub warnings: Vec<VerificationWarning>,
+    /// How the issuer was trusted, if known.
+    pub trust_mechanism: Option<TrustMechanism>,
+}
+
 /// Information about the verified credential.
 #[derive(uniffi::Record)]
 pub struct CredentialInfo {
@@ -49,7 +85,10 @@ pub struct Failure {
 
 impl From<CredentialInfo> for Outcome {
     fn from(credential_info: CredentialInfo) -> Self {
-        Outcome::Verified { credential_info }
+        Outcome::Verified {
+            credential_info,
+            warnings: vec![],
+        }
     }
 }
 
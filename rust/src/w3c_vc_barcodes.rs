@@ -1,4 +1,4 @@
-use std::io::Cursor;
+use std::{io::Cursor, sync::Arc};
 
 use ssi::{
     dids::{AnyDidMethod, DIDResolver},
@@ -23,6 +23,120 @@ use w3c_vc_barcodes::{
     verify, MachineReadableZone, MRZ,
 };
 
+use crate::{
+    credential::{
+        cwt::{Cwt, CwtError, HC1_PREFIX},
+        json_vc::JsonVc,
+        ParsedCredential, ParsedCredentialInner,
+    },
+    verifier::crypto::{CoseAlgorithm, RustCrypto},
+};
+
+/// Prefix of QR-code encoded optical barcode credentials.
+const VC1_PREFIX: &str = "VC1-";
+
+#[derive(thiserror::Error, uniffi::Error, Debug)]
+pub enum BarcodeError {
+    #[error("unrecognized barcode payload prefix: {0:?}")]
+    UnknownPrefix(String),
+    #[error(transparent)]
+    Cwt(#[from] CwtError),
+    #[error("failed to decode the optical barcode credential: {0}")]
+    OpticalBarcodeDecoding(String),
+}
+
+#[uniffi::export]
+/// Parse a scanned barcode payload into a credential, detecting its encoding from the prefix:
+/// - `HC1:` base45-encoded CWT,
+/// - `9` base10-encoded CWT,
+/// - `VC1-` CBOR-LD optical barcode credential.
+pub async fn parse_barcode_payload(payload: String) -> Result<Arc<ParsedCredential>, BarcodeError> {
+    let payload = payload.trim();
+
+    if payload.starts_with(HC1_PREFIX) {
+        let cwt = Cwt::new_from_base45(payload.to_string())?;
+        return Ok(ParsedCredential::new_cwt(cwt));
+    }
+
+    if payload.starts_with('9') {
+        let cwt = Cwt::new_from_base10(payload.to_string())?;
+        return Ok(ParsedCredential::new_cwt(cwt));
+    }
+
+    if payload.starts_with(VC1_PREFIX) {
+        let input = MachineReadableZone::decode_qr_code_payload(payload)
+            .map_err(|e| BarcodeError::OpticalBarcodeDecoding(e.to_string()))?;
+        let vc = decode_from_bytes::<MachineReadableZone>(&input)
+            .await
+            .map_err(|e| BarcodeError::OpticalBarcodeDecoding(e.to_string()))?;
+        let json = serde_json::to_string(&vc)
+            .map_err(|e| BarcodeError::OpticalBarcodeDecoding(e.to_string()))?;
+        let json_vc = JsonVc::new_from_json(json)
+            .map_err(|e| BarcodeError::OpticalBarcodeDecoding(e.to_string()))?;
+        return Ok(ParsedCredential::new_ldp_vc(json_vc));
+    }
+
+    Err(BarcodeError::UnknownPrefix(
+        payload.chars().take(4).collect(),
+    ))
+}
+
+#[derive(thiserror::Error, uniffi::Error, Debug)]
+pub enum BarcodeVerifyError {
+    #[error(transparent)]
+    Parsing(#[from] BarcodeError),
+    #[error("unsupported signature algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("verification failed: {0}")]
+    Verification(String),
+}
+
+#[uniffi::export]
+/// Parse a scanned barcode payload, as [parse_barcode_payload] does, and verify its signature.
+///
+/// CWTs signed with ES256 or EdDSA must have a signer certificate issued by one of
+/// `roots_pem`. Optical barcode credentials are verified through their data-integrity proof,
+/// e.g. `Ed25519Signature2020`, and do not use `roots_pem`.
+pub async fn verify_barcode_credential(
+    payload: String,
+    roots_pem: Vec<String>,
+) -> Result<(), BarcodeVerifyError> {
+    let credential = parse_barcode_payload(payload).await?;
+
+    match &credential.inner {
+        ParsedCredentialInner::Cwt(cwt) => {
+            match cwt
+                .signature_algorithm()
+                .map_err(|e| BarcodeVerifyError::UnsupportedAlgorithm(format!("{e:#}")))?
+            {
+                CoseAlgorithm::ES256 | CoseAlgorithm::EdDSA => {}
+                algorithm @ CoseAlgorithm::ES256K => {
+                    return Err(BarcodeVerifyError::UnsupportedAlgorithm(format!(
+                        "{algorithm:?}"
+                    )))
+                }
+            }
+            cwt.verify_with_roots(&RustCrypto, roots_pem, None)
+                .await
+                .map_err(|e| BarcodeVerifyError::Verification(e.to_string()))?;
+        }
+        ParsedCredentialInner::LdpVc(json_vc) => {
+            json_vc
+                .verify()
+                .await
+                .map_err(|e| BarcodeVerifyError::Verification(e.to_string()))?;
+        }
+        _ => {
+            return Err(BarcodeVerifyError::Verification(format!(
+                "barcode credentials cannot be in the {} format",
+                credential.format()
+            )))
+        }
+    }
+
+    Ok(())
+}
+
 #[uniffi::export]
 pub async fn verify_pdf417_barcode(payload: String) -> Result<(), VCBVerificationError> {
     let mut cursor = Cursor::new(payload);
@@ -147,8 +261,207 @@ impl TypedStatusMapProvider<Uri, BitstringStatusListCredential20240406> for Stat
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::CborValue;
+
     use super::*;
 
+    fn base45_encode(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+        let mut encoded = String::new();
+        for chunk in bytes.chunks(2) {
+            let (mut value, digits) = match *chunk {
+                [a, b] => ((a as usize) * 256 + b as usize, 3),
+                [a] => (a as usize, 2),
+                _ => unreachable!(),
+            };
+            for _ in 0..digits {
+                encoded.push(ALPHABET[value % 45] as char);
+                value /= 45;
+            }
+        }
+        encoded
+    }
+
+    /// An unsigned COSE_Sign1 CWT with an issuer claim.
+    fn cwt_bytes() -> Vec<u8> {
+        use serde_cbor::Value;
+
+        let protected = serde_cbor::to_vec(&Value::Map(BTreeMap::from([(
+            Value::Integer(1),
+            Value::Integer(-7),
+        )])))
+        .unwrap();
+        let claims = serde_cbor::to_vec(&Value::Map(BTreeMap::from([(
+            Value::Integer(1),
+            Value::Text("did:example:issuer".into()),
+        )])))
+        .unwrap();
+
+        serde_cbor::to_vec(&Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(BTreeMap::new()),
+            Value::Bytes(claims),
+            Value::Bytes(vec![0; 64]),
+        ]))
+        .unwrap()
+    }
+
+    const IACA_CERTIFICATE: &str = include_str!("../tests/res/mdl/iaca-certificate.pem");
+    const IACA_KEY: &str = include_str!("../tests/res/mdl/iaca-key.pem");
+
+    /// A signer certificate for an Ed25519 key, issued by the test IACA.
+    fn ed25519_signer_certificate(key: &ed25519_dalek::VerifyingKey) -> Vec<u8> {
+        use p256::{ecdsa::DerSignature, pkcs8::DecodePrivateKey};
+        use x509_cert::{
+            builder::{Builder, CertificateBuilder, Profile},
+            der::{asn1::BitString, DecodePem, Encode},
+            ext::pkix::{
+                crl::dp::DistributionPoint,
+                name::{DistributionPointName, GeneralName},
+                CrlDistributionPoints, KeyUsage, KeyUsages,
+            },
+            spki::{AlgorithmIdentifierOwned, ObjectIdentifier, SubjectPublicKeyInfoOwned},
+            time::Validity,
+            Certificate,
+        };
+
+        let iaca_certificate = Certificate::from_pem(IACA_CERTIFICATE).unwrap();
+        let iaca_key = p256::ecdsa::SigningKey::from_pkcs8_pem(IACA_KEY).unwrap();
+
+        let spki = SubjectPublicKeyInfoOwned {
+            algorithm: AlgorithmIdentifierOwned {
+                oid: ObjectIdentifier::new_unwrap("1.3.101.112"),
+                parameters: None,
+            },
+            subject_public_key: BitString::from_bytes(key.as_bytes()).unwrap(),
+        };
+
+        let mut builder = CertificateBuilder::new(
+            Profile::Manual {
+                issuer: Some(iaca_certificate.tbs_certificate.subject),
+            },
+            1u32.into(),
+            Validity::from_now(std::time::Duration::from_secs(60 * 60)).unwrap(),
+            "CN=Example Barcode Issuer,C=US".parse().unwrap(),
+            spki,
+            &iaca_key,
+        )
+        .unwrap();
+        builder
+            .add_extension(&KeyUsage(KeyUsages::DigitalSignature.into()))
+            .unwrap();
+        builder
+            .add_extension(&CrlDistributionPoints(vec![DistributionPoint {
+                distribution_point: Some(DistributionPointName::FullName(vec![
+                    GeneralName::UniformResourceIdentifier(
+                        "https://example.com/crl".to_string().try_into().unwrap(),
+                    ),
+                ])),
+                reasons: None,
+                crl_issuer: None,
+            }]))
+            .unwrap();
+
+        builder.build::<DerSignature>().unwrap().to_der().unwrap()
+    }
+
+    /// An `HC1:` payload of a CWT signed with EdDSA by `signing_key`, whose `x5chain` is a
+    /// certificate for `certified_key`.
+    fn eddsa_cwt_payload(
+        signing_key: &ed25519_dalek::SigningKey,
+        certified_key: &ed25519_dalek::VerifyingKey,
+    ) -> String {
+        use ed25519_dalek::Signer;
+        use serde_cbor::Value;
+
+        let protected = serde_cbor::to_vec(&Value::Map(BTreeMap::from([
+            (Value::Integer(1), Value::Integer(-8)),
+            (
+                Value::Integer(33),
+                Value::Bytes(ed25519_signer_certificate(certified_key)),
+            ),
+        ])))
+        .unwrap();
+        let claims = serde_cbor::to_vec(&Value::Map(BTreeMap::from([(
+            Value::Integer(1),
+            Value::Text("Example Barcode Issuer".into()),
+        )])))
+        .unwrap();
+        let sig_structure = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Text("Signature1".into()),
+            Value::Bytes(protected.clone()),
+            Value::Bytes(vec![]),
+            Value::Bytes(claims.clone()),
+        ]))
+        .unwrap();
+        let signature = signing_key.sign(&sig_structure);
+
+        let cwt = serde_cbor::to_vec(&Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(BTreeMap::new()),
+            Value::Bytes(claims),
+            Value::Bytes(signature.to_bytes().to_vec()),
+        ]))
+        .unwrap();
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&cwt, 6);
+        format!("{HC1_PREFIX}{}", base45_encode(&compressed))
+    }
+
+    #[tokio::test]
+    async fn verify_eddsa_signed_cwt_payload() {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let payload = eddsa_cwt_payload(&signing_key, &signing_key.verifying_key());
+
+        verify_barcode_credential(payload, vec![IACA_CERTIFICATE.to_string()])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn reject_eddsa_cwt_payload_signed_by_other_key() {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let certified_key = ed25519_dalek::SigningKey::from_bytes(&[8; 32]).verifying_key();
+        let payload = eddsa_cwt_payload(&signing_key, &certified_key);
+
+        assert!(matches!(
+            verify_barcode_credential(payload, vec![IACA_CERTIFICATE.to_string()]).await,
+            Err(BarcodeVerifyError::Verification(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn parse_base45_cwt_payload() {
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&cwt_bytes(), 6);
+        let payload = format!("{HC1_PREFIX}{}", base45_encode(&compressed));
+
+        let credential = parse_barcode_payload(payload).await.unwrap();
+        let cwt = credential.as_cwt().expect("expected a CWT credential");
+
+        assert!(matches!(
+            cwt.claims().get("Issuer"),
+            Some(CborValue::Text(issuer)) if issuer == "did:example:issuer"
+        ));
+    }
+
+    #[tokio::test]
+    async fn parse_optical_barcode_payload() {
+        let ead = include_str!("../tests/res/ead-vcb");
+
+        let credential = parse_barcode_payload(ead.into()).await.unwrap();
+
+        assert!(credential.as_json_vc().is_some());
+    }
+
+    #[tokio::test]
+    async fn parse_unknown_barcode_payload() {
+        assert!(matches!(
+            parse_barcode_payload("XYZ:123".into()).await,
+            Err(BarcodeError::UnknownPrefix(prefix)) if prefix == "XYZ:"
+        ));
+    }
+
     #[tokio::test]
     async fn verify_vcb_dl() {
         let pdf417 = "@\n\x1e\rANSI 000000090002DL00410234ZZ02750202DLDAQF987654321\nDCSSMITH\nDDEN\nDACJOHN\nDDFN\nDADNONE\nDDGN\nDCAC\nDCBNONE\nDCDNONE\nDBD01012024\nDBB04191988\nDBA04192030\nDBC1\nDAU069 IN\nDAYBRO\nDAG123 MAIN ST\nDAIANYVILLE\nDAJUTO\nDAKF87P20000  \nDCFUTODOCDISCRIM\nDCGUTO\nDAW158\nDCK1234567890\nDDAN\rZZZZA2QZkpgGDGYAAGYABGYACGJ2CGHYYpBi4oxicGKYYzhiyGNAa5ZIggRi6ohicGKAYqER1ggAgGL4YqhjApRicGGwY1gQY4BjmGOJYQXq3wuVrSeLM5iGEziaBjhWosXMWRAG107uT_9bSteuPasCXFQKuPdSdF-xmUoFkA0yRJoW4ERvATNyewT263ZHMGOQYrA==\r";